@@ -0,0 +1,159 @@
+//! Compares `mystdrs::collections::HashMap` with `std::collections::HashMap`.
+//!
+//! Run with `cargo bench --bench hash_map`. Both maps use their default
+//! `RandomState`, which in both crates is randomly keyed SipHash-1-3.
+
+use mystdrs::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const N: usize = 100_000;
+const ROUNDS: u32 = 10;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// The operations both maps are driven through.
+trait Map: Default {
+    fn insert(&mut self, k: u64, v: u64) -> Option<u64>;
+    fn get(&self, k: u64) -> Option<&u64>;
+    fn remove(&mut self, k: u64) -> Option<u64>;
+}
+
+impl Map for OurMap {
+    fn insert(&mut self, k: u64, v: u64) -> Option<u64> {
+        HashMap::insert(self, k, v)
+    }
+
+    fn get(&self, k: u64) -> Option<&u64> {
+        HashMap::get(self, &k)
+    }
+
+    fn remove(&mut self, k: u64) -> Option<u64> {
+        HashMap::remove(self, &k)
+    }
+}
+
+impl Map for StdMap {
+    fn insert(&mut self, k: u64, v: u64) -> Option<u64> {
+        std::collections::HashMap::insert(self, k, v)
+    }
+
+    fn get(&self, k: u64) -> Option<&u64> {
+        std::collections::HashMap::get(self, &k)
+    }
+
+    fn remove(&mut self, k: u64) -> Option<u64> {
+        std::collections::HashMap::remove(self, &k)
+    }
+}
+
+fn filled<M: Map>(keys: &[u64]) -> M {
+    let mut map = M::default();
+    for &k in keys {
+        map.insert(k, k);
+    }
+    map
+}
+
+fn insert<M: Map>(keys: &[u64], _: &[u64]) -> Duration {
+    let start = Instant::now();
+    black_box(filled::<M>(keys));
+    start.elapsed()
+}
+
+fn lookup_hit<M: Map>(keys: &[u64], _: &[u64]) -> Duration {
+    let map = filled::<M>(keys);
+    let start = Instant::now();
+    for &k in keys {
+        black_box(map.get(black_box(k)));
+    }
+    start.elapsed()
+}
+
+fn lookup_miss<M: Map>(keys: &[u64], misses: &[u64]) -> Duration {
+    let map = filled::<M>(keys);
+    let start = Instant::now();
+    for &k in misses {
+        black_box(map.get(black_box(k)));
+    }
+    start.elapsed()
+}
+
+fn remove<M: Map>(keys: &[u64], _: &[u64]) -> Duration {
+    let mut map = filled::<M>(keys);
+    let start = Instant::now();
+    for &k in keys {
+        black_box(map.remove(k));
+    }
+    start.elapsed()
+}
+
+/// Inserts, lookups and removals interleaved over half the keys, so the
+/// table stays about half full and keeps accumulating tombstones.
+fn mixed<M: Map>(keys: &[u64], _: &[u64]) -> Duration {
+    let mut map = M::default();
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    let start = Instant::now();
+    for _ in 0..N * 2 {
+        let k = keys[(rng.next() % (N as u64 / 2)) as usize];
+        match rng.next() % 4 {
+            0 | 1 => black_box(map.get(k).is_some()),
+            2 => black_box(map.insert(k, k).is_some()),
+            _ => black_box(map.remove(k).is_some()),
+        };
+    }
+    let elapsed = start.elapsed();
+    black_box(map);
+    elapsed
+}
+
+/// A workload times its own measured phase, leaving out the setup.
+type Workload = fn(&[u64], &[u64]) -> Duration;
+
+type OurMap = HashMap<u64, u64>;
+type StdMap = std::collections::HashMap<u64, u64>;
+
+/// Returns the fastest of `ROUNDS` runs, which is the least noisy.
+fn best(workload: Workload, keys: &[u64], misses: &[u64]) -> Duration {
+    (0..ROUNDS).map(|_| workload(keys, misses)).min().unwrap()
+}
+
+fn main() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let keys: Vec<u64> = (0..N).map(|_| rng.next()).collect();
+    let misses: Vec<u64> = (0..N).map(|_| rng.next()).collect();
+
+    let workloads: [(&str, Workload, Workload); 5] = [
+        ("insert", insert::<OurMap>, insert::<StdMap>),
+        ("lookup hit", lookup_hit::<OurMap>, lookup_hit::<StdMap>),
+        ("lookup miss", lookup_miss::<OurMap>, lookup_miss::<StdMap>),
+        ("remove", remove::<OurMap>, remove::<StdMap>),
+        ("mixed", mixed::<OurMap>, mixed::<StdMap>),
+    ];
+
+    println!("{} keys, best of {} runs", N, ROUNDS);
+    println!(
+        "{:<12} {:>12} {:>12} {:>8}",
+        "workload", "mystdrs", "std", "ratio"
+    );
+    for (name, ours, std) in workloads.iter() {
+        let ours = best(*ours, &keys, &misses);
+        let std = best(*std, &keys, &misses);
+        println!(
+            "{:<12} {:>12.2?} {:>12.2?} {:>8.2}",
+            name,
+            ours,
+            std,
+            ours.as_secs_f64() / std.as_secs_f64()
+        );
+    }
+}
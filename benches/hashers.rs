@@ -0,0 +1,90 @@
+//! Compares the throughput of the `mystdrs::hash` hashers.
+//!
+//! Run with `cargo bench --bench hashers`. Each hasher hashes integer keys
+//! and short string keys on its own, then backs a `HashMap` doing inserts
+//! followed by lookups.
+
+use mystdrs::collections::HashMap;
+use mystdrs::hash::{
+    BuildHasher, BuildHasherDefault, FxHasher, Hash, RandomState, SipHasher24, WyHasher,
+};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const N: usize = 100_000;
+const ROUNDS: u32 = 10;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Returns the fastest of `ROUNDS` runs, which is the least noisy.
+fn best(f: impl Fn()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn hash_all<S: BuildHasher, K: Hash>(state: &S, keys: &[K]) {
+    for k in keys {
+        black_box(state.hash_one(black_box(k)));
+    }
+}
+
+fn map_insert_lookup<S: BuildHasher + Default>(keys: &[u64]) {
+    let mut map = HashMap::with_hasher(S::default());
+    for &k in keys {
+        map.insert(k, k);
+    }
+    for k in keys {
+        black_box(map.get(k));
+    }
+}
+
+/// Times one hasher on every workload, in nanoseconds per key.
+fn run<S: BuildHasher + Default>(name: &str, ints: &[u64], strings: &[String]) {
+    let state = S::default();
+    let per_key = |d: Duration| d.as_secs_f64() * 1e9 / N as f64;
+    println!(
+        "{:<10} {:>10.2} {:>10.2} {:>10.2}",
+        name,
+        per_key(best(|| hash_all(&state, ints))),
+        per_key(best(|| hash_all(&state, strings))),
+        per_key(best(|| map_insert_lookup::<S>(ints))),
+    );
+}
+
+fn main() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let ints: Vec<u64> = (0..N).map(|_| rng.next()).collect();
+    let strings: Vec<String> = (0..N)
+        .map(|_| {
+            let len = 4 + (rng.next() % 20) as usize;
+            (0..len)
+                .map(|_| (b'a' + (rng.next() % 26) as u8) as char)
+                .collect()
+        })
+        .collect();
+
+    println!("{} keys, best of {} runs, ns per key", N, ROUNDS);
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}",
+        "hasher", "u64", "str 4-23", "map u64"
+    );
+    run::<RandomState>("sip13", &ints, &strings);
+    run::<BuildHasherDefault<SipHasher24>>("sip24", &ints, &strings);
+    run::<BuildHasherDefault<FxHasher>>("fx", &ints, &strings);
+    run::<BuildHasherDefault<WyHasher>>("wy", &ints, &strings);
+}
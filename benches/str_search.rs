@@ -0,0 +1,100 @@
+//! Compares `mystdrs::str::find`, which uses Two-Way for `&str` needles,
+//! with a naive search and with std's `str::find`.
+//!
+//! Run with `cargo bench --bench str_search`. The inputs are the classic
+//! bad cases for a naive search, where nearly every window matches all but
+//! the last byte of the needle. There the naive search takes time
+//! proportional to haystack length times needle length and Two-Way stays
+//! linear. A random text case shows the common case for comparison.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 10;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Tries every window, comparing from the start of the needle.
+fn naive_find(haystack: &str, needle: &str) -> Option<usize> {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    let last = haystack.len().checked_sub(needle.len())?;
+    (0..=last).find(|&i| needle.iter().zip(&haystack[i..]).all(|(&n, &h)| n == h))
+}
+
+/// Returns the fastest of `ROUNDS` runs, which is the least noisy.
+fn best(f: impl Fn() -> Option<usize>) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn run(name: &str, haystack: &str, needle: &str) {
+    let expected = haystack.find(needle);
+    assert_eq!(mystdrs::str::find(haystack, needle), expected);
+    assert_eq!(naive_find(haystack, needle), expected);
+
+    let (haystack, needle) = (black_box(haystack), black_box(needle));
+    let two_way = best(|| mystdrs::str::find(haystack, needle));
+    let naive = best(|| naive_find(haystack, needle));
+    let std = best(|| haystack.find(needle));
+    println!(
+        "{:<22} {:>12.2?} {:>12.2?} {:>12.2?} {:>8.1}",
+        name,
+        two_way,
+        naive,
+        std,
+        naive.as_secs_f64() / two_way.as_secs_f64()
+    );
+}
+
+fn main() {
+    const N: usize = 1 << 20;
+
+    println!("haystacks of {} bytes, best of {} runs", N, ROUNDS);
+    println!(
+        "{:<22} {:>12} {:>12} {:>12} {:>8}",
+        "input", "two-way", "naive", "std", "speedup"
+    );
+
+    // Every window matches the needle's `a`s and fails on its `b`.
+    let haystack = "a".repeat(N);
+    for &m in &[16, 256, 4096] {
+        let needle = "a".repeat(m) + "b";
+        run(&format!("a^n, a^{}b", m), &haystack, &needle);
+    }
+
+    // A needle whose mismatch comes first, which the naive search finds
+    // quickly and Two-Way has to reach through its right half.
+    let needle = "b".to_string() + &"a".repeat(256);
+    run("a^n, ba^256", &haystack, &needle);
+
+    // Periodic needle against an almost-periodic haystack.
+    let haystack = "ab".repeat(N / 2);
+    let needle = "ab".repeat(512) + "b";
+    run("(ab)^n, (ab)^512b", &haystack, &needle);
+
+    // Lowercase random text with a needle that never occurs.
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let haystack: String = (0..N)
+        .map(|_| (b'a' + (rng.next() % 26) as u8) as char)
+        .collect();
+    run(
+        "random, 32 bytes",
+        &haystack,
+        "thequickbrownfoxjumpsoverthelazy",
+    );
+}
@@ -0,0 +1,721 @@
+//! A chunked bump (arena) allocator.
+
+use super::{dangling, AllocError, Allocator, Global, Layout};
+use std::cell::Cell;
+use std::fmt;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+/// Usable bytes in the first chunk when no capacity is requested.
+const DEFAULT_CHUNK_CAPACITY: usize = 4096 - mem::size_of::<ChunkHeader>();
+
+/// Alignment of every chunk, so small alignments never need padding at the
+/// start of a fresh chunk.
+const CHUNK_ALIGN: usize = 16;
+
+/// Lives at the start of every chunk; the chunks form a singly linked list
+/// from the newest back to the oldest.
+struct ChunkHeader {
+    prev: Option<NonNull<ChunkHeader>>,
+    layout: Layout,
+}
+
+impl ChunkHeader {
+    /// First usable byte of the chunk.
+    unsafe fn data(chunk: NonNull<ChunkHeader>) -> *mut u8 {
+        (chunk.as_ptr() as *mut u8).add(mem::size_of::<ChunkHeader>())
+    }
+
+    /// One past the last usable byte of the chunk.
+    unsafe fn end(chunk: NonNull<ChunkHeader>) -> *mut u8 {
+        (chunk.as_ptr() as *mut u8).add((*chunk.as_ptr()).layout.size())
+    }
+
+    fn capacity(chunk: NonNull<ChunkHeader>) -> usize {
+        unsafe { (*chunk.as_ptr()).layout.size() - mem::size_of::<ChunkHeader>() }
+    }
+}
+
+/// Lives at the start of every side chunk. Side chunks serve requests made
+/// through an outer level while a scope is open, kept apart from the main
+/// chunks so that rolling the scope back leaves them alone.
+struct SideChunk {
+    prev: Option<NonNull<SideChunk>>,
+    layout: Layout,
+    /// The level whose requests this chunk serves; it is freed when that
+    /// level ends.
+    level: usize,
+    /// Next free byte of the chunk.
+    ptr: *mut u8,
+}
+
+impl SideChunk {
+    unsafe fn data(chunk: NonNull<SideChunk>) -> *mut u8 {
+        (chunk.as_ptr() as *mut u8).add(mem::size_of::<SideChunk>())
+    }
+
+    unsafe fn end(chunk: NonNull<SideChunk>) -> *mut u8 {
+        (chunk.as_ptr() as *mut u8).add((*chunk.as_ptr()).layout.size())
+    }
+
+    fn capacity(chunk: NonNull<SideChunk>) -> usize {
+        unsafe { (*chunk.as_ptr()).layout.size() - mem::size_of::<SideChunk>() }
+    }
+}
+
+/// A bump allocator: allocation is a pointer increment into the current
+/// chunk, and a new, larger chunk is taken from [`Global`] when it runs out.
+///
+/// Individual deallocations are no-ops (except that the most recent
+/// allocation can be given back or grown in place), so memory is reclaimed
+/// all at once with [`reset`](Bump::reset), when the arena is dropped, or
+/// when a [`BumpScope`] ends.
+///
+/// While a scope is open, the arena itself and any outer scope can still
+/// allocate. Their requests are served from separate side chunks, which
+/// rolling the inner scope back leaves alone. A side chunk is freed when
+/// the level it serves ends.
+///
+/// Collections use it through a shared reference, so everything allocated
+/// for one request can live in one arena:
+///
+/// ```
+/// use mystdrs::alloc::Bump;
+/// use mystdrs::vec::Vec;
+///
+/// let bump = Bump::new();
+/// let mut v = Vec::new_in(&bump);
+/// v.push(1);
+/// v.push(2);
+/// assert_eq!(v, [1, 2]);
+/// ```
+pub struct Bump {
+    current: Cell<Option<NonNull<ChunkHeader>>>,
+    ptr: Cell<*mut u8>,
+    end: Cell<*mut u8>,
+    /// Number of open scopes; only that level allocates from the main
+    /// chunks.
+    depth: Cell<usize>,
+    /// Side chunks of the levels below `depth`, newest first.
+    side: Cell<Option<NonNull<SideChunk>>>,
+    first_chunk_capacity: usize,
+}
+
+// The arena owns its chunks outright; it is only the interior mutability
+// that makes it `!Sync`.
+unsafe impl Send for Bump {}
+
+impl Bump {
+    /// Creates an empty arena. Nothing is allocated until the first request.
+    pub const fn new() -> Self {
+        Self::with_capacity(DEFAULT_CHUNK_CAPACITY)
+    }
+
+    /// Creates an empty arena whose first chunk will hold at least
+    /// `capacity` bytes.
+    pub const fn with_capacity(capacity: usize) -> Self {
+        Bump {
+            current: Cell::new(None),
+            ptr: Cell::new(ptr::null_mut()),
+            end: Cell::new(ptr::null_mut()),
+            depth: Cell::new(0),
+            side: Cell::new(None),
+            first_chunk_capacity: capacity,
+        }
+    }
+
+    /// Total number of bytes obtained from the global allocator, including
+    /// what has not been handed out yet.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks().map(ChunkHeader::capacity).sum::<usize>()
+            + self.side_chunks().map(SideChunk::capacity).sum::<usize>()
+    }
+
+    /// Number of chunks currently owned by the arena.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks().count() + self.side_chunks().count()
+    }
+
+    /// Frees everything allocated from the arena at once. The newest (and
+    /// largest) chunk is kept so the next round of allocations starts warm.
+    pub fn reset(&mut self) {
+        unsafe { free_side_chunks(&self.side, 0) };
+        let current = match self.current.get() {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        unsafe {
+            free_chunks((*current.as_ptr()).prev.take(), None);
+            self.ptr.set(ChunkHeader::data(current));
+        }
+    }
+
+    /// Opens a scope: everything allocated through the returned guard is
+    /// rolled back when it is dropped, while allocations made before the
+    /// scope stay valid. Growing them, or allocating through the arena
+    /// itself, still works while the scope is open.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a scope is already open; nest through
+    /// [`BumpScope::scope`] instead.
+    pub fn scope(&self) -> BumpScope<'_> {
+        self.open_scope(0)
+    }
+
+    fn open_scope(&self, level: usize) -> BumpScope<'_> {
+        assert_eq!(
+            self.depth.get(),
+            level,
+            "a Bump scope can only be opened from the innermost level"
+        );
+        self.depth.set(level + 1);
+        BumpScope {
+            bump: self,
+            level: level + 1,
+            chunk: self.current.get(),
+            ptr: self.ptr.get(),
+            end: self.end.get(),
+        }
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = NonNull<ChunkHeader>> {
+        let mut next = self.current.get();
+        std::iter::from_fn(move || {
+            let chunk = next?;
+            next = unsafe { (*chunk.as_ptr()).prev };
+            Some(chunk)
+        })
+    }
+
+    fn side_chunks(&self) -> impl Iterator<Item = NonNull<SideChunk>> {
+        let mut next = self.side.get();
+        std::iter::from_fn(move || {
+            let chunk = next?;
+            next = unsafe { (*chunk.as_ptr()).prev };
+            Some(chunk)
+        })
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        if let Some(p) = self.try_bump(layout) {
+            return Ok(p);
+        }
+        self.push_chunk(layout)?;
+        Ok(self
+            .try_bump(layout)
+            .expect("fresh chunk is large enough for the request"))
+    }
+
+    /// Carves `layout` out of the current chunk, if it fits.
+    fn try_bump(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let start = bump_start(self.ptr.get(), self.end.get(), layout)?;
+        unsafe {
+            self.ptr.set(start.as_ptr().add(layout.size()));
+        }
+        Some(start)
+    }
+
+    /// Serves a request made through `level` while a deeper scope is open,
+    /// from the newest side chunk of that level or a fresh one.
+    fn alloc_side(&self, level: usize, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let newest = self
+            .side_chunks()
+            .find(|&chunk| unsafe { (*chunk.as_ptr()).level } == level);
+        if let Some(chunk) = newest {
+            unsafe {
+                let header = chunk.as_ptr();
+                if let Some(start) = bump_start((*header).ptr, SideChunk::end(chunk), layout) {
+                    (*header).ptr = start.as_ptr().add(layout.size());
+                    return Ok(start);
+                }
+            }
+        }
+        let wanted = match newest {
+            Some(chunk) => SideChunk::capacity(chunk).saturating_mul(2),
+            None => self.first_chunk_capacity,
+        };
+        let chunk_layout = chunk_layout::<SideChunk>(wanted, layout)?;
+        let chunk = Global.allocate(chunk_layout)?.cast::<SideChunk>();
+        unsafe {
+            let data = SideChunk::data(chunk);
+            let start = data.add((data as usize).wrapping_neg() & (layout.align() - 1));
+            chunk.as_ptr().write(SideChunk {
+                prev: self.side.get(),
+                layout: chunk_layout,
+                level,
+                ptr: start.add(layout.size()),
+            });
+            self.side.set(Some(chunk));
+            Ok(NonNull::new_unchecked(start))
+        }
+    }
+
+    /// Starts a new chunk big enough for `layout`, doubling the size of the
+    /// previous one.
+    fn push_chunk(&self, layout: Layout) -> Result<(), AllocError> {
+        let prev = self.current.get();
+        let wanted = match prev {
+            Some(chunk) => ChunkHeader::capacity(chunk).saturating_mul(2),
+            None => self.first_chunk_capacity,
+        };
+        let chunk_layout = chunk_layout::<ChunkHeader>(wanted, layout)?;
+        let chunk = Global.allocate(chunk_layout)?.cast::<ChunkHeader>();
+        unsafe {
+            chunk.as_ptr().write(ChunkHeader {
+                prev,
+                layout: chunk_layout,
+            });
+            self.ptr.set(ChunkHeader::data(chunk));
+            self.end.set(ChunkHeader::end(chunk));
+        }
+        self.current.set(Some(chunk));
+        Ok(())
+    }
+
+    /// Returns `true` if `ptr..ptr + size` is the most recent allocation.
+    fn is_last(&self, ptr: NonNull<u8>, size: usize) -> bool {
+        size != 0 && ptr.as_ptr().wrapping_add(size) == self.ptr.get()
+    }
+
+    fn is_top(&self, level: usize) -> bool {
+        self.depth.get() == level
+    }
+
+    /// Allocates for `level`: from the main chunks if it is the innermost
+    /// level, and from its side chunks otherwise.
+    fn alloc_at(&self, level: usize, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if self.is_top(level) {
+            self.alloc_layout(layout)
+        } else {
+            self.alloc_side(level, layout)
+        }
+    }
+
+    fn allocate_at(&self, level: usize, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let p = self.alloc_at(level, layout)?;
+        Ok(NonNull::slice_from_raw_parts(p, layout.size()))
+    }
+
+    unsafe fn deallocate_at(&self, level: usize, ptr: NonNull<u8>, layout: Layout) {
+        // Only the most recent allocation can be reclaimed early; the rest
+        // waits for `reset`, its level ending, or the arena being dropped.
+        if self.is_top(level) && self.is_last(ptr, layout.size()) {
+            self.ptr.set(ptr.as_ptr());
+        }
+    }
+
+    unsafe fn grow_at(
+        &self,
+        level: usize,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let fits_in_place = self.is_top(level)
+            && self.is_last(ptr, old_layout.size())
+            && (ptr.as_ptr() as usize).is_multiple_of(new_layout.align())
+            && new_layout.size() <= self.end.get() as usize - ptr.as_ptr() as usize;
+        if fits_in_place {
+            self.ptr.set(ptr.as_ptr().add(new_layout.size()));
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+        let new = self.alloc_at(level, new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), old_layout.size());
+        Ok(NonNull::slice_from_raw_parts(new, new_layout.size()))
+    }
+
+    unsafe fn shrink_at(
+        &self,
+        level: usize,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if new_layout.size() == 0 {
+            self.deallocate_at(level, ptr, old_layout);
+            return Ok(NonNull::slice_from_raw_parts(dangling(new_layout), 0));
+        }
+        if (ptr.as_ptr() as usize).is_multiple_of(new_layout.align()) {
+            if self.is_top(level) && self.is_last(ptr, old_layout.size()) {
+                self.ptr.set(ptr.as_ptr().add(new_layout.size()));
+            }
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+        let new = self.alloc_at(level, new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), new_layout.size());
+        Ok(NonNull::slice_from_raw_parts(new, new_layout.size()))
+    }
+}
+
+/// Returns where `layout` would start in `ptr..end`, if it fits.
+fn bump_start(ptr: *mut u8, end: *mut u8, layout: Layout) -> Option<NonNull<u8>> {
+    let available = end as usize - ptr as usize;
+    let pad = (ptr as usize).wrapping_neg() & (layout.align() - 1);
+    if pad > available || layout.size() > available - pad {
+        return None;
+    }
+    unsafe { Some(NonNull::new_unchecked(ptr.add(pad))) }
+}
+
+/// Returns the layout of a chunk headed by an `H` with room for at least
+/// `wanted` bytes and for `layout` at any alignment.
+fn chunk_layout<H>(wanted: usize, layout: Layout) -> Result<Layout, AllocError> {
+    let needed = layout
+        .size()
+        .checked_add(layout.align())
+        .ok_or(AllocError)?;
+    let size = wanted
+        .max(needed)
+        .checked_add(mem::size_of::<H>())
+        .ok_or(AllocError)?;
+    Layout::from_size_align(size, CHUNK_ALIGN.max(mem::align_of::<H>())).map_err(|_| AllocError)
+}
+
+/// Frees the side chunks of `level` and every level above it.
+unsafe fn free_side_chunks(side: &Cell<Option<NonNull<SideChunk>>>, level: usize) {
+    let mut kept: Option<NonNull<SideChunk>> = None;
+    let mut next = side.get();
+    while let Some(chunk) = next {
+        let header = chunk.as_ptr();
+        next = (*header).prev;
+        if (*header).level < level {
+            kept = Some(chunk);
+            continue;
+        }
+        match kept {
+            Some(kept) => (*kept.as_ptr()).prev = next,
+            None => side.set(next),
+        }
+        Global.deallocate(chunk.cast(), (*header).layout);
+    }
+}
+
+/// Frees chunks from `from` back to (but not including) `until`.
+unsafe fn free_chunks(mut from: Option<NonNull<ChunkHeader>>, until: Option<NonNull<ChunkHeader>>) {
+    while from != until {
+        let chunk = from.expect("rollback target is in the chunk list");
+        let ChunkHeader { prev, layout } = chunk.as_ptr().read();
+        Global.deallocate(chunk.cast(), layout);
+        from = prev;
+    }
+}
+
+impl Default for Bump {
+    fn default() -> Self {
+        Bump::new()
+    }
+}
+
+impl Drop for Bump {
+    fn drop(&mut self) {
+        unsafe {
+            free_chunks(self.current.get(), None);
+            free_side_chunks(&self.side, 0);
+        }
+    }
+}
+
+impl fmt::Debug for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bump")
+            .field("chunks", &self.chunk_count())
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+unsafe impl Allocator for Bump {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.allocate_at(0, layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.deallocate_at(0, ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.grow_at(0, ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.shrink_at(0, ptr, old_layout, new_layout)
+    }
+}
+
+/// A checkpoint in a [`Bump`] arena, created by [`Bump::scope`].
+///
+/// The scope is itself an allocator: collections built with
+/// `Vec::new_in(&scope)` borrow it, so they must be gone before it drops and
+/// rolls the arena back to where it was when the scope opened.
+pub struct BumpScope<'a> {
+    bump: &'a Bump,
+    level: usize,
+    chunk: Option<NonNull<ChunkHeader>>,
+    ptr: *mut u8,
+    end: *mut u8,
+}
+
+impl BumpScope<'_> {
+    /// Opens a nested scope that rolls back to the current position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this scope already has an open nested scope.
+    pub fn scope(&self) -> BumpScope<'_> {
+        self.bump.open_scope(self.level)
+    }
+
+    /// Returns the arena this scope allocates from.
+    pub fn arena(&self) -> &Bump {
+        self.bump
+    }
+}
+
+unsafe impl Allocator for BumpScope<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.bump.allocate_at(self.level, layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.bump.deallocate_at(self.level, ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.bump.grow_at(self.level, ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.bump.shrink_at(self.level, ptr, old_layout, new_layout)
+    }
+}
+
+impl Drop for BumpScope<'_> {
+    fn drop(&mut self) {
+        let bump = self.bump;
+        unsafe {
+            free_chunks(bump.current.get(), self.chunk);
+            free_side_chunks(&bump.side, self.level);
+        }
+        bump.current.set(self.chunk);
+        bump.ptr.set(self.ptr);
+        bump.end.set(self.end);
+        bump.depth.set(self.level - 1);
+    }
+}
+
+impl fmt::Debug for BumpScope<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BumpScope")
+            .field("level", &self.level)
+            .field("arena", self.bump)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bump;
+    use crate::alloc::{Allocator, Layout};
+    use crate::vec::Vec;
+
+    #[test]
+    fn respects_alignment() {
+        let bump = Bump::new();
+        for &align in &[1, 2, 4, 8, 16, 64, 256, 4096] {
+            let layout = Layout::from_size_align(3, align).unwrap();
+            let p = bump.allocate(layout).unwrap();
+            assert_eq!(p.as_ptr() as *mut u8 as usize % align, 0);
+            assert_eq!(p.len(), 3);
+        }
+    }
+
+    #[test]
+    fn chunks_grow_geometrically() {
+        let bump = Bump::with_capacity(64);
+        let layout = Layout::new::<[u8; 48]>();
+        for _ in 0..20 {
+            bump.allocate(layout).unwrap();
+        }
+        assert!(bump.chunk_count() > 1);
+        assert!(bump.chunk_count() <= 6);
+        // Requests bigger than the next chunk still succeed.
+        let big = Layout::from_size_align(100_000, 8).unwrap();
+        assert_eq!(bump.allocate(big).unwrap().len(), 100_000);
+    }
+
+    #[test]
+    fn last_allocation_grows_in_place() {
+        let bump = Bump::new();
+        let mut v: Vec<u32, _> = Vec::new_in(&bump);
+        v.push(0);
+        let first = v.as_ptr();
+        for i in 1..200 {
+            v.push(i);
+        }
+        assert_eq!(v.as_ptr(), first);
+        assert!(v.iter().copied().eq(0..200));
+        v.shrink_to_fit();
+        assert_eq!(v.as_ptr(), first);
+    }
+
+    #[test]
+    fn reset_reuses_newest_chunk() {
+        let mut bump = Bump::with_capacity(128);
+        for _ in 0..10 {
+            let mut v: Vec<u64, _> = Vec::new_in(&bump);
+            v.extend(0..100u64);
+            let mut w: Vec<u64, _> = Vec::new_in(&bump);
+            w.extend(0..10u64);
+            assert_eq!(v.len() + w.len(), 110);
+        }
+        assert!(bump.chunk_count() > 1);
+        let kept = bump.allocated_bytes();
+        bump.reset();
+        assert_eq!(bump.chunk_count(), 1);
+        assert!(bump.allocated_bytes() < kept);
+        let again = bump.allocated_bytes();
+        let v: Vec<u8, _> = Vec::with_capacity_in(again / 2, &bump);
+        assert_eq!(v.capacity(), again / 2);
+        assert_eq!(bump.chunk_count(), 1);
+    }
+
+    #[test]
+    fn scopes_roll_back() {
+        let bump = Bump::with_capacity(64);
+        let scope = bump.scope();
+        let mut outer: Vec<u8, _> = Vec::new_in(&scope);
+        outer.extend_from_slice(b"outer");
+        let before = (bump.chunk_count(), bump.ptr.get());
+        {
+            let inner = scope.scope();
+            let mut v: Vec<u64, _> = Vec::new_in(&inner);
+            v.extend(0..1000u64);
+            {
+                let nested = inner.scope();
+                let w: Vec<u64, _> = Vec::with_capacity_in(5000, &nested);
+                assert_eq!(w.capacity(), 5000);
+                // Outer levels grow into side chunks the nested scope
+                // does not roll back.
+                v.reserve(10_000);
+                outer.reserve(100);
+            }
+            assert_eq!(v.iter().sum::<u64>(), 999 * 500);
+            assert!(bump.chunk_count() > before.0);
+        }
+        // Only `outer`'s side chunk is left on top of the main chunks.
+        assert_eq!(bump.ptr.get(), before.1);
+        assert_eq!(bump.chunk_count(), before.0 + 1);
+        outer.push(b'!');
+        assert_eq!(outer, *b"outer!");
+        drop(outer);
+        drop(scope);
+        assert_eq!(bump.chunk_count(), 0);
+    }
+
+    #[test]
+    fn outer_levels_allocate_while_a_scope_is_open() {
+        let bump = Bump::with_capacity(64);
+        let mut root: Vec<u32, _> = Vec::new_in(&bump);
+        root.push(0);
+        {
+            let scope = bump.scope();
+            let mut mid: Vec<u32, _> = Vec::new_in(&scope);
+            mid.push(0);
+            {
+                let inner = scope.scope();
+                let mut v: Vec<u32, _> = Vec::new_in(&inner);
+                for i in 1..500 {
+                    // Infallible pushes through every level.
+                    root.push(i);
+                    mid.push(i);
+                    v.push(i);
+                }
+                let fresh: Vec<u8, _> = Vec::with_capacity_in(300, &bump);
+                assert_eq!(fresh.capacity(), 300);
+                assert!(v.iter().copied().eq(1..500));
+            }
+            assert!(mid.iter().copied().eq(0..500));
+            mid.push(500);
+            root.push(500);
+            assert!(mid.iter().copied().eq(0..501));
+        }
+        assert!(root.iter().copied().eq(0..501));
+        let kept = bump.chunk_count();
+        root.extend(501..2000);
+        assert!(root.iter().copied().eq(0..2000));
+        assert!(bump.chunk_count() >= kept);
+    }
+
+    #[test]
+    fn reset_frees_side_chunks() {
+        let mut bump = Bump::with_capacity(64);
+        {
+            let mut root: Vec<u64, _> = Vec::new_in(&bump);
+            root.push(0);
+            let _scope = bump.scope();
+            root.extend(1..1000u64);
+            assert!(root.iter().copied().eq(0..1000));
+        }
+        assert!(bump.chunk_count() >= 2);
+        bump.reset();
+        assert_eq!(bump.chunk_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "innermost level")]
+    fn sibling_scopes_are_rejected() {
+        let bump = Bump::new();
+        let _a = bump.scope();
+        let _b = bump.scope();
+    }
+
+    #[test]
+    fn nested_collections_share_one_arena() {
+        let bump = Bump::new();
+        let mut rows: Vec<Vec<u32, &Bump>, &Bump> = Vec::new_in(&bump);
+        for r in 0..16 {
+            let mut row = Vec::with_capacity_in(16, &bump);
+            row.extend((0..16).map(|c| r * 16 + c));
+            rows.push(row);
+        }
+        let total: u32 = rows.iter().map(|r| r.iter().sum::<u32>()).sum();
+        assert_eq!(total, (0..256).sum());
+        assert_eq!(bump.chunk_count(), 1);
+    }
+
+    #[test]
+    fn zero_sized_requests_do_not_allocate() {
+        let bump = Bump::new();
+        let layout = Layout::from_size_align(0, 32).unwrap();
+        let p = bump.allocate(layout).unwrap();
+        assert_eq!(p.as_ptr() as *mut u8 as usize % 32, 0);
+        assert_eq!(bump.chunk_count(), 0);
+    }
+}
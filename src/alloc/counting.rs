@@ -0,0 +1,343 @@
+//! An allocator wrapper that records what passes through it.
+
+use super::{AllocError, Allocator, Global, Layout};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Wraps another allocator and counts every request made through it.
+///
+/// Counters are atomics, so a `Counting` can be shared between threads
+/// when the inner allocator allows it. Take a [`snapshot`](Counting::snapshot)
+/// before an operation and [`diff`](AllocStats::diff) it against one taken
+/// afterwards to assert exactly what the operation did:
+///
+/// ```
+/// use mystdrs::alloc::{Counting, Global};
+/// use mystdrs::vec::Vec;
+///
+/// let counting = Counting::new(Global);
+/// let before = counting.snapshot();
+/// {
+///     let mut v = Vec::with_capacity_in(8, &counting);
+///     v.extend(0..8u32);
+/// }
+/// let diff = counting.snapshot().diff(&before);
+/// assert_eq!(diff.allocations, 1);
+/// assert!(diff.is_balanced());
+/// ```
+#[derive(Debug, Default)]
+pub struct Counting<A = Global> {
+    inner: A,
+    allocations: AtomicUsize,
+    deallocations: AtomicUsize,
+    reallocations: AtomicUsize,
+    failures: AtomicUsize,
+    bytes_allocated: AtomicUsize,
+    bytes_deallocated: AtomicUsize,
+    live_blocks: AtomicUsize,
+    live_bytes: AtomicUsize,
+    peak_bytes: AtomicUsize,
+}
+
+/// A point-in-time copy of a [`Counting`] allocator's counters.
+///
+/// Byte counts refer to the sizes requested in each `Layout`, not to any
+/// slack the inner allocator may hand out.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Successful `allocate`/`allocate_zeroed` calls.
+    pub allocations: usize,
+    /// `deallocate` calls.
+    pub deallocations: usize,
+    /// Successful `grow`/`shrink` calls.
+    pub reallocations: usize,
+    /// Requests of any kind the inner allocator refused.
+    pub failures: usize,
+    /// Total bytes ever acquired, including growth.
+    pub bytes_allocated: usize,
+    /// Total bytes ever released, including shrinkage.
+    pub bytes_deallocated: usize,
+    /// Blocks currently allocated.
+    pub live_blocks: usize,
+    /// Bytes currently allocated.
+    pub live_bytes: usize,
+    /// High-water mark of `live_bytes`.
+    pub peak_bytes: usize,
+}
+
+/// The change between two [`AllocStats`] snapshots, from
+/// [`AllocStats::diff`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AllocDiff {
+    /// Successful `allocate`/`allocate_zeroed` calls in the interval.
+    pub allocations: usize,
+    /// `deallocate` calls in the interval.
+    pub deallocations: usize,
+    /// Successful `grow`/`shrink` calls in the interval.
+    pub reallocations: usize,
+    /// Requests the inner allocator refused in the interval.
+    pub failures: usize,
+    /// Bytes acquired in the interval, including growth.
+    pub bytes_allocated: usize,
+    /// Bytes released in the interval, including shrinkage.
+    pub bytes_deallocated: usize,
+    /// Net blocks allocated; negative if more were freed than allocated.
+    pub live_blocks: isize,
+    /// Net bytes allocated; negative if more were freed than allocated.
+    pub live_bytes: isize,
+}
+
+impl AllocStats {
+    /// Returns what changed between `earlier` and `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a counter that only ever grows is smaller in `self` than in
+    /// `earlier`, which means the snapshots were passed the wrong way round.
+    pub fn diff(&self, earlier: &AllocStats) -> AllocDiff {
+        let since = |now: usize, then: usize| {
+            now.checked_sub(then)
+                .expect("AllocStats::diff: `earlier` was taken after `self`")
+        };
+        AllocDiff {
+            allocations: since(self.allocations, earlier.allocations),
+            deallocations: since(self.deallocations, earlier.deallocations),
+            reallocations: since(self.reallocations, earlier.reallocations),
+            failures: since(self.failures, earlier.failures),
+            bytes_allocated: since(self.bytes_allocated, earlier.bytes_allocated),
+            bytes_deallocated: since(self.bytes_deallocated, earlier.bytes_deallocated),
+            live_blocks: self.live_blocks as isize - earlier.live_blocks as isize,
+            live_bytes: self.live_bytes as isize - earlier.live_bytes as isize,
+        }
+    }
+}
+
+impl AllocDiff {
+    /// Returns `true` if everything allocated in the interval was also
+    /// freed in it, i.e. nothing leaked.
+    pub fn is_balanced(&self) -> bool {
+        self.live_blocks == 0 && self.live_bytes == 0
+    }
+}
+
+impl<A> Counting<A> {
+    /// Wraps `inner` with all counters at zero.
+    pub const fn new(inner: A) -> Self {
+        Counting {
+            inner,
+            allocations: AtomicUsize::new(0),
+            deallocations: AtomicUsize::new(0),
+            reallocations: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+            bytes_allocated: AtomicUsize::new(0),
+            bytes_deallocated: AtomicUsize::new(0),
+            live_blocks: AtomicUsize::new(0),
+            live_bytes: AtomicUsize::new(0),
+            peak_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the wrapped allocator.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Copies out the current counters.
+    pub fn snapshot(&self) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            deallocations: self.deallocations.load(Ordering::Relaxed),
+            reallocations: self.reallocations.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            bytes_allocated: self.bytes_allocated.load(Ordering::Relaxed),
+            bytes_deallocated: self.bytes_deallocated.load(Ordering::Relaxed),
+            live_blocks: self.live_blocks.load(Ordering::Relaxed),
+            live_bytes: self.live_bytes.load(Ordering::Relaxed),
+            peak_bytes: self.peak_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Restarts peak tracking from the current number of live bytes.
+    pub fn reset_peak(&self) {
+        self.peak_bytes
+            .store(self.live_bytes.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn acquired(&self, bytes: usize) {
+        self.bytes_allocated.fetch_add(bytes, Ordering::Relaxed);
+        let live = self.live_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn released(&self, bytes: usize) {
+        self.bytes_deallocated.fetch_add(bytes, Ordering::Relaxed);
+        self.live_bytes.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    fn record<T>(&self, result: Result<T, AllocError>) -> Result<T, AllocError> {
+        if result.is_err() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn record_alloc(
+        &self,
+        layout: Layout,
+        result: Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let block = self.record(result)?;
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.live_blocks.fetch_add(1, Ordering::Relaxed);
+        self.acquired(layout.size());
+        Ok(block)
+    }
+}
+
+unsafe impl<A: Allocator> Allocator for Counting<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.record_alloc(layout, self.inner.allocate(layout))
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.record_alloc(layout, self.inner.allocate_zeroed(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.inner.deallocate(ptr, layout);
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+        self.live_blocks.fetch_sub(1, Ordering::Relaxed);
+        self.released(layout.size());
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let block = self.record(self.inner.grow(ptr, old_layout, new_layout))?;
+        self.reallocations.fetch_add(1, Ordering::Relaxed);
+        self.acquired(new_layout.size() - old_layout.size());
+        Ok(block)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let block = self.record(self.inner.grow_zeroed(ptr, old_layout, new_layout))?;
+        self.reallocations.fetch_add(1, Ordering::Relaxed);
+        self.acquired(new_layout.size() - old_layout.size());
+        Ok(block)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let block = self.record(self.inner.shrink(ptr, old_layout, new_layout))?;
+        self.reallocations.fetch_add(1, Ordering::Relaxed);
+        self.released(old_layout.size() - new_layout.size());
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Counting;
+    use crate::alloc::{Allocator, Global, Layout};
+    use crate::test_util::{Budget, DropCounter};
+    use crate::vec::Vec;
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn push_within_capacity_does_not_allocate() {
+        let counting = Counting::new(Global);
+        let mut v: Vec<u64, _> = Vec::with_capacity_in(16, &counting);
+        let before = counting.snapshot();
+        v.extend(0..16);
+        let diff = counting.snapshot().diff(&before);
+        assert_eq!(diff.allocations + diff.reallocations, 0);
+        v.push(16);
+        let diff = counting.snapshot().diff(&before);
+        assert_eq!(diff.reallocations, 1);
+        assert_eq!(diff.live_bytes, 16 * 8);
+        drop(v);
+        let stats = counting.snapshot();
+        assert_eq!(stats.live_blocks, 0);
+        assert_eq!(stats.peak_bytes, 32 * 8);
+    }
+
+    #[test]
+    fn doubling_growth_is_logarithmic() {
+        let counting = Counting::new(Global);
+        let mut v: Vec<u8, _> = Vec::new_in(&counting);
+        for i in 0..1024u32 {
+            v.push(i as u8);
+        }
+        let stats = counting.snapshot();
+        // 8 -> 16 -> ... -> 1024.
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.reallocations, 7);
+        assert_eq!(stats.live_bytes, 1024);
+    }
+
+    #[test]
+    fn panicking_destructor_still_frees_buffer() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        let before = counting.snapshot();
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut v = Vec::new_in(&counting);
+            for i in 0..10 {
+                v.push(DropCounter::new(&drops, i == 3));
+            }
+            let mut it = v.into_iter();
+            it.next();
+        }));
+        assert!(r.is_err());
+        assert_eq!(drops.get(), 10);
+        let diff = counting.snapshot().diff(&before);
+        assert!(diff.is_balanced(), "{:?}", diff);
+        assert_eq!(diff.deallocations, 1);
+    }
+
+    #[test]
+    fn failures_are_counted() {
+        let counting = Counting::new(Budget::new(2));
+        assert!(counting.allocate(Layout::new::<u32>()).is_err());
+        let stats = counting.snapshot();
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.allocations, 0);
+    }
+
+    #[test]
+    fn shrink_and_reset_peak() {
+        let counting = Counting::new(Global);
+        let mut v: Vec<u32, _> = Vec::with_capacity_in(100, &counting);
+        v.push(1);
+        v.shrink_to_fit();
+        let stats = counting.snapshot();
+        assert_eq!(stats.live_bytes, 4);
+        assert_eq!(stats.peak_bytes, 400);
+        assert_eq!(stats.bytes_deallocated, 396);
+        counting.reset_peak();
+        assert_eq!(counting.snapshot().peak_bytes, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "`earlier` was taken after `self`")]
+    fn diff_in_the_wrong_order_panics() {
+        let counting = Counting::new(Global);
+        let before = counting.snapshot();
+        drop(Vec::<u8, _>::with_capacity_in(1, &counting));
+        let _ = before.diff(&counting.snapshot());
+    }
+}
@@ -0,0 +1,332 @@
+//! Memory allocation APIs.
+//!
+//! [`Allocator`] is a stable-Rust rendition of the still-unstable
+//! `core::alloc::Allocator`: every collection in the crate takes an
+//! allocator type parameter defaulting to [`Global`], so arenas, pools and
+//! instrumented allocators can be plugged in without nightly.
+
+use std::alloc as sys;
+use std::error::Error;
+use std::fmt;
+use std::ptr::{self, NonNull};
+
+mod bump;
+mod counting;
+mod pool;
+pub mod slab;
+
+pub use self::bump::{Bump, BumpScope};
+pub use self::counting::{AllocDiff, AllocStats, Counting};
+pub use self::pool::Pool;
+pub use self::slab::Slab;
+pub use std::alloc::{Layout, LayoutError};
+
+/// The error type returned when an allocator cannot satisfy a request,
+/// either because memory is exhausted or because the layout is unsupported.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl Error for AllocError {}
+
+/// An implementation of `Allocator` can allocate, grow, shrink and
+/// deallocate arbitrary blocks of memory described by a [`Layout`].
+///
+/// Zero-sized layouts are legal and are expected to return a dangling,
+/// suitably aligned pointer without touching the underlying memory source.
+///
+/// # Safety
+///
+/// Memory blocks returned by an allocator must point to valid memory and
+/// stay valid until deallocated through the same allocator (or a clone of
+/// it), and moving the allocator must not invalidate them.
+pub unsafe trait Allocator {
+    /// Attempts to allocate a block of memory fitting `layout`. The returned
+    /// slice may be larger than requested.
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+    /// Like [`allocate`](Allocator::allocate), but the memory is zeroed.
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let block = self.allocate(layout)?;
+        unsafe { ptr::write_bytes(block.as_ptr() as *mut u8, 0, block.len()) };
+        Ok(block)
+    }
+
+    /// Deallocates the memory referenced by `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block currently allocated by this allocator and
+    /// `layout` must fit that block.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Grows the block at `ptr` to fit `new_layout`, preserving its contents.
+    /// On error the old block is left untouched.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block currently allocated by this allocator,
+    /// `old_layout` must fit it, and `new_layout.size()` must be at least
+    /// `old_layout.size()`.
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+        let new = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr() as *mut u8, old_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new)
+    }
+
+    /// Like [`grow`](Allocator::grow), but the new bytes are zeroed.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`grow`](Allocator::grow).
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new = self.grow(ptr, old_layout, new_layout)?;
+        let base = new.as_ptr() as *mut u8;
+        ptr::write_bytes(
+            base.add(old_layout.size()),
+            0,
+            new.len() - old_layout.size(),
+        );
+        Ok(new)
+    }
+
+    /// Shrinks the block at `ptr` to fit `new_layout`, preserving the
+    /// leading `new_layout.size()` bytes. On error the old block is left
+    /// untouched.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block currently allocated by this allocator,
+    /// `old_layout` must fit it, and `new_layout.size()` must be at most
+    /// `old_layout.size()`.
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+        let new = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr() as *mut u8, new_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new)
+    }
+
+    /// Borrows this allocator, so that `&A` can be handed to a collection
+    /// while the caller keeps ownership.
+    fn by_ref(&self) -> &Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        (**self).allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        (**self).grow(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        (**self).grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        (**self).shrink(ptr, old_layout, new_layout)
+    }
+}
+
+/// The global memory allocator, forwarding to whatever `#[global_allocator]`
+/// the final binary registers. This is the default for every collection.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Global;
+
+/// Returns an aligned, non-null pointer for zero-sized blocks.
+pub(crate) fn dangling(layout: Layout) -> NonNull<u8> {
+    unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(layout.align())) }
+}
+
+fn block(ptr: *mut u8, size: usize) -> Result<NonNull<[u8]>, AllocError> {
+    NonNull::new(ptr)
+        .map(|p| NonNull::slice_from_raw_parts(p, size))
+        .ok_or(AllocError)
+}
+
+impl Global {
+    fn alloc_impl(&self, layout: Layout, zeroed: bool) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(NonNull::slice_from_raw_parts(dangling(layout), 0));
+        }
+        let raw = unsafe {
+            if zeroed {
+                sys::alloc_zeroed(layout)
+            } else {
+                sys::alloc(layout)
+            }
+        };
+        block(raw, layout.size())
+    }
+
+    unsafe fn grow_impl(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        zeroed: bool,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+        if old_layout.size() == 0 {
+            return self.alloc_impl(new_layout, zeroed);
+        }
+        if old_layout.align() != new_layout.align() {
+            let new = self.alloc_impl(new_layout, zeroed)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr() as *mut u8, old_layout.size());
+            self.deallocate(ptr, old_layout);
+            return Ok(new);
+        }
+        let raw = sys::realloc(ptr.as_ptr(), old_layout, new_layout.size());
+        let new = block(raw, new_layout.size())?;
+        if zeroed {
+            ptr::write_bytes(
+                raw.add(old_layout.size()),
+                0,
+                new_layout.size() - old_layout.size(),
+            );
+        }
+        Ok(new)
+    }
+}
+
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_impl(layout, false)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_impl(layout, true)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            sys::dealloc(ptr.as_ptr(), layout)
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.grow_impl(ptr, old_layout, new_layout, false)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.grow_impl(ptr, old_layout, new_layout, true)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+        if new_layout.size() == 0 {
+            self.deallocate(ptr, old_layout);
+            return Ok(NonNull::slice_from_raw_parts(dangling(new_layout), 0));
+        }
+        if old_layout.align() != new_layout.align() {
+            let new = self.alloc_impl(new_layout, false)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr() as *mut u8, new_layout.size());
+            self.deallocate(ptr, old_layout);
+            return Ok(new);
+        }
+        let raw = sys::realloc(ptr.as_ptr(), old_layout, new_layout.size());
+        block(raw, new_layout.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Allocator, Global, Layout};
+
+    #[test]
+    fn global_round_trip() {
+        let layout = Layout::from_size_align(24, 8).unwrap();
+        let block = Global.allocate_zeroed(layout).unwrap();
+        assert_eq!(block.len(), 24);
+        let ptr = block.as_ptr() as *mut u8;
+        unsafe {
+            assert!((0..24).all(|i| *ptr.add(i) == 0));
+            ptr.write_bytes(7, 24);
+            let bigger = Layout::from_size_align(64, 8).unwrap();
+            let grown = Global.grow_zeroed(block.cast(), layout, bigger).unwrap();
+            let p = grown.as_ptr() as *mut u8;
+            assert!((0..24).all(|i| *p.add(i) == 7));
+            assert!((24..64).all(|i| *p.add(i) == 0));
+            let smaller = Layout::from_size_align(8, 8).unwrap();
+            let shrunk = Global.shrink(grown.cast(), bigger, smaller).unwrap();
+            assert_eq!(*(shrunk.as_ptr() as *mut u8), 7);
+            Global.deallocate(shrunk.cast(), smaller);
+        }
+    }
+
+    #[test]
+    fn zero_sized_blocks_are_aligned() {
+        let layout = Layout::from_size_align(0, 64).unwrap();
+        let block = Global.allocate(layout).unwrap();
+        assert_eq!(block.len(), 0);
+        assert_eq!(block.as_ptr() as *mut u8 as usize % 64, 0);
+        unsafe { Global.deallocate(block.cast(), layout) };
+    }
+}
@@ -0,0 +1,313 @@
+//! A free-list pool allocator for fixed-size blocks.
+
+use super::{dangling, AllocError, Allocator, Global, Layout};
+use std::cell::Cell;
+use std::fmt;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+/// Blocks carved out of each chunk unless configured otherwise.
+const DEFAULT_BLOCKS_PER_CHUNK: usize = 64;
+
+/// Header at the start of every chunk, linking them for deallocation.
+struct ChunkHeader {
+    next: Option<NonNull<ChunkHeader>>,
+    layout: Layout,
+}
+
+/// A freed block holds the link to the next free block.
+struct FreeBlock {
+    next: Option<NonNull<FreeBlock>>,
+}
+
+/// An allocator handing out blocks of a single fixed layout.
+///
+/// Freed blocks go onto an intrusive free list and are reused before any
+/// new memory is requested, so a workload that keeps allocating and freeing
+/// nodes of one type settles into zero calls to the global allocator.
+/// Requests that do not fit the block layout fail with [`AllocError`]; a
+/// block can be grown or shrunk in place as long as it still fits.
+///
+/// All memory is returned to [`Global`] when the pool is dropped.
+pub struct Pool {
+    block: Layout,
+    blocks_per_chunk: usize,
+    free: Cell<Option<NonNull<FreeBlock>>>,
+    chunks: Cell<Option<NonNull<ChunkHeader>>>,
+    /// Unused tail of the newest chunk, carved lazily.
+    bump: Cell<*mut u8>,
+    bump_end: Cell<*mut u8>,
+    live: Cell<usize>,
+    capacity: Cell<usize>,
+}
+
+// The pool owns its chunks; `Cell` is what keeps it `!Sync`.
+unsafe impl Send for Pool {}
+
+impl Pool {
+    /// Creates a pool of blocks fitting `layout`. Blocks are padded to hold
+    /// a free-list link, so tiny layouts cost a pointer each.
+    pub fn new(layout: Layout) -> Self {
+        Self::with_blocks_per_chunk(layout, DEFAULT_BLOCKS_PER_CHUNK)
+    }
+
+    /// Creates a pool of blocks fitting one `T`.
+    pub fn for_type<T>() -> Self {
+        Self::new(Layout::new::<T>())
+    }
+
+    /// Creates a pool that requests `blocks_per_chunk` blocks at a time
+    /// from the global allocator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `blocks_per_chunk` is zero.
+    pub fn with_blocks_per_chunk(layout: Layout, blocks_per_chunk: usize) -> Self {
+        assert!(
+            blocks_per_chunk > 0,
+            "a pool chunk needs at least one block"
+        );
+        let block = Layout::from_size_align(
+            layout.size().max(mem::size_of::<FreeBlock>()),
+            layout.align().max(mem::align_of::<FreeBlock>()),
+        )
+        .expect("block layout overflows")
+        .pad_to_align();
+        Pool {
+            block,
+            blocks_per_chunk,
+            free: Cell::new(None),
+            chunks: Cell::new(None),
+            bump: Cell::new(ptr::null_mut()),
+            bump_end: Cell::new(ptr::null_mut()),
+            live: Cell::new(0),
+            capacity: Cell::new(0),
+        }
+    }
+
+    /// The layout of every block, after padding.
+    pub fn block_layout(&self) -> Layout {
+        self.block
+    }
+
+    /// Number of blocks currently handed out.
+    pub fn live_blocks(&self) -> usize {
+        self.live.get()
+    }
+
+    /// Number of blocks obtained from the global allocator so far.
+    pub fn capacity(&self) -> usize {
+        self.capacity.get()
+    }
+
+    fn fits(&self, layout: Layout) -> bool {
+        layout.size() <= self.block.size() && layout.align() <= self.block.align()
+    }
+
+    fn alloc_block(&self) -> Result<NonNull<u8>, AllocError> {
+        if let Some(block) = self.free.get() {
+            self.free.set(unsafe { (*block.as_ptr()).next });
+            self.live.set(self.live.get() + 1);
+            return Ok(block.cast());
+        }
+        if self.bump.get() == self.bump_end.get() {
+            self.push_chunk()?;
+        }
+        let block = self.bump.get();
+        unsafe { self.bump.set(block.add(self.block.size())) };
+        self.live.set(self.live.get() + 1);
+        Ok(unsafe { NonNull::new_unchecked(block) })
+    }
+
+    fn push_chunk(&self) -> Result<(), AllocError> {
+        let header = Layout::new::<ChunkHeader>();
+        let blocks = Layout::from_size_align(
+            self.block
+                .size()
+                .checked_mul(self.blocks_per_chunk)
+                .ok_or(AllocError)?,
+            self.block.align(),
+        )
+        .map_err(|_| AllocError)?;
+        let (layout, offset) = header.extend(blocks).map_err(|_| AllocError)?;
+        let chunk = Global.allocate(layout)?.cast::<ChunkHeader>();
+        unsafe {
+            chunk.as_ptr().write(ChunkHeader {
+                next: self.chunks.get(),
+                layout,
+            });
+            let start = (chunk.as_ptr() as *mut u8).add(offset);
+            self.bump.set(start);
+            self.bump_end.set(start.add(blocks.size()));
+        }
+        self.chunks.set(Some(chunk));
+        self.capacity
+            .set(self.capacity.get() + self.blocks_per_chunk);
+        Ok(())
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        let mut next = self.chunks.get();
+        while let Some(chunk) = next {
+            unsafe {
+                let ChunkHeader { next: n, layout } = chunk.as_ptr().read();
+                Global.deallocate(chunk.cast(), layout);
+                next = n;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("block", &self.block)
+            .field("live_blocks", &self.live_blocks())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+unsafe impl Allocator for Pool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(NonNull::slice_from_raw_parts(dangling(layout), 0));
+        }
+        if !self.fits(layout) {
+            return Err(AllocError);
+        }
+        let block = self.alloc_block()?;
+        Ok(NonNull::slice_from_raw_parts(block, self.block.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        let block = ptr.cast::<FreeBlock>();
+        block.as_ptr().write(FreeBlock {
+            next: self.free.get(),
+        });
+        self.free.set(Some(block));
+        self.live.set(self.live.get() - 1);
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+        if self.fits(new_layout) {
+            Ok(NonNull::slice_from_raw_parts(ptr, self.block.size()))
+        } else {
+            Err(AllocError)
+        }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if new_layout.size() == 0 {
+            self.deallocate(ptr, old_layout);
+            return Ok(NonNull::slice_from_raw_parts(dangling(new_layout), 0));
+        }
+        if self.fits(new_layout) {
+            Ok(NonNull::slice_from_raw_parts(ptr, self.block.size()))
+        } else {
+            Err(AllocError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pool;
+    use crate::alloc::{Allocator, Layout};
+    use crate::test_util::XorShift;
+    use crate::vec::Vec;
+    use std::ptr::NonNull;
+
+    #[test]
+    fn blocks_are_recycled_lifo() {
+        let pool = Pool::for_type::<[u64; 3]>();
+        let layout = Layout::new::<[u64; 3]>();
+        let a = pool.allocate(layout).unwrap();
+        let b = pool.allocate(layout).unwrap();
+        assert_ne!(a, b);
+        unsafe {
+            pool.deallocate(a.cast(), layout);
+            pool.deallocate(b.cast(), layout);
+        }
+        assert_eq!(pool.allocate(layout).unwrap(), b);
+        assert_eq!(pool.allocate(layout).unwrap(), a);
+        assert_eq!(pool.live_blocks(), 2);
+        assert_eq!(pool.capacity(), 64);
+    }
+
+    #[test]
+    fn rejects_layouts_that_do_not_fit() {
+        let pool = Pool::new(Layout::from_size_align(16, 8).unwrap());
+        assert!(pool.allocate(Layout::new::<[u8; 17]>()).is_err());
+        assert!(pool
+            .allocate(Layout::from_size_align(8, 16).unwrap())
+            .is_err());
+        assert!(pool.allocate(Layout::new::<u16>()).is_ok());
+        assert_eq!(pool.live_blocks(), 1);
+    }
+
+    #[test]
+    fn tiny_blocks_hold_a_link() {
+        let pool = Pool::for_type::<u8>();
+        assert!(pool.block_layout().size() >= std::mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn vec_grows_in_place_up_to_block_size() {
+        let pool = Pool::for_type::<[u32; 8]>();
+        let mut v: Vec<u32, _> = Vec::new_in(&pool);
+        v.extend(0..8);
+        assert_eq!(pool.live_blocks(), 1);
+        assert!(v.try_reserve(1).is_err());
+        drop(v);
+        assert_eq!(pool.live_blocks(), 0);
+    }
+
+    #[test]
+    fn random_interleavings_never_overlap() {
+        let mut rng = XorShift::new(0x9001);
+        let pool = Pool::with_blocks_per_chunk(Layout::new::<[u64; 2]>(), 7);
+        let layout = Layout::new::<[u64; 2]>();
+        let mut live: std::vec::Vec<(NonNull<[u64; 2]>, u64)> = std::vec::Vec::new();
+        for step in 0..20_000u64 {
+            if rng.below(5) < 3 || live.is_empty() {
+                let p = pool.allocate(layout).unwrap().cast::<[u64; 2]>();
+                unsafe { p.as_ptr().write([step, !step]) };
+                live.push((p, step));
+            } else {
+                let (p, tag) = live.swap_remove(rng.below(live.len()));
+                unsafe {
+                    assert_eq!(*p.as_ptr(), [tag, !tag]);
+                    pool.deallocate(p.cast(), layout);
+                }
+            }
+            assert_eq!(pool.live_blocks(), live.len());
+        }
+        for (p, tag) in live.drain(..) {
+            unsafe {
+                assert_eq!(*p.as_ptr(), [tag, !tag]);
+                pool.deallocate(p.cast(), layout);
+            }
+        }
+        assert_eq!(pool.live_blocks(), 0);
+        assert_eq!(pool.capacity() % 7, 0);
+    }
+}
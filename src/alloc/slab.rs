@@ -0,0 +1,516 @@
+//! A typed slab: pre-allocated storage for values of one type, addressed
+//! by stable `usize` keys.
+
+use super::{Allocator, Global};
+use crate::raw_vec::TryReserveError;
+use crate::vec::Vec;
+use std::fmt;
+use std::iter::{Enumerate, FusedIterator};
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+enum Entry<T> {
+    Occupied(T),
+    /// Index of the next vacant slot; `entries.len()` ends the free list.
+    Vacant(usize),
+}
+
+/// Storage for values of type `T` handing out stable `usize` keys.
+///
+/// Removed slots are threaded onto a free list and reused by later inserts,
+/// so keys stay small and no value ever moves while it is stored.
+///
+/// ```
+/// use mystdrs::alloc::Slab;
+///
+/// let mut slab = Slab::new();
+/// let a = slab.insert("a");
+/// let b = slab.insert("b");
+/// assert_eq!(slab[a], "a");
+/// assert_eq!(slab.remove(a), "a");
+/// assert_eq!(slab.insert("c"), a);
+/// assert_eq!(slab.get(b), Some(&"b"));
+/// ```
+pub struct Slab<T, A: Allocator = Global> {
+    entries: Vec<Entry<T>, A>,
+    len: usize,
+    next: usize,
+}
+
+impl<T> Slab<T> {
+    /// Creates an empty slab without allocating.
+    pub const fn new() -> Self {
+        Slab::new_in(Global)
+    }
+
+    /// Creates an empty slab with room for `capacity` values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Slab::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> Slab<T, A> {
+    /// Creates an empty slab in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        Slab {
+            entries: Vec::new_in(alloc),
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Creates an empty slab in `alloc` with room for `capacity` values.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Slab {
+            entries: Vec::with_capacity_in(capacity, alloc),
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Returns the number of values the slab can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the slab stores no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reserves room for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        let vacant = self.entries.len() - self.len;
+        if additional > vacant {
+            self.entries.reserve(additional - vacant);
+        }
+    }
+
+    /// Fallible version of [`reserve`](Slab::reserve).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let vacant = self.entries.len() - self.len;
+        if additional > vacant {
+            self.entries.try_reserve(additional - vacant)?;
+        }
+        Ok(())
+    }
+
+    /// Removes every value, keeping the allocated storage.
+    ///
+    /// If a value's destructor panics, the slab is still left empty.
+    pub fn clear(&mut self) {
+        // Reset the bookkeeping first so that it matches the emptied
+        // `entries` even if a destructor unwinds out of `clear`.
+        self.len = 0;
+        self.next = 0;
+        self.entries.clear();
+    }
+
+    /// Returns a reference to the value at `key`, if occupied.
+    pub fn get(&self, key: usize) -> Option<&T> {
+        match self.entries.get(key) {
+            Some(Entry::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value at `key`, if occupied.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        match self.entries.get_mut(key) {
+            Some(Entry::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `key` refers to a stored value.
+    pub fn contains(&self, key: usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Stores `value` and returns its key.
+    pub fn insert(&mut self, value: T) -> usize {
+        let key = self.next;
+        self.insert_at(key, value);
+        key
+    }
+
+    /// Fallible version of [`insert`](Slab::insert): returns an error
+    /// instead of aborting if the slab cannot grow. On error `value` is
+    /// dropped and the slab is unchanged.
+    pub fn try_insert(&mut self, value: T) -> Result<usize, TryReserveError> {
+        self.try_reserve(1)?;
+        Ok(self.insert(value))
+    }
+
+    /// Returns a handle to the slot the next insert will use, so the value
+    /// can be built knowing its own key.
+    pub fn vacant_entry(&mut self) -> VacantEntry<'_, T, A> {
+        VacantEntry {
+            key: self.next,
+            slab: self,
+        }
+    }
+
+    /// Removes and returns the value at `key`, or `None` if the slot is
+    /// vacant or out of range.
+    pub fn try_remove(&mut self, key: usize) -> Option<T> {
+        let entry = self.entries.get_mut(key)?;
+        if let Entry::Vacant(_) = entry {
+            return None;
+        }
+        match mem::replace(entry, Entry::Vacant(self.next)) {
+            Entry::Occupied(value) => {
+                self.len -= 1;
+                self.next = key;
+                Some(value)
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+    }
+
+    /// Removes and returns the value at `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` does not refer to a stored value.
+    pub fn remove(&mut self, key: usize) -> T {
+        self.try_remove(key).expect("invalid slab key")
+    }
+
+    /// Keeps only the values for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut T) -> bool,
+    {
+        for key in 0..self.entries.len() {
+            let keep = match &mut self.entries[key] {
+                Entry::Occupied(value) => f(key, value),
+                Entry::Vacant(_) => true,
+            };
+            if !keep {
+                self.remove(key);
+            }
+        }
+    }
+
+    /// Iterates over `(key, &value)` pairs in key order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: self.entries.iter().enumerate(),
+            len: self.len,
+        }
+    }
+
+    /// Iterates over `(key, &mut value)` pairs in key order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.iter_mut().enumerate(),
+            len: self.len,
+        }
+    }
+
+    fn insert_at(&mut self, key: usize, value: T) {
+        if key == self.entries.len() {
+            self.entries.push(Entry::Occupied(value));
+            self.next = key + 1;
+        } else {
+            self.next = match mem::replace(&mut self.entries[key], Entry::Occupied(value)) {
+                Entry::Vacant(next) => next,
+                Entry::Occupied(_) => unreachable!("free list points at an occupied slot"),
+            };
+        }
+        self.len += 1;
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Slab::new()
+    }
+}
+
+impl<T, A: Allocator> Index<usize> for Slab<T, A> {
+    type Output = T;
+
+    fn index(&self, key: usize) -> &T {
+        self.get(key).expect("invalid slab key")
+    }
+}
+
+impl<T, A: Allocator> IndexMut<usize> for Slab<T, A> {
+    fn index_mut(&mut self, key: usize) -> &mut T {
+        self.get_mut(key).expect("invalid slab key")
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Slab<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a Slab<T, A> {
+    type Item = (usize, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut Slab<T, A> {
+    type Item = (usize, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// A handle to a vacant slot, created by [`Slab::vacant_entry`].
+pub struct VacantEntry<'a, T, A: Allocator = Global> {
+    slab: &'a mut Slab<T, A>,
+    key: usize,
+}
+
+impl<'a, T, A: Allocator> VacantEntry<'a, T, A> {
+    /// The key the value will be stored under.
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Stores `value` in the slot and returns a reference to it.
+    pub fn insert(self, value: T) -> &'a mut T {
+        self.slab.insert_at(self.key, value);
+        match &mut self.slab.entries[self.key] {
+            Entry::Occupied(value) => value,
+            Entry::Vacant(_) => unreachable!(),
+        }
+    }
+}
+
+/// An iterator over the occupied slots of a [`Slab`].
+pub struct Iter<'a, T> {
+    entries: Enumerate<slice::Iter<'a, Entry<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, entry) in &mut self.entries {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some((key, entry)) = self.entries.next_back() {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// A mutable iterator over the occupied slots of a [`Slab`].
+pub struct IterMut<'a, T> {
+    entries: Enumerate<slice::IterMut<'a, Entry<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (usize, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, entry) in &mut self.entries {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some((key, entry)) = self.entries.next_back() {
+            if let Entry::Occupied(value) = entry {
+                self.len -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Slab;
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn keys_are_reused_lifo() {
+        let mut slab = Slab::new();
+        let keys: std::vec::Vec<usize> = (0..5).map(|i| slab.insert(i)).collect();
+        assert_eq!(keys, [0, 1, 2, 3, 4]);
+        slab.remove(1);
+        slab.remove(3);
+        assert_eq!(slab.insert(10), 3);
+        assert_eq!(slab.insert(11), 1);
+        assert_eq!(slab.insert(12), 5);
+        assert_eq!(slab.len(), 6);
+    }
+
+    #[test]
+    fn vacant_entry_knows_its_key() {
+        let mut slab = Slab::new();
+        slab.insert((0, 0));
+        let entry = slab.vacant_entry();
+        let key = entry.key();
+        let value = entry.insert((key, 7));
+        value.1 += 1;
+        assert_eq!(slab[key], (1, 8));
+    }
+
+    #[test]
+    fn try_remove_rejects_vacant_and_out_of_range() {
+        let mut slab = Slab::new();
+        let a = slab.insert('a');
+        assert_eq!(slab.try_remove(a), Some('a'));
+        assert_eq!(slab.try_remove(a), None);
+        assert_eq!(slab.try_remove(100), None);
+        assert!(slab.is_empty());
+    }
+
+    #[test]
+    fn panic_in_value_drop_during_clear() {
+        let counter = Rc::new(Cell::new(0));
+        let mut slab = Slab::new();
+        for i in 0..6 {
+            slab.insert(DropCounter::new(&counter, i == 2));
+        }
+        slab.remove(4);
+        let r = panic::catch_unwind(AssertUnwindSafe(|| slab.clear()));
+        assert!(r.is_err());
+        assert_eq!(counter.get(), 6);
+        assert_eq!(slab.len(), 0);
+        assert!(slab.iter().next().is_none());
+        assert_eq!(slab.insert(DropCounter::new(&counter, false)), 0);
+        assert_eq!(slab.insert(DropCounter::new(&counter, false)), 1);
+        assert_eq!(slab.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid slab key")]
+    fn index_vacant_panics() {
+        let mut slab = Slab::new();
+        let a = slab.insert(1);
+        slab.remove(a);
+        let _ = slab[a];
+    }
+
+    #[test]
+    fn retain_and_iterate() {
+        let mut slab = Slab::new();
+        for i in 0..10 {
+            slab.insert(i * 10);
+        }
+        slab.retain(|key, value| {
+            *value += 1;
+            key % 3 != 0
+        });
+        let items: std::vec::Vec<(usize, i32)> = slab.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(
+            items,
+            [(1, 11), (2, 21), (4, 41), (5, 51), (7, 71), (8, 81)]
+        );
+        assert_eq!(slab.iter().next_back(), Some((8, &81)));
+        assert_eq!(slab.iter().len(), 6);
+        for (_, v) in &mut slab {
+            *v = -*v;
+        }
+        assert_eq!(slab[5], -51);
+    }
+
+    #[test]
+    fn random_interleavings_match_model() {
+        let mut rng = XorShift::new(0x51ab);
+        for _ in 0..50 {
+            let mut slab = Slab::new();
+            let mut model = BTreeMap::new();
+            for step in 0..2000u64 {
+                if rng.below(3) != 0 || model.is_empty() {
+                    let key = slab.insert(step);
+                    assert!(model.insert(key, step).is_none(), "key {} reused", key);
+                } else {
+                    let nth = rng.below(model.len());
+                    let key = *model.keys().nth(nth).unwrap();
+                    assert_eq!(slab.remove(key), model.remove(&key).unwrap());
+                }
+                if rng.below(16) == 0 {
+                    let key = rng.below(slab.capacity() + 1);
+                    assert_eq!(slab.get(key), model.get(&key));
+                }
+                assert_eq!(slab.len(), model.len());
+            }
+            assert!(slab.iter().map(|(k, v)| (k, *v)).eq(model.into_iter()));
+            // Keys never exceed the high-water mark of live values.
+            assert!(slab.capacity() < 2000);
+        }
+    }
+
+    #[test]
+    fn try_insert_reports_failure() {
+        let budget = Budget::new(0);
+        let mut slab = Slab::new_in(&budget);
+        assert!(slab.try_insert(0u64).is_err());
+        assert!(slab.is_empty());
+        budget.set(usize::MAX);
+        slab.try_reserve(2).unwrap();
+        budget.set(0);
+        let cap = slab.capacity();
+        for i in 0..cap {
+            assert_eq!(slab.try_insert(i as u64), Ok(i));
+        }
+        assert!(slab.try_insert(99).is_err());
+        slab.remove(0);
+        // A vacant slot needs no allocation.
+        assert_eq!(slab.try_insert(13), Ok(0));
+        assert_eq!(slab.len(), cap);
+        assert!(slab.try_reserve(1).is_err());
+    }
+}
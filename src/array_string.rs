@@ -0,0 +1,755 @@
+//! A string with a fixed capacity stored inline, `ArrayString<N>`.
+//!
+//! The UTF-8 bytes live in an [`ArrayVec<u8, N>`](ArrayVec), so an
+//! `ArrayString` never allocates. As with `ArrayVec`, the `try_*` methods
+//! return [`CapacityError`] when the text does not fit and the plain ones
+//! panic; capacity is counted in bytes, not characters.
+
+pub use crate::array_vec::CapacityError;
+
+use crate::array_vec::{self, ArrayVec};
+use crate::hash::{Hash, Hasher};
+use std::borrow::Borrow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::{Deref, DerefMut, Range, RangeBounds};
+use std::ptr;
+use std::str;
+
+/// A UTF-8 string holding up to `N` bytes inline.
+///
+/// ```
+/// use mystdrs::array_string::ArrayString;
+/// use std::fmt::Write;
+///
+/// let mut s: ArrayString<8> = ArrayString::new();
+/// s.push_str("héllo");
+/// assert_eq!(s.len(), 6);
+/// assert!(s.try_push_str("!!!").is_err());
+/// s.push('!');
+/// assert_eq!(s, "héllo!");
+/// assert!(write!(s, "{}", 42).is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct ArrayString<const N: usize> {
+    vec: ArrayVec<u8, N>,
+}
+
+impl<const N: usize> ArrayString<N> {
+    /// Creates an empty string.
+    pub const fn new() -> Self {
+        ArrayString {
+            vec: ArrayVec::new(),
+        }
+    }
+
+    /// Returns the number of bytes the string can hold, which is `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the length of the string in bytes.
+    pub const fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Returns `true` if the string is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Returns `true` if the string holds `N` bytes.
+    pub const fn is_full(&self) -> bool {
+        self.vec.is_full()
+    }
+
+    /// Returns how many more bytes fit.
+    pub const fn remaining_capacity(&self) -> usize {
+        self.vec.remaining_capacity()
+    }
+
+    /// Extracts a string slice containing the whole string.
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.vec) }
+    }
+
+    /// Extracts a mutable string slice containing the whole string.
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { str::from_utf8_unchecked_mut(&mut self.vec) }
+    }
+
+    /// Appends `ch` to the end of the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it does not fit.
+    pub fn push(&mut self, ch: char) {
+        if self.try_push(ch).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Appends `ch`, or hands it back in the error if it does not fit.
+    pub fn try_push(&mut self, ch: char) -> Result<(), CapacityError<char>> {
+        let mut buf = [0; 4];
+        let bytes = ch.encode_utf8(&mut buf).as_bytes();
+        self.vec
+            .try_extend_from_slice(bytes)
+            .map_err(|_| CapacityError::new(ch))
+    }
+
+    /// Appends `s` to the end of the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it does not fit. Nothing is appended in that case.
+    pub fn push_str(&mut self, s: &str) {
+        if self.try_push_str(s).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Appends `s`, or returns it in the error and appends nothing if it
+    /// does not fit.
+    pub fn try_push_str<'a>(&mut self, s: &'a str) -> Result<(), CapacityError<&'a str>> {
+        self.vec
+            .try_extend_from_slice(s.as_bytes())
+            .map_err(|_| CapacityError::new(s))
+    }
+
+    /// Removes the last character and returns it, or `None` if the string
+    /// is empty.
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.chars().next_back()?;
+        let new_len = self.len() - ch.len_utf8();
+        unsafe { self.vec.set_len(new_len) };
+        Some(ch)
+    }
+
+    /// Shortens the string to `new_len` bytes. Has no effect if `new_len`
+    /// is greater than the current length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            assert!(
+                self.is_char_boundary(new_len),
+                "new length is not on a char boundary"
+            );
+            self.vec.truncate(new_len);
+        }
+    }
+
+    /// Empties the string.
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+
+    /// Inserts `ch` at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary, or if
+    /// `ch` does not fit.
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        if self.try_insert(idx, ch).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Inserts `ch` at byte position `idx`, or hands it back in the error
+    /// if it does not fit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary.
+    pub fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), CapacityError<char>> {
+        assert!(
+            self.is_char_boundary(idx),
+            "index is not on a char boundary"
+        );
+        self.try_insert_bytes(idx, ch.encode_utf8(&mut [0; 4]).as_bytes())
+            .map_err(|_| CapacityError::new(ch))
+    }
+
+    /// Inserts `s` at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary, or if
+    /// `s` does not fit. Nothing is inserted in that case.
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        if self.try_insert_str(idx, s).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Inserts `s` at byte position `idx`, or returns it in the error and
+    /// inserts nothing if it does not fit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary.
+    pub fn try_insert_str<'a>(
+        &mut self,
+        idx: usize,
+        s: &'a str,
+    ) -> Result<(), CapacityError<&'a str>> {
+        assert!(
+            self.is_char_boundary(idx),
+            "index is not on a char boundary"
+        );
+        self.try_insert_bytes(idx, s.as_bytes())
+            .map_err(|_| CapacityError::new(s))
+    }
+
+    fn try_insert_bytes(&mut self, idx: usize, bytes: &[u8]) -> Result<(), CapacityError> {
+        if bytes.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+        let len = self.len();
+        unsafe {
+            let p = self.vec.as_mut_ptr().add(idx);
+            ptr::copy(p, p.add(bytes.len()), len - idx);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), p, bytes.len());
+            self.vec.set_len(len + bytes.len());
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the character at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not the start of a character in the string.
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = match self[idx..].chars().next() {
+            Some(ch) => ch,
+            None => panic!("cannot remove a char from the end of a string"),
+        };
+        let next = idx + ch.len_utf8();
+        let len = self.len();
+        unsafe {
+            let p = self.vec.as_mut_ptr();
+            ptr::copy(p.add(next), p.add(idx), len - next);
+            self.vec.set_len(len - (next - idx));
+        }
+        ch
+    }
+
+    /// Keeps only the characters for which `f` returns `true`.
+    ///
+    /// If `f` panics, the string is left holding the characters kept so
+    /// far.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> bool,
+    {
+        struct Guard<'a, const N: usize> {
+            vec: &'a mut ArrayVec<u8, N>,
+            kept: usize,
+        }
+
+        impl<const N: usize> Drop for Guard<'_, N> {
+            fn drop(&mut self) {
+                unsafe { self.vec.set_len(self.kept) };
+            }
+        }
+
+        let len = self.len();
+        // Hide everything from the string while bytes are shuffled, since
+        // the stretch between kept and unvisited bytes may not be UTF-8.
+        unsafe { self.vec.set_len(0) };
+        let mut g = Guard {
+            vec: &mut self.vec,
+            kept: 0,
+        };
+        let mut idx = 0;
+        while idx < len {
+            let base = g.vec.as_mut_ptr();
+            let ch = unsafe {
+                let rest = std::slice::from_raw_parts(base.add(idx), len - idx);
+                str::from_utf8_unchecked(rest).chars().next().unwrap()
+            };
+            let ch_len = ch.len_utf8();
+            if f(ch) {
+                unsafe { ptr::copy(base.add(idx), base.add(g.kept), ch_len) };
+                g.kept += ch_len;
+            }
+            idx += ch_len;
+        }
+    }
+
+    /// Removes the bytes in `range` and returns their characters as an
+    /// iterator. The range is removed even if the iterator is not
+    /// consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or either end does not lie on
+    /// a char boundary.
+    ///
+    /// ```
+    /// use mystdrs::array_string::ArrayString;
+    /// use std::convert::TryFrom;
+    ///
+    /// let mut s = ArrayString::<16>::try_from("α is alpha").unwrap();
+    /// assert!(s.drain(..s.find(' ').unwrap()).eq(['α']));
+    /// assert_eq!(s, " is alpha");
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, N>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = self.char_range(range);
+        Drain {
+            inner: self.vec.drain(range),
+        }
+    }
+
+    /// Replaces the bytes in `range` with `replace_with`, which need not be
+    /// the same length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or either end does not lie on
+    /// a char boundary, or if the result does not fit. Nothing is replaced
+    /// in that case.
+    pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        if self.try_replace_range(range, replace_with).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Replaces the bytes in `range` with `replace_with`, or returns it in
+    /// the error and replaces nothing if the result does not fit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or either end does not lie on
+    /// a char boundary.
+    pub fn try_replace_range<'a, R>(
+        &mut self,
+        range: R,
+        replace_with: &'a str,
+    ) -> Result<(), CapacityError<&'a str>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = self.char_range(range);
+        if replace_with.len() > self.remaining_capacity() + range.len() {
+            return Err(CapacityError::new(replace_with));
+        }
+        // `Bytes` knows its exact length, so the splice never has to
+        // buffer the surplus.
+        self.vec.splice(range, replace_with.bytes());
+        Ok(())
+    }
+
+    /// Splits the string in two at byte position `at`, returning the bytes
+    /// from `at` on in a new string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is out of bounds or not on a char boundary.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(self.is_char_boundary(at), "index is not on a char boundary");
+        let mut other = ArrayString::new();
+        other.vec.extend(self.vec.drain(at..));
+        other
+    }
+
+    /// Checks `range` against the length and the char boundaries.
+    #[track_caller]
+    fn char_range<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let range = crate::slice::range(range, self.len());
+        assert!(
+            self.is_char_boundary(range.start),
+            "start of range should be a character boundary"
+        );
+        assert!(
+            self.is_char_boundary(range.end),
+            "end of range should be a character boundary"
+        );
+        range
+    }
+}
+
+#[cold]
+#[track_caller]
+fn capacity_exceeded() -> ! {
+    panic!("ArrayString capacity exceeded");
+}
+
+impl<const N: usize> Deref for ArrayString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> DerefMut for ArrayString<N> {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Write for ArrayString<N> {
+    /// Appends `s`, or fails and appends nothing if it does not fit.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_push_str(s).map_err(|_| fmt::Error)
+    }
+
+    fn write_char(&mut self, ch: char) -> fmt::Result {
+        self.try_push(ch).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<ArrayString<M>> for ArrayString<N> {
+    fn eq(&self, other: &ArrayString<M>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> PartialEq<ArrayString<N>> for str {
+    fn eq(&self, other: &ArrayString<N>) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<ArrayString<N>> for &str {
+    fn eq(&self, other: &ArrayString<N>) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for ArrayString<N> {}
+
+impl<const N: usize> PartialOrd for ArrayString<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for ArrayString<N> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> Hash for ArrayString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl<const N: usize> Borrow<str> for ArrayString<N> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for ArrayString<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for ArrayString<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a str> for ArrayString<N> {
+    type Error = CapacityError<&'a str>;
+
+    fn try_from(s: &'a str) -> Result<Self, CapacityError<&'a str>> {
+        let mut string = ArrayString::new();
+        string.try_push_str(s)?;
+        Ok(string)
+    }
+}
+
+impl<const N: usize> Extend<char> for ArrayString<N> {
+    /// Appends every character of `iter`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string fills up before `iter` runs out.
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        for ch in iter {
+            self.push(ch);
+        }
+    }
+}
+
+impl<'a, const N: usize> Extend<&'a str> for ArrayString<N> {
+    /// Appends every string of `iter`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string fills up before `iter` runs out.
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(s);
+        }
+    }
+}
+
+/// A draining iterator over the characters of an [`ArrayString`], created
+/// by [`ArrayString::drain`].
+pub struct Drain<'a, const N: usize> {
+    /// Drains whole characters at a time, so the remaining bytes stay
+    /// UTF-8.
+    inner: array_vec::Drain<'a, u8, N>,
+}
+
+impl<const N: usize> Drain<'_, N> {
+    /// Returns the remaining characters as a string slice.
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.inner.as_slice()) }
+    }
+}
+
+impl<const N: usize> Iterator for Drain<'_, N> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.inner.nth(ch.len_utf8() - 1);
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.inner.len();
+        (n.div_ceil(4), Some(n))
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for Drain<'_, N> {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.inner.nth_back(ch.len_utf8() - 1);
+        Some(ch)
+    }
+}
+
+impl<const N: usize> FusedIterator for Drain<'_, N> {}
+
+impl<const N: usize> fmt::Debug for Drain<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_str()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrayString, CapacityError};
+    use crate::test_util::XorShift;
+    use std::convert::TryFrom;
+    use std::fmt::Write;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn push_and_pop_multibyte() {
+        let mut s: ArrayString<6> = ArrayString::new();
+        s.push('a');
+        s.push('é');
+        s.push('€');
+        assert_eq!(s, "aé€");
+        assert_eq!(s.try_push('😀'), Err(CapacityError::new('😀')));
+        assert_eq!(s.remaining_capacity(), 0);
+        assert_eq!(s.pop(), Some('€'));
+        assert_eq!(s.pop(), Some('é'));
+        assert_eq!(s.len(), 1);
+        s.clear();
+        assert_eq!(s.pop(), None);
+    }
+
+    #[test]
+    fn push_str_is_all_or_nothing() {
+        let mut s: ArrayString<5> = ArrayString::new();
+        s.push_str("ab");
+        assert_eq!(s.try_push_str("cdef").unwrap_err().element(), "cdef");
+        assert_eq!(s, "ab");
+        assert!(s.try_push_str("cde").is_ok());
+        assert!(s.is_full());
+        assert!(ArrayString::<2>::try_from("abc").is_err());
+        assert_eq!(ArrayString::<3>::try_from("abc").unwrap(), "abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "ArrayString capacity exceeded")]
+    fn push_str_when_full() {
+        let mut s: ArrayString<2> = ArrayString::new();
+        s.push_str("abc");
+    }
+
+    #[test]
+    fn insert_remove_truncate() {
+        let mut s: ArrayString<16> = ArrayString::try_from("hllo").unwrap();
+        s.insert(1, 'é');
+        s.insert(0, '¡');
+        assert_eq!(s, "¡héllo");
+        assert_eq!(s.remove(3), 'é');
+        assert_eq!(s.remove(0), '¡');
+        assert_eq!(s, "hllo");
+        s.truncate(2);
+        assert_eq!(s, "hl");
+        s.truncate(10);
+        assert_eq!(s, "hl");
+        let mut tight: ArrayString<2> = ArrayString::try_from("a").unwrap();
+        assert!(tight.try_insert(0, 'é').is_err());
+        assert_eq!(tight, "a");
+    }
+
+    #[test]
+    #[should_panic(expected = "new length is not on a char boundary")]
+    fn truncate_inside_a_char() {
+        let mut s: ArrayString<4> = ArrayString::try_from("é").unwrap();
+        s.truncate(1);
+    }
+
+    #[test]
+    fn retain_keeps_valid_utf8() {
+        let mut s: ArrayString<32> = ArrayString::try_from("a€b😀cé").unwrap();
+        s.retain(|c| !c.is_ascii());
+        assert_eq!(s, "€😀é");
+
+        let mut s: ArrayString<32> = ArrayString::try_from("aé€b😀").unwrap();
+        let mut seen = 0;
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            s.retain(|c| {
+                seen += 1;
+                assert!(seen < 4);
+                c != 'a'
+            })
+        }));
+        assert!(r.is_err());
+        assert_eq!(s, "é€");
+    }
+
+    /// Picks a char boundary of `s` uniformly among all of them.
+    fn boundary(rng: &mut XorShift, s: &str) -> usize {
+        let boundaries: Vec<usize> = (0..=s.len()).filter(|&i| s.is_char_boundary(i)).collect();
+        boundaries[rng.below(boundaries.len())]
+    }
+
+    #[test]
+    fn range_methods_match_std() {
+        let mut rng = XorShift::new(0xa57);
+        let mut ours: ArrayString<24> = ArrayString::new();
+        let mut model = String::new();
+        for _ in 0..5000 {
+            let a = boundary(&mut rng, &model);
+            let b = a + boundary(&mut rng, &model[a..]);
+            let text: String = (0..rng.below(4)).map(|_| rng.char()).collect();
+            match rng.below(4) {
+                0 => {
+                    let fits = model.len() + text.len() <= 24;
+                    assert_eq!(ours.try_insert_str(a, &text).is_ok(), fits);
+                    if fits {
+                        model.insert_str(a, &text);
+                    }
+                }
+                1 => {
+                    let mut drain = ours.drain(a..b);
+                    let mut model_drain = model.drain(a..b);
+                    assert_eq!(drain.next_back(), model_drain.next_back());
+                    assert_eq!(drain.as_str(), model_drain.as_str());
+                    assert_eq!(drain.next(), model_drain.next());
+                }
+                2 => {
+                    let fits = model.len() - (b - a) + text.len() <= 24;
+                    assert_eq!(ours.try_replace_range(a..b, &text).is_ok(), fits);
+                    if fits {
+                        model.replace_range(a..b, &text);
+                    }
+                }
+                _ => {
+                    let tail = ours.split_off(a);
+                    assert_eq!(tail, model.split_off(a).as_str());
+                    if rng.below(2) == 0 {
+                        ours.push_str(&tail);
+                        model.push_str(&tail);
+                    }
+                }
+            }
+            assert_eq!(ours, model.as_str());
+        }
+    }
+
+    #[test]
+    fn range_methods_check_their_arguments() {
+        let mut s: ArrayString<4> = ArrayString::try_from("aé").unwrap();
+        assert_eq!(s.try_insert_str(1, "bc").unwrap_err().element(), "bc");
+        assert_eq!(
+            s.try_replace_range(..1, "xyz").unwrap_err().element(),
+            "xyz"
+        );
+        assert_eq!(s, "aé");
+        s.replace_range(1.., "xyz");
+        assert_eq!(s, "axyz");
+        assert!(panic::catch_unwind(|| {
+            let mut s: ArrayString<4> = ArrayString::try_from("é").unwrap();
+            s.drain(1..);
+        })
+        .is_err());
+        assert!(panic::catch_unwind(|| {
+            let mut s: ArrayString<4> = ArrayString::try_from("é").unwrap();
+            s.split_off(1);
+        })
+        .is_err());
+        let r = panic::catch_unwind(AssertUnwindSafe(|| s.insert_str(0, "!")));
+        assert!(r.is_err());
+        assert_eq!(s, "axyz");
+    }
+
+    #[test]
+    fn formatting_and_traits() {
+        let mut s: ArrayString<8> = ArrayString::new();
+        write!(s, "{}-{}", 12, 34).unwrap();
+        assert_eq!(s, "12-34");
+        assert!(write!(s, "{}", 5678).is_err());
+        assert_eq!(format!("{} {:?}", s, s), "12-34 \"12-34\"");
+        let other: ArrayString<16> = ArrayString::try_from("12-34").unwrap();
+        assert_eq!(s, other);
+        assert!("12-34" == s);
+        assert!(s < ArrayString::<8>::try_from("2").unwrap());
+        s.extend("ab".chars());
+        assert_eq!(&*s, "12-34ab");
+        assert_eq!(s.to_uppercase(), "12-34AB");
+    }
+}
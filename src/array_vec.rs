@@ -0,0 +1,963 @@
+//! A vector with a fixed capacity stored inline, `ArrayVec<T, N>`.
+//!
+//! The elements live in a `[MaybeUninit<T>; N]` inside the value itself,
+//! so an `ArrayVec` never allocates and can sit on the stack. Every method
+//! that adds elements has a `try_*` form returning [`CapacityError`] once
+//! the `N` slots are used up; the plain forms panic instead. The exception
+//! is `splice`, whose replacement is only counted as it is written, so it
+//! always panics on overflow. Element shifting is shared with
+//! [`Vec`](crate::vec::Vec) through [`crate::slice`], and both dereference
+//! to a slice for everything else.
+
+use crate::hash::{Hash, Hasher};
+use crate::slice::{Buffer, RawDrain, RawSplice};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut, RangeBounds};
+use std::ptr;
+use std::slice;
+
+/// The error returned when an element does not fit in an [`ArrayVec`] or
+/// an [`ArrayString`](crate::array_string::ArrayString). It hands back the
+/// element that was not added.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T = ()> {
+    element: T,
+}
+
+impl<T> CapacityError<T> {
+    /// Creates an error holding `element`.
+    pub const fn new(element: T) -> Self {
+        CapacityError { element }
+    }
+
+    /// Returns the element that did not fit.
+    pub fn element(self) -> T {
+        self.element
+    }
+
+    /// Drops the element, leaving an error that can outlive it.
+    pub fn simplify(self) -> CapacityError {
+        CapacityError { element: () }
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CapacityError: {}", self)
+    }
+}
+
+impl<T> Error for CapacityError<T> {}
+
+/// A vector holding up to `N` elements inline.
+///
+/// ```
+/// use mystdrs::array_vec::ArrayVec;
+///
+/// let mut v: ArrayVec<u32, 3> = ArrayVec::new();
+/// v.push(1);
+/// v.extend_from_slice(&[2, 3]);
+/// assert!(v.is_full());
+/// assert_eq!(v.try_push(4).unwrap_err().element(), 4);
+/// assert_eq!(v.iter().sum::<u32>(), 6);
+/// ```
+pub struct ArrayVec<T, const N: usize> {
+    len: usize,
+    data: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// The number of elements the vector can hold.
+    pub const CAPACITY: usize = N;
+
+    /// Creates an empty vector.
+    pub const fn new() -> Self {
+        ArrayVec {
+            len: 0,
+            // An array of `MaybeUninit` needs no initialization.
+            data: unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() },
+        }
+    }
+
+    /// Returns the number of elements the vector can hold, which is `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the vector.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector holds no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the vector holds `N` elements.
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns how many more elements fit.
+    pub const fn remaining_capacity(&self) -> usize {
+        N - self.len
+    }
+
+    /// Returns a raw pointer to the buffer.
+    pub fn as_ptr(&self) -> *const T {
+        self.data.as_ptr().cast()
+    }
+
+    /// Returns a raw mutable pointer to the buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr().cast()
+    }
+
+    /// Extracts a slice containing the whole vector.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    /// Extracts a mutable slice containing the whole vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    /// Forces the length of the vector to `new_len`.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be at most `N` and the elements at `old_len..new_len`
+    /// must be initialized.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= N);
+        self.len = new_len;
+    }
+
+    /// Appends an element to the back of the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector is full.
+    pub fn push(&mut self, value: T) {
+        if self.try_push(value).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Appends an element to the back of the vector, or hands it back in
+    /// the error if the vector is full.
+    pub fn try_push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError::new(value));
+        }
+        unsafe { self.push_unchecked(value) };
+        Ok(())
+    }
+
+    /// Appends an element without checking the capacity.
+    ///
+    /// # Safety
+    ///
+    /// The vector must not be full.
+    pub unsafe fn push_unchecked(&mut self, value: T) {
+        debug_assert!(!self.is_full());
+        ptr::write(self.as_mut_ptr().add(self.len), value);
+        self.len += 1;
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.as_ptr().add(self.len))) }
+        }
+    }
+
+    /// Inserts an element at position `index`, shifting all elements after
+    /// it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len` or if the vector is full.
+    pub fn insert(&mut self, index: usize, element: T) {
+        if self.try_insert(index, element).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Inserts an element at position `index`, or hands it back in the
+    /// error if the vector is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`; an out-of-bounds index is a bug, not a
+    /// capacity problem.
+    pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), CapacityError<T>> {
+        crate::slice::assert_insert_index(index, self.len);
+        if self.is_full() {
+            return Err(CapacityError::new(element));
+        }
+        let base = self.as_mut_ptr();
+        unsafe { crate::slice::insert(base, &mut self.len, index, element) };
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index`, shifting all
+    /// elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let base = self.as_mut_ptr();
+        unsafe { crate::slice::remove(base, &mut self.len, index) }
+    }
+
+    /// Removes an element from the vector and returns it, replacing it with
+    /// the last element. This does not preserve ordering but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let base = self.as_mut_ptr();
+        unsafe { crate::slice::swap_remove(base, &mut self.len, index) }
+    }
+
+    /// Shortens the vector, keeping the first `len` elements and dropping
+    /// the rest. Has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        let base = self.as_mut_ptr();
+        unsafe { crate::slice::truncate(base, &mut self.len, len) };
+    }
+
+    /// Clears the vector, removing all values.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements for which `f` returns `true`.
+    ///
+    /// If `f` or an element's destructor panics, the vector is left holding
+    /// the elements not yet visited plus those already kept.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let base = self.as_mut_ptr();
+        unsafe { crate::slice::retain(base, &mut self.len, f) };
+    }
+
+    /// Clones and appends all elements of `other` to the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if they do not all fit. Nothing is appended in that case.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        if self.try_extend_from_slice(other).is_err() {
+            capacity_exceeded();
+        }
+    }
+
+    /// Clones and appends all elements of `other`, or returns an error and
+    /// appends nothing if they do not all fit.
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        if other.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+        for item in other {
+            // The length is bumped per element so a panicking `clone`
+            // leaves the vector consistent.
+            unsafe { self.push_unchecked(item.clone()) };
+        }
+        Ok(())
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator.
+    /// Whatever the iterator has not yielded is dropped with it, and the
+    /// elements after the range are then shifted down.
+    ///
+    /// If the iterator is leaked, the vector keeps only the elements
+    /// before the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// ```
+    /// use mystdrs::array_vec::ArrayVec;
+    ///
+    /// let mut v = ArrayVec::from([1, 2, 3, 4, 5]);
+    /// assert!(v.drain(1..3).eq([2, 3]));
+    /// assert_eq!(v, [1, 4, 5]);
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, N>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Drain {
+            inner: RawDrain::new(self, range),
+        }
+    }
+
+    /// Replaces the elements in `range` with those of `replace_with`,
+    /// returning the removed elements as an iterator.
+    ///
+    /// The replacement happens when the iterator is dropped, as with
+    /// [`Vec::splice`](crate::vec::Vec::splice). Replacement elements beyond
+    /// the drained gap and `replace_with`'s `size_hint` lower bound are
+    /// collected into a temporary heap buffer first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds. Panics when the iterator is
+    /// dropped if the replacement does not fit; the vector then keeps the
+    /// replacement elements written so far, followed by the tail.
+    ///
+    /// ```
+    /// use mystdrs::array_vec::ArrayVec;
+    ///
+    /// let mut v: ArrayVec<i32, 8> = ArrayVec::from([1, 2, 3, 4]).into_iter().collect();
+    /// assert!(v.splice(1..3, [7, 8, 9]).eq([2, 3]));
+    /// assert_eq!(v, [1, 7, 8, 9, 4]);
+    /// ```
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, I::IntoIter, N>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Splice {
+            inner: RawSplice::new(self, range, replace_with.into_iter()),
+        }
+    }
+
+    /// Returns the elements as an array if the vector is full, or hands
+    /// the vector back otherwise.
+    pub fn into_inner(self) -> Result<[T; N], Self> {
+        if self.is_full() {
+            let me = ManuallyDrop::new(self);
+            Ok(unsafe { ptr::read(me.as_ptr().cast::<[T; N]>()) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+#[cold]
+#[track_caller]
+fn capacity_exceeded() -> ! {
+    panic!("ArrayVec capacity exceeded");
+}
+
+impl<T, const N: usize> Buffer<T> for ArrayVec<T, N> {
+    fn as_ptr(&self) -> *const T {
+        self.as_ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.as_mut_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    unsafe fn set_len(&mut self, len: usize) {
+        self.set_len(len);
+    }
+
+    /// Cannot grow, so this only checks that the room is there.
+    fn reserve(&mut self, additional: usize) {
+        if additional > self.remaining_capacity() {
+            capacity_exceeded();
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        ArrayVec::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut v = ArrayVec::new();
+        v.extend_from_slice(self);
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<ArrayVec<U, M>> for ArrayVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &ArrayVec<U, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U]> for ArrayVec<T, N> {
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize, const M: usize> PartialEq<[U; M]> for ArrayVec<T, N> {
+    fn eq(&self, other: &[U; M]) -> bool {
+        self.as_slice() == &other[..]
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+impl<T: Hash, const N: usize> Hash for ArrayVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl<T, const N: usize> Extend<T> for ArrayVec<T, N> {
+    /// Appends every item of `iter`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector fills up before `iter` runs out.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, const N: usize> Extend<&'a T> for ArrayVec<T, N> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for ArrayVec<T, N> {
+    /// Collects `iter` into a vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `iter` yields more than `N` items.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = ArrayVec::new();
+        v.extend(iter);
+        v
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for ArrayVec<T, N> {
+    fn from(arr: [T; N]) -> Self {
+        let arr = ManuallyDrop::new(arr);
+        let mut v = ArrayVec::new();
+        unsafe {
+            ptr::copy_nonoverlapping(arr.as_ptr(), v.as_mut_ptr(), N);
+            v.set_len(N);
+        }
+        v
+    }
+}
+
+impl<T: Clone, const N: usize> TryFrom<&[T]> for ArrayVec<T, N> {
+    type Error = CapacityError;
+
+    fn try_from(s: &[T]) -> Result<Self, CapacityError> {
+        let mut v = ArrayVec::new();
+        v.try_extend_from_slice(s)?;
+        Ok(v)
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(mut self) -> IntoIter<T, N> {
+        let end = self.len;
+        // The iterator owns the elements from here on.
+        self.len = 0;
+        IntoIter {
+            vec: self,
+            start: 0,
+            end,
+        }
+    }
+}
+
+/// An iterator that moves out of an [`ArrayVec`], created by
+/// `ArrayVec::into_iter`.
+pub struct IntoIter<T, const N: usize> {
+    /// Has length zero; the elements at `start..end` are still owned.
+    vec: ArrayVec<T, N>,
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> IntoIter<T, N> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.vec.as_ptr().add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.vec.as_ptr().add(self.start)) };
+            self.start += 1;
+            Some(item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.start;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            unsafe { Some(ptr::read(self.vec.as_ptr().add(self.end))) }
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for IntoIter<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            let rest = ptr::slice_from_raw_parts_mut(
+                self.vec.as_mut_ptr().add(self.start),
+                self.end - self.start,
+            );
+            self.start = self.end;
+            ptr::drop_in_place(rest);
+        }
+    }
+}
+
+/// A draining iterator for [`ArrayVec`], created by
+/// [`ArrayVec::drain`].
+pub struct Drain<'a, T, const N: usize> {
+    inner: RawDrain<'a, T, ArrayVec<T, N>>,
+}
+
+impl<T, const N: usize> Drain<'_, T, N> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<T, const N: usize> Iterator for Drain<'_, T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Drain<'_, T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Drain<'_, T, N> {}
+
+impl<T, const N: usize> FusedIterator for Drain<'_, T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for Drain<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_slice()).finish()
+    }
+}
+
+/// A splicing iterator for [`ArrayVec`], created by
+/// [`ArrayVec::splice`].
+pub struct Splice<'a, I: Iterator, const N: usize> {
+    inner: RawSplice<'a, I, ArrayVec<I::Item, N>>,
+}
+
+impl<I: Iterator, const N: usize> Iterator for Splice<'_, I, N> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: Iterator, const N: usize> DoubleEndedIterator for Splice<'_, I, N> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<I: Iterator, const N: usize> ExactSizeIterator for Splice<'_, I, N> {}
+
+impl<I: Iterator, const N: usize> fmt::Debug for Splice<'_, I, N>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Splice")
+            .field(&self.inner.as_slice())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrayVec, CapacityError};
+    use crate::test_util::{DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::convert::TryFrom;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn push_pop_and_capacity() {
+        let mut v: ArrayVec<u32, 4> = ArrayVec::new();
+        assert_eq!((v.capacity(), v.remaining_capacity()), (4, 4));
+        for i in 0..4 {
+            v.push(i);
+        }
+        assert!(v.is_full());
+        assert_eq!(v.try_push(9), Err(CapacityError::new(9)));
+        assert_eq!(v, [0, 1, 2, 3]);
+        assert_eq!(v.pop(), Some(3));
+        assert_eq!(v.remaining_capacity(), 1);
+        v.clear();
+        assert_eq!(v.pop(), None);
+        assert_eq!(
+            format!("{}", CapacityError::new(1)),
+            "insufficient capacity"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ArrayVec capacity exceeded")]
+    fn push_when_full() {
+        let mut v: ArrayVec<u8, 1> = ArrayVec::new();
+        v.push(1);
+        v.push(2);
+    }
+
+    #[test]
+    fn matches_std_vec() {
+        let mut rng = XorShift::new(8);
+        let mut v: ArrayVec<u64, 32> = ArrayVec::new();
+        let mut model = Vec::new();
+        for _ in 0..5000 {
+            let x = rng.next();
+            match rng.below(6) {
+                0 => {
+                    let pushed = v.try_push(x).is_ok();
+                    assert_eq!(pushed, model.len() < 32);
+                    if pushed {
+                        model.push(x);
+                    }
+                }
+                1 if !model.is_empty() => {
+                    let i = rng.below(model.len());
+                    assert_eq!(v.remove(i), model.remove(i));
+                }
+                2 if !model.is_empty() => {
+                    let i = rng.below(model.len());
+                    assert_eq!(v.swap_remove(i), model.swap_remove(i));
+                }
+                3 => {
+                    let i = rng.below(model.len() + 1);
+                    if v.try_insert(i, x).is_ok() {
+                        model.insert(i, x);
+                    }
+                    assert!(model.len() <= 32);
+                }
+                4 => {
+                    v.retain(|&e| e % 3 != 0);
+                    model.retain(|&e| e % 3 != 0);
+                }
+                _ => assert_eq!(v.pop(), model.pop()),
+            }
+            assert_eq!(v.as_slice(), &model[..]);
+        }
+    }
+
+    #[test]
+    fn drain_and_splice_match_std_vec() {
+        let mut rng = XorShift::new(0xd2a1);
+        let mut v: ArrayVec<u64, 32> = ArrayVec::new();
+        let mut model = Vec::new();
+        for _ in 0..3000 {
+            let a = rng.below(model.len() + 1);
+            let b = a + rng.below(model.len() - a + 1);
+            match rng.below(4) {
+                0 => {
+                    let n = rng.below(33 - model.len());
+                    let items: Vec<u64> = (0..n).map(|_| rng.next()).collect();
+                    v.extend(items.iter().copied());
+                    model.extend(items);
+                }
+                1 => {
+                    // Take a few from the front and leave the rest.
+                    let take = rng.below(b - a + 1);
+                    let mut ours = v.drain(a..b);
+                    let mut theirs = model.drain(a..b);
+                    for _ in 0..take {
+                        assert_eq!(ours.next_back(), theirs.next_back());
+                    }
+                    assert_eq!(ours.as_slice(), theirs.as_slice());
+                }
+                _ => {
+                    let room = 32 - model.len() + (b - a);
+                    let n = rng.below(room + 1);
+                    let items: Vec<u64> = (0..n).map(|_| rng.next()).collect();
+                    // A filtered iterator has no useful lower bound, which
+                    // exercises every path of the splice.
+                    let keep = rng.below(2) == 0;
+                    let ours: Vec<u64> = v
+                        .splice(a..b, items.iter().copied().filter(|_| keep))
+                        .collect();
+                    let theirs: Vec<u64> = model
+                        .splice(a..b, items.iter().copied().filter(|_| keep))
+                        .collect();
+                    assert_eq!(ours, theirs);
+                }
+            }
+            assert_eq!(v.as_slice(), &model[..]);
+        }
+    }
+
+    #[test]
+    fn splice_past_capacity_panics_and_keeps_what_fit() {
+        let mut v = ArrayVec::from([1, 2, 3, 4]);
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.splice(1..2, [7, 8]);
+        }));
+        assert!(r.is_err());
+        assert_eq!(v, [1, 7, 3, 4]);
+
+        let count = Rc::new(Cell::new(0));
+        let mut v: ArrayVec<DropCounter, 3> =
+            (0..3).map(|_| DropCounter::new(&count, false)).collect();
+        let more = (0..3).map(|_| DropCounter::new(&count, false));
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.splice(..1, more);
+        }));
+        assert!(r.is_err());
+        // Only the drained element is gone; the iterator never got to
+        // make the two that did not fit.
+        assert_eq!(v.len(), 3);
+        assert_eq!(count.get(), 1);
+        drop(v);
+        assert_eq!(count.get(), 4);
+    }
+
+    #[test]
+    fn drain_drops_the_rest_once() {
+        let count = Rc::new(Cell::new(0));
+        let mut v: ArrayVec<DropCounter, 6> =
+            (0..6).map(|_| DropCounter::new(&count, false)).collect();
+        let mut drain = v.drain(1..5);
+        drop(drain.next());
+        assert_eq!(count.get(), 1);
+        drop(drain);
+        assert_eq!((count.get(), v.len()), (4, 2));
+
+        let mut v = ArrayVec::from([1, 2, 3]);
+        std::mem::forget(v.drain(1..2));
+        assert_eq!(v, [1]);
+    }
+
+    #[test]
+    fn extend_from_slice_is_all_or_nothing() {
+        let mut v: ArrayVec<String, 3> = ArrayVec::new();
+        v.extend_from_slice(&["a".to_string()]);
+        let two = ["b".to_string(), "c".to_string()];
+        assert!(v.try_extend_from_slice(&two).is_ok());
+        assert!(v.try_extend_from_slice(&two[..1]).is_err());
+        assert_eq!(v.len(), 3);
+        assert!(ArrayVec::<u8, 2>::try_from(&[1, 2, 3][..]).is_err());
+        assert_eq!(
+            ArrayVec::<u8, 4>::try_from(&[1, 2, 3][..]).unwrap(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn into_inner_and_from_array() {
+        let v = ArrayVec::from([1, 2, 3]);
+        assert_eq!(v.into_inner(), Ok([1, 2, 3]));
+        let mut v: ArrayVec<u8, 3> = ArrayVec::new();
+        v.push(1);
+        let v = v.into_inner().unwrap_err();
+        assert_eq!(v, [1]);
+        let empty: ArrayVec<u8, 0> = ArrayVec::new();
+        assert!(empty.is_full());
+        assert_eq!(empty.into_inner(), Ok([]));
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let count = Rc::new(Cell::new(0));
+        let mut v: ArrayVec<DropCounter, 8> =
+            (0..8).map(|_| DropCounter::new(&count, false)).collect();
+        v.truncate(6);
+        assert_eq!(count.get(), 2);
+        drop(v.remove(0));
+        drop(v.swap_remove(0));
+        assert_eq!(count.get(), 4);
+        let mut n = 0;
+        v.retain(|_| {
+            n += 1;
+            n % 2 == 0
+        });
+        assert_eq!(count.get(), 6);
+        let mut iter = v.into_iter();
+        drop(iter.next());
+        assert_eq!(count.get(), 7);
+        drop(iter);
+        assert_eq!(count.get(), 8);
+
+        let v: ArrayVec<DropCounter, 2> = ArrayVec::from([
+            DropCounter::new(&count, false),
+            DropCounter::new(&count, false),
+        ]);
+        let arr = v.into_inner().ok().unwrap();
+        assert_eq!(count.get(), 8);
+        drop(arr);
+        assert_eq!(count.get(), 10);
+    }
+
+    #[test]
+    fn panicking_destructor_in_truncate() {
+        let count = Rc::new(Cell::new(0));
+        let mut v: ArrayVec<DropCounter, 4> = ArrayVec::new();
+        v.push(DropCounter::new(&count, false));
+        v.push(DropCounter::new(&count, true));
+        v.push(DropCounter::new(&count, false));
+        let r = panic::catch_unwind(AssertUnwindSafe(|| v.truncate(0)));
+        assert!(r.is_err());
+        assert_eq!(v.len(), 0);
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let mut v: ArrayVec<(), 3> = ArrayVec::new();
+        v.extend([(), (), ()]);
+        assert!(v.try_push(()).is_err());
+        assert_eq!(v.into_iter().count(), 3);
+    }
+
+    #[test]
+    fn traits() {
+        let v: ArrayVec<u32, 4> = [3, 1].iter().copied().collect();
+        assert_eq!(format!("{:?}", v), "[3, 1]");
+        assert_eq!(format!("{:?}", v.clone().into_iter()), "IntoIter([3, 1])");
+        assert_eq!(v, ArrayVec::<u32, 2>::from([3, 1]));
+        assert_eq!(
+            format!("{:?}", CapacityError::new(5)),
+            "CapacityError: insufficient capacity"
+        );
+        let mut sorted = v.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, [1, 3]);
+        assert!(v.into_iter().rev().eq([1, 3]));
+    }
+}
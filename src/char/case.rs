@@ -0,0 +1,132 @@
+//! Case conversion.
+
+use super::tables;
+use std::fmt;
+use std::iter::FusedIterator;
+
+/// Returns the uppercase mapping of `ch`, which may be several chars.
+///
+/// ```
+/// use mystdrs::char;
+///
+/// assert_eq!(char::to_uppercase('a').to_string(), "A");
+/// assert_eq!(char::to_uppercase('ß').to_string(), "SS");
+/// assert_eq!(char::to_uppercase('ﬃ').len(), 3);
+/// assert_eq!(char::to_uppercase('1').to_string(), "1");
+/// ```
+pub fn to_uppercase(ch: char) -> ToUppercase {
+    ToUppercase(if ch.is_ascii() {
+        CaseMappingIter::one(ch.to_ascii_uppercase())
+    } else {
+        CaseMappingIter::new(tables::UPPERCASE.lookup(ch))
+    })
+}
+
+/// Returns the lowercase mapping of `ch`, which may be several chars.
+///
+/// ```
+/// use mystdrs::char;
+///
+/// assert_eq!(char::to_lowercase('Σ').to_string(), "σ");
+/// assert_eq!(char::to_lowercase('İ').to_string(), "i\u{307}");
+/// ```
+pub fn to_lowercase(ch: char) -> ToLowercase {
+    ToLowercase(if ch.is_ascii() {
+        CaseMappingIter::one(ch.to_ascii_lowercase())
+    } else {
+        CaseMappingIter::new(tables::LOWERCASE.lookup(ch))
+    })
+}
+
+/// The chars of a case mapping, at most three.
+#[derive(Clone, Debug)]
+struct CaseMappingIter {
+    chars: [char; 3],
+    start: usize,
+    end: usize,
+}
+
+impl CaseMappingIter {
+    fn one(ch: char) -> Self {
+        CaseMappingIter {
+            chars: [ch, '\0', '\0'],
+            start: 0,
+            end: 1,
+        }
+    }
+
+    /// `chars` is padded with `'\0'`, which is never part of a mapping.
+    fn new(chars: [char; 3]) -> Self {
+        let end = chars.iter().position(|&ch| ch == '\0').unwrap_or(3);
+        CaseMappingIter {
+            chars,
+            start: 0,
+            end,
+        }
+    }
+
+    fn as_slice(&self) -> &[char] {
+        &self.chars[self.start..self.end]
+    }
+}
+
+/// Implements the iterator traits and `Display` for a wrapper around
+/// `CaseMappingIter`.
+macro_rules! case_mapping_iter {
+    ($name:ident) => {
+        impl Iterator for $name {
+            type Item = char;
+
+            fn next(&mut self) -> Option<char> {
+                let iter = &mut self.0;
+                if iter.start == iter.end {
+                    return None;
+                }
+                iter.start += 1;
+                Some(iter.chars[iter.start - 1])
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.0.end - self.0.start;
+                (len, Some(len))
+            }
+        }
+
+        impl DoubleEndedIterator for $name {
+            fn next_back(&mut self) -> Option<char> {
+                let iter = &mut self.0;
+                if iter.start == iter.end {
+                    return None;
+                }
+                iter.end -= 1;
+                Some(iter.chars[iter.end])
+            }
+        }
+
+        impl ExactSizeIterator for $name {}
+
+        impl FusedIterator for $name {}
+
+        /// Writes the chars not yet returned.
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0
+                    .as_slice()
+                    .iter()
+                    .try_for_each(|&ch| fmt::Write::write_char(f, ch))
+            }
+        }
+    };
+}
+
+/// The uppercase mapping of a char, created by [`to_uppercase`].
+#[derive(Clone, Debug)]
+pub struct ToUppercase(CaseMappingIter);
+
+case_mapping_iter!(ToUppercase);
+
+/// The lowercase mapping of a char, created by [`to_lowercase`].
+#[derive(Clone, Debug)]
+pub struct ToLowercase(CaseMappingIter);
+
+case_mapping_iter!(ToLowercase);
@@ -0,0 +1,148 @@
+//! UTF-16 decoding.
+
+use super::{combine_surrogates, is_lead_surrogate, is_trail_surrogate};
+use std::error::Error;
+use std::fmt;
+use std::iter::FusedIterator;
+
+/// Decodes UTF-16 code units into characters, yielding an error for each
+/// surrogate that is not part of a lead/trail pair.
+///
+/// ```
+/// use mystdrs::char::decode_utf16;
+///
+/// // "𝄞mu" with an unpaired trail surrogate in the middle.
+/// let units = [0xD834, 0xDD1E, 0x006D, 0xDD1E, 0x0075];
+/// let decoded: Vec<_> = decode_utf16(units.iter().copied())
+///     .map(|r| r.map_err(|e| e.unpaired_surrogate()))
+///     .collect();
+/// assert_eq!(decoded, [Ok('𝄞'), Ok('m'), Err(0xDD1E), Ok('u')]);
+/// ```
+pub fn decode_utf16<I: IntoIterator<Item = u16>>(iter: I) -> DecodeUtf16<I::IntoIter> {
+    DecodeUtf16 {
+        iter: iter.into_iter(),
+        buf: None,
+    }
+}
+
+/// An iterator that decodes UTF-16, created by [`decode_utf16`].
+#[derive(Clone, Debug)]
+pub struct DecodeUtf16<I: Iterator<Item = u16>> {
+    iter: I,
+    /// A unit read while looking for a trail surrogate that turned out not
+    /// to be one.
+    buf: Option<u16>,
+}
+
+/// An unpaired surrogate found by [`decode_utf16`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeUtf16Error {
+    code: u16,
+}
+
+impl DecodeUtf16Error {
+    /// Returns the surrogate that caused the error.
+    pub fn unpaired_surrogate(&self) -> u16 {
+        self.code
+    }
+}
+
+impl fmt::Display for DecodeUtf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unpaired surrogate found: {:x}", self.code)
+    }
+}
+
+impl Error for DecodeUtf16Error {}
+
+impl<I: Iterator<Item = u16>> Iterator for DecodeUtf16<I> {
+    type Item = Result<char, DecodeUtf16Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let unit = match self.buf.take() {
+            Some(unit) => unit,
+            None => self.iter.next()?,
+        };
+        if is_trail_surrogate(unit) {
+            return Some(Err(DecodeUtf16Error { code: unit }));
+        }
+        if !is_lead_surrogate(unit) {
+            return Some(Ok(unsafe {
+                std::char::from_u32_unchecked(u32::from(unit))
+            }));
+        }
+        match self.iter.next() {
+            Some(trail) if is_trail_surrogate(trail) => {
+                let code = combine_surrogates(unit, trail);
+                Some(Ok(unsafe { std::char::from_u32_unchecked(code) }))
+            }
+            next => {
+                self.buf = next;
+                Some(Err(DecodeUtf16Error { code: unit }))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.iter.size_hint();
+        let buffered = usize::from(self.buf.is_some());
+        // Any two neighbouring units, the buffered one included, may be a
+        // surrogate pair.
+        let low = low.saturating_add(buffered).div_ceil(2);
+        let high = high.and_then(|high| high.checked_add(buffered));
+        (low, high)
+    }
+}
+
+impl<I: Iterator<Item = u16> + FusedIterator> FusedIterator for DecodeUtf16<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_utf16;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn matches_std_on_ill_formed_input() {
+        let mut rng = XorShift::new(16);
+        // Mostly surrogates, so that pairs, lone leads and lone trails all
+        // show up next to each other and at the ends.
+        const UNITS: [u16; 6] = [0x41, 0xE9, 0xFFFF, 0xD800, 0xDBFF, 0xDC00];
+        for _ in 0..10_000 {
+            let units: Vec<u16> = (0..rng.below(10))
+                .map(|_| UNITS[rng.below(UNITS.len())])
+                .collect();
+            let ours: Vec<_> = decode_utf16(units.iter().copied())
+                .map(|r| r.map_err(|e| e.unpaired_surrogate()))
+                .collect();
+            let std: Vec<_> = std::char::decode_utf16(units.iter().copied())
+                .map(|r| r.map_err(|e| e.unpaired_surrogate()))
+                .collect();
+            assert_eq!(ours, std, "{:x?}", units);
+
+            let mut iter = decode_utf16(units.iter().copied());
+            let mut left = ours.len();
+            loop {
+                let (low, high) = iter.size_hint();
+                assert!(low <= left && left <= high.unwrap(), "{:x?}", units);
+                if iter.next().is_none() {
+                    break;
+                }
+                left -= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn error_display() {
+        let err = decode_utf16([0xD83Du16]).next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "unpaired surrogate found: d83d");
+        assert_eq!(
+            err.to_string(),
+            std::char::decode_utf16([0xD83Du16])
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Generates `tables.rs` from the Unicode Character Database files in
+//! `fixtures/`.
+//!
+//! The generator runs as a test that fails if `tables.rs` is out of date.
+//! After replacing the fixtures with those of a new Unicode version,
+//! regenerate the tables with
+//!
+//! ```text
+//! MYSTDRS_BLESS=1 cargo test --lib char::generate
+//! ```
+//!
+//! and, on a toolchain whose `char::UNICODE_VERSION` is the new one, check
+//! them against std with
+//!
+//! ```text
+//! cargo test --lib char -- --ignored
+//! ```
+//!
+//! The fixtures are the official files of that version, unmodified. Only
+//! these parts are used:
+//!
+//! - `UnicodeData.txt`: the General_Category, for `is_numeric`, and the
+//!   simple uppercase and lowercase mappings;
+//! - `SpecialCasing.txt`: the unconditional mappings, which override the
+//!   simple ones;
+//! - `DerivedCoreProperties.txt`: Alphabetic, and the Unicode version from
+//!   the file's first line;
+//! - `PropList.txt`: White_Space.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/char");
+
+/// One past the last code point.
+const END: usize = 0x11_0000;
+
+/// Boundaries between heads in a `RunTable`, at most.
+const MAX_DELTAS_PER_HEAD: usize = 32;
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/fixtures/{}", DIR, name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+/// Returns the fields of each data line of a UCD file, comments removed.
+fn data_lines(text: &str) -> impl Iterator<Item = Vec<&str>> {
+    text.lines().filter_map(|line| {
+        let data = line.split('#').next().unwrap().trim();
+        if data.is_empty() {
+            None
+        } else {
+            Some(data.split(';').map(str::trim).collect())
+        }
+    })
+}
+
+fn code(field: &str) -> u32 {
+    u32::from_str_radix(field, 16).unwrap_or_else(|e| panic!("{:?}: {}", field, e))
+}
+
+fn codes(field: &str) -> Vec<u32> {
+    field.split_whitespace().map(code).collect()
+}
+
+/// Returns the code points with `property` in a file listing
+/// `XXXX..YYYY ; Property` lines.
+fn property(text: &str, property: &str) -> Vec<bool> {
+    let mut set = vec![false; END];
+    for fields in data_lines(text).filter(|fields| fields[1] == property) {
+        let (first, last) = match fields[0].split_once("..") {
+            Some((first, last)) => (code(first), code(last)),
+            None => (code(fields[0]), code(fields[0])),
+        };
+        for cp in first..=last {
+            set[cp as usize] = true;
+        }
+    }
+    set
+}
+
+/// Parses `DerivedCoreProperties-X.Y.Z.txt` from the first line.
+fn unicode_version(text: &str) -> (u32, u32, u32) {
+    let first_line = text.lines().next().unwrap();
+    let name = first_line
+        .split_whitespace()
+        .find(|word| word.starts_with("DerivedCoreProperties-"))
+        .expect("no file name on the first line");
+    let version = name["DerivedCoreProperties-".len()..]
+        .trim_end_matches(':')
+        .trim_end_matches(".txt");
+    let parts: Vec<u32> = version.split('.').map(|p| p.parse().unwrap()).collect();
+    (parts[0], parts[1], parts[2])
+}
+
+struct UnicodeData {
+    numeric: Vec<bool>,
+    upper: BTreeMap<u32, Vec<u32>>,
+    lower: BTreeMap<u32, Vec<u32>>,
+}
+
+fn unicode_data(text: &str) -> UnicodeData {
+    let mut data = UnicodeData {
+        numeric: vec![false; END],
+        upper: BTreeMap::new(),
+        lower: BTreeMap::new(),
+    };
+    let mut range_start = None;
+    for fields in data_lines(text) {
+        let cp = code(fields[0]);
+        // Large blocks are a `<Name, First>` line and a `<Name, Last>` line.
+        let first = match range_start.take() {
+            Some(first) => first,
+            None if fields[1].ends_with(", First>") => {
+                range_start = Some(cp);
+                continue;
+            }
+            None => cp,
+        };
+        if fields[2].starts_with('N') {
+            for numeric in &mut data.numeric[first as usize..=cp as usize] {
+                *numeric = true;
+            }
+        }
+        if !fields[12].is_empty() {
+            data.upper.insert(cp, vec![code(fields[12])]);
+        }
+        if !fields[13].is_empty() {
+            data.lower.insert(cp, vec![code(fields[13])]);
+        }
+    }
+    data
+}
+
+/// Overrides `data`'s simple mappings with the unconditional full ones.
+fn special_casing(text: &str, data: &mut UnicodeData) {
+    // code; lower; title; upper; (condition;)
+    for fields in data_lines(text).filter(|fields| fields.len() < 5 || fields[4].is_empty()) {
+        let cp = code(fields[0]);
+        data.lower.insert(cp, codes(fields[1]));
+        data.upper.insert(cp, codes(fields[3]));
+    }
+}
+
+/// Writes `items` as the body of a slice literal, several per line.
+fn write_items<T>(out: &mut String, items: &[T], per_line: usize, item: impl Fn(&T) -> String) {
+    for line in items.chunks(per_line) {
+        let line: Vec<String> = line.iter().map(&item).collect();
+        writeln!(out, "        {},", line.join(", ")).unwrap();
+    }
+}
+
+fn run_table(out: &mut String, name: &str, set: &[bool]) {
+    let boundaries: Vec<u32> = (0..=END)
+        .filter(|&cp| {
+            let before = cp > 0 && set[cp - 1];
+            let here = cp < END && set[cp];
+            before != here
+        })
+        .map(|cp| cp as u32)
+        .collect();
+
+    let (mut heads, mut deltas) = (Vec::new(), Vec::new());
+    let mut since_head = 0;
+    for (i, &boundary) in boundaries.iter().enumerate() {
+        let delta = i.checked_sub(1).map(|prev| boundary - boundaries[prev]);
+        match delta {
+            Some(delta) if delta <= 255 && since_head < MAX_DELTAS_PER_HEAD => {
+                deltas.push(delta as u8);
+                since_head += 1;
+            }
+            _ => {
+                assert!(deltas.len() < 1 << 11, "too many deltas for {}", name);
+                heads.push(boundary | (deltas.len() as u32) << 21);
+                since_head = 0;
+            }
+        }
+    }
+
+    writeln!(out, "pub(super) static {}: RunTable = RunTable {{", name).unwrap();
+    out.push_str("    heads: &[\n");
+    write_items(out, &heads, 8, |head| format!("0x{:08x}", head));
+    out.push_str("    ],\n    deltas: &[\n");
+    write_items(out, &deltas, 16, |delta| delta.to_string());
+    out.push_str("    ],\n};\n");
+}
+
+fn case_table(out: &mut String, name: &str, mappings: &BTreeMap<u32, Vec<u32>>) {
+    let mut multi = Vec::new();
+    // (start, count, step, delta)
+    let mut runs: Vec<(u32, u32, u32, i64)> = Vec::new();
+    for (&cp, mapping) in mappings {
+        if mapping.len() > 1 {
+            assert!(mapping.len() <= 3, "{:04X} maps to more than 3 chars", cp);
+            multi.push((cp, mapping));
+            continue;
+        }
+        if mapping[0] == cp {
+            continue;
+        }
+        let delta = i64::from(mapping[0]) - i64::from(cp);
+        if let Some(run) = runs.last_mut() {
+            let (start, count, step, run_delta) = *run;
+            // A run of one can still pick its step.
+            let step = if count == 1 { cp - start } else { step };
+            if run_delta == delta && step <= 2 && cp == start + count * step {
+                *run = (start, count + 1, step, delta);
+                continue;
+            }
+        }
+        runs.push((cp, 1, 1, delta));
+    }
+
+    let ch = |cp: u32| format!("'\\u{{{:x}}}'", cp);
+    writeln!(out, "pub(super) static {}: CaseTable = CaseTable {{", name).unwrap();
+    out.push_str("    multi: &[\n");
+    write_items(out, &multi, 2, |&(cp, mapping)| {
+        let mut padded: Vec<String> = mapping.iter().map(|&m| ch(m)).collect();
+        padded.resize(3, "'\\0'".to_string());
+        format!("({}, [{}])", ch(cp), padded.join(", "))
+    });
+    out.push_str("    ],\n    runs: &[\n");
+    write_items(out, &runs, 4, |&(start, count, step, delta)| {
+        assert!(count < 1 << 15 && (step == 1 || step == 2));
+        format!("(0x{:x}, {}, {})", start, count << 1 | (step - 1), delta)
+    });
+    out.push_str("    ],\n};\n");
+}
+
+/// Returns the contents `tables.rs` should have.
+fn generate() -> String {
+    let derived = fixture("DerivedCoreProperties.txt");
+    let mut data = unicode_data(&fixture("UnicodeData.txt"));
+    special_casing(&fixture("SpecialCasing.txt"), &mut data);
+
+    let mut out = String::new();
+    out.push_str(
+        "//! Unicode property tables, generated by `generate.rs` from the files in\n\
+         //! `fixtures/`. Do not edit by hand.\n\n\
+         use super::unicode::{CaseTable, RunTable};\n\n",
+    );
+    let (major, minor, update) = unicode_version(&derived);
+    writeln!(
+        out,
+        "pub(super) const UNICODE_VERSION: (u8, u8, u8) = ({}, {}, {});\n",
+        major, minor, update
+    )
+    .unwrap();
+    run_table(&mut out, "ALPHABETIC", &property(&derived, "Alphabetic"));
+    out.push('\n');
+    run_table(&mut out, "NUMERIC", &data.numeric);
+    out.push('\n');
+    run_table(
+        &mut out,
+        "WHITE_SPACE",
+        &property(&fixture("PropList.txt"), "White_Space"),
+    );
+    out.push('\n');
+    case_table(&mut out, "UPPERCASE", &data.upper);
+    out.push('\n');
+    case_table(&mut out, "LOWERCASE", &data.lower);
+    out
+}
+
+#[test]
+fn tables_are_up_to_date() {
+    let path = format!("{}/tables.rs", DIR);
+    let generated = generate();
+    if std::env::var_os("MYSTDRS_BLESS").is_some() {
+        std::fs::write(&path, generated).unwrap();
+        return;
+    }
+    let current = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        current == generated,
+        "src/char/tables.rs is out of date; regenerate it with \
+         `MYSTDRS_BLESS=1 cargo test --lib char::generate`"
+    );
+}
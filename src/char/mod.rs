@@ -0,0 +1,200 @@
+//! Character encoding utilities.
+//!
+//! [`decode_utf16`] turns UTF-16 code units into characters, reporting each
+//! unpaired surrogate as a [`DecodeUtf16Error`] instead of guessing. The raw
+//! encoders take any code point, surrogates included, which is what WTF-8
+//! in [`crate::wtf8`] needs.
+//!
+//! The classification functions and the case mappings follow the Unicode
+//! version in [`UNICODE_VERSION`]. Their tables in `tables.rs` are generated
+//! from the Unicode Character Database files in `fixtures/` by a test in
+//! `generate.rs`, which also says how to regenerate them.
+
+mod case;
+mod decode;
+#[cfg(test)]
+mod generate;
+#[rustfmt::skip]
+mod tables;
+mod unicode;
+
+pub use self::case::{to_lowercase, to_uppercase, ToLowercase, ToUppercase};
+pub use self::decode::{decode_utf16, DecodeUtf16, DecodeUtf16Error};
+
+/// The version of Unicode that the classification functions and the case
+/// mappings follow, as `(major, minor, update)`.
+pub const UNICODE_VERSION: (u8, u8, u8) = tables::UNICODE_VERSION;
+
+/// Returns whether `ch` has the Alphabetic property.
+///
+/// ```
+/// use mystdrs::char;
+///
+/// assert!(char::is_alphabetic('a'));
+/// assert!(char::is_alphabetic('京'));
+/// assert!(!char::is_alphabetic('1'));
+/// ```
+pub fn is_alphabetic(ch: char) -> bool {
+    match ch {
+        'a'..='z' | 'A'..='Z' => true,
+        _ => ch > '\x7f' && tables::ALPHABETIC.contains(ch),
+    }
+}
+
+/// Returns whether `ch` has a numeric General_Category: Nd, Nl or No.
+///
+/// ```
+/// use mystdrs::char;
+///
+/// assert!(char::is_numeric('7'));
+/// assert!(char::is_numeric('½'));
+/// assert!(char::is_numeric('Ⅻ'));
+/// assert!(!char::is_numeric('x'));
+/// ```
+pub fn is_numeric(ch: char) -> bool {
+    match ch {
+        '0'..='9' => true,
+        _ => ch > '\x7f' && tables::NUMERIC.contains(ch),
+    }
+}
+
+/// Returns whether `ch` has the White_Space property.
+///
+/// ```
+/// use mystdrs::char;
+///
+/// assert!(char::is_whitespace('\n'));
+/// assert!(char::is_whitespace('\u{a0}'));
+/// assert!(!char::is_whitespace('\u{200b}'));
+/// ```
+pub fn is_whitespace(ch: char) -> bool {
+    match ch {
+        ' ' | '\x09'..='\x0d' => true,
+        _ => ch > '\x7f' && tables::WHITE_SPACE.contains(ch),
+    }
+}
+
+/// The first code point that takes a surrogate pair in UTF-16.
+const SUPPLEMENTARY: u32 = 0x1_0000;
+
+/// Returns whether `unit` is a UTF-16 lead (high) surrogate.
+pub(crate) fn is_lead_surrogate(unit: u16) -> bool {
+    (0xD800..0xDC00).contains(&unit)
+}
+
+/// Returns whether `unit` is a UTF-16 trail (low) surrogate.
+pub(crate) fn is_trail_surrogate(unit: u16) -> bool {
+    (0xDC00..0xE000).contains(&unit)
+}
+
+/// Combines a lead and a trail surrogate into the code point they encode.
+pub(crate) fn combine_surrogates(lead: u16, trail: u16) -> u32 {
+    SUPPLEMENTARY + ((u32::from(lead) - 0xD800) << 10 | (u32::from(trail) - 0xDC00))
+}
+
+/// Encodes `code` as UTF-8 into `dst`, returning the bytes written.
+/// Surrogates are encoded like any other three-byte code point.
+///
+/// `code` must be at most `0x10FFFF`.
+pub(crate) fn encode_utf8_raw(code: u32, dst: &mut [u8; 4]) -> &mut [u8] {
+    let len = match code {
+        0..=0x7F => {
+            dst[0] = code as u8;
+            1
+        }
+        0x80..=0x7FF => {
+            dst[0] = 0xC0 | (code >> 6) as u8;
+            dst[1] = 0x80 | (code & 0x3F) as u8;
+            2
+        }
+        0x800..=0xFFFF => {
+            dst[0] = 0xE0 | (code >> 12) as u8;
+            dst[1] = 0x80 | (code >> 6 & 0x3F) as u8;
+            dst[2] = 0x80 | (code & 0x3F) as u8;
+            3
+        }
+        _ => {
+            dst[0] = 0xF0 | (code >> 18) as u8;
+            dst[1] = 0x80 | (code >> 12 & 0x3F) as u8;
+            dst[2] = 0x80 | (code >> 6 & 0x3F) as u8;
+            dst[3] = 0x80 | (code & 0x3F) as u8;
+            4
+        }
+    };
+    &mut dst[..len]
+}
+
+/// Encodes `code` as UTF-16 into `dst`, returning the units written. A
+/// surrogate code point comes out as that lone surrogate.
+///
+/// `code` must be at most `0x10FFFF`.
+pub(crate) fn encode_utf16_raw(code: u32, dst: &mut [u16; 2]) -> &mut [u16] {
+    if code < SUPPLEMENTARY {
+        dst[0] = code as u16;
+        &mut dst[..1]
+    } else {
+        let code = code - SUPPLEMENTARY;
+        dst[0] = 0xD800 | (code >> 10) as u16;
+        dst[1] = 0xDC00 | (code & 0x3FF) as u16;
+        &mut dst[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        combine_surrogates, encode_utf16_raw, encode_utf8_raw, is_alphabetic, is_numeric,
+        is_whitespace, to_lowercase, to_uppercase, UNICODE_VERSION,
+    };
+
+    #[test]
+    fn raw_encoders_match_std_for_every_scalar_value() {
+        for ch in (0..=0x10FFFF).filter_map(std::char::from_u32) {
+            let code = u32::from(ch);
+            assert_eq!(
+                encode_utf8_raw(code, &mut [0; 4]),
+                ch.encode_utf8(&mut [0; 4]).as_bytes()
+            );
+            let units = encode_utf16_raw(code, &mut [0; 2]).to_vec();
+            assert_eq!(units, ch.encode_utf16(&mut [0; 2]));
+            if let [lead, trail] = units[..] {
+                assert_eq!(combine_surrogates(lead, trail), code);
+            }
+        }
+        // Surrogates encode as themselves.
+        assert_eq!(encode_utf8_raw(0xD800, &mut [0; 4]), b"\xED\xA0\x80");
+        assert_eq!(encode_utf16_raw(0xDFFF, &mut [0; 2]), [0xDFFF]);
+    }
+
+    #[test]
+    #[ignore = "compares against std, whose Unicode version follows the toolchain; \
+                run with `cargo test --lib char -- --ignored`"]
+    fn tables_match_std_for_every_scalar_value() {
+        assert_eq!(
+            UNICODE_VERSION,
+            std::char::UNICODE_VERSION,
+            "the fixtures and the toolchain are on different Unicode versions, \
+             so std is no oracle for the tables"
+        );
+        for ch in (0..=0x10FFFF).filter_map(std::char::from_u32) {
+            assert_eq!(is_alphabetic(ch), ch.is_alphabetic(), "{:?}", ch);
+            assert_eq!(is_numeric(ch), ch.is_numeric(), "{:?}", ch);
+            assert_eq!(is_whitespace(ch), ch.is_whitespace(), "{:?}", ch);
+            assert!(to_uppercase(ch).eq(ch.to_uppercase()), "{:?}", ch);
+            assert!(to_lowercase(ch).eq(ch.to_lowercase()), "{:?}", ch);
+        }
+    }
+
+    #[test]
+    fn case_mappings_are_double_ended() {
+        let mut upper = to_uppercase('\u{fb03}');
+        assert_eq!(upper.len(), 3);
+        assert_eq!(upper.next_back(), Some('I'));
+        assert_eq!(upper.to_string(), "FF");
+        assert_eq!(upper.next(), Some('F'));
+        assert_eq!(upper.len(), 1);
+        assert_eq!(upper.next_back(), Some('F'));
+        assert_eq!(upper.next(), None);
+        assert_eq!(upper.to_string(), "");
+    }
+}
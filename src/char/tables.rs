@@ -0,0 +1,315 @@
+//! Unicode property tables, generated by `generate.rs` from the files in
+//! `fixtures/`. Do not edit by hand.
+
+use super::unicode::{CaseTable, RunTable};
+
+pub(super) const UNICODE_VERSION: (u8, u8, u8) = (17, 0, 0);
+
+pub(super) static ALPHABETIC: RunTable = RunTable {
+    heads: &[
+        0x00000041, 0x01c002c2, 0x05c00531, 0x09c006f0, 0x0dc008e3, 0x11c009d8, 0x15c00a59, 0x19c00b04,
+        0x1dc00b92, 0x21c00c45, 0x25c00cdc, 0x29c00dbc, 0x2dc00ec0, 0x31c010c8, 0x32601249, 0x366013a0,
+        0x36e0166d, 0x3ae018b0, 0x3ee01b4d, 0x42e01e00, 0x42e01f16, 0x46e01ff2, 0x4ae0214a, 0x4b6024b6,
+        0x4b802c00, 0x4f802ddf, 0x50003005, 0x52e03400, 0x52e04dc0, 0x5300a48d, 0x5360a60d, 0x5760a90a,
+        0x5b60aaf6, 0x5d80d7a4, 0x5e00f900, 0x5e00fa6e, 0x60a0fd3e, 0x64a1003f, 0x65810280, 0x69810593,
+        0x6b210737, 0x6f210920, 0x73210b92, 0x77210fb0, 0x7b211212, 0x7f21133d, 0x832113d4, 0x8721171d,
+        0x8b2119e5, 0x8f211d0b, 0x92411ee0, 0x9381239a, 0x94012f90, 0x94413430, 0x94a143fb, 0x94c14647,
+        0x94c16100, 0x94e16800, 0x94e16a39, 0x96a16d40, 0x99218cd6, 0x99a1aff0, 0x9a61b123, 0x9b81b2fc,
+        0x9b81bc00, 0x9ca1d400, 0xa0a1d547, 0xa101d6a6, 0xa3c1df00, 0xa6a1e290, 0xa701e4d0, 0xaa61ee00,
+        0xae61ee58, 0xb261f130, 0xb3020000, 0xb302a6e0, 0xb322b81e, 0xb342ceae, 0xb362ebe1, 0xb382ee5e,
+        0xb382f800, 0xb382fa1e, 0xb3830000, 0xb383134b, 0xb3a3347a,
+    ],
+    deltas: &[
+        26, 6, 26, 47, 1, 10, 1, 4, 1, 5, 23, 1, 31, 1, 4, 12,
+        14, 5, 7, 1, 1, 1, 86, 1, 29, 18, 1, 2, 2, 4, 1, 1,
+        6, 1, 1, 3, 1, 1, 1, 20, 1, 83, 1, 139, 8, 166, 38, 2,
+        1, 6, 41, 39, 14, 1, 1, 1, 2, 1, 2, 1, 1, 8, 27, 4,
+        4, 29, 11, 5, 56, 1, 7, 14, 102, 1, 8, 4, 8, 4, 10, 3,
+        2, 1, 16, 48, 13, 101, 24, 33, 9, 2, 4, 1, 5, 24, 2, 19,
+        19, 25, 7, 11, 5, 24, 1, 7, 7, 1, 8, 42, 10, 12, 7, 6,
+        76, 1, 16, 1, 3, 4, 15, 13, 19, 1, 8, 2, 2, 2, 22, 1,
+        7, 1, 1, 3, 4, 3, 8, 2, 2, 2, 2, 1, 1, 8, 4, 2,
+        1, 5, 12, 2, 10, 1, 4, 3, 1, 6, 4, 2, 2, 22, 1, 7,
+        1, 2, 1, 2, 1, 2, 4, 5, 4, 2, 2, 2, 4, 1, 4, 1,
+        1, 17, 6, 11, 3, 1, 9, 1, 3, 1, 22, 1, 7, 1, 2, 1,
+        5, 3, 9, 1, 3, 1, 2, 3, 1, 15, 4, 21, 4, 4, 1, 8,
+        2, 2, 2, 22, 1, 7, 1, 2, 1, 5, 3, 8, 2, 2, 2, 2,
+        9, 2, 4, 2, 1, 5, 13, 1, 16, 2, 1, 6, 3, 3, 4, 3,
+        2, 1, 1, 1, 2, 3, 2, 3, 3, 3, 12, 4, 5, 3, 3, 1,
+        3, 3, 1, 6, 1, 40, 13, 1, 3, 1, 23, 1, 16, 3, 1, 3,
+        1, 3, 8, 2, 1, 3, 1, 2, 2, 4, 28, 4, 1, 8, 1, 3,
+        1, 23, 1, 10, 1, 5, 3, 8, 1, 3, 1, 3, 8, 2, 3, 1,
+        4, 13, 3, 12, 13, 1, 3, 1, 41, 2, 8, 1, 3, 1, 3, 1,
+        1, 5, 4, 7, 5, 22, 6, 1, 3, 1, 18, 3, 24, 1, 1, 1,
+        2, 7, 8, 6, 1, 1, 1, 8, 18, 2, 13, 58, 5, 7, 6, 1,
+        51, 2, 1, 1, 1, 5, 1, 24, 1, 1, 1, 19, 1, 3, 5, 1,
+        1, 6, 1, 14, 4, 32, 1, 63, 8, 1, 36, 4, 19, 4, 16, 1,
+        36, 67, 55, 1, 1, 2, 5, 16, 64, 10, 4, 2, 38, 1, 5, 1,
+        2, 43, 1, 1, 4, 2, 7, 1, 1, 1, 4, 2, 41, 1, 4, 2,
+        33, 1, 4, 2, 7, 1, 1, 1, 4, 2, 15, 1, 57, 1, 4, 2,
+        67, 37, 16, 86, 2, 6, 3, 2, 17, 1, 26, 5, 75, 3, 11, 7,
+        20, 11, 21, 12, 20, 12, 13, 1, 3, 1, 2, 12, 52, 2, 19, 14,
+        1, 4, 1, 67, 89, 7, 43, 70, 10, 31, 1, 12, 4, 9, 23, 30,
+        2, 5, 11, 44, 4, 26, 54, 28, 4, 63, 2, 20, 50, 1, 23, 2,
+        11, 3, 49, 52, 1, 15, 1, 51, 42, 2, 4, 10, 44, 1, 11, 14,
+        55, 22, 3, 10, 36, 2, 11, 5, 43, 2, 3, 41, 4, 1, 6, 1,
+        2, 3, 1, 5, 192, 19, 34, 2, 6, 2, 38, 2, 6, 2, 8, 1,
+        1, 1, 1, 1, 1, 1, 31, 2, 53, 1, 7, 1, 1, 3, 3, 1,
+        7, 3, 4, 2, 6, 4, 13, 3, 1, 7, 116, 1, 13, 1, 16, 13,
+        101, 1, 4, 1, 2, 10, 1, 1, 3, 5, 6, 1, 1, 1, 1, 1,
+        1, 4, 1, 11, 2, 4, 5, 4, 1, 17, 41, 52, 229, 6, 4, 3,
+        2, 12, 38, 1, 1, 5, 1, 2, 56, 7, 1, 16, 23, 9, 7, 1,
+        7, 1, 7, 1, 7, 1, 7, 1, 7, 1, 7, 1, 1, 32, 47, 1,
+        3, 25, 9, 7, 5, 2, 5, 4, 86, 6, 3, 1, 90, 1, 4, 5,
+        43, 1, 94, 17, 32, 48, 16, 64, 67, 46, 2, 3, 16, 10, 2, 20,
+        47, 5, 8, 3, 113, 39, 9, 2, 103, 2, 82, 20, 21, 1, 33, 24,
+        52, 12, 68, 1, 1, 44, 6, 3, 1, 1, 3, 33, 5, 35, 13, 29,
+        3, 51, 1, 12, 15, 1, 16, 16, 10, 5, 1, 55, 9, 14, 18, 23,
+        3, 69, 1, 1, 1, 1, 24, 3, 2, 16, 2, 11, 6, 2, 6, 2,
+        6, 9, 7, 1, 7, 1, 43, 1, 14, 6, 123, 21, 12, 23, 4, 49,
+        2, 106, 38, 7, 12, 5, 5, 12, 1, 13, 1, 5, 1, 1, 1, 2,
+        1, 2, 1, 108, 33, 18, 64, 2, 54, 40, 12, 116, 5, 1, 135, 36,
+        26, 6, 26, 11, 89, 3, 6, 2, 6, 2, 6, 2, 3, 35, 12, 1,
+        26, 1, 19, 1, 2, 15, 2, 14, 34, 123, 69, 53, 29, 3, 49, 47,
+        32, 13, 30, 5, 43, 5, 30, 2, 36, 4, 8, 1, 5, 42, 158, 18,
+        36, 4, 36, 4, 40, 8, 52, 12, 11, 1, 15, 1, 1, 2, 1, 11,
+        1, 15, 1, 7, 1, 2, 3, 52, 12, 9, 22, 10, 8, 24, 6, 1,
+        42, 1, 9, 69, 6, 2, 1, 1, 44, 1, 2, 3, 1, 2, 23, 10,
+        23, 9, 31, 65, 19, 1, 2, 10, 22, 26, 6, 26, 38, 56, 6, 2,
+        64, 4, 1, 2, 5, 8, 1, 3, 1, 29, 42, 29, 3, 29, 35, 8,
+        1, 28, 27, 54, 10, 22, 10, 19, 13, 110, 73, 55, 51, 13, 51, 13,
+        40, 34, 28, 3, 1, 5, 23, 250, 42, 1, 2, 3, 2, 16, 6, 50,
+        3, 3, 29, 10, 1, 8, 22, 42, 18, 21, 27, 23, 9, 70, 43, 5,
+        10, 57, 9, 1, 13, 25, 23, 51, 17, 4, 8, 35, 3, 1, 9, 64,
+        1, 4, 9, 2, 10, 1, 1, 1, 35, 1, 34, 2, 1, 6, 4, 62,
+        7, 1, 1, 1, 4, 1, 15, 1, 10, 7, 57, 23, 4, 1, 8, 2,
+        2, 2, 22, 1, 7, 1, 2, 1, 5, 8, 2, 2, 2, 2, 3, 1,
+        6, 1, 5, 7, 28, 10, 1, 1, 2, 1, 1, 38, 1, 10, 1, 1,
+        2, 1, 1, 4, 1, 2, 3, 1, 1, 44, 66, 1, 3, 1, 4, 20,
+        3, 30, 66, 2, 2, 1, 1, 184, 54, 2, 7, 25, 6, 34, 63, 1,
+        1, 3, 1, 59, 54, 2, 1, 71, 27, 14, 21, 7, 185, 57, 103, 64,
+        31, 8, 2, 1, 2, 8, 1, 2, 1, 30, 1, 2, 2, 2, 2, 4,
+        93, 8, 2, 46, 2, 6, 1, 1, 1, 27, 51, 2, 10, 17, 72, 5,
+        1, 18, 73, 103, 8, 88, 33, 31, 9, 1, 45, 1, 7, 1, 1, 49,
+        30, 2, 22, 1, 14, 73, 7, 1, 2, 44, 3, 1, 1, 2, 1, 3,
+        1, 1, 2, 2, 24, 6, 1, 2, 1, 37, 1, 2, 1, 4, 1, 1,
+        23, 44, 23, 9, 17, 1, 41, 3, 3, 111, 1, 79, 102, 111, 17, 196,
+        97, 15, 17, 6, 25, 5, 47, 7, 31, 17, 79, 17, 30, 18, 48, 16,
+        4, 31, 21, 5, 19, 45, 211, 64, 32, 25, 2, 25, 44, 75, 4, 57,
+        7, 17, 64, 2, 1, 1, 12, 7, 9, 41, 32, 97, 115, 4, 1, 7,
+        1, 2, 1, 15, 1, 29, 3, 2, 1, 14, 4, 8, 107, 5, 13, 3,
+        9, 7, 10, 4, 1, 85, 1, 71, 1, 2, 2, 1, 2, 2, 2, 4,
+        1, 12, 1, 1, 1, 7, 1, 65, 1, 4, 2, 8, 1, 7, 1, 28,
+        1, 4, 1, 5, 1, 3, 7, 1, 2, 25, 1, 25, 1, 31, 1, 25,
+        1, 31, 1, 25, 1, 31, 1, 25, 1, 31, 1, 25, 1, 8, 31, 6,
+        6, 213, 7, 1, 17, 2, 7, 1, 2, 1, 5, 5, 62, 33, 1, 112,
+        45, 10, 7, 16, 1, 30, 18, 44, 28, 228, 30, 2, 1, 207, 31, 1,
+        22, 8, 2, 224, 7, 1, 4, 1, 2, 1, 15, 1, 197, 59, 68, 3,
+        1, 3, 1, 4, 1, 27, 1, 2, 1, 1, 2, 1, 1, 10, 1, 4,
+        1, 1, 1, 1, 6, 1, 4, 1, 1, 1, 1, 1, 1, 3, 1, 2,
+        1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 1, 1, 2,
+        4, 1, 7, 1, 4, 1, 4, 1, 1, 1, 10, 1, 17, 5, 3, 1,
+        5, 1, 17, 26, 6, 26, 6, 26, 32, 2, 2, 15, 5,
+    ],
+};
+
+pub(super) static NUMERIC: RunTable = RunTable {
+    heads: &[
+        0x00000030, 0x00e00660, 0x01800966, 0x05800f34, 0x05801040, 0x05e01369, 0x060016ee, 0x06e01946,
+        0x08c02070, 0x09e02460, 0x0a402776, 0x0a602cfd, 0x0a803007, 0x0b203192, 0x0c80a620, 0x0ce0a830,
+        0x0e40abf0, 0x0e60ff10, 0x0e810107, 0x0f2102e1, 0x10810858, 0x14210cfa, 0x14c10e60, 0x17211450,
+        0x17811650, 0x186118e0, 0x18c11bf0, 0x19e11f50, 0x1a412400, 0x1a616130, 0x1a816a60, 0x1b616d70,
+        0x1b816e80, 0x1ba16ff4, 0x1bc1ccf0, 0x1be1d2c0, 0x1c81d7ce, 0x1ca1e140, 0x1cc1e2f0, 0x1ce1e4f0,
+        0x1d41e8c7, 0x1da1ec71, 0x1ec1f100, 0x1ee1fbf0,
+    ],
+    deltas: &[
+        10, 120, 2, 5, 1, 2, 3, 10, 134, 10, 198, 10, 10, 118, 10, 4,
+        6, 108, 10, 118, 10, 118, 10, 2, 6, 110, 13, 115, 10, 8, 7, 103,
+        10, 104, 7, 7, 19, 109, 10, 96, 10, 118, 10, 70, 10, 70, 10, 20,
+        3, 239, 10, 6, 10, 22, 10, 10, 128, 11, 165, 10, 6, 10, 182, 10,
+        86, 10, 134, 10, 6, 10, 1, 3, 6, 6, 10, 198, 51, 2, 5, 60,
+        78, 22, 30, 1, 1, 25, 9, 14, 3, 4, 138, 10, 30, 8, 1, 15,
+        32, 10, 39, 15, 10, 188, 10, 6, 154, 10, 38, 10, 198, 10, 22, 10,
+        86, 10, 10, 10, 45, 12, 57, 17, 2, 27, 36, 4, 29, 1, 8, 1,
+        134, 5, 202, 10, 8, 25, 7, 39, 9, 75, 5, 22, 6, 160, 2, 2,
+        16, 2, 46, 64, 9, 52, 2, 30, 3, 75, 5, 104, 8, 24, 8, 41,
+        7, 6, 48, 10, 6, 10, 31, 158, 10, 42, 4, 112, 7, 134, 30, 128,
+        10, 60, 10, 144, 10, 7, 20, 251, 10, 10, 118, 10, 10, 102, 10, 6,
+        20, 76, 12, 19, 93, 10, 10, 86, 29, 227, 10, 70, 10, 54, 10, 10,
+        102, 21, 111, 10, 10, 86, 10, 134, 10, 1, 7, 10, 23, 3, 10, 20,
+        12, 20, 108, 25, 50, 10, 10, 10, 247, 10, 9, 128, 10, 59, 1, 3,
+        1, 4, 76, 45, 1, 15, 13, 10,
+    ],
+};
+
+pub(super) static WHITE_SPACE: RunTable = RunTable {
+    heads: &[
+        0x00000009, 0x00e01680, 0x01002000, 0x01e03000,
+    ],
+    deltas: &[
+        5, 18, 1, 100, 1, 26, 1, 1, 11, 29, 2, 5, 1, 47, 1, 1,
+    ],
+};
+
+pub(super) static UPPERCASE: CaseTable = CaseTable {
+    multi: &[
+        ('\u{df}', ['\u{53}', '\u{53}', '\0']), ('\u{149}', ['\u{2bc}', '\u{4e}', '\0']),
+        ('\u{1f0}', ['\u{4a}', '\u{30c}', '\0']), ('\u{390}', ['\u{399}', '\u{308}', '\u{301}']),
+        ('\u{3b0}', ['\u{3a5}', '\u{308}', '\u{301}']), ('\u{587}', ['\u{535}', '\u{552}', '\0']),
+        ('\u{1e96}', ['\u{48}', '\u{331}', '\0']), ('\u{1e97}', ['\u{54}', '\u{308}', '\0']),
+        ('\u{1e98}', ['\u{57}', '\u{30a}', '\0']), ('\u{1e99}', ['\u{59}', '\u{30a}', '\0']),
+        ('\u{1e9a}', ['\u{41}', '\u{2be}', '\0']), ('\u{1f50}', ['\u{3a5}', '\u{313}', '\0']),
+        ('\u{1f52}', ['\u{3a5}', '\u{313}', '\u{300}']), ('\u{1f54}', ['\u{3a5}', '\u{313}', '\u{301}']),
+        ('\u{1f56}', ['\u{3a5}', '\u{313}', '\u{342}']), ('\u{1f80}', ['\u{1f08}', '\u{399}', '\0']),
+        ('\u{1f81}', ['\u{1f09}', '\u{399}', '\0']), ('\u{1f82}', ['\u{1f0a}', '\u{399}', '\0']),
+        ('\u{1f83}', ['\u{1f0b}', '\u{399}', '\0']), ('\u{1f84}', ['\u{1f0c}', '\u{399}', '\0']),
+        ('\u{1f85}', ['\u{1f0d}', '\u{399}', '\0']), ('\u{1f86}', ['\u{1f0e}', '\u{399}', '\0']),
+        ('\u{1f87}', ['\u{1f0f}', '\u{399}', '\0']), ('\u{1f88}', ['\u{1f08}', '\u{399}', '\0']),
+        ('\u{1f89}', ['\u{1f09}', '\u{399}', '\0']), ('\u{1f8a}', ['\u{1f0a}', '\u{399}', '\0']),
+        ('\u{1f8b}', ['\u{1f0b}', '\u{399}', '\0']), ('\u{1f8c}', ['\u{1f0c}', '\u{399}', '\0']),
+        ('\u{1f8d}', ['\u{1f0d}', '\u{399}', '\0']), ('\u{1f8e}', ['\u{1f0e}', '\u{399}', '\0']),
+        ('\u{1f8f}', ['\u{1f0f}', '\u{399}', '\0']), ('\u{1f90}', ['\u{1f28}', '\u{399}', '\0']),
+        ('\u{1f91}', ['\u{1f29}', '\u{399}', '\0']), ('\u{1f92}', ['\u{1f2a}', '\u{399}', '\0']),
+        ('\u{1f93}', ['\u{1f2b}', '\u{399}', '\0']), ('\u{1f94}', ['\u{1f2c}', '\u{399}', '\0']),
+        ('\u{1f95}', ['\u{1f2d}', '\u{399}', '\0']), ('\u{1f96}', ['\u{1f2e}', '\u{399}', '\0']),
+        ('\u{1f97}', ['\u{1f2f}', '\u{399}', '\0']), ('\u{1f98}', ['\u{1f28}', '\u{399}', '\0']),
+        ('\u{1f99}', ['\u{1f29}', '\u{399}', '\0']), ('\u{1f9a}', ['\u{1f2a}', '\u{399}', '\0']),
+        ('\u{1f9b}', ['\u{1f2b}', '\u{399}', '\0']), ('\u{1f9c}', ['\u{1f2c}', '\u{399}', '\0']),
+        ('\u{1f9d}', ['\u{1f2d}', '\u{399}', '\0']), ('\u{1f9e}', ['\u{1f2e}', '\u{399}', '\0']),
+        ('\u{1f9f}', ['\u{1f2f}', '\u{399}', '\0']), ('\u{1fa0}', ['\u{1f68}', '\u{399}', '\0']),
+        ('\u{1fa1}', ['\u{1f69}', '\u{399}', '\0']), ('\u{1fa2}', ['\u{1f6a}', '\u{399}', '\0']),
+        ('\u{1fa3}', ['\u{1f6b}', '\u{399}', '\0']), ('\u{1fa4}', ['\u{1f6c}', '\u{399}', '\0']),
+        ('\u{1fa5}', ['\u{1f6d}', '\u{399}', '\0']), ('\u{1fa6}', ['\u{1f6e}', '\u{399}', '\0']),
+        ('\u{1fa7}', ['\u{1f6f}', '\u{399}', '\0']), ('\u{1fa8}', ['\u{1f68}', '\u{399}', '\0']),
+        ('\u{1fa9}', ['\u{1f69}', '\u{399}', '\0']), ('\u{1faa}', ['\u{1f6a}', '\u{399}', '\0']),
+        ('\u{1fab}', ['\u{1f6b}', '\u{399}', '\0']), ('\u{1fac}', ['\u{1f6c}', '\u{399}', '\0']),
+        ('\u{1fad}', ['\u{1f6d}', '\u{399}', '\0']), ('\u{1fae}', ['\u{1f6e}', '\u{399}', '\0']),
+        ('\u{1faf}', ['\u{1f6f}', '\u{399}', '\0']), ('\u{1fb2}', ['\u{1fba}', '\u{399}', '\0']),
+        ('\u{1fb3}', ['\u{391}', '\u{399}', '\0']), ('\u{1fb4}', ['\u{386}', '\u{399}', '\0']),
+        ('\u{1fb6}', ['\u{391}', '\u{342}', '\0']), ('\u{1fb7}', ['\u{391}', '\u{342}', '\u{399}']),
+        ('\u{1fbc}', ['\u{391}', '\u{399}', '\0']), ('\u{1fc2}', ['\u{1fca}', '\u{399}', '\0']),
+        ('\u{1fc3}', ['\u{397}', '\u{399}', '\0']), ('\u{1fc4}', ['\u{389}', '\u{399}', '\0']),
+        ('\u{1fc6}', ['\u{397}', '\u{342}', '\0']), ('\u{1fc7}', ['\u{397}', '\u{342}', '\u{399}']),
+        ('\u{1fcc}', ['\u{397}', '\u{399}', '\0']), ('\u{1fd2}', ['\u{399}', '\u{308}', '\u{300}']),
+        ('\u{1fd3}', ['\u{399}', '\u{308}', '\u{301}']), ('\u{1fd6}', ['\u{399}', '\u{342}', '\0']),
+        ('\u{1fd7}', ['\u{399}', '\u{308}', '\u{342}']), ('\u{1fe2}', ['\u{3a5}', '\u{308}', '\u{300}']),
+        ('\u{1fe3}', ['\u{3a5}', '\u{308}', '\u{301}']), ('\u{1fe4}', ['\u{3a1}', '\u{313}', '\0']),
+        ('\u{1fe6}', ['\u{3a5}', '\u{342}', '\0']), ('\u{1fe7}', ['\u{3a5}', '\u{308}', '\u{342}']),
+        ('\u{1ff2}', ['\u{1ffa}', '\u{399}', '\0']), ('\u{1ff3}', ['\u{3a9}', '\u{399}', '\0']),
+        ('\u{1ff4}', ['\u{38f}', '\u{399}', '\0']), ('\u{1ff6}', ['\u{3a9}', '\u{342}', '\0']),
+        ('\u{1ff7}', ['\u{3a9}', '\u{342}', '\u{399}']), ('\u{1ffc}', ['\u{3a9}', '\u{399}', '\0']),
+        ('\u{fb00}', ['\u{46}', '\u{46}', '\0']), ('\u{fb01}', ['\u{46}', '\u{49}', '\0']),
+        ('\u{fb02}', ['\u{46}', '\u{4c}', '\0']), ('\u{fb03}', ['\u{46}', '\u{46}', '\u{49}']),
+        ('\u{fb04}', ['\u{46}', '\u{46}', '\u{4c}']), ('\u{fb05}', ['\u{53}', '\u{54}', '\0']),
+        ('\u{fb06}', ['\u{53}', '\u{54}', '\0']), ('\u{fb13}', ['\u{544}', '\u{546}', '\0']),
+        ('\u{fb14}', ['\u{544}', '\u{535}', '\0']), ('\u{fb15}', ['\u{544}', '\u{53b}', '\0']),
+        ('\u{fb16}', ['\u{54e}', '\u{546}', '\0']), ('\u{fb17}', ['\u{544}', '\u{53d}', '\0']),
+    ],
+    runs: &[
+        (0x61, 52, -32), (0xb5, 2, 743), (0xe0, 46, -32), (0xf8, 14, -32),
+        (0xff, 2, 121), (0x101, 49, -1), (0x131, 2, -232), (0x133, 7, -1),
+        (0x13a, 17, -1), (0x14b, 47, -1), (0x17a, 7, -1), (0x17f, 2, -300),
+        (0x180, 2, 195), (0x183, 5, -1), (0x188, 2, -1), (0x18c, 2, -1),
+        (0x192, 2, -1), (0x195, 2, 97), (0x199, 2, -1), (0x19a, 2, 163),
+        (0x19b, 2, 42561), (0x19e, 2, 130), (0x1a1, 7, -1), (0x1a8, 2, -1),
+        (0x1ad, 2, -1), (0x1b0, 2, -1), (0x1b4, 5, -1), (0x1b9, 2, -1),
+        (0x1bd, 2, -1), (0x1bf, 2, 56), (0x1c5, 2, -1), (0x1c6, 2, -2),
+        (0x1c8, 2, -1), (0x1c9, 2, -2), (0x1cb, 2, -1), (0x1cc, 2, -2),
+        (0x1ce, 17, -1), (0x1dd, 2, -79), (0x1df, 19, -1), (0x1f2, 2, -1),
+        (0x1f3, 2, -2), (0x1f5, 2, -1), (0x1f9, 41, -1), (0x223, 19, -1),
+        (0x23c, 2, -1), (0x23f, 4, 10815), (0x242, 2, -1), (0x247, 11, -1),
+        (0x250, 2, 10783), (0x251, 2, 10780), (0x252, 2, 10782), (0x253, 2, -210),
+        (0x254, 2, -206), (0x256, 4, -205), (0x259, 2, -202), (0x25b, 2, -203),
+        (0x25c, 2, 42319), (0x260, 2, -205), (0x261, 2, 42315), (0x263, 2, -207),
+        (0x264, 2, 42343), (0x265, 2, 42280), (0x266, 2, 42308), (0x268, 2, -209),
+        (0x269, 2, -211), (0x26a, 2, 42308), (0x26b, 2, 10743), (0x26c, 2, 42305),
+        (0x26f, 2, -211), (0x271, 2, 10749), (0x272, 2, -213), (0x275, 2, -214),
+        (0x27d, 2, 10727), (0x280, 2, -218), (0x282, 2, 42307), (0x283, 2, -218),
+        (0x287, 2, 42282), (0x288, 2, -218), (0x289, 2, -69), (0x28a, 4, -217),
+        (0x28c, 2, -71), (0x292, 2, -219), (0x29d, 2, 42261), (0x29e, 2, 42258),
+        (0x345, 2, 84), (0x371, 5, -1), (0x377, 2, -1), (0x37b, 6, 130),
+        (0x3ac, 2, -38), (0x3ad, 6, -37), (0x3b1, 34, -32), (0x3c2, 2, -31),
+        (0x3c3, 18, -32), (0x3cc, 2, -64), (0x3cd, 4, -63), (0x3d0, 2, -62),
+        (0x3d1, 2, -57), (0x3d5, 2, -47), (0x3d6, 2, -54), (0x3d7, 2, -8),
+        (0x3d9, 25, -1), (0x3f0, 2, -86), (0x3f1, 2, -80), (0x3f2, 2, 7),
+        (0x3f3, 2, -116), (0x3f5, 2, -96), (0x3f8, 2, -1), (0x3fb, 2, -1),
+        (0x430, 64, -32), (0x450, 32, -80), (0x461, 35, -1), (0x48b, 55, -1),
+        (0x4c2, 15, -1), (0x4cf, 2, -15), (0x4d1, 97, -1), (0x561, 76, -48),
+        (0x10d0, 86, 3008), (0x10fd, 6, 3008), (0x13f8, 12, -8), (0x1c80, 2, -6254),
+        (0x1c81, 2, -6253), (0x1c82, 2, -6244), (0x1c83, 4, -6242), (0x1c85, 2, -6243),
+        (0x1c86, 2, -6236), (0x1c87, 2, -6181), (0x1c88, 2, 35266), (0x1c8a, 2, -1),
+        (0x1d79, 2, 35332), (0x1d7d, 2, 3814), (0x1d8e, 2, 35384), (0x1e01, 151, -1),
+        (0x1e9b, 2, -59), (0x1ea1, 97, -1), (0x1f00, 16, 8), (0x1f10, 12, 8),
+        (0x1f20, 16, 8), (0x1f30, 16, 8), (0x1f40, 12, 8), (0x1f51, 9, 8),
+        (0x1f60, 16, 8), (0x1f70, 4, 74), (0x1f72, 8, 86), (0x1f76, 4, 100),
+        (0x1f78, 4, 128), (0x1f7a, 4, 112), (0x1f7c, 4, 126), (0x1fb0, 4, 8),
+        (0x1fbe, 2, -7205), (0x1fd0, 4, 8), (0x1fe0, 4, 8), (0x1fe5, 2, 7),
+        (0x214e, 2, -28), (0x2170, 32, -16), (0x2184, 2, -1), (0x24d0, 52, -26),
+        (0x2c30, 96, -48), (0x2c61, 2, -1), (0x2c65, 2, -10795), (0x2c66, 2, -10792),
+        (0x2c68, 7, -1), (0x2c73, 2, -1), (0x2c76, 2, -1), (0x2c81, 101, -1),
+        (0x2cec, 5, -1), (0x2cf3, 2, -1), (0x2d00, 76, -7264), (0x2d27, 2, -7264),
+        (0x2d2d, 2, -7264), (0xa641, 47, -1), (0xa681, 29, -1), (0xa723, 15, -1),
+        (0xa733, 63, -1), (0xa77a, 5, -1), (0xa77f, 11, -1), (0xa78c, 2, -1),
+        (0xa791, 5, -1), (0xa794, 2, 48), (0xa797, 21, -1), (0xa7b5, 17, -1),
+        (0xa7c8, 5, -1), (0xa7cd, 17, -1), (0xa7f6, 2, -1), (0xab53, 2, -928),
+        (0xab70, 160, -38864), (0xff41, 52, -32), (0x10428, 80, -40), (0x104d8, 72, -40),
+        (0x10597, 22, -39), (0x105a3, 30, -39), (0x105b3, 14, -39), (0x105bb, 4, -39),
+        (0x10cc0, 102, -64), (0x10d70, 44, -32), (0x118c0, 64, -32), (0x16e60, 64, -32),
+        (0x16ebb, 50, -27), (0x1e922, 68, -34),
+    ],
+};
+
+pub(super) static LOWERCASE: CaseTable = CaseTable {
+    multi: &[
+        ('\u{130}', ['\u{69}', '\u{307}', '\0']),
+    ],
+    runs: &[
+        (0x41, 52, 32), (0xc0, 46, 32), (0xd8, 14, 32), (0x100, 49, 1),
+        (0x132, 7, 1), (0x139, 17, 1), (0x14a, 47, 1), (0x178, 2, -121),
+        (0x179, 7, 1), (0x181, 2, 210), (0x182, 5, 1), (0x186, 2, 206),
+        (0x187, 2, 1), (0x189, 4, 205), (0x18b, 2, 1), (0x18e, 2, 79),
+        (0x18f, 2, 202), (0x190, 2, 203), (0x191, 2, 1), (0x193, 2, 205),
+        (0x194, 2, 207), (0x196, 2, 211), (0x197, 2, 209), (0x198, 2, 1),
+        (0x19c, 2, 211), (0x19d, 2, 213), (0x19f, 2, 214), (0x1a0, 7, 1),
+        (0x1a6, 2, 218), (0x1a7, 2, 1), (0x1a9, 2, 218), (0x1ac, 2, 1),
+        (0x1ae, 2, 218), (0x1af, 2, 1), (0x1b1, 4, 217), (0x1b3, 5, 1),
+        (0x1b7, 2, 219), (0x1b8, 2, 1), (0x1bc, 2, 1), (0x1c4, 2, 2),
+        (0x1c5, 2, 1), (0x1c7, 2, 2), (0x1c8, 2, 1), (0x1ca, 2, 2),
+        (0x1cb, 19, 1), (0x1de, 19, 1), (0x1f1, 2, 2), (0x1f2, 5, 1),
+        (0x1f6, 2, -97), (0x1f7, 2, -56), (0x1f8, 41, 1), (0x220, 2, -130),
+        (0x222, 19, 1), (0x23a, 2, 10795), (0x23b, 2, 1), (0x23d, 2, -163),
+        (0x23e, 2, 10792), (0x241, 2, 1), (0x243, 2, -195), (0x244, 2, 69),
+        (0x245, 2, 71), (0x246, 11, 1), (0x370, 5, 1), (0x376, 2, 1),
+        (0x37f, 2, 116), (0x386, 2, 38), (0x388, 6, 37), (0x38c, 2, 64),
+        (0x38e, 4, 63), (0x391, 34, 32), (0x3a3, 18, 32), (0x3cf, 2, 8),
+        (0x3d8, 25, 1), (0x3f4, 2, -60), (0x3f7, 2, 1), (0x3f9, 2, -7),
+        (0x3fa, 2, 1), (0x3fd, 6, -130), (0x400, 32, 80), (0x410, 64, 32),
+        (0x460, 35, 1), (0x48a, 55, 1), (0x4c0, 2, 15), (0x4c1, 15, 1),
+        (0x4d0, 97, 1), (0x531, 76, 48), (0x10a0, 76, 7264), (0x10c7, 2, 7264),
+        (0x10cd, 2, 7264), (0x13a0, 160, 38864), (0x13f0, 12, 8), (0x1c89, 2, 1),
+        (0x1c90, 86, -3008), (0x1cbd, 6, -3008), (0x1e00, 151, 1), (0x1e9e, 2, -7615),
+        (0x1ea0, 97, 1), (0x1f08, 16, -8), (0x1f18, 12, -8), (0x1f28, 16, -8),
+        (0x1f38, 16, -8), (0x1f48, 12, -8), (0x1f59, 9, -8), (0x1f68, 16, -8),
+        (0x1f88, 16, -8), (0x1f98, 16, -8), (0x1fa8, 16, -8), (0x1fb8, 4, -8),
+        (0x1fba, 4, -74), (0x1fbc, 2, -9), (0x1fc8, 8, -86), (0x1fcc, 2, -9),
+        (0x1fd8, 4, -8), (0x1fda, 4, -100), (0x1fe8, 4, -8), (0x1fea, 4, -112),
+        (0x1fec, 2, -7), (0x1ff8, 4, -128), (0x1ffa, 4, -126), (0x1ffc, 2, -9),
+        (0x2126, 2, -7517), (0x212a, 2, -8383), (0x212b, 2, -8262), (0x2132, 2, 28),
+        (0x2160, 32, 16), (0x2183, 2, 1), (0x24b6, 52, 26), (0x2c00, 96, 48),
+        (0x2c60, 2, 1), (0x2c62, 2, -10743), (0x2c63, 2, -3814), (0x2c64, 2, -10727),
+        (0x2c67, 7, 1), (0x2c6d, 2, -10780), (0x2c6e, 2, -10749), (0x2c6f, 2, -10783),
+        (0x2c70, 2, -10782), (0x2c72, 2, 1), (0x2c75, 2, 1), (0x2c7e, 4, -10815),
+        (0x2c80, 101, 1), (0x2ceb, 5, 1), (0x2cf2, 2, 1), (0xa640, 47, 1),
+        (0xa680, 29, 1), (0xa722, 15, 1), (0xa732, 63, 1), (0xa779, 5, 1),
+        (0xa77d, 2, -35332), (0xa77e, 11, 1), (0xa78b, 2, 1), (0xa78d, 2, -42280),
+        (0xa790, 5, 1), (0xa796, 21, 1), (0xa7aa, 2, -42308), (0xa7ab, 2, -42319),
+        (0xa7ac, 2, -42315), (0xa7ad, 2, -42305), (0xa7ae, 2, -42308), (0xa7b0, 2, -42258),
+        (0xa7b1, 2, -42282), (0xa7b2, 2, -42261), (0xa7b3, 2, 928), (0xa7b4, 17, 1),
+        (0xa7c4, 2, -48), (0xa7c5, 2, -42307), (0xa7c6, 2, -35384), (0xa7c7, 5, 1),
+        (0xa7cb, 2, -42343), (0xa7cc, 17, 1), (0xa7dc, 2, -42561), (0xa7f5, 2, 1),
+        (0xff21, 52, 32), (0x10400, 80, 40), (0x104b0, 72, 40), (0x10570, 22, 39),
+        (0x1057c, 30, 39), (0x1058c, 14, 39), (0x10594, 4, 39), (0x10c80, 102, 64),
+        (0x10d50, 44, 32), (0x118a0, 64, 32), (0x16e40, 64, 32), (0x16ea0, 50, 27),
+        (0x1e900, 68, 34),
+    ],
+};
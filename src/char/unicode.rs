@@ -0,0 +1,75 @@
+//! The shapes of the generated tables in `tables.rs`, and their lookups.
+
+/// A set of code points, stored as the boundaries where membership flips.
+///
+/// Boundary `i` starts a range of the set if `i` is even and ends one if it
+/// is odd. Most boundaries are stored in `deltas` as their distance from the
+/// previous boundary. A boundary more than 255 past the previous one is a
+/// head instead, as is every 32nd boundary so that lookups stay short. Each
+/// entry of `heads` holds the head's code point in its low 21 bits and, in
+/// its high 11 bits, the index in `deltas` of the boundary after it.
+pub(super) struct RunTable {
+    pub(super) heads: &'static [u32],
+    pub(super) deltas: &'static [u8],
+}
+
+impl RunTable {
+    pub(super) fn contains(&self, ch: char) -> bool {
+        let code = u32::from(ch);
+        let heads_before = self.heads.partition_point(|&head| head & 0x1F_FFFF <= code);
+        let head = match heads_before.checked_sub(1) {
+            Some(head) => head,
+            None => return false,
+        };
+        let mut boundary = self.heads[head] & 0x1F_FFFF;
+        let mut delta = (self.heads[head] >> 21) as usize;
+        let end = self
+            .heads
+            .get(head + 1)
+            .map_or(self.deltas.len(), |&next| (next >> 21) as usize);
+        while delta < end {
+            let next = boundary + u32::from(self.deltas[delta]);
+            if next > code {
+                break;
+            }
+            boundary = next;
+            delta += 1;
+        }
+        // Each head before this one has no delta, so the index of the
+        // boundary is the number of deltas and heads before it.
+        (delta + head).is_multiple_of(2)
+    }
+}
+
+/// Case mappings in one direction.
+///
+/// `multi` holds the code points that map to more than one char, sorted,
+/// each with its mapping padded with `'\0'`. Every other mapping is in
+/// `runs`: `(start, count << 1 | spaced, delta)` maps `count` code points
+/// from `start` on, every one if `spaced` is clear and every other one if
+/// it is set, to themselves plus `delta`. Code points in neither table map
+/// to themselves.
+pub(super) struct CaseTable {
+    pub(super) multi: &'static [(char, [char; 3])],
+    pub(super) runs: &'static [(u32, u16, i32)],
+}
+
+impl CaseTable {
+    /// Returns the mapping of `ch`, padded with `'\0'`.
+    pub(super) fn lookup(&self, ch: char) -> [char; 3] {
+        if let Ok(i) = self.multi.binary_search_by_key(&ch, |&(from, _)| from) {
+            return self.multi[i].1;
+        }
+        let code = u32::from(ch);
+        let runs_before = self.runs.partition_point(|&(start, _, _)| start <= code);
+        if let Some(&(start, packed, delta)) = runs_before.checked_sub(1).map(|i| &self.runs[i]) {
+            let (count, step) = (u32::from(packed >> 1), u32::from(packed & 1) + 1);
+            let offset = code - start;
+            if offset.is_multiple_of(step) && offset / step < count {
+                let mapped = (code as i32 + delta) as u32;
+                return [unsafe { std::char::from_u32_unchecked(mapped) }, '\0', '\0'];
+            }
+        }
+        [ch, '\0', '\0']
+    }
+}
@@ -0,0 +1,676 @@
+//! A priority queue implemented as a binary max-heap stored in a [`Vec`].
+//!
+//! The greatest element sits at index 0 and every element is at least as
+//! great as its children at `2 * i + 1` and `2 * i + 2`. [`PeekMut`] lets
+//! the top be modified in place and restores that order when it is dropped.
+//! For a min-heap, wrap the elements in [`std::cmp::Reverse`].
+
+use crate::alloc::{Allocator, Global};
+use crate::raw_vec::TryReserveError;
+use crate::vec::{self, Vec};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+/// A binary max-heap, allocated from `A`.
+///
+/// ```
+/// use mystdrs::collections::BinaryHeap;
+/// use std::cmp::Reverse;
+///
+/// let mut heap = BinaryHeap::from([3, 1, 4, 1, 5]);
+/// assert_eq!(heap.pop(), Some(5));
+/// assert_eq!(heap.peek(), Some(&4));
+///
+/// let mut min = BinaryHeap::new();
+/// min.extend([Reverse(3), Reverse(1), Reverse(2)]);
+/// assert_eq!(min.pop(), Some(Reverse(1)));
+/// ```
+pub struct BinaryHeap<T, A: Allocator = Global> {
+    data: Vec<T, A>,
+}
+
+impl<T> BinaryHeap<T> {
+    /// Creates an empty heap without allocating.
+    pub const fn new() -> Self {
+        BinaryHeap { data: Vec::new() }
+    }
+
+    /// Creates an empty heap with room for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BinaryHeap {
+            data: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, A: Allocator> BinaryHeap<T, A> {
+    /// Creates an empty heap in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        BinaryHeap {
+            data: Vec::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty heap in `alloc` with room for at least `capacity`
+    /// elements.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        BinaryHeap {
+            data: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Fallible version of [`with_capacity_in`](BinaryHeap::with_capacity_in).
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        Ok(BinaryHeap {
+            data: Vec::try_with_capacity_in(capacity, alloc)?,
+        })
+    }
+
+    /// Returns the allocator backing the heap.
+    pub fn allocator(&self) -> &A {
+        self.data.allocator()
+    }
+
+    /// Returns the number of elements the heap can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the greatest element.
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns an iterator over the elements in heap order, which is
+    /// arbitrary apart from the greatest element coming first.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.data.iter(),
+        }
+    }
+
+    /// Returns the elements in heap order.
+    pub fn as_slice(&self) -> &[T] {
+        self.data.as_slice()
+    }
+
+    /// Returns the underlying vector, in heap order.
+    pub fn into_vec(self) -> Vec<T, A> {
+        self.data
+    }
+
+    /// Makes room for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional);
+    }
+
+    /// Fallible version of [`reserve`](BinaryHeap::reserve).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.data.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity to the length.
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+    }
+
+    /// Removes every element, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl<T: Ord, A: Allocator> BinaryHeap<T, A> {
+    /// Returns a guard giving mutable access to the greatest element. The
+    /// heap is reordered when the guard is dropped, if it was written
+    /// through.
+    ///
+    /// ```
+    /// use mystdrs::collections::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from([1, 5, 2]);
+    /// if let Some(mut top) = heap.peek_mut() {
+    ///     *top = 0;
+    /// }
+    /// assert_eq!(heap.peek(), Some(&2));
+    /// ```
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, A>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(PeekMut {
+                heap: self,
+                sift: false,
+            })
+        }
+    }
+
+    /// Adds `item` to the heap in `O(log n)` time.
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        self.sift_up(self.len() - 1);
+    }
+
+    /// Fallible version of [`push`](BinaryHeap::push).
+    pub fn try_push(&mut self, item: T) -> Result<(), TryReserveError> {
+        self.data.try_push(item)?;
+        self.sift_up(self.len() - 1);
+        Ok(())
+    }
+
+    /// Removes and returns the greatest element in `O(log n)` time.
+    pub fn pop(&mut self) -> Option<T> {
+        let mut item = self.data.pop()?;
+        if !self.is_empty() {
+            mem::swap(&mut item, &mut self.data[0]);
+            self.sift_down(0);
+        }
+        Some(item)
+    }
+
+    /// Returns the elements as a vector sorted in ascending order, sorting
+    /// in place in `O(n log n)` time.
+    pub fn into_sorted_vec(mut self) -> Vec<T, A> {
+        for end in (1..self.len()).rev() {
+            self.data.swap(0, end);
+            self.sift_down_range(0, end);
+        }
+        self.data
+    }
+
+    /// Moves every element of `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        if self.len() < other.len() {
+            mem::swap(self, other);
+        }
+        let start = self.len();
+        self.data.reserve(other.len());
+        while let Some(item) = other.data.pop() {
+            self.data.push(item);
+        }
+        self.rebuild_tail(start);
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, visiting them
+    /// in heap order.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let len = self.len();
+        self.data.retain(f);
+        if self.len() < len {
+            self.rebuild();
+        }
+    }
+
+    /// Moves the element at `pos` towards the root until its parent is at
+    /// least as great, and returns where it ends up.
+    fn sift_up(&mut self, mut pos: usize) -> usize {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.data[pos] <= self.data[parent] {
+                break;
+            }
+            self.data.swap(pos, parent);
+            pos = parent;
+        }
+        pos
+    }
+
+    fn sift_down(&mut self, pos: usize) {
+        self.sift_down_range(pos, self.len());
+    }
+
+    /// Moves the element at `pos` towards the leaves until it is at least
+    /// as great as its children, treating only `..end` as the heap.
+    fn sift_down_range(&mut self, mut pos: usize, end: usize) {
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                break;
+            }
+            if child + 1 < end && self.data[child] < self.data[child + 1] {
+                child += 1;
+            }
+            if self.data[pos] >= self.data[child] {
+                break;
+            }
+            self.data.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    /// Restores heap order over the whole vector in `O(n)` time.
+    fn rebuild(&mut self) {
+        let len = self.len();
+        for pos in (0..len / 2).rev() {
+            self.sift_down_range(pos, len);
+        }
+    }
+
+    /// Restores heap order after elements were pushed onto the vector from
+    /// `start` on, by sifting each one up or by rebuilding, whichever needs
+    /// fewer comparisons.
+    fn rebuild_tail(&mut self, start: usize) {
+        let len = self.len();
+        let tail = len - start;
+        // Sifting up costs about `log2(len)` comparisons per new element,
+        // a rebuild about `2 * len` in total.
+        let log2 = (usize::BITS - len.leading_zeros()) as usize;
+        if tail.saturating_mul(log2) < 2 * len {
+            for pos in start..len {
+                self.sift_up(pos);
+            }
+        } else {
+            self.rebuild();
+        }
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for BinaryHeap<T, A> {
+    fn clone(&self) -> Self {
+        BinaryHeap {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for BinaryHeap<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Default for BinaryHeap<T> {
+    fn default() -> Self {
+        BinaryHeap::new()
+    }
+}
+
+impl<T: Ord, A: Allocator> Extend<T> for BinaryHeap<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.len();
+        self.data.extend(iter);
+        self.rebuild_tail(start);
+    }
+}
+
+impl<'a, T: Ord + Copy + 'a, A: Allocator> Extend<&'a T> for BinaryHeap<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        BinaryHeap::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+impl<T: Ord, A: Allocator> From<Vec<T, A>> for BinaryHeap<T, A> {
+    /// Turns `vec` into a heap in place, in `O(n)` time.
+    fn from(vec: Vec<T, A>) -> Self {
+        let mut heap = BinaryHeap { data: vec };
+        heap.rebuild();
+        heap
+    }
+}
+
+impl<T: Ord, const N: usize> From<[T; N]> for BinaryHeap<T> {
+    fn from(arr: [T; N]) -> Self {
+        BinaryHeap::from(Vec::from(arr))
+    }
+}
+
+impl<T, A: Allocator> From<BinaryHeap<T, A>> for Vec<T, A> {
+    fn from(heap: BinaryHeap<T, A>) -> Self {
+        heap.data
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a BinaryHeap<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for BinaryHeap<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    /// Returns the elements in heap order.
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter {
+            iter: self.data.into_iter(),
+        }
+    }
+}
+
+/// Mutable access to the greatest element of a heap, created by
+/// [`BinaryHeap::peek_mut`].
+///
+/// Dropping the guard after writing through it sifts the element down to
+/// its place. Leaking the guard with [`mem::forget`] instead leaves the heap
+/// unordered, which is safe but makes later results unspecified.
+pub struct PeekMut<'a, T: Ord, A: Allocator = Global> {
+    heap: &'a mut BinaryHeap<T, A>,
+    /// Set once the element may have changed.
+    sift: bool,
+}
+
+impl<T: Ord, A: Allocator> PeekMut<'_, T, A> {
+    /// Removes the peeked element from the heap and returns it.
+    pub fn pop(mut this: Self) -> T {
+        // `pop` reorders the heap itself.
+        this.sift = false;
+        this.heap.pop().unwrap()
+    }
+}
+
+impl<T: Ord, A: Allocator> Deref for PeekMut<'_, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.data[0]
+    }
+}
+
+impl<T: Ord, A: Allocator> DerefMut for PeekMut<'_, T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.sift = true;
+        &mut self.heap.data[0]
+    }
+}
+
+impl<T: Ord, A: Allocator> Drop for PeekMut<'_, T, A> {
+    fn drop(&mut self) {
+        if self.sift {
+            self.heap.sift_down(0);
+        }
+    }
+}
+
+impl<T: Ord + fmt::Debug, A: Allocator> fmt::Debug for PeekMut<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PeekMut").field(&**self).finish()
+    }
+}
+
+/// An iterator over the elements of a heap in heap order, created by
+/// [`BinaryHeap::iter`].
+pub struct Iter<'a, T> {
+    iter: slice::Iter<'a, T>,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An owning iterator over the elements of a heap in heap order, created
+/// by `BinaryHeap::into_iter`.
+pub struct IntoIter<T, A: Allocator = Global> {
+    iter: vec::IntoIter<T, A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter")
+            .field(&self.iter.as_slice())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryHeap, PeekMut};
+    use crate::alloc::{Counting, Global};
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::cmp::Reverse;
+    use std::rc::Rc;
+
+    fn is_heap<T: Ord>(heap: &BinaryHeap<T>) -> bool {
+        let data = heap.as_slice();
+        (1..data.len()).all(|i| data[(i - 1) / 2] >= data[i])
+    }
+
+    #[test]
+    fn matches_std() {
+        let mut rng = XorShift::new(17);
+        let mut heap = BinaryHeap::new();
+        let mut model = std::collections::BinaryHeap::new();
+        for round in 0..20_000 {
+            // Alternate growing and shrinking phases.
+            let grow = (round / 2000) % 2 == 0;
+            match rng.below(10) {
+                0..=5 if grow => {
+                    let v = rng.below(1000) as u32;
+                    heap.push(v);
+                    model.push(v);
+                }
+                0..=1 => {
+                    let v = rng.below(1000) as u32;
+                    heap.push(v);
+                    model.push(v);
+                }
+                6 => {
+                    let v = rng.below(1000) as u32;
+                    if let (Some(mut a), Some(mut b)) = (heap.peek_mut(), model.peek_mut()) {
+                        *a = v;
+                        *b = v;
+                    }
+                }
+                _ => assert_eq!(heap.pop(), model.pop()),
+            }
+            assert_eq!(heap.peek(), model.peek());
+            assert_eq!(heap.len(), model.len());
+        }
+        assert!(is_heap(&heap));
+        assert_eq!(heap.into_sorted_vec(), model.into_sorted_vec()[..]);
+    }
+
+    #[test]
+    fn peek_mut_sifts_only_when_written() {
+        let mut heap = BinaryHeap::from([5, 3, 4, 1]);
+        {
+            let top = heap.peek_mut().unwrap();
+            assert_eq!(*top, 5);
+        }
+        assert_eq!(heap.as_slice(), [5, 3, 4, 1]);
+        *heap.peek_mut().unwrap() = 2;
+        assert!(is_heap(&heap));
+        assert_eq!(heap.peek(), Some(&4));
+        let top = heap.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(top), 4);
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3]);
+        assert!(BinaryHeap::<u32>::new().peek_mut().is_none());
+    }
+
+    #[test]
+    fn heapify_extend_and_append() {
+        let mut rng = XorShift::new(3);
+        for (a_len, b_len) in [(0, 0), (1, 0), (0, 7), (100, 3), (3, 100), (500, 500)] {
+            let a: Vec<u32> = (0..a_len).map(|_| rng.below(100) as u32).collect();
+            let b: Vec<u32> = (0..b_len).map(|_| rng.below(100) as u32).collect();
+            let mut expected: Vec<u32> = a.iter().chain(&b).copied().collect();
+            expected.sort_unstable();
+
+            let mut heap: BinaryHeap<u32> = a.iter().copied().collect();
+            let mut other: BinaryHeap<u32> = b.iter().copied().collect();
+            assert!(is_heap(&heap) && is_heap(&other));
+            heap.append(&mut other);
+            assert!(other.is_empty());
+            assert!(is_heap(&heap));
+            assert_eq!(heap.into_sorted_vec(), expected[..]);
+
+            let mut heap: BinaryHeap<u32> = a.iter().copied().collect();
+            heap.extend(&b);
+            assert!(is_heap(&heap));
+            assert_eq!(heap.into_sorted_vec(), expected[..]);
+        }
+    }
+
+    #[test]
+    fn retain_restores_order() {
+        let mut heap: BinaryHeap<u32> = (0..100).collect();
+        heap.retain(|v| v % 3 != 0);
+        assert!(is_heap(&heap));
+        assert_eq!(heap.len(), 66);
+        assert_eq!(heap.pop(), Some(98));
+        let sorted = heap.into_sorted_vec();
+        assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn min_heap_with_reverse() {
+        let mut heap = BinaryHeap::new();
+        for v in [5, 1, 8, 3, 2] {
+            heap.push(Reverse(v));
+        }
+        let order: Vec<u32> = std::iter::from_fn(|| heap.pop().map(|Reverse(v)| v)).collect();
+        assert_eq!(order, [1, 2, 3, 5, 8]);
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let count = Rc::new(Cell::new(0));
+        let counting = Counting::new(Global);
+        {
+            struct Keyed {
+                key: u32,
+                _drop: DropCounter,
+            }
+
+            impl PartialEq for Keyed {
+                fn eq(&self, other: &Self) -> bool {
+                    self.key == other.key
+                }
+            }
+            impl Eq for Keyed {}
+            impl PartialOrd for Keyed {
+                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+            impl Ord for Keyed {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    self.key.cmp(&other.key)
+                }
+            }
+
+            let mut heap = BinaryHeap::new_in(&counting);
+            for v in 0..50 {
+                heap.push(Keyed {
+                    key: v * 7 % 50,
+                    _drop: DropCounter::new(&count, false),
+                });
+            }
+            drop(heap.pop());
+            heap.retain(|k| k.key % 2 == 0);
+            assert_eq!(count.get(), 1 + 24);
+            let mut iter = heap.into_iter();
+            drop(iter.next());
+            assert_eq!(count.get(), 26);
+        }
+        assert_eq!(count.get(), 50);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn try_push_reports_failure() {
+        let budget = Budget::new(16);
+        let mut heap = BinaryHeap::new_in(&budget);
+        while heap.try_push(1u32).is_ok() {}
+        assert_eq!(heap.len(), 4);
+        assert!(heap.try_reserve(1).is_err());
+        assert_eq!(heap.pop(), Some(1));
+    }
+
+    #[test]
+    fn traits() {
+        let heap = BinaryHeap::from([1, 3, 2]);
+        assert_eq!(format!("{:?}", heap), "[3, 1, 2]");
+        assert_eq!(
+            format!("{:?}", heap.clone().into_iter()),
+            "IntoIter([3, 1, 2])"
+        );
+        assert_eq!(heap.iter().len(), 3);
+        assert_eq!(crate::vec::Vec::from(heap).as_slice(), [3, 1, 2]);
+        assert!(BinaryHeap::<u32>::default().is_empty());
+    }
+}
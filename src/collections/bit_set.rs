@@ -0,0 +1,486 @@
+//! A set of small integers stored as a [`BitVec`], `BitSet`.
+//!
+//! Value `n` is in the set when bit `n` is set. The bit vector grows to
+//! cover the largest value inserted and never shrinks on removal, so two
+//! equal sets may have backing vectors of different lengths; equality and
+//! hashing ignore the clear bits at the end. The set operations work a
+//! word at a time, and the in-place ones report whether anything changed,
+//! which is what a dataflow fixpoint loop needs.
+
+use super::bit_vec::{BitVec, Ones, WORD_BITS};
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::TryReserveError;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// A set of `usize` values backed by a bit vector.
+///
+/// ```
+/// use mystdrs::collections::BitSet;
+///
+/// let mut live = BitSet::from([1, 4, 70]);
+/// let used = BitSet::from([2, 4]);
+/// assert!(live.union_with(&used));
+/// assert!(!live.union_with(&used));
+/// assert_eq!(live.iter().collect::<Vec<_>>(), [1, 2, 4, 70]);
+/// ```
+pub struct BitSet<A: Allocator = Global> {
+    bits: BitVec<A>,
+}
+
+impl BitSet {
+    /// Constructs a new, empty `BitSet` without allocating.
+    pub const fn new() -> Self {
+        BitSet::new_in(Global)
+    }
+
+    /// Constructs a new, empty `BitSet` that can hold values below
+    /// `nbits` without reallocating.
+    pub fn with_capacity(nbits: usize) -> Self {
+        BitSet::with_capacity_in(nbits, Global)
+    }
+}
+
+impl<A: Allocator> BitSet<A> {
+    /// Constructs a new, empty `BitSet` in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        BitSet {
+            bits: BitVec::new_in(alloc),
+        }
+    }
+
+    /// Constructs a new, empty `BitSet` in `alloc` that can hold values
+    /// below `nbits` without reallocating.
+    pub fn with_capacity_in(nbits: usize, alloc: A) -> Self {
+        BitSet {
+            bits: BitVec::with_capacity_in(nbits, alloc),
+        }
+    }
+
+    /// Constructs a set holding the indices of the set bits of `bits`.
+    pub fn from_bit_vec(bits: BitVec<A>) -> Self {
+        BitSet { bits }
+    }
+
+    /// Returns the backing bit vector.
+    pub fn into_bit_vec(self) -> BitVec<A> {
+        self.bits
+    }
+
+    /// Returns a reference to the backing bit vector.
+    pub fn as_bit_vec(&self) -> &BitVec<A> {
+        &self.bits
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.bits.allocator()
+    }
+
+    /// Returns the number of values the set can hold without
+    /// reallocating, counting from zero.
+    pub fn capacity(&self) -> usize {
+        self.bits.capacity()
+    }
+
+    /// Returns the number of values in the set. This counts the set bits,
+    /// so it takes time linear in the largest value ever inserted.
+    pub fn len(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Returns `true` if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        !self.bits.any()
+    }
+
+    /// Removes every value, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.bits.fill(false);
+    }
+
+    /// Shrinks the backing vector to the largest value in the set and
+    /// frees the unused capacity.
+    pub fn shrink_to_fit(&mut self) {
+        let used = self.bits.as_words().iter().rposition(|&w| w != 0);
+        let nbits = used.map_or(0, |i| (i + 1) * WORD_BITS).min(self.bits.len());
+        self.bits.truncate(nbits);
+        self.bits.shrink_to_fit();
+    }
+
+    /// Returns `true` if the set contains `value`.
+    pub fn contains(&self, value: usize) -> bool {
+        self.bits.get(value).unwrap_or(false)
+    }
+
+    /// Adds `value` to the set, growing the bit vector to cover it.
+    /// Returns whether the value was newly inserted.
+    pub fn insert(&mut self, value: usize) -> bool {
+        if value >= self.bits.len() {
+            let nbits = value.checked_add(1).expect("capacity overflow");
+            self.bits.resize(nbits, false);
+        } else if self.contains(value) {
+            return false;
+        }
+        self.bits.set(value, true);
+        true
+    }
+
+    /// Fallible version of [`insert`](BitSet::insert): returns an error
+    /// instead of aborting if the bit vector cannot grow to cover `value`.
+    /// On error the set is unchanged.
+    pub fn try_insert(&mut self, value: usize) -> Result<bool, TryReserveError> {
+        if value >= self.bits.len() {
+            let nbits = value
+                .checked_add(1)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+            self.bits.try_reserve(nbits - self.bits.len())?;
+        }
+        Ok(self.insert(value))
+    }
+
+    /// Removes `value` from the set. Returns whether it was present.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let present = self.contains(value);
+        if present {
+            self.bits.set(value, false);
+        }
+        present
+    }
+
+    /// Returns an iterator over the values in increasing order.
+    pub fn iter(&self) -> Ones<'_> {
+        self.bits.ones()
+    }
+
+    /// Returns the smallest value in the set.
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    /// Returns the largest value in the set.
+    pub fn last(&self) -> Option<usize> {
+        let words = self.bits.as_words();
+        let i = words.iter().rposition(|&w| w != 0)?;
+        Some(i * WORD_BITS + (WORD_BITS - 1 - words[i].leading_zeros() as usize))
+    }
+
+    /// Grows the backing vector to at least the length of `other`'s.
+    fn cover<B: Allocator>(&mut self, other: &BitSet<B>) {
+        let nbits = other.bits.len();
+        if nbits > self.bits.len() {
+            self.bits.resize(nbits, false);
+        }
+    }
+
+    /// Combines each word of `self` with the matching word of `other`
+    /// (zero past its end) and reports whether any word changed.
+    fn combine<B, F>(&mut self, other: &BitSet<B>, f: F) -> bool
+    where
+        B: Allocator,
+        F: Fn(u64, u64) -> u64,
+    {
+        let theirs = other.bits.as_words();
+        let mut changed = false;
+        for (i, word) in self.bits.words_mut().iter_mut().enumerate() {
+            let new = f(*word, theirs.get(i).copied().unwrap_or(0));
+            changed |= new != *word;
+            *word = new;
+        }
+        changed
+    }
+
+    /// Adds every value of `other` to `self`. Returns whether `self`
+    /// changed.
+    pub fn union_with<B: Allocator>(&mut self, other: &BitSet<B>) -> bool {
+        self.cover(other);
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Removes the values of `self` that are not in `other`. Returns
+    /// whether `self` changed.
+    pub fn intersect_with<B: Allocator>(&mut self, other: &BitSet<B>) -> bool {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Removes the values of `other` from `self`. Returns whether `self`
+    /// changed.
+    pub fn difference_with<B: Allocator>(&mut self, other: &BitSet<B>) -> bool {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Keeps the values in exactly one of `self` and `other`. Returns
+    /// whether `self` changed.
+    pub fn symmetric_difference_with<B: Allocator>(&mut self, other: &BitSet<B>) -> bool {
+        self.cover(other);
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// Returns `true` if `self` and `other` have no values in common.
+    pub fn is_disjoint<B: Allocator>(&self, other: &BitSet<B>) -> bool {
+        let (ours, theirs) = (self.bits.as_words(), other.bits.as_words());
+        ours.iter().zip(theirs).all(|(a, b)| a & b == 0)
+    }
+
+    /// Returns `true` if every value of `self` is in `other`.
+    pub fn is_subset<B: Allocator>(&self, other: &BitSet<B>) -> bool {
+        let theirs = other.bits.as_words();
+        self.bits
+            .as_words()
+            .iter()
+            .enumerate()
+            .all(|(i, a)| a & !theirs.get(i).copied().unwrap_or(0) == 0)
+    }
+
+    /// Returns `true` if every value of `other` is in `self`.
+    pub fn is_superset<B: Allocator>(&self, other: &BitSet<B>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns the words up to and including the last non-zero one.
+    fn used_words(&self) -> &[u64] {
+        let words = self.bits.as_words();
+        let used = words.iter().rposition(|&w| w != 0).map_or(0, |i| i + 1);
+        &words[..used]
+    }
+}
+
+impl<A: Allocator + Clone> Clone for BitSet<A> {
+    fn clone(&self) -> Self {
+        BitSet {
+            bits: self.bits.clone(),
+        }
+    }
+}
+
+impl<A: Allocator> fmt::Debug for BitSet<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Default for BitSet {
+    fn default() -> Self {
+        BitSet::new()
+    }
+}
+
+impl<A1: Allocator, A2: Allocator> PartialEq<BitSet<A2>> for BitSet<A1> {
+    fn eq(&self, other: &BitSet<A2>) -> bool {
+        self.used_words() == other.used_words()
+    }
+}
+
+impl<A: Allocator> Eq for BitSet<A> {}
+
+impl<A: Allocator> Hash for BitSet<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.used_words().hash(state);
+    }
+}
+
+impl<A: Allocator> Extend<usize> for BitSet<A> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, A: Allocator> Extend<&'a usize> for BitSet<A> {
+    fn extend<I: IntoIterator<Item = &'a usize>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for BitSet {
+    fn from(arr: [usize; N]) -> Self {
+        let mut set = BitSet::new();
+        set.extend(arr);
+        set
+    }
+}
+
+impl<A: Allocator> From<BitVec<A>> for BitSet<A> {
+    fn from(bits: BitVec<A>) -> Self {
+        BitSet::from_bit_vec(bits)
+    }
+}
+
+impl<'a, A: Allocator> IntoIterator for &'a BitSet<A> {
+    type Item = usize;
+    type IntoIter = Ones<'a>;
+
+    fn into_iter(self) -> Ones<'a> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitSet;
+    use crate::alloc::{Counting, Global};
+    use crate::hash::{BuildHasher, RandomState};
+    use crate::raw_vec::TryReserveError;
+    use crate::test_util::{Budget, XorShift};
+    use std::collections::BTreeSet;
+
+    fn random_set(rng: &mut XorShift, max: usize) -> (BitSet, BTreeSet<usize>) {
+        let mut set = BitSet::new();
+        let mut model = BTreeSet::new();
+        for _ in 0..rng.below(40) {
+            let v = rng.below(max);
+            assert_eq!(set.insert(v), model.insert(v));
+        }
+        (set, model)
+    }
+
+    #[test]
+    fn insert_remove_contains() {
+        let mut set = BitSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(200));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(200));
+        assert!(!set.contains(201));
+        assert!(!set.contains(usize::MAX));
+        assert_eq!(set.first(), Some(3));
+        assert_eq!(set.last(), Some(200));
+        assert!(set.remove(200));
+        assert!(!set.remove(200));
+        assert_eq!(set.last(), Some(3));
+        assert_eq!(set.as_bit_vec().len(), 201);
+        set.shrink_to_fit();
+        assert_eq!(set.as_bit_vec().len(), 64);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3]);
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.last(), None);
+    }
+
+    #[test]
+    fn set_operations_match_std() {
+        let mut rng = XorShift::new(0xb175e7);
+        for _ in 0..300 {
+            let (max_a, max_b) = (1 + rng.below(300), 1 + rng.below(300));
+            let (a, ma) = random_set(&mut rng, max_a);
+            let (b, mb) = random_set(&mut rng, max_b);
+
+            let mut u = a.clone();
+            assert_eq!(u.union_with(&b), !mb.is_subset(&ma));
+            assert_eq!(u.iter().collect::<BTreeSet<_>>(), &ma | &mb);
+
+            let mut i = a.clone();
+            assert_eq!(i.intersect_with(&b), !ma.is_subset(&mb));
+            assert_eq!(i.iter().collect::<BTreeSet<_>>(), &ma & &mb);
+
+            let mut d = a.clone();
+            assert_eq!(d.difference_with(&b), !ma.is_disjoint(&mb));
+            assert_eq!(d.iter().collect::<BTreeSet<_>>(), &ma - &mb);
+
+            let mut s = a.clone();
+            assert_eq!(s.symmetric_difference_with(&b), !mb.is_empty());
+            assert_eq!(s.iter().collect::<BTreeSet<_>>(), &ma ^ &mb);
+
+            assert_eq!(a.is_disjoint(&b), ma.is_disjoint(&mb));
+            assert_eq!(a.is_subset(&b), ma.is_subset(&mb));
+            assert_eq!(a.is_superset(&b), ma.is_superset(&mb));
+            assert_eq!(a == b, ma == mb);
+            assert_eq!(a.len(), ma.len());
+        }
+    }
+
+    #[test]
+    fn equality_ignores_trailing_clear_bits() {
+        let mut a = BitSet::from([1, 500]);
+        a.remove(500);
+        let b = BitSet::from([1]);
+        assert_ne!(a.as_bit_vec().len(), b.as_bit_vec().len());
+        assert_eq!(a, b);
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&a), state.hash_one(&b));
+        assert_eq!(format!("{:?}", a), "{1}");
+    }
+
+    #[test]
+    fn liveness_fixpoint() {
+        // live_in(n) = use(n) | (live_out(n) - def(n)) over a loop
+        // 0 -> 1 -> 2 -> 1, 2 -> 3, iterated until nothing changes.
+        let succ: [&[usize]; 4] = [&[1], &[2], &[1, 3], &[]];
+        let uses: [BitSet; 4] = [
+            BitSet::new(),
+            BitSet::from([0]),
+            BitSet::from([1]),
+            BitSet::from([2]),
+        ];
+        let defs: [BitSet; 4] = [
+            BitSet::from([0, 1]),
+            BitSet::from([2]),
+            BitSet::new(),
+            BitSet::new(),
+        ];
+        let mut live_in: Vec<BitSet> = (0..4).map(|_| BitSet::new()).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for n in (0..4).rev() {
+                let mut out = BitSet::new();
+                for &s in succ[n] {
+                    out.union_with(&live_in[s]);
+                }
+                out.difference_with(&defs[n]);
+                out.union_with(&uses[n]);
+                if out != live_in[n] {
+                    live_in[n] = out;
+                    changed = true;
+                }
+            }
+        }
+        let sets: Vec<Vec<usize>> = live_in.iter().map(|s| s.iter().collect()).collect();
+        assert_eq!(sets, [vec![], vec![0, 1], vec![0, 1, 2], vec![2]]);
+    }
+
+    #[test]
+    fn allocator() {
+        let counting = Counting::new(Global);
+        {
+            let mut a = BitSet::with_capacity_in(128, &counting);
+            a.extend([5, 127].iter());
+            let mut b = a.clone();
+            b.insert(1000);
+            assert!(a.union_with(&b));
+            assert!(a.contains(1000));
+            assert!(std::ptr::eq(*b.allocator(), &counting));
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn try_insert_reports_failure() {
+        let budget = Budget::new(64);
+        let mut set = BitSet::new_in(&budget);
+        assert_eq!(set.try_insert(100), Ok(true));
+        budget.set(0);
+        let cap = set.capacity();
+        assert_eq!(set.try_insert(100), Ok(false));
+        assert_eq!(set.try_insert(cap - 1), Ok(true));
+        assert!(set.try_insert(cap).is_err());
+        assert_eq!(
+            set.try_insert(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(set.iter().collect::<Vec<_>>(), [100, cap - 1]);
+        assert_eq!(set.as_bit_vec().len(), cap);
+    }
+}
@@ -0,0 +1,616 @@
+//! A growable vector of bits packed into `u64` words, `BitVec`.
+//!
+//! Bit `i` is bit `i % 64` of word `i / 64`. The bits of the last word past
+//! the length are always zero, so whole-word operations such as
+//! [`count_ones`](BitVec::count_ones) and equality never have to mask them
+//! out. [`BitSet`](super::BitSet) builds on the same representation.
+
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::{handle_reserve, TryReserveError};
+use crate::vec::Vec;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::{Index, Range, RangeBounds};
+use std::slice;
+
+pub(crate) const WORD_BITS: usize = 64;
+
+/// Returns how many words hold `nbits` bits.
+pub(crate) fn words_for(nbits: usize) -> usize {
+    nbits / WORD_BITS + !nbits.is_multiple_of(WORD_BITS) as usize
+}
+
+/// Returns a word with bits `lo..hi` set; `lo < hi <= 64`.
+fn mask(lo: usize, hi: usize) -> u64 {
+    (u64::MAX >> (WORD_BITS - (hi - lo))) << lo
+}
+
+/// A growable vector of bits.
+///
+/// ```
+/// use mystdrs::collections::BitVec;
+///
+/// let mut bits = BitVec::from_elem(10, false);
+/// bits.set_range(2..5, true);
+/// bits.push(true);
+/// assert_eq!(bits.count_ones(), 4);
+/// assert_eq!(bits.ones().collect::<Vec<_>>(), [2, 3, 4, 10]);
+/// ```
+pub struct BitVec<A: Allocator = Global> {
+    words: Vec<u64, A>,
+    nbits: usize,
+}
+
+impl BitVec {
+    /// Constructs a new, empty `BitVec` without allocating.
+    pub const fn new() -> Self {
+        BitVec::new_in(Global)
+    }
+
+    /// Constructs a new, empty `BitVec` with room for at least `nbits`
+    /// bits.
+    pub fn with_capacity(nbits: usize) -> Self {
+        BitVec::with_capacity_in(nbits, Global)
+    }
+
+    /// Constructs a `BitVec` of `nbits` bits, all set to `value`.
+    pub fn from_elem(nbits: usize, value: bool) -> Self {
+        let mut bits = BitVec::with_capacity(nbits);
+        bits.resize(nbits, value);
+        bits
+    }
+}
+
+impl<A: Allocator> BitVec<A> {
+    /// Constructs a new, empty `BitVec` in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        BitVec {
+            words: Vec::new_in(alloc),
+            nbits: 0,
+        }
+    }
+
+    /// Constructs a new, empty `BitVec` in `alloc` with room for at least
+    /// `nbits` bits.
+    pub fn with_capacity_in(nbits: usize, alloc: A) -> Self {
+        BitVec {
+            words: Vec::with_capacity_in(words_for(nbits), alloc),
+            nbits: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.words.allocator()
+    }
+
+    /// Returns the number of bits.
+    pub fn len(&self) -> usize {
+        self.nbits
+    }
+
+    /// Returns `true` if there are no bits.
+    pub fn is_empty(&self) -> bool {
+        self.nbits == 0
+    }
+
+    /// Returns the number of bits the vector can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.words.capacity().saturating_mul(WORD_BITS)
+    }
+
+    /// Returns the backing words. Bits past the length are zero.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Reserves capacity for at least `additional` more bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve(additional));
+    }
+
+    /// Tries to reserve capacity for at least `additional` more bits,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let nbits = self
+            .nbits
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        self.words.try_reserve(words_for(nbits) - self.words.len())
+    }
+
+    /// Shrinks the capacity as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.words.shrink_to_fit();
+    }
+
+    /// Returns bit `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.nbits {
+            Some(self.words[index / WORD_BITS] >> (index % WORD_BITS) & 1 == 1)
+        } else {
+            None
+        }
+    }
+
+    /// Sets bit `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.nbits, "index out of bounds");
+        let bit = 1 << (index % WORD_BITS);
+        let word = &mut self.words[index / WORD_BITS];
+        if value {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Sets every bit in `range` to `value`, a word at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn set_range<R>(&mut self, range: R, value: bool)
+    where
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = crate::slice::range(range, self.nbits);
+        if start == end {
+            return;
+        }
+        let (first, last) = (start / WORD_BITS, (end - 1) / WORD_BITS);
+        for (i, word) in self.words[first..=last].iter_mut().enumerate() {
+            let lo = if i == 0 { start % WORD_BITS } else { 0 };
+            let hi = if first + i == last {
+                (end - 1) % WORD_BITS + 1
+            } else {
+                WORD_BITS
+            };
+            if value {
+                *word |= mask(lo, hi);
+            } else {
+                *word &= !mask(lo, hi);
+            }
+        }
+    }
+
+    /// Sets every bit to `value`.
+    pub fn fill(&mut self, value: bool) {
+        self.set_range(.., value);
+    }
+
+    /// Appends a bit.
+    pub fn push(&mut self, value: bool) {
+        if self.nbits.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.nbits += 1;
+        self.set(self.nbits - 1, value);
+    }
+
+    /// Appends a bit, returning an error instead of aborting if the buffer
+    /// cannot grow.
+    pub fn try_push(&mut self, value: bool) -> Result<(), TryReserveError> {
+        if self.nbits.is_multiple_of(WORD_BITS) {
+            self.words.try_push(0)?;
+        }
+        self.nbits += 1;
+        self.set(self.nbits - 1, value);
+        Ok(())
+    }
+
+    /// Removes the last bit and returns it, or `None` if the vector is
+    /// empty.
+    pub fn pop(&mut self) -> Option<bool> {
+        let value = self.get(self.nbits.checked_sub(1)?)?;
+        self.truncate(self.nbits - 1);
+        Some(value)
+    }
+
+    /// Shortens the vector to `len` bits. Has no effect if `len` is not
+    /// below the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.nbits {
+            return;
+        }
+        self.words.truncate(words_for(len));
+        if !len.is_multiple_of(WORD_BITS) {
+            *self.words.last_mut().unwrap() &= mask(0, len % WORD_BITS);
+        }
+        self.nbits = len;
+    }
+
+    /// Resizes the vector to `len` bits, filling any new ones with
+    /// `value`.
+    pub fn resize(&mut self, len: usize, value: bool) {
+        if len <= self.nbits {
+            self.truncate(len);
+            return;
+        }
+        let old = self.nbits;
+        let words = words_for(len);
+        self.words.reserve(words - self.words.len());
+        while self.words.len() < words {
+            self.words.push(0);
+        }
+        self.nbits = len;
+        if value {
+            self.set_range(old.., true);
+        }
+    }
+
+    /// Removes every bit.
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.nbits = 0;
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns the number of clear bits.
+    pub fn count_zeros(&self) -> usize {
+        self.nbits - self.count_ones()
+    }
+
+    /// Returns `true` if any bit is set.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|&w| w != 0)
+    }
+
+    /// Returns `true` if every bit is set, including when the vector is
+    /// empty.
+    pub fn all(&self) -> bool {
+        self.count_ones() == self.nbits
+    }
+
+    /// Returns an iterator over the bits.
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            bits: self,
+            range: 0..self.nbits,
+        }
+    }
+
+    /// Returns an iterator over the indices of the set bits, in increasing
+    /// order. Clear words are skipped whole.
+    pub fn ones(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    pub(crate) fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.words
+    }
+}
+
+impl<A: Allocator + Clone> Clone for BitVec<A> {
+    fn clone(&self) -> Self {
+        BitVec {
+            words: self.words.clone(),
+            nbits: self.nbits,
+        }
+    }
+}
+
+impl<A: Allocator> fmt::Debug for BitVec<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BitVec(")?;
+        for bit in self {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl Default for BitVec {
+    fn default() -> Self {
+        BitVec::new()
+    }
+}
+
+impl<A1: Allocator, A2: Allocator> PartialEq<BitVec<A2>> for BitVec<A1> {
+    fn eq(&self, other: &BitVec<A2>) -> bool {
+        self.nbits == other.nbits && self.words[..] == other.words[..]
+    }
+}
+
+impl<A: Allocator> Eq for BitVec<A> {}
+
+impl<A: Allocator> Hash for BitVec<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.nbits);
+        u64::hash_slice(&self.words, state);
+    }
+}
+
+impl<A: Allocator> Index<usize> for BitVec<A> {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        match self.get(index) {
+            Some(true) => &true,
+            Some(false) => &false,
+            None => panic!("index out of bounds"),
+        }
+    }
+}
+
+impl<A: Allocator> Extend<bool> for BitVec<A> {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitVec::new();
+        bits.extend(iter);
+        bits
+    }
+}
+
+impl<'a, A: Allocator> IntoIterator for &'a BitVec<A> {
+    type Item = bool;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+/// An iterator over the bits of a [`BitVec`], created by
+/// [`BitVec::iter`].
+pub struct Iter<'a, A: Allocator = Global> {
+    bits: &'a BitVec<A>,
+    range: Range<usize>,
+}
+
+impl<A: Allocator> Clone for Iter<'_, A> {
+    fn clone(&self) -> Self {
+        Iter {
+            bits: self.bits,
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<A: Allocator> Iterator for Iter<'_, A> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        self.range.next().map(|i| self.bits[i])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<A: Allocator> DoubleEndedIterator for Iter<'_, A> {
+    fn next_back(&mut self) -> Option<bool> {
+        self.range.next_back().map(|i| self.bits[i])
+    }
+}
+
+impl<A: Allocator> ExactSizeIterator for Iter<'_, A> {}
+
+impl<A: Allocator> FusedIterator for Iter<'_, A> {}
+
+impl<A: Allocator> fmt::Debug for Iter<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the indices of the set bits in a slice of words,
+/// created by [`BitVec::ones`] and [`BitSet::iter`](super::BitSet::iter).
+#[derive(Clone)]
+pub struct Ones<'a> {
+    words: slice::Iter<'a, u64>,
+    /// The unvisited bits of the current word.
+    word: u64,
+    /// The index of the current word's first bit.
+    base: usize,
+}
+
+impl<'a> Ones<'a> {
+    pub(crate) fn new(words: &'a [u64]) -> Self {
+        let mut words = words.iter();
+        let word = words.next().copied().unwrap_or(0);
+        Ones {
+            words,
+            word,
+            base: 0,
+        }
+    }
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            self.word = *self.words.next()?;
+            self.base += WORD_BITS;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.word &= self.word - 1;
+        Some(self.base + bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let upper = self.words.len().checked_mul(WORD_BITS);
+        let known = self.word.count_ones() as usize;
+        (known, upper.and_then(|n| n.checked_add(known)))
+    }
+}
+
+impl FusedIterator for Ones<'_> {}
+
+impl fmt::Debug for Ones<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitVec;
+    use crate::alloc::{Counting, Global};
+    use crate::hash::{BuildHasher, RandomState};
+    use crate::test_util::{Budget, XorShift};
+
+    #[test]
+    fn push_pop_get_set() {
+        let mut bits = BitVec::new();
+        for i in 0..200 {
+            bits.push(i % 3 == 0);
+        }
+        assert_eq!(bits.len(), 200);
+        assert_eq!(bits.as_words().len(), 4);
+        assert_eq!(bits.count_ones(), 67);
+        assert_eq!(bits.get(3), Some(true));
+        assert_eq!(bits.get(200), None);
+        bits.set(1, true);
+        assert!(bits[1]);
+        for i in (0..200).rev() {
+            assert_eq!(bits.pop(), Some(i % 3 == 0 || i == 1));
+        }
+        assert_eq!(bits.pop(), None);
+        assert!(bits.as_words().is_empty());
+    }
+
+    #[test]
+    fn matches_bool_model() {
+        let mut rng = XorShift::new(0xb17);
+        for _ in 0..100 {
+            let mut bits = BitVec::new();
+            let mut model: std::vec::Vec<bool> = std::vec::Vec::new();
+            for _ in 0..100 {
+                let len = model.len();
+                match rng.below(6) {
+                    0 => {
+                        let n = rng.below(150);
+                        let value = rng.below(2) == 0;
+                        bits.resize(n, value);
+                        model.resize(n, value);
+                    }
+                    1 => {
+                        let a = rng.below(len + 1);
+                        let b = a + rng.below(len - a + 1);
+                        let value = rng.below(2) == 0;
+                        bits.set_range(a..b, value);
+                        model[a..b].iter_mut().for_each(|bit| *bit = value);
+                    }
+                    2 if len > 0 => {
+                        let i = rng.below(len);
+                        bits.set(i, !model[i]);
+                        model[i] = !model[i];
+                    }
+                    3 => {
+                        let n = rng.below(len + 1);
+                        bits.truncate(n);
+                        model.truncate(n);
+                    }
+                    4 => assert_eq!(bits.pop(), model.pop()),
+                    _ => {
+                        let value = rng.below(2) == 0;
+                        bits.push(value);
+                        model.push(value);
+                    }
+                }
+                assert_eq!(bits.iter().collect::<std::vec::Vec<_>>(), model);
+                let ones: std::vec::Vec<usize> = (0..model.len()).filter(|&i| model[i]).collect();
+                assert_eq!(bits.ones().collect::<std::vec::Vec<_>>(), ones);
+                assert_eq!(bits.count_ones(), ones.len());
+                assert_eq!(bits.all(), model.iter().all(|&b| b));
+                assert_eq!(bits.any(), model.iter().any(|&b| b));
+                // The bits past the length stay clear.
+                if let Some(&last) = bits.as_words().last() {
+                    assert_eq!(last.count_ones() as usize, {
+                        let start = (bits.as_words().len() - 1) * 64;
+                        model[start..].iter().filter(|&&b| b).count()
+                    });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_range_within_and_across_words() {
+        let mut bits = BitVec::from_elem(256, false);
+        bits.set_range(3..5, true);
+        assert_eq!(bits.as_words()[0], 0b11000);
+        bits.set_range(60..130, true);
+        assert_eq!(bits.as_words()[1], u64::MAX);
+        assert_eq!(bits.as_words()[2], 0b11);
+        assert_eq!(bits.count_ones(), 72);
+        bits.set_range(64..128, false);
+        assert_eq!(bits.count_ones(), 8);
+        bits.fill(true);
+        assert!(bits.all());
+        bits.set_range(10..10, false);
+        assert_eq!(bits.count_zeros(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 11 out of range for slice of length 10")]
+    fn set_range_out_of_bounds() {
+        BitVec::from_elem(10, false).set_range(5..11, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn set_out_of_bounds() {
+        BitVec::from_elem(64, false).set(64, true);
+    }
+
+    #[test]
+    fn traits_and_allocator() {
+        let a: BitVec = [true, false, true].iter().copied().collect();
+        let mut b = a.clone();
+        assert_eq!(a, b);
+        assert_eq!(format!("{:?}", a), "BitVec(101)");
+        b.push(false);
+        assert_ne!(a, b);
+        b.pop();
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&a), state.hash_one(&b));
+
+        let counting = Counting::new(Global);
+        {
+            let mut bits = BitVec::with_capacity_in(65, &counting);
+            assert_eq!(bits.capacity(), 128);
+            bits.extend((0..65).map(|i| i % 2 == 0));
+            assert_eq!(counting.snapshot().allocations, 1);
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+
+        // The first word allocates room for four.
+        let budget = Budget::new(32);
+        let mut bits = BitVec::new_in(&budget);
+        for _ in 0..256 {
+            bits.try_push(true).unwrap();
+        }
+        assert!(bits.try_push(true).is_err());
+        assert!(bits.try_reserve(1).is_err());
+        assert_eq!(bits.len(), 256);
+    }
+}
@@ -0,0 +1,1942 @@
+//! An ordered map implemented as a B-tree.
+//!
+//! Each node stores up to eleven sorted entries side by side, so a lookup
+//! compares keys within a handful of contiguous nodes instead of chasing a
+//! pointer per comparison as a binary search tree would. Insertion splits
+//! full nodes on the way back up and removal merges or rebalances
+//! underfull ones, keeping every leaf at the same depth.
+
+use super::btree_node::{Handle, NodeRef, SearchResult};
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::TryReserveError;
+use crate::vec::Vec;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{self, FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Bound, Index, RangeBounds};
+use std::ptr;
+
+/// Empties `$map`, returning an iterator that owns its old entries and
+/// frees the old tree. A macro rather than a method so that the iterator
+/// borrows only the allocator, leaving the root free to be refilled.
+macro_rules! take_all {
+    ($map:expr) => {
+        IntoIter::new($map.root.take(), mem::take(&mut $map.length), &$map.alloc)
+    };
+}
+
+/// An ordered map based on a B-tree, allocated from `A`.
+///
+/// Iteration and [`range`](BTreeMap::range) queries visit the entries in
+/// key order.
+///
+/// ```
+/// use mystdrs::collections::BTreeMap;
+///
+/// let mut scores = BTreeMap::new();
+/// scores.insert("carol", 7);
+/// scores.insert("alice", 9);
+/// scores.insert("bob", 4);
+/// *scores.entry("bob").or_insert(0) += 1;
+/// assert_eq!(scores.first_key_value(), Some((&"alice", &9)));
+/// let from_b: Vec<_> = scores.range("b"..).map(|(k, _)| *k).collect();
+/// assert_eq!(from_b, ["bob", "carol"]);
+/// ```
+pub struct BTreeMap<K, V, A: Allocator = Global> {
+    root: Option<NodeRef<K, V>>,
+    length: usize,
+    alloc: A,
+    marker: PhantomData<Box<(K, V)>>,
+}
+
+unsafe impl<K: Send, V: Send, A: Allocator + Send> Send for BTreeMap<K, V, A> {}
+unsafe impl<K: Sync, V: Sync, A: Allocator + Sync> Sync for BTreeMap<K, V, A> {}
+
+impl<K, V> BTreeMap<K, V> {
+    /// Creates an empty map. Does not allocate.
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<K, V, A: Allocator> BTreeMap<K, V, A> {
+    /// Creates an empty map whose nodes will be allocated from `alloc`.
+    pub const fn new_in(alloc: A) -> Self {
+        BTreeMap {
+            root: None,
+            length: 0,
+            alloc,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        drop(take_all!(self));
+    }
+
+    /// Returns an iterator over the entries in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            range: LeafRange::full(self.root),
+            length: self.length,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the entries in key order, with mutable
+    /// references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            range: LeafRange::full(self.root),
+            length: self.length,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the keys in order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Returns an iterator over the values in key order.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Returns an iterator over mutable references to the values in key
+    /// order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Consumes the map, returning an iterator over the keys in order.
+    pub fn into_keys(self) -> IntoKeys<K, V, A> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Consumes the map, returning an iterator over the values in key
+    /// order.
+    pub fn into_values(self) -> IntoValues<K, V, A> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    fn first_kv(&self) -> Option<Handle<K, V>> {
+        self.root?.first_leaf_edge().next_kv()
+    }
+
+    fn last_kv(&self) -> Option<Handle<K, V>> {
+        self.root?.last_leaf_edge().next_back_kv()
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.first_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.last_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+
+    /// Returns the entry with the smallest key, for in-place manipulation.
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, A>> {
+        let handle = self.first_kv()?;
+        Some(OccupiedEntry { handle, map: self })
+    }
+
+    /// Returns the entry with the largest key, for in-place manipulation.
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, A>> {
+        let handle = self.last_kv()?;
+        Some(OccupiedEntry { handle, map: self })
+    }
+
+    /// Removes and returns the entry with the smallest key.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.first_entry().map(OccupiedEntry::remove_entry)
+    }
+
+    /// Removes and returns the entry with the largest key.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.last_entry().map(OccupiedEntry::remove_entry)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, visiting them in
+    /// key order.
+    ///
+    /// The tree is rebuilt from the entries that are kept, in linear time.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let old = take_all!(self);
+        let kept = old.filter_map(|(k, mut v)| if f(&k, &mut v) { Some((k, v)) } else { None });
+        let root = self.root.insert(NodeRef::new_leaf(&self.alloc));
+        root.bulk_push(kept, &mut self.length, &self.alloc);
+    }
+}
+
+impl<K: Ord, V, A: Allocator> BTreeMap<K, V, A> {
+    fn find<Q>(&self, key: &Q) -> Option<Handle<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.root?.search_tree(key) {
+            SearchResult::Found(handle) => Some(handle),
+            SearchResult::GoDown(_) => None,
+        }
+    }
+
+    /// Returns a reference to the value for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// Returns the stored key and the value for `key`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|kv| unsafe { kv.into_kv() })
+    }
+
+    /// Returns `true` if the map holds an entry for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Returns a mutable reference to the value for `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|kv| unsafe { kv.into_kv_mut().1 })
+    }
+
+    /// Inserts `value` for `key` and returns the value it replaces, if any.
+    /// An existing key is kept, not replaced by `key`.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Fallible version of [`insert`](BTreeMap::insert): returns an error
+    /// instead of aborting if a node cannot be allocated. On error `key`
+    /// and `value` are dropped and the map is unchanged.
+    ///
+    /// Like `insert`, this replaces the value of a key already in the map.
+    /// It is not std's unstable `try_insert`, which fails on such a key.
+    pub fn try_insert_or_replace(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<Option<V>, TryReserveError> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Ok(Some(entry.insert(value))),
+            Entry::Vacant(entry) => entry.try_insert(value).map(|_| None),
+        }
+    }
+
+    /// Removes the entry for `key` and returns its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes the entry for `key` and returns the stored key and value.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let handle = self.find(key)?;
+        Some(OccupiedEntry { handle, map: self }.remove_entry())
+    }
+
+    /// Returns the entry for `key`, for in-place manipulation.
+    ///
+    /// ```
+    /// use mystdrs::collections::BTreeMap;
+    ///
+    /// let mut counts = BTreeMap::new();
+    /// for word in "b a b c b".split(' ') {
+    ///     *counts.entry(word).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.into_iter().collect::<Vec<_>>(), [("a", 1), ("b", 3), ("c", 1)]);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, A> {
+        let edge = match self.root.map(|root| root.search_tree(&key)) {
+            Some(SearchResult::Found(handle)) => {
+                return Entry::Occupied(OccupiedEntry { handle, map: self })
+            }
+            Some(SearchResult::GoDown(edge)) => Some(edge),
+            None => None,
+        };
+        Entry::Vacant(VacantEntry {
+            key,
+            edge,
+            map: self,
+        })
+    }
+
+    /// Inserts `key` and `value`, replacing both the key and the value of an
+    /// existing entry, which is returned. Used by `BTreeSet::replace`.
+    pub(super) fn replace_entry(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let edge = match self.root.map(|root| root.search_tree(&key)) {
+            Some(SearchResult::Found(handle)) => {
+                return Some(unsafe { handle.replace_kv(key, value) });
+            }
+            Some(SearchResult::GoDown(edge)) => Some(edge),
+            None => None,
+        };
+        VacantEntry {
+            key,
+            edge,
+            map: self,
+        }
+        .insert(value);
+        None
+    }
+
+    /// Returns a cursor at the gap before the first key inside `bound..`:
+    /// the smallest key at least `x` for `Included(x)`, the smallest key
+    /// above `x` for `Excluded(x)`, or the first key for `Unbounded`.
+    ///
+    /// ```
+    /// use mystdrs::collections::BTreeMap;
+    /// use std::ops::Bound::Included;
+    ///
+    /// let starts = BTreeMap::from([(0, "a"), (10, "b"), (20, "c")]);
+    /// let mut cursor = starts.lower_bound(Included(&12));
+    /// assert_eq!(cursor.peek_prev(), Some((&10, &"b")));
+    /// assert_eq!(cursor.move_next(), Some((&20, &"c")));
+    /// assert_eq!(cursor.move_next(), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            edge: self.root.map(|root| root.lower_bound(bound)),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a cursor at the gap after the last key inside `..bound`:
+    /// the largest key at most `x` for `Included(x)`, the largest key below
+    /// `x` for `Excluded(x)`, or the last key for `Unbounded`.
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            edge: self.root.map(|root| root.upper_bound(bound)),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the entries whose keys lie in `range`, in
+    /// key order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends, or if it starts and ends
+    /// at the same excluded bound.
+    ///
+    /// ```
+    /// use mystdrs::collections::BTreeMap;
+    /// use std::ops::Bound::{Excluded, Included};
+    ///
+    /// let map: BTreeMap<u32, char> = (1..=5).zip("abcde".chars()).collect();
+    /// let inner: String = map.range((Excluded(1), Included(4))).map(|(_, c)| c).collect();
+    /// assert_eq!(inner, "bcd");
+    /// assert_eq!(map.range(..=2).next_back(), Some((&2, &'b')));
+    /// ```
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<T>,
+        T: Ord + ?Sized,
+        R: RangeBounds<T>,
+    {
+        Range {
+            inner: LeafRange::bounded(self.root, &range),
+            marker: PhantomData,
+        }
+    }
+
+    /// Like [`range`](BTreeMap::range), but with mutable references to the
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `range`.
+    pub fn range_mut<T, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+    where
+        K: Borrow<T>,
+        T: Ord + ?Sized,
+        R: RangeBounds<T>,
+    {
+        RangeMut {
+            inner: LeafRange::bounded(self.root, &range),
+            marker: PhantomData,
+        }
+    }
+
+    /// Moves every entry of `other` into `self`, leaving `other` empty.
+    /// Where both maps hold a key, the entry from `other` wins.
+    ///
+    /// Takes time linear in the size of both maps.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            mem::swap(self, other);
+            return;
+        }
+        let mut ours = take_all!(self).peekable();
+        let mut theirs = take_all!(other).peekable();
+        let merged = iter::from_fn(|| match (ours.peek(), theirs.peek()) {
+            (Some((a, _)), Some((b, _))) => match a.cmp(b) {
+                Ordering::Less => ours.next(),
+                Ordering::Greater => theirs.next(),
+                Ordering::Equal => {
+                    ours.next();
+                    theirs.next()
+                }
+            },
+            (Some(_), None) => ours.next(),
+            (None, _) => theirs.next(),
+        });
+        let root = self.root.insert(NodeRef::new_leaf(&self.alloc));
+        root.bulk_push(merged, &mut self.length, &self.alloc);
+    }
+
+    /// Splits the map in two at `key`: `self` keeps the entries whose keys
+    /// are smaller and the rest are returned.
+    ///
+    /// Both halves are rebuilt, in time linear in the length of the map.
+    ///
+    /// ```
+    /// use mystdrs::collections::BTreeMap;
+    ///
+    /// let mut low: BTreeMap<u32, ()> = (0..10).map(|k| (k, ())).collect();
+    /// let high = low.split_off(&6);
+    /// assert_eq!(low.keys().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+    /// assert_eq!(high.keys().copied().collect::<Vec<_>>(), [6, 7, 8, 9]);
+    /// ```
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        A: Clone,
+    {
+        let mut high = BTreeMap::new_in(self.alloc.clone());
+        if self.is_empty() {
+            return high;
+        }
+        let mut old = take_all!(self).peekable();
+        let low = iter::from_fn(|| old.next_if(|(k, _)| k.borrow() < key));
+        let root = self.root.insert(NodeRef::new_leaf(&self.alloc));
+        root.bulk_push(low, &mut self.length, &self.alloc);
+        let root = high.root.insert(NodeRef::new_leaf(&high.alloc));
+        root.bulk_push(old, &mut high.length, &high.alloc);
+        high
+    }
+
+    /// Builds the tree of an empty map from pairs sorted by key, keeping
+    /// only the last of each run of equal keys.
+    fn bulk_build<I: Iterator<Item = (K, V)>>(&mut self, sorted: I) {
+        debug_assert!(self.root.is_none());
+        let mut sorted = sorted.peekable();
+        let deduped = iter::from_fn(|| loop {
+            let next = sorted.next()?;
+            match sorted.peek() {
+                Some(peeked) if peeked.0 == next.0 => continue,
+                _ => return Some(next),
+            }
+        });
+        let root = self.root.insert(NodeRef::new_leaf(&self.alloc));
+        root.bulk_push(deduped, &mut self.length, &self.alloc);
+    }
+}
+
+/// Panics if `range` starts after it ends, like std's map does, since no
+/// key could lie inside it.
+fn check_range<T: Ord + ?Sized, R: RangeBounds<T>>(range: &R) {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+            panic!("range start and end are equal and excluded in BTreeMap")
+        }
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) if start > end => panic!("range start is greater than range end in BTreeMap"),
+        _ => {}
+    }
+}
+
+impl<K, V, A: Allocator> Drop for BTreeMap<K, V, A> {
+    fn drop(&mut self) {
+        drop(take_all!(self));
+    }
+}
+
+impl<K: Clone, V: Clone, A: Allocator + Clone> Clone for BTreeMap<K, V, A> {
+    fn clone(&self) -> Self {
+        let mut map = BTreeMap::new_in(self.alloc.clone());
+        if !self.is_empty() {
+            let pairs = self.iter().map(|(k, v)| (k.clone(), v.clone()));
+            let root = map.root.insert(NodeRef::new_leaf(&map.alloc));
+            root.bulk_push(pairs, &mut map.length, &map.alloc);
+        }
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for BTreeMap<K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq, A: Allocator> PartialEq for BTreeMap<K, V, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq, A: Allocator> Eq for BTreeMap<K, V, A> {}
+
+impl<K: PartialOrd, V: PartialOrd, A: Allocator> PartialOrd for BTreeMap<K, V, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K: Ord, V: Ord, A: Allocator> Ord for BTreeMap<K, V, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<K: Hash, V: Hash, A: Allocator> Hash for BTreeMap<K, V, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.length);
+        for entry in self {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<K, V> Default for BTreeMap<K, V> {
+    fn default() -> Self {
+        BTreeMap::new()
+    }
+}
+
+impl<K, Q, V, A> Index<&Q> for BTreeMap<K, V, A>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+    A: Allocator,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if the map holds no entry for `key`.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Ord, V, A: Allocator> Extend<(K, V)> for BTreeMap<K, V, A> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V, A> Extend<(&'a K, &'a V)> for BTreeMap<K, V, A>
+where
+    K: Ord + Copy,
+    V: Copy,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+/// Sorts the pairs and builds the tree bottom-up rather than inserting
+/// them one at a time. Of several pairs with equal keys, the last wins.
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut pairs: Vec<(K, V)> = iter.into_iter().collect();
+        let mut map = BTreeMap::new();
+        if pairs.is_empty() {
+            return map;
+        }
+        // Stable, so that equal keys stay in the order they came in.
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        map.bulk_build(pairs.into_iter());
+        map
+    }
+}
+
+impl<K: Ord, V, const N: usize> From<[(K, V); N]> for BTreeMap<K, V> {
+    fn from(arr: [(K, V); N]) -> Self {
+        BTreeMap::from_iter(arr)
+    }
+}
+
+impl<'a, K, V, A: Allocator> IntoIterator for &'a BTreeMap<K, V, A> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, A: Allocator> IntoIterator for &'a mut BTreeMap<K, V, A> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, A: Allocator> IntoIterator for BTreeMap<K, V, A> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    fn into_iter(self) -> IntoIter<K, V, A> {
+        let mut me = ManuallyDrop::new(self);
+        let alloc = unsafe { ptr::read(&me.alloc) };
+        IntoIter::new(me.root.take(), me.length, alloc)
+    }
+}
+
+/// A view into a single entry of a map, created by [`BTreeMap::entry`].
+pub enum Entry<'a, K, V, A: Allocator = Global> {
+    Occupied(OccupiedEntry<'a, K, V, A>),
+    Vacant(VacantEntry<'a, K, V, A>),
+}
+
+impl<'a, K, V, A: Allocator> Entry<'a, K, V, A> {
+    /// Inserts `default` if the entry is vacant and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entry is vacant and returns
+    /// the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Like [`or_insert_with`](Entry::or_insert_with), but `default` is
+    /// given the key.
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V: Default, A: Allocator> Entry<'a, K, V, A> {
+    /// Inserts `V::default()` if the entry is vacant and returns the value.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for Entry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+/// An occupied entry, part of the [`Entry`] enum.
+pub struct OccupiedEntry<'a, K, V, A: Allocator = Global> {
+    handle: Handle<K, V>,
+    map: &'a mut BTreeMap<K, V, A>,
+}
+
+impl<'a, K, V, A: Allocator> OccupiedEntry<'a, K, V, A> {
+    /// Returns the key stored in the map.
+    pub fn key(&self) -> &K {
+        unsafe { self.handle.into_kv().0 }
+    }
+
+    /// Returns the value.
+    pub fn get(&self) -> &V {
+        unsafe { self.handle.into_kv().1 }
+    }
+
+    /// Returns the value mutably.
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { self.handle.into_kv_mut().1 }
+    }
+
+    /// Converts the entry into a mutable reference to the value that lives
+    /// as long as the map borrow.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { self.handle.into_kv_mut().1 }
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry and returns its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry and returns the stored key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        let map = self.map;
+        map.length -= 1;
+        let root = map.root.as_mut().unwrap();
+        self.handle.remove_kv_tracking(root, &map.alloc)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for OccupiedEntry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+/// A vacant entry, part of the [`Entry`] enum.
+pub struct VacantEntry<'a, K, V, A: Allocator = Global> {
+    key: K,
+    /// The leaf edge to insert at, or `None` if the map has no root yet.
+    edge: Option<Handle<K, V>>,
+    map: &'a mut BTreeMap<K, V, A>,
+}
+
+impl<'a, K, V, A: Allocator> VacantEntry<'a, K, V, A> {
+    /// Returns the key that would be inserted.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes back the key without inserting.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` for the entry's key and returns a reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        let root = match &mut map.root {
+            Some(root) => root,
+            None => map.root.insert(NodeRef::new_leaf(&map.alloc)),
+        };
+        let edge = self.edge.unwrap_or_else(|| root.first_leaf_edge());
+        let val_ptr = edge.insert_recursing(self.key, value, root, &map.alloc);
+        map.length += 1;
+        unsafe { &mut *val_ptr }
+    }
+
+    /// Fallible version of [`insert`](VacantEntry::insert). On error the
+    /// key and `value` are dropped and the map is unchanged.
+    pub fn try_insert(self, value: V) -> Result<&'a mut V, TryReserveError> {
+        let map = self.map;
+        let root = match &mut map.root {
+            Some(root) => root,
+            None => map.root.insert(NodeRef::try_new_leaf(&map.alloc)?),
+        };
+        let edge = self.edge.unwrap_or_else(|| root.first_leaf_edge());
+        let val_ptr = edge.try_insert_recursing(self.key, value, root, &map.alloc)?;
+        map.length += 1;
+        Ok(unsafe { &mut *val_ptr })
+    }
+}
+
+impl<K: fmt::Debug, V, A: Allocator> fmt::Debug for VacantEntry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(self.key()).finish()
+    }
+}
+
+/// The leaf edges before the first and after the last entry of a run of
+/// entries. The run is empty when they meet.
+struct LeafRange<K, V> {
+    front: Option<Handle<K, V>>,
+    back: Option<Handle<K, V>>,
+}
+
+impl<K, V> Clone for LeafRange<K, V> {
+    fn clone(&self) -> Self {
+        LeafRange {
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<K, V> LeafRange<K, V> {
+    fn full(root: Option<NodeRef<K, V>>) -> Self {
+        LeafRange {
+            front: root.map(NodeRef::first_leaf_edge),
+            back: root.map(NodeRef::last_leaf_edge),
+        }
+    }
+
+    fn bounded<T, R>(root: Option<NodeRef<K, V>>, range: &R) -> Self
+    where
+        K: Borrow<T>,
+        T: Ord + ?Sized,
+        R: RangeBounds<T>,
+    {
+        check_range(range);
+        LeafRange {
+            front: root.map(|root| root.lower_bound(range.start_bound())),
+            back: root.map(|root| root.upper_bound(range.end_bound())),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.front == self.back
+    }
+
+    fn next_kv(&mut self) -> Option<Handle<K, V>> {
+        if self.is_empty() {
+            return None;
+        }
+        let kv = self.front?.next_kv()?;
+        self.front = Some(kv.next_leaf_edge());
+        Some(kv)
+    }
+
+    fn next_back_kv(&mut self) -> Option<Handle<K, V>> {
+        if self.is_empty() {
+            return None;
+        }
+        let kv = self.back?.next_back_kv()?;
+        self.back = Some(kv.next_back_leaf_edge());
+        Some(kv)
+    }
+}
+
+/// An iterator over the entries of a map, created by [`BTreeMap::iter`].
+pub struct Iter<'a, K, V> {
+    range: LeafRange<K, V>,
+    length: usize,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            range: self.range.clone(),
+            length: self.length,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = self.range.next_kv()?;
+        self.length -= 1;
+        Some(unsafe { kv.into_kv() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = self.range.next_back_kv()?;
+        self.length -= 1;
+        Some(unsafe { kv.into_kv() })
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the entries of a map, created by
+/// [`BTreeMap::iter_mut`].
+pub struct IterMut<'a, K, V> {
+    range: LeafRange<K, V>,
+    length: usize,
+    marker: PhantomData<&'a mut (K, V)>,
+}
+
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        let kv = self.range.next_kv()?;
+        self.length -= 1;
+        Some(unsafe { kv.into_kv_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        let kv = self.range.next_back_kv()?;
+        self.length -= 1;
+        Some(unsafe { kv.into_kv_mut() })
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for IterMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.range.clone();
+        let entries = Iter {
+            range,
+            length: self.length,
+            marker: PhantomData,
+        };
+        f.debug_list().entries(entries).finish()
+    }
+}
+
+/// An owning iterator over the entries of a map, created by
+/// `BTreeMap::into_iter`.
+///
+/// Frees each node as soon as both ends have moved past it.
+pub struct IntoIter<K, V, A: Allocator = Global> {
+    front: Option<Handle<K, V>>,
+    back: Option<Handle<K, V>>,
+    length: usize,
+    alloc: A,
+    marker: PhantomData<(K, V)>,
+}
+
+unsafe impl<K: Send, V: Send, A: Allocator + Send> Send for IntoIter<K, V, A> {}
+unsafe impl<K: Sync, V: Sync, A: Allocator + Sync> Sync for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> IntoIter<K, V, A> {
+    fn new(root: Option<NodeRef<K, V>>, length: usize, alloc: A) -> Self {
+        IntoIter {
+            front: root.map(NodeRef::first_leaf_edge),
+            back: root.map(NodeRef::last_leaf_edge),
+            length,
+            alloc,
+            marker: PhantomData,
+        }
+    }
+
+    /// Frees whatever nodes are left once every entry has been moved out.
+    /// By then both ends sit on the same leaf edge, so what is left is the
+    /// path from that edge's leaf up to the root.
+    fn deallocate_rest(&mut self) {
+        if let Some(front) = self.front.take() {
+            unsafe { front.deallocating_end(&self.alloc) };
+        }
+        self.back = None;
+    }
+}
+
+impl<K, V, A: Allocator> Iterator for IntoIter<K, V, A> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.length == 0 {
+            self.deallocate_rest();
+            return None;
+        }
+        self.length -= 1;
+        unsafe {
+            let kv = self.front?.deallocating_next_kv(&self.alloc)?;
+            self.front = Some(kv.next_leaf_edge());
+            Some(kv.read_kv())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<K, V, A: Allocator> DoubleEndedIterator for IntoIter<K, V, A> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        if self.length == 0 {
+            self.deallocate_rest();
+            return None;
+        }
+        self.length -= 1;
+        unsafe {
+            let kv = self.back?.deallocating_next_back_kv(&self.alloc)?;
+            self.back = Some(kv.next_back_leaf_edge());
+            Some(kv.read_kv())
+        }
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> Drop for IntoIter<K, V, A> {
+    fn drop(&mut self) {
+        /// Keeps dropping the remaining entries, and then frees the nodes,
+        /// if dropping one of them panics.
+        struct DropGuard<'a, K, V, A: Allocator>(&'a mut IntoIter<K, V, A>);
+
+        impl<K, V, A: Allocator> Drop for DropGuard<'_, K, V, A> {
+            fn drop(&mut self) {
+                self.0.for_each(drop);
+            }
+        }
+
+        while let Some(kv) = self.next() {
+            let guard = DropGuard(self);
+            drop(kv);
+            mem::forget(guard);
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = Iter {
+            range: LeafRange {
+                front: self.front,
+                back: self.back,
+            },
+            length: self.length,
+            marker: PhantomData,
+        };
+        f.debug_list().entries(entries).finish()
+    }
+}
+
+/// An iterator over a range of entries of a map, created by
+/// [`BTreeMap::range`].
+pub struct Range<'a, K, V> {
+    inner: LeafRange<K, V>,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Range<'_, K, V> {}
+
+impl<K, V> Clone for Range<'_, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next_back_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Range<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over a range of entries of a map, created by
+/// [`BTreeMap::range_mut`].
+pub struct RangeMut<'a, K, V> {
+    inner: LeafRange<K, V>,
+    marker: PhantomData<&'a mut (K, V)>,
+}
+
+unsafe impl<K: Sync, V: Send> Send for RangeMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for RangeMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.inner.next_kv().map(|kv| unsafe { kv.into_kv_mut() })
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.inner
+            .next_back_kv()
+            .map(|kv| unsafe { kv.into_kv_mut() })
+    }
+}
+
+impl<K, V> FusedIterator for RangeMut<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for RangeMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = Range {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(entries).finish()
+    }
+}
+
+/// A position in a map, either between two entries or at one of its ends,
+/// created by [`BTreeMap::lower_bound`] and [`BTreeMap::upper_bound`].
+///
+/// Moving the cursor passes over one entry and returns it, so the nearest
+/// entries on either side of a key can be found without building a range.
+pub struct Cursor<'a, K, V> {
+    /// The leaf edge at the cursor, or `None` if the map has no root.
+    edge: Option<Handle<K, V>>,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Cursor<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Cursor<'_, K, V> {}
+
+impl<K, V> Clone for Cursor<'_, K, V> {
+    fn clone(&self) -> Self {
+        Cursor {
+            edge: self.edge,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /// Moves the cursor past the next entry and returns it, or returns
+    /// `None` without moving at the end of the map.
+    pub fn move_next(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = self.edge?.next_kv()?;
+        self.edge = Some(kv.next_leaf_edge());
+        Some(unsafe { kv.into_kv() })
+    }
+
+    /// Moves the cursor back past the previous entry and returns it, or
+    /// returns `None` without moving at the start of the map.
+    pub fn move_prev(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = self.edge?.next_back_kv()?;
+        self.edge = Some(kv.next_back_leaf_edge());
+        Some(unsafe { kv.into_kv() })
+    }
+
+    /// Returns the entry after the cursor without moving it.
+    pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
+        self.edge?.next_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+
+    /// Returns the entry before the cursor without moving it.
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
+        self.edge?.next_back_kv().map(|kv| unsafe { kv.into_kv() })
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Cursor<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cursor")
+            .field("prev", &self.peek_prev())
+            .field("next", &self.peek_next())
+            .finish()
+    }
+}
+
+/// An iterator over the keys of a map, created by [`BTreeMap::keys`].
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K: fmt::Debug, V> fmt::Debug for Keys<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the values of a map, created by [`BTreeMap::values`].
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V: fmt::Debug> fmt::Debug for Values<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the values of a map, created by
+/// [`BTreeMap::values_mut`].
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+/// An owning iterator over the keys of a map, created by
+/// [`BTreeMap::into_keys`].
+pub struct IntoKeys<K, V, A: Allocator = Global> {
+    inner: IntoIter<K, V, A>,
+}
+
+impl<K, V, A: Allocator> Iterator for IntoKeys<K, V, A> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, A: Allocator> DoubleEndedIterator for IntoKeys<K, V, A> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoKeys<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoKeys<K, V, A> {}
+
+/// An owning iterator over the values of a map, created by
+/// [`BTreeMap::into_values`].
+pub struct IntoValues<K, V, A: Allocator = Global> {
+    inner: IntoIter<K, V, A>,
+}
+
+impl<K, V, A: Allocator> Iterator for IntoValues<K, V, A> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, A: Allocator> DoubleEndedIterator for IntoValues<K, V, A> {
+    fn next_back(&mut self) -> Option<V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoValues<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoValues<K, V, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::btree_node::{NodeRef, CAPACITY, MIN_LEN};
+    use super::{BTreeMap, Entry};
+    use crate::alloc::{Allocator, Counting, Global};
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::ops::Bound;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    /// Checks the B-tree invariants: sorted keys, node sizes, parent links,
+    /// leaves all at the same depth and the cached length.
+    fn check<K: Ord, V, A: Allocator>(map: &BTreeMap<K, V, A>) {
+        fn walk<K: Ord, V>(node: NodeRef<K, V>, is_root: bool, count: &mut usize) {
+            let len = node.len();
+            assert!(len <= CAPACITY);
+            assert!(is_root || len >= MIN_LEN, "underfull node of {} keys", len);
+            for idx in 1..len {
+                assert!(unsafe { node.key(idx - 1) < node.key(idx) });
+            }
+            *count += len;
+            if node.height > 0 {
+                for idx in 0..=len {
+                    let child = node.edge(idx);
+                    let parent = child.parent().unwrap();
+                    assert!(parent.node == node && parent.idx == idx);
+                    walk(child, false, count);
+                }
+            }
+        }
+
+        let mut count = 0;
+        if let Some(root) = map.root {
+            assert!(root.parent().is_none());
+            walk(root, true, &mut count);
+        }
+        assert_eq!(count, map.len());
+        assert!(map.iter().zip(map.iter().skip(1)).all(|(a, b)| a.0 < b.0));
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = BTreeMap::new();
+        assert_eq!(map.get(&1), None);
+        for i in (0..1000).rev() {
+            assert_eq!(map.insert(i, i * 10), None);
+        }
+        check(&map);
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.insert(7, 0), Some(70));
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&if i == 7 { 0 } else { i * 10 }));
+        }
+        assert_eq!(map.get(&1000), None);
+        for i in (0..1000).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i * 10));
+            check(&map);
+        }
+        assert_eq!(map.len(), 500);
+        assert_eq!(map.remove(&0), None);
+        assert!(map.keys().copied().eq((1..1000).step_by(2)));
+        *map.get_mut(&1).unwrap() = 5;
+        assert_eq!(map[&1], 5);
+    }
+
+    #[test]
+    fn borrowed_lookups() {
+        let mut map = BTreeMap::new();
+        map.insert("one".to_string(), 1);
+        map.insert("two".to_string(), 2);
+        assert_eq!(map.get("one"), Some(&1));
+        assert!(map.contains_key("two"));
+        assert_eq!(map.get_key_value("two").unwrap().0, "two");
+        assert_eq!(map.remove("one"), Some(1));
+        assert_eq!(
+            map.range::<str, _>((Bound::Included("a"), Bound::Excluded("u")))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn index_missing_key() {
+        let map: BTreeMap<u32, u32> = BTreeMap::new();
+        let _ = map[&1];
+    }
+
+    #[test]
+    fn first_last_and_pop() {
+        let mut map: BTreeMap<u32, u32> = (0..100).map(|k| (k, k)).collect();
+        assert_eq!(map.first_key_value(), Some((&0, &0)));
+        assert_eq!(map.last_key_value(), Some((&99, &99)));
+        *map.first_entry().unwrap().get_mut() += 1;
+        assert_eq!(map.last_entry().unwrap().remove(), 99);
+        assert_eq!(map.pop_first(), Some((0, 1)));
+        for k in (1..99).rev() {
+            assert_eq!(map.pop_last(), Some((k, k)));
+            check(&map);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.first_key_value(), None);
+        map.insert(3, 3);
+        assert_eq!(map.last_key_value(), Some((&3, &3)));
+    }
+
+    #[test]
+    fn entry_api() {
+        let mut map: BTreeMap<u32, u32> = BTreeMap::new();
+        for i in 0..300 {
+            *map.entry(i % 50).or_insert(0) += 1;
+        }
+        check(&map);
+        assert!(map.values().all(|&v| v == 6));
+        match map.entry(3) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &3);
+                assert_eq!(entry.insert(10), 6);
+                assert_eq!(entry.remove_entry(), (3, 10));
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry(3) {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 3),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        assert_eq!(*map.entry(3).or_insert_with_key(|k| k * 2), 6);
+        map.entry(4).and_modify(|v| *v = 0).or_default();
+        assert_eq!(map[&4], 0);
+        assert_eq!(*map.entry(100).or_default(), 0);
+        check(&map);
+    }
+
+    #[test]
+    fn iterators_from_both_ends() {
+        let mut map: BTreeMap<u32, u32> = (0..500).map(|k| (k, k)).collect();
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), 500);
+        assert_eq!(iter.next(), Some((&0, &0)));
+        assert_eq!(iter.next_back(), Some((&499, &499)));
+        assert_eq!(iter.len(), 498);
+        assert!(iter.by_ref().map(|(k, _)| *k).eq(1..499));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        for (k, v) in map.iter_mut().rev().take(10) {
+            *v = k + 1000;
+        }
+        assert_eq!(map[&490], 1490);
+        assert_eq!(map[&489], 489);
+        assert!(map.values_mut().rev().map(|v| *v).take(2).eq([1499, 1498]));
+
+        let mut into = map.clone().into_iter();
+        let mut forward = 0;
+        let mut backward = 500;
+        for step in 0..500 {
+            if step % 3 == 0 {
+                backward -= 1;
+                assert_eq!(into.next_back().unwrap().0, backward);
+            } else {
+                assert_eq!(into.next().unwrap().0, forward);
+                forward += 1;
+            }
+        }
+        assert_eq!(into.next(), None);
+        assert_eq!(into.next_back(), None);
+        assert!(map.into_keys().rev().eq((0..500).rev()));
+    }
+
+    #[test]
+    fn range_bounds() {
+        let map: BTreeMap<u32, ()> = (0..300).map(|k| (k * 2, ())).collect();
+        let std: std::collections::BTreeMap<u32, ()> = map.keys().map(|&k| (k, ())).collect();
+        let bounds = |i: u32| match i % 3 {
+            0 => Bound::Included(i / 3),
+            1 => Bound::Excluded(i / 3),
+            _ => Bound::Unbounded,
+        };
+        let mut rng = XorShift::new(77);
+        for _ in 0..3000 {
+            let (start, end) = (
+                bounds(rng.below(1900) as u32),
+                bounds(rng.below(1900) as u32),
+            );
+            let (lo, hi) = match (start, end) {
+                (
+                    Bound::Included(s) | Bound::Excluded(s),
+                    Bound::Included(e) | Bound::Excluded(e),
+                ) => (s, e),
+                _ => (0, 1),
+            };
+            if lo > hi || (lo == hi && start == Bound::Excluded(lo) && end == Bound::Excluded(hi)) {
+                continue;
+            }
+            let ours: Vec<u32> = map.range((start, end)).map(|(k, _)| *k).collect();
+            let expected: Vec<u32> = std.range((start, end)).map(|(k, _)| *k).collect();
+            assert_eq!(ours, expected, "{:?}..{:?}", start, end);
+            let reversed: Vec<u32> = map.range((start, end)).rev().map(|(k, _)| *k).collect();
+            assert!(reversed.iter().rev().eq(expected.iter()));
+        }
+        assert_eq!(map.range(7..7).next(), None);
+        assert_eq!(map.range(..).count(), 300);
+        assert_eq!(BTreeMap::<u32, ()>::new().range(1..5).next(), None);
+    }
+
+    #[test]
+    fn range_mut_and_alternating_ends() {
+        let mut map: BTreeMap<u32, u32> = (0..200).map(|k| (k, 0)).collect();
+        for (_, v) in map.range_mut(50..150) {
+            *v = 1;
+        }
+        assert_eq!(map.values().sum::<u32>(), 100);
+        let mut range = map.range(10..=20);
+        let mut seen = Vec::new();
+        loop {
+            match (range.next(), range.next_back()) {
+                (Some(a), Some(b)) => seen.extend([*a.0, *b.0]),
+                (Some(a), None) | (None, Some(a)) => seen.push(*a.0),
+                (None, None) => break,
+            }
+        }
+        seen.sort_unstable();
+        assert!(seen.into_iter().eq(10..=20));
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end in BTreeMap")]
+    fn range_backwards_panics() {
+        let map: BTreeMap<u32, u32> = BTreeMap::new();
+        let (start, end) = (5, 3);
+        map.range(start..end);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start and end are equal and excluded in BTreeMap")]
+    fn range_excluded_both_ends_panics() {
+        let map: BTreeMap<u32, u32> = BTreeMap::new();
+        map.range((Bound::Excluded(3), Bound::Excluded(3)));
+    }
+
+    #[test]
+    fn cursors_find_nearest_keys() {
+        let map: BTreeMap<u32, ()> = (0..500).map(|k| (k * 4, ())).collect();
+        let std: std::collections::BTreeMap<u32, ()> = map.keys().map(|&k| (k, ())).collect();
+        for x in 0..2010 {
+            let below = std.range(..x).next_back().map(|(k, _)| k);
+            let at_or_below = std.range(..=x).next_back().map(|(k, _)| k);
+            let at_or_above = std.range(x..).next().map(|(k, _)| k);
+            let above = std
+                .range((Bound::Excluded(x), Bound::Unbounded))
+                .next()
+                .map(|(k, _)| k);
+
+            let cursor = map.lower_bound(Bound::Included(&x));
+            assert_eq!(cursor.peek_prev().map(|(k, _)| k), below);
+            assert_eq!(cursor.peek_next().map(|(k, _)| k), at_or_above);
+            let cursor = map.lower_bound(Bound::Excluded(&x));
+            assert_eq!(cursor.peek_prev().map(|(k, _)| k), at_or_below);
+            assert_eq!(cursor.peek_next().map(|(k, _)| k), above);
+            let cursor = map.upper_bound(Bound::Included(&x));
+            assert_eq!(cursor.peek_prev().map(|(k, _)| k), at_or_below);
+            assert_eq!(cursor.peek_next().map(|(k, _)| k), above);
+            let cursor = map.upper_bound(Bound::Excluded(&x));
+            assert_eq!(cursor.peek_prev().map(|(k, _)| k), below);
+            assert_eq!(cursor.peek_next().map(|(k, _)| k), at_or_above);
+        }
+
+        let mut cursor = map.lower_bound(Bound::Unbounded);
+        assert_eq!(cursor.move_prev(), None);
+        let mut walked = Vec::new();
+        while let Some((k, _)) = cursor.move_next() {
+            walked.push(*k);
+        }
+        assert!(walked.iter().eq(map.keys()));
+        assert_eq!(cursor.move_next(), None);
+        assert_eq!(cursor.move_prev(), Some((&1996, &())));
+        assert_eq!(map.upper_bound(Bound::Unbounded).peek_next(), None);
+        assert_eq!(
+            BTreeMap::<u32, ()>::new()
+                .lower_bound(Bound::Included(&1))
+                .move_next(),
+            None
+        );
+    }
+
+    #[test]
+    fn from_iter_keeps_last_duplicate() {
+        for n in [0, 1, 11, 12, 100, 1000, 5000] {
+            let mut rng = XorShift::new(n as u64 + 1);
+            let pairs: Vec<(u32, usize)> =
+                (0..n).map(|i| (rng.below(n.max(1)) as u32, i)).collect();
+            let map: BTreeMap<u32, usize> = pairs.iter().copied().collect();
+            let std: std::collections::BTreeMap<u32, usize> = pairs.iter().copied().collect();
+            check(&map);
+            assert!(map.iter().eq(std.iter()));
+        }
+    }
+
+    #[test]
+    fn append_and_split_off() {
+        let mut rng = XorShift::new(5);
+        for _ in 0..50 {
+            let (n, m) = (rng.below(400), rng.below(400));
+            let mut a: BTreeMap<u32, u32> = (0..n).map(|_| (rng.below(600) as u32, 0)).collect();
+            let mut b: BTreeMap<u32, u32> = (0..m).map(|_| (rng.below(600) as u32, 1)).collect();
+            let mut std_a: std::collections::BTreeMap<u32, u32> =
+                a.iter().map(|(&k, &v)| (k, v)).collect();
+            let mut std_b: std::collections::BTreeMap<u32, u32> =
+                b.iter().map(|(&k, &v)| (k, v)).collect();
+            a.append(&mut b);
+            std_a.append(&mut std_b);
+            check(&a);
+            check(&b);
+            assert!(b.is_empty());
+            assert!(a.iter().eq(std_a.iter()));
+
+            let at = rng.below(700) as u32;
+            let high = a.split_off(&at);
+            let std_high = std_a.split_off(&at);
+            check(&a);
+            check(&high);
+            assert!(a.iter().eq(std_a.iter()));
+            assert!(high.iter().eq(std_high.iter()));
+        }
+    }
+
+    #[test]
+    fn retain_and_clear() {
+        let mut map: BTreeMap<u32, u32> = (0..1000).map(|k| (k, k)).collect();
+        map.retain(|k, v| {
+            *v += 1;
+            k % 3 == 0
+        });
+        check(&map);
+        assert_eq!(map.len(), 334);
+        assert!(map.iter().all(|(k, v)| k % 3 == 0 && *v == k + 1));
+        map.retain(|_, _| false);
+        assert!(map.is_empty());
+        map.insert(1, 1);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().next(), None);
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        {
+            let mut map = BTreeMap::new_in(&counting);
+            for i in 0..200 {
+                map.insert(i, DropCounter::new(&drops, false));
+            }
+            map.insert(0, DropCounter::new(&drops, false));
+            assert_eq!(drops.get(), 1);
+            map.remove(&1);
+            map.retain(|k, _| k % 2 == 0);
+            assert_eq!(drops.get(), 2 + 99);
+            let mut high = map.split_off(&100);
+            assert_eq!(drops.get(), 101);
+            map.append(&mut high);
+            assert_eq!(drops.get(), 101);
+            let mut iter = map.into_iter();
+            iter.next();
+            iter.next_back();
+            drop(iter);
+            assert_eq!(drops.get(), 201);
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn panicking_destructors_do_not_leak() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let mut map = BTreeMap::new_in(&counting);
+            for i in 0..100 {
+                map.insert(i, DropCounter::new(&drops, i == 37));
+            }
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 100);
+
+        let mut map = BTreeMap::new_in(&counting);
+        for i in 0..100 {
+            map.insert(i, DropCounter::new(&drops, i == 3));
+        }
+        let result = catch_unwind(AssertUnwindSafe(|| map.retain(|k, _| k % 2 == 0)));
+        assert!(result.is_err());
+        check(&map);
+        drop(map);
+        assert_eq!(drops.get(), 200);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn try_insert_or_replace_fails_without_changing_the_map() {
+        let budget = Budget::new(0);
+        let counting = Counting::new(&budget);
+        let mut map = BTreeMap::new_in(&counting);
+        let mut model = std::collections::BTreeMap::new();
+        let mut rng = XorShift::new(0xb7_4e);
+        let (mut failures, mut partial) = (0, 0);
+        for _ in 0..4000 {
+            let key = rng.below(3000) as u32;
+            // Often too little for every node a split chain needs, so that
+            // some insertions fail after reserving the lower nodes.
+            budget.set(if rng.below(2) == 0 {
+                usize::MAX
+            } else {
+                rng.below(700)
+            });
+            let before = counting.snapshot();
+            match map.try_insert_or_replace(key, key * 2) {
+                Ok(old) => assert_eq!(old, model.insert(key, key * 2)),
+                Err(_) => {
+                    let diff = counting.snapshot().diff(&before);
+                    assert!(diff.is_balanced());
+                    assert!(!model.contains_key(&key));
+                    failures += 1;
+                    // Some nodes were reserved before one failed, then freed.
+                    partial += (diff.allocations > 0) as usize;
+                }
+            }
+            assert_eq!(map.len(), model.len());
+            check(&map);
+        }
+        assert!(failures >= 10 && partial >= 1, "{} {}", failures, partial);
+        assert!(map.iter().eq(model.iter()));
+        drop(map);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+
+        let budget = Budget::new(0);
+        let mut map = BTreeMap::new_in(&budget);
+        assert!(map.try_insert_or_replace(1, 1).is_err());
+        assert!(map.is_empty());
+        match map.entry(1) {
+            Entry::Vacant(entry) => assert!(entry.try_insert(1).is_err()),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn zero_sized_entries() {
+        let mut map = BTreeMap::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.iter().count(), 1);
+        assert_eq!(map.remove(&()), Some(()));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn traits() {
+        let a = BTreeMap::from([(2, "two"), (1, "one")]);
+        let mut b = BTreeMap::new();
+        b.extend([(&1, &"one"), (&2, &"two")]);
+        assert_eq!(a, b);
+        assert_eq!(format!("{:?}", a), r#"{1: "one", 2: "two"}"#);
+        b.insert(3, "three");
+        assert_ne!(a, b);
+        assert!(a < b);
+        let c = b.clone();
+        check(&c);
+        assert_eq!(b, c);
+        assert_eq!(
+            format!("{:?}", c.range(2..)),
+            r#"[(2, "two"), (3, "three")]"#
+        );
+        assert_eq!(format!("{:?}", c.keys()), "[1, 2, 3]");
+        assert!(b.into_values().eq(["one", "two", "three"]));
+        assert_eq!(BTreeMap::<u8, u8>::default(), BTreeMap::new());
+    }
+
+    #[test]
+    fn matches_std() {
+        let mut rng = XorShift::new(2024);
+        let mut ours = BTreeMap::new();
+        let mut std = std::collections::BTreeMap::new();
+        for round in 0..60_000 {
+            // Alternate between growing and shrinking phases so that every
+            // size of tree sees both splits and merges.
+            let growing = (round / 5000) % 2 == 0;
+            let k = rng.below(3000) as u32;
+            match rng.below(8) {
+                0 | 1 if growing => {
+                    let v = rng.next();
+                    assert_eq!(ours.insert(k, v), std.insert(k, v));
+                }
+                0..=3 => assert_eq!(ours.remove(&k), std.remove(&k)),
+                4 => assert_eq!(ours.get(&k), std.get(&k)),
+                5 => {
+                    *ours.entry(k).or_insert(0) += 1;
+                    *std.entry(k).or_insert(0) += 1;
+                }
+                6 => match rng.below(2) {
+                    0 => assert_eq!(ours.pop_first(), std.pop_first()),
+                    _ => assert_eq!(ours.pop_last(), std.pop_last()),
+                },
+                _ => {
+                    let end = k + rng.below(100) as u32;
+                    assert!(ours.range(k..end).eq(std.range(k..end)));
+                }
+            }
+            assert_eq!(ours.len(), std.len());
+            if round % 1000 == 0 {
+                check(&ours);
+            }
+        }
+        check(&ours);
+        assert!(ours.iter().eq(std.iter()));
+    }
+}
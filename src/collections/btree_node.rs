@@ -0,0 +1,958 @@
+//! The nodes of a B-tree and the operations on them that `BTreeMap` is
+//! built from.
+//!
+//! A node holds up to [`CAPACITY`] keys and values in sorted order. An
+//! internal node also holds one more edge than it has keys, edge `i`
+//! leading to the subtree of keys that sort between key `i - 1` and key
+//! `i`. Every node other than the root holds at least [`MIN_LEN`] keys, and
+//! all leaves are at the same depth. Nodes point back at their parent, so
+//! iterators can walk the tree without keeping a stack.
+//!
+//! [`NodeRef`] and [`Handle`] are plain copyable pointers that track
+//! neither ownership nor borrows: the map decides which handles may be
+//! used, and for how long the references they produce live.
+
+use crate::alloc::{Allocator, Layout};
+use crate::raw_vec::{handle_reserve, TryReserveError};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem::MaybeUninit;
+use std::ops::{Bound, Range};
+use std::ptr::{self, NonNull};
+
+const B: usize = 6;
+pub(super) const CAPACITY: usize = 2 * B - 1;
+pub(super) const MIN_LEN: usize = B - 1;
+
+/// The index of the key that moves up into the parent when a full node is
+/// split, leaving `B - 1` keys on its left and `B - 1` on its right.
+const SPLIT_IDX: usize = B - 1;
+
+#[repr(C)]
+struct LeafNode<K, V> {
+    parent: Option<NonNull<InternalNode<K, V>>>,
+    parent_idx: u16,
+    len: u16,
+    keys: [MaybeUninit<K>; CAPACITY],
+    vals: [MaybeUninit<V>; CAPACITY],
+}
+
+/// An internal node starts with a leaf node, so a pointer to either kind
+/// can be stored as a pointer to a `LeafNode` and cast back by height.
+#[repr(C)]
+struct InternalNode<K, V> {
+    data: LeafNode<K, V>,
+    edges: [MaybeUninit<NonNull<LeafNode<K, V>>>; 2 * B],
+}
+
+/// A node and its height above the leaves, which decides whether it is an
+/// internal node.
+pub(super) struct NodeRef<K, V> {
+    node: NonNull<LeafNode<K, V>>,
+    pub(super) height: usize,
+}
+
+impl<K, V> Clone for NodeRef<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for NodeRef<K, V> {}
+
+impl<K, V> PartialEq for NodeRef<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+/// A position in a node: either the edge `idx`, or the key-value pair
+/// `idx`, depending on what the handle was obtained as.
+pub(super) struct Handle<K, V> {
+    pub(super) node: NodeRef<K, V>,
+    pub(super) idx: usize,
+}
+
+impl<K, V> Clone for Handle<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Handle<K, V> {}
+
+impl<K, V> PartialEq for Handle<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node && self.idx == other.idx
+    }
+}
+
+/// The result of splitting a full node: `left` keeps the smaller keys,
+/// `kv` moves up into the parent and `right` is a new node with the
+/// larger keys.
+struct SplitResult<K, V> {
+    left: NodeRef<K, V>,
+    kv: (K, V),
+    right: NodeRef<K, V>,
+}
+
+/// Nodes allocated before an insertion starts, so that it cannot fail
+/// halfway: one for each full node the insertion will split and, if the
+/// root is among them, one for the new root. Their heights count up from
+/// zero, and they are linked through their parent pointers, lowest first.
+pub(super) struct SpareNodes<K, V> {
+    next: Option<NonNull<LeafNode<K, V>>>,
+    height: usize,
+}
+
+impl<K, V> SpareNodes<K, V> {
+    /// Takes the lowest spare node.
+    fn take(&mut self) -> NodeRef<K, V> {
+        let node = self
+            .next
+            .expect("insertion needs more nodes than were reserved");
+        unsafe {
+            self.next = (*node.as_ptr()).parent.map(NonNull::cast);
+            (*node.as_ptr()).parent = None;
+        }
+        self.height += 1;
+        NodeRef {
+            node,
+            height: self.height - 1,
+        }
+    }
+
+    /// Frees the spare nodes that were not used.
+    unsafe fn dealloc<A: Allocator>(mut self, alloc: &A) {
+        while self.next.is_some() {
+            self.take().dealloc(alloc);
+        }
+    }
+}
+
+pub(super) enum SearchResult<K, V> {
+    /// The key-value pair holding the key.
+    Found(Handle<K, V>),
+    /// The leaf edge where the key would be inserted.
+    GoDown(Handle<K, V>),
+}
+
+/// Inserts `val` at `idx` into the first `len` initialized slots at `ptr`,
+/// shifting the later ones up by one.
+unsafe fn slice_insert<T>(ptr: *mut T, len: usize, idx: usize, val: T) {
+    ptr::copy(ptr.add(idx), ptr.add(idx + 1), len - idx);
+    ptr.add(idx).write(val);
+}
+
+/// Removes the value at `idx` from the first `len` initialized slots at
+/// `ptr`, shifting the later ones down by one.
+unsafe fn slice_remove<T>(ptr: *mut T, len: usize, idx: usize) -> T {
+    let val = ptr.add(idx).read();
+    ptr::copy(ptr.add(idx + 1), ptr.add(idx), len - idx - 1);
+    val
+}
+
+impl<K, V> NodeRef<K, V> {
+    fn layout(height: usize) -> Layout {
+        if height == 0 {
+            Layout::new::<LeafNode<K, V>>()
+        } else {
+            Layout::new::<InternalNode<K, V>>()
+        }
+    }
+
+    fn try_alloc<A: Allocator>(height: usize, alloc: &A) -> Result<Self, TryReserveError> {
+        let layout = Self::layout(height);
+        let node = alloc
+            .allocate(layout)
+            .map_err(|_| TryReserveError::AllocError { layout })?
+            .cast::<LeafNode<K, V>>();
+        unsafe {
+            ptr::addr_of_mut!((*node.as_ptr()).parent).write(None);
+            ptr::addr_of_mut!((*node.as_ptr()).len).write(0);
+        }
+        Ok(NodeRef { node, height })
+    }
+
+    fn alloc<A: Allocator>(height: usize, alloc: &A) -> Self {
+        handle_reserve(Self::try_alloc(height, alloc))
+    }
+
+    /// Allocates an empty leaf.
+    pub(super) fn new_leaf<A: Allocator>(alloc: &A) -> Self {
+        Self::alloc(0, alloc)
+    }
+
+    /// Fallible version of [`new_leaf`](NodeRef::new_leaf).
+    pub(super) fn try_new_leaf<A: Allocator>(alloc: &A) -> Result<Self, TryReserveError> {
+        Self::try_alloc(0, alloc)
+    }
+
+    /// Allocates an internal node with no keys whose only edge is `child`.
+    fn new_internal<A: Allocator>(child: Self, alloc: &A) -> Self {
+        let node = Self::alloc(child.height + 1, alloc);
+        node.set_edge(0, child);
+        node
+    }
+
+    /// Frees the node without dropping anything in it.
+    ///
+    /// # Safety
+    ///
+    /// The node must have been allocated by `alloc`, and nothing may use it
+    /// afterwards.
+    pub(super) unsafe fn dealloc<A: Allocator>(self, alloc: &A) {
+        alloc.deallocate(self.node.cast(), Self::layout(self.height));
+    }
+
+    pub(super) fn len(self) -> usize {
+        unsafe { usize::from((*self.node.as_ptr()).len) }
+    }
+
+    fn set_len(self, len: usize) {
+        debug_assert!(len <= CAPACITY);
+        unsafe { (*self.node.as_ptr()).len = len as u16 };
+    }
+
+    fn key_ptr(self, idx: usize) -> *mut K {
+        unsafe {
+            ptr::addr_of_mut!((*self.node.as_ptr()).keys)
+                .cast::<K>()
+                .add(idx)
+        }
+    }
+
+    fn val_ptr(self, idx: usize) -> *mut V {
+        unsafe {
+            ptr::addr_of_mut!((*self.node.as_ptr()).vals)
+                .cast::<V>()
+                .add(idx)
+        }
+    }
+
+    fn edge_ptr(self, idx: usize) -> *mut NonNull<LeafNode<K, V>> {
+        debug_assert!(self.height > 0);
+        unsafe {
+            let internal = self.node.as_ptr().cast::<InternalNode<K, V>>();
+            ptr::addr_of_mut!((*internal).edges)
+                .cast::<NonNull<LeafNode<K, V>>>()
+                .add(idx)
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `idx` must be below `len`, and the key must not be mutated while the
+    /// reference lives.
+    pub(super) unsafe fn key<'a>(self, idx: usize) -> &'a K {
+        &*self.key_ptr(idx)
+    }
+
+    /// Returns the child below edge `idx` of an internal node.
+    pub(super) fn edge(self, idx: usize) -> Self {
+        debug_assert!(idx <= self.len());
+        NodeRef {
+            node: unsafe { *self.edge_ptr(idx) },
+            height: self.height - 1,
+        }
+    }
+
+    /// Stores `child` as edge `idx` and points it back at this node.
+    fn set_edge(self, idx: usize, child: Self) {
+        unsafe { self.edge_ptr(idx).write(child.node) };
+        child.set_parent(self, idx);
+    }
+
+    fn set_parent(self, parent: Self, idx: usize) {
+        unsafe {
+            (*self.node.as_ptr()).parent = Some(parent.node.cast());
+            (*self.node.as_ptr()).parent_idx = idx as u16;
+        }
+    }
+
+    /// Points the children below `edges` back at this node, after they
+    /// were moved into it or shifted within it.
+    fn correct_parent_links(self, edges: Range<usize>) {
+        for idx in edges {
+            self.edge(idx).set_parent(self, idx);
+        }
+    }
+
+    /// Returns the parent's edge that leads to this node, or `None` for the
+    /// root.
+    pub(super) fn parent(self) -> Option<Handle<K, V>> {
+        let leaf = self.node.as_ptr();
+        unsafe {
+            (*leaf).parent.map(|parent| Handle {
+                node: NodeRef {
+                    node: parent.cast(),
+                    height: self.height + 1,
+                },
+                idx: usize::from((*leaf).parent_idx),
+            })
+        }
+    }
+
+    pub(super) fn clear_parent(self) {
+        unsafe { (*self.node.as_ptr()).parent = None };
+    }
+
+    /// Returns the leftmost leaf edge of the subtree.
+    pub(super) fn first_leaf_edge(self) -> Handle<K, V> {
+        let mut node = self;
+        while node.height > 0 {
+            node = node.edge(0);
+        }
+        Handle { node, idx: 0 }
+    }
+
+    /// Returns the rightmost leaf edge of the subtree.
+    pub(super) fn last_leaf_edge(self) -> Handle<K, V> {
+        let mut node = self;
+        while node.height > 0 {
+            node = node.edge(node.len());
+        }
+        Handle {
+            node,
+            idx: node.len(),
+        }
+    }
+
+    /// Looks `key` up in the subtree.
+    pub(super) fn search_tree<Q>(self, key: &Q) -> SearchResult<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self;
+        loop {
+            let mut idx = 0;
+            while idx < node.len() {
+                match key.cmp(unsafe { node.key(idx) }.borrow()) {
+                    Ordering::Greater => idx += 1,
+                    Ordering::Equal => return SearchResult::Found(Handle { node, idx }),
+                    Ordering::Less => break,
+                }
+            }
+            if node.height == 0 {
+                return SearchResult::GoDown(Handle { node, idx });
+            }
+            node = node.edge(idx);
+        }
+    }
+
+    /// Returns the leaf edge right before the first key inside `bound..`.
+    pub(super) fn lower_bound<Q>(self, bound: Bound<&Q>) -> Handle<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.descend_to_leaf(|node| match bound {
+            Bound::Included(key) => node.position(|k| k >= key),
+            Bound::Excluded(key) => node.position(|k| k > key),
+            Bound::Unbounded => 0,
+        })
+    }
+
+    /// Returns the leaf edge right after the last key inside `..bound`.
+    pub(super) fn upper_bound<Q>(self, bound: Bound<&Q>) -> Handle<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.descend_to_leaf(|node| match bound {
+            Bound::Included(key) => node.position(|k| k > key),
+            Bound::Excluded(key) => node.position(|k| k >= key),
+            Bound::Unbounded => node.len(),
+        })
+    }
+
+    /// Walks down from this node, taking the edge that `pick` returns at
+    /// each level, and returns the leaf edge it picks at the bottom.
+    fn descend_to_leaf(self, pick: impl Fn(Self) -> usize) -> Handle<K, V> {
+        let mut node = self;
+        loop {
+            let idx = pick(node);
+            if node.height == 0 {
+                return Handle { node, idx };
+            }
+            node = node.edge(idx);
+        }
+    }
+
+    /// Returns the index of the first key that satisfies `pred`, or `len`
+    /// if none does. The keys must be partitioned by `pred`.
+    fn position<Q>(self, pred: impl Fn(&Q) -> bool) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+    {
+        (0..self.len())
+            .find(|&idx| pred(unsafe { self.key(idx) }.borrow()))
+            .unwrap_or_else(|| self.len())
+    }
+
+    /// Appends a key-value pair, and for an internal node the edge to its
+    /// right, to a node that is not full.
+    fn push(self, key: K, val: V, edge: Option<Self>) {
+        let len = self.len();
+        Handle {
+            node: self,
+            idx: len,
+        }
+        .insert_fit(key, val, edge);
+    }
+
+    /// Moves the upper half of a full node into `right`, a new node of the
+    /// same height, and takes out the median key-value pair.
+    fn split(self, right: Self) -> SplitResult<K, V> {
+        debug_assert_eq!(self.len(), CAPACITY);
+        debug_assert_eq!(self.height, right.height);
+        let right_len = CAPACITY - SPLIT_IDX - 1;
+        let kv = unsafe {
+            let kv = (
+                self.key_ptr(SPLIT_IDX).read(),
+                self.val_ptr(SPLIT_IDX).read(),
+            );
+            ptr::copy_nonoverlapping(self.key_ptr(SPLIT_IDX + 1), right.key_ptr(0), right_len);
+            ptr::copy_nonoverlapping(self.val_ptr(SPLIT_IDX + 1), right.val_ptr(0), right_len);
+            if self.height > 0 {
+                ptr::copy_nonoverlapping(
+                    self.edge_ptr(SPLIT_IDX + 1),
+                    right.edge_ptr(0),
+                    right_len + 1,
+                );
+            }
+            kv
+        };
+        self.set_len(SPLIT_IDX);
+        right.set_len(right_len);
+        if right.height > 0 {
+            right.correct_parent_links(0..right_len + 1);
+        }
+        SplitResult {
+            left: self,
+            kv,
+            right,
+        }
+    }
+
+    /// Adds `pairs` to the end of a tree that starts out as a single empty
+    /// leaf, counting each one into `length`. The pairs must be sorted by
+    /// strictly increasing key.
+    ///
+    /// Nodes are filled to capacity from left to right, which is both
+    /// faster than inserting one pair at a time and leaves the tree half
+    /// the size. If `pairs` panics the tree is left valid, though not
+    /// necessarily balanced.
+    pub(super) fn bulk_push<I, A>(&mut self, pairs: I, length: &mut usize, alloc: &A)
+    where
+        I: Iterator<Item = (K, V)>,
+        A: Allocator,
+    {
+        debug_assert!(self.height == 0 && self.len() == 0);
+        let mut leaf = *self;
+        for (key, val) in pairs {
+            if leaf.len() < CAPACITY {
+                leaf.push(key, val, None);
+            } else {
+                // Find the lowest ancestor with room, growing the tree by a
+                // level if there is none.
+                let mut open = leaf;
+                loop {
+                    match open.parent() {
+                        Some(parent) => {
+                            open = parent.node;
+                            if open.len() < CAPACITY {
+                                break;
+                            }
+                        }
+                        None => {
+                            open = Self::new_internal(*self, alloc);
+                            *self = open;
+                            break;
+                        }
+                    }
+                }
+                // Hang a chain of empty nodes down to leaf level to the
+                // right of the new pair.
+                let mut right = Self::new_leaf(alloc);
+                for _ in 1..open.height {
+                    right = Self::new_internal(right, alloc);
+                }
+                open.push(key, val, Some(right));
+                leaf = open.last_leaf_edge().node;
+            }
+            *length += 1;
+        }
+        self.fix_right_border();
+    }
+
+    /// Tops up the underfull nodes that `bulk_push` leaves along the right
+    /// border from their left siblings, which it filled to capacity.
+    fn fix_right_border(self) {
+        let mut node = self;
+        while node.height > 0 {
+            let len = node.len();
+            let last = node.edge(len);
+            if last.len() < MIN_LEN {
+                Handle { node, idx: len - 1 }.steal_left(MIN_LEN - last.len());
+            }
+            node = last;
+        }
+    }
+
+    /// Restores [`MIN_LEN`] for a node that may have fallen below it,
+    /// continuing up the tree while merges leave parents underfull, and
+    /// drops the root's level once it is left without keys.
+    fn fix_underfull<A: Allocator>(self, root: &mut Self, alloc: &A) {
+        let mut node = self;
+        loop {
+            let parent = match node.parent() {
+                Some(parent) => parent,
+                None => {
+                    if node.len() == 0 && node.height > 0 {
+                        let child = node.edge(0);
+                        child.clear_parent();
+                        unsafe { node.dealloc(alloc) };
+                        *root = child;
+                    }
+                    return;
+                }
+            };
+            if node.len() >= MIN_LEN {
+                return;
+            }
+            // Balance against the left sibling if there is one, else the
+            // right.
+            let kv = Handle {
+                node: parent.node,
+                idx: parent.idx.saturating_sub(1),
+            };
+            let (left, right) = (kv.node.edge(kv.idx), kv.node.edge(kv.idx + 1));
+            if left.len() + 1 + right.len() <= CAPACITY {
+                kv.merge(alloc);
+                node = parent.node;
+            } else {
+                if parent.idx > 0 {
+                    kv.steal_left(1);
+                } else {
+                    kv.steal_right(1);
+                }
+                return;
+            }
+        }
+    }
+}
+
+impl<K, V> Handle<K, V> {
+    /// Returns the key-value pair right of this edge, ascending out of
+    /// nodes whose end it is at, or `None` at the end of the tree.
+    pub(super) fn next_kv(self) -> Option<Self> {
+        let mut edge = self;
+        while edge.idx == edge.node.len() {
+            edge = edge.node.parent()?;
+        }
+        Some(edge)
+    }
+
+    /// Returns the key-value pair left of this edge, ascending out of nodes
+    /// whose start it is at, or `None` at the start of the tree.
+    pub(super) fn next_back_kv(self) -> Option<Self> {
+        let mut edge = self;
+        while edge.idx == 0 {
+            edge = edge.node.parent()?;
+        }
+        Some(Handle {
+            node: edge.node,
+            idx: edge.idx - 1,
+        })
+    }
+
+    /// Like [`next_kv`](Handle::next_kv), but frees every node it ascends
+    /// out of, and the root too if it reaches the end.
+    ///
+    /// # Safety
+    ///
+    /// Everything in the nodes being left must already have been moved out
+    /// or dropped, and nothing may point into them.
+    pub(super) unsafe fn deallocating_next_kv<A: Allocator>(self, alloc: &A) -> Option<Self> {
+        let mut edge = self;
+        while edge.idx == edge.node.len() {
+            let parent = edge.node.parent();
+            edge.node.dealloc(alloc);
+            edge = parent?;
+        }
+        Some(edge)
+    }
+
+    /// The mirror image of [`deallocating_next_kv`].
+    ///
+    /// # Safety
+    ///
+    /// As for [`deallocating_next_kv`].
+    ///
+    /// [`deallocating_next_kv`]: Handle::deallocating_next_kv
+    pub(super) unsafe fn deallocating_next_back_kv<A: Allocator>(self, alloc: &A) -> Option<Self> {
+        let mut edge = self;
+        while edge.idx == 0 {
+            let parent = edge.node.parent();
+            edge.node.dealloc(alloc);
+            edge = parent?;
+        }
+        Some(Handle {
+            node: edge.node,
+            idx: edge.idx - 1,
+        })
+    }
+
+    /// Frees this edge's node and all of its ancestors.
+    ///
+    /// # Safety
+    ///
+    /// As for [`deallocating_next_kv`](Handle::deallocating_next_kv), for
+    /// every node on the way up.
+    pub(super) unsafe fn deallocating_end<A: Allocator>(self, alloc: &A) {
+        let mut node = Some(self.node);
+        while let Some(current) = node {
+            node = current.parent().map(|parent| parent.node);
+            current.dealloc(alloc);
+        }
+    }
+
+    /// Returns the leaf edge right after this key-value pair.
+    pub(super) fn next_leaf_edge(self) -> Self {
+        if self.node.height == 0 {
+            Handle {
+                node: self.node,
+                idx: self.idx + 1,
+            }
+        } else {
+            self.node.edge(self.idx + 1).first_leaf_edge()
+        }
+    }
+
+    /// Returns the leaf edge right before this key-value pair.
+    pub(super) fn next_back_leaf_edge(self) -> Self {
+        if self.node.height == 0 {
+            self
+        } else {
+            self.node.edge(self.idx).last_leaf_edge()
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The handle must be a key-value pair, and the pair must not be
+    /// mutated while the references live.
+    pub(super) unsafe fn into_kv<'a>(self) -> (&'a K, &'a V) {
+        (&*self.node.key_ptr(self.idx), &*self.node.val_ptr(self.idx))
+    }
+
+    /// # Safety
+    ///
+    /// The handle must be a key-value pair, and nothing else may access the
+    /// pair while the references live.
+    pub(super) unsafe fn into_kv_mut<'a>(self) -> (&'a K, &'a mut V) {
+        (
+            &*self.node.key_ptr(self.idx),
+            &mut *self.node.val_ptr(self.idx),
+        )
+    }
+
+    /// Swaps in a new key and value for this key-value pair and returns
+    /// the old ones.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be a key-value pair that nothing else is accessing.
+    pub(super) unsafe fn replace_kv(self, key: K, val: V) -> (K, V) {
+        (
+            ptr::replace(self.node.key_ptr(self.idx), key),
+            ptr::replace(self.node.val_ptr(self.idx), val),
+        )
+    }
+
+    /// Moves the key-value pair out, leaving its slot logically
+    /// uninitialized.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be a key-value pair, which must not be read again.
+    pub(super) unsafe fn read_kv(self) -> (K, V) {
+        (
+            self.node.key_ptr(self.idx).read(),
+            self.node.val_ptr(self.idx).read(),
+        )
+    }
+
+    /// Inserts a key-value pair, and for an internal node the edge to its
+    /// right, at this edge of a node that is not full. Returns a pointer to
+    /// the inserted value.
+    fn insert_fit(self, key: K, val: V, edge: Option<NodeRef<K, V>>) -> *mut V {
+        let node = self.node;
+        let len = node.len();
+        debug_assert!(len < CAPACITY);
+        unsafe {
+            slice_insert(node.key_ptr(0), len, self.idx, key);
+            slice_insert(node.val_ptr(0), len, self.idx, val);
+            if let Some(edge) = edge {
+                slice_insert(node.edge_ptr(0), len + 1, self.idx + 1, edge.node);
+            }
+        }
+        node.set_len(len + 1);
+        if node.height > 0 {
+            node.correct_parent_links(self.idx + 1..len + 2);
+        }
+        node.val_ptr(self.idx)
+    }
+
+    /// Inserts at this edge, splitting the node first if it is full.
+    /// Returns a pointer to the inserted value and the split, which the
+    /// caller must insert into the parent.
+    fn insert(
+        self,
+        key: K,
+        val: V,
+        edge: Option<NodeRef<K, V>>,
+        spare: &mut SpareNodes<K, V>,
+    ) -> (*mut V, Option<SplitResult<K, V>>) {
+        if self.node.len() < CAPACITY {
+            return (self.insert_fit(key, val, edge), None);
+        }
+        let split = self.node.split(spare.take());
+        let target = if self.idx <= SPLIT_IDX {
+            Handle {
+                node: split.left,
+                idx: self.idx,
+            }
+        } else {
+            Handle {
+                node: split.right,
+                idx: self.idx - SPLIT_IDX - 1,
+            }
+        };
+        (target.insert_fit(key, val, edge), Some(split))
+    }
+
+    /// Allocates the nodes that inserting at this leaf edge will need.
+    fn reserve_insert<A: Allocator>(self, alloc: &A) -> Result<SpareNodes<K, V>, TryReserveError> {
+        let mut needed = 0;
+        let mut node = self.node;
+        while node.len() == CAPACITY {
+            needed += 1;
+            match node.parent() {
+                Some(parent) => node = parent.node,
+                None => {
+                    needed += 1;
+                    break;
+                }
+            }
+        }
+        let mut spare = SpareNodes {
+            next: None,
+            height: needed,
+        };
+        // Link from the top down so that the lowest node comes out first.
+        while spare.height > 0 {
+            let node = match NodeRef::try_alloc(spare.height - 1, alloc) {
+                Ok(node) => node,
+                Err(e) => {
+                    unsafe { spare.dealloc(alloc) };
+                    return Err(e);
+                }
+            };
+            unsafe { (*node.node.as_ptr()).parent = spare.next.map(NonNull::cast) };
+            spare.next = Some(node.node);
+            spare.height -= 1;
+        }
+        Ok(spare)
+    }
+
+    /// Inserts a key-value pair at this leaf edge, splitting nodes all the
+    /// way up as needed and growing `root` by a level if the old root
+    /// splits. Returns a pointer to the inserted value.
+    pub(super) fn insert_recursing<A: Allocator>(
+        self,
+        key: K,
+        val: V,
+        root: &mut NodeRef<K, V>,
+        alloc: &A,
+    ) -> *mut V {
+        let spare = handle_reserve(self.reserve_insert(alloc));
+        self.insert_into_spare(key, val, root, spare)
+    }
+
+    /// Fallible version of [`insert_recursing`](Handle::insert_recursing).
+    /// Every node is allocated before the tree is touched, so on error the
+    /// tree is unchanged.
+    pub(super) fn try_insert_recursing<A: Allocator>(
+        self,
+        key: K,
+        val: V,
+        root: &mut NodeRef<K, V>,
+        alloc: &A,
+    ) -> Result<*mut V, TryReserveError> {
+        let spare = self.reserve_insert(alloc)?;
+        Ok(self.insert_into_spare(key, val, root, spare))
+    }
+
+    fn insert_into_spare(
+        self,
+        key: K,
+        val: V,
+        root: &mut NodeRef<K, V>,
+        mut spare: SpareNodes<K, V>,
+    ) -> *mut V {
+        let (val_ptr, mut split) = self.insert(key, val, None, &mut spare);
+        while let Some(SplitResult { left, kv, right }) = split {
+            split = match left.parent() {
+                Some(parent) => parent.insert(kv.0, kv.1, Some(right), &mut spare).1,
+                None => {
+                    let new_root = spare.take();
+                    new_root.set_edge(0, left);
+                    new_root.push(kv.0, kv.1, Some(right));
+                    *root = new_root;
+                    None
+                }
+            };
+        }
+        debug_assert!(spare.next.is_none(), "an insertion left spare nodes");
+        val_ptr
+    }
+
+    /// Removes this key-value pair, rebalancing the tree and shrinking
+    /// `root` by a level if it runs out of keys.
+    pub(super) fn remove_kv_tracking<A: Allocator>(
+        self,
+        root: &mut NodeRef<K, V>,
+        alloc: &A,
+    ) -> (K, V) {
+        if self.node.height == 0 {
+            let kv = self.remove_from_leaf();
+            self.node.fix_underfull(root, alloc);
+            return kv;
+        }
+        // Replace the pair with its predecessor, which is always in a leaf,
+        // before anything moves.
+        let leaf_edge = self.node.edge(self.idx).last_leaf_edge();
+        let pred = Handle {
+            node: leaf_edge.node,
+            idx: leaf_edge.idx - 1,
+        };
+        let (pred_key, pred_val) = pred.remove_from_leaf();
+        let kv = unsafe {
+            (
+                ptr::replace(self.node.key_ptr(self.idx), pred_key),
+                ptr::replace(self.node.val_ptr(self.idx), pred_val),
+            )
+        };
+        pred.node.fix_underfull(root, alloc);
+        kv
+    }
+
+    /// Removes this key-value pair of a leaf without rebalancing.
+    fn remove_from_leaf(self) -> (K, V) {
+        let node = self.node;
+        let len = node.len();
+        let kv = unsafe {
+            (
+                slice_remove(node.key_ptr(0), len, self.idx),
+                slice_remove(node.val_ptr(0), len, self.idx),
+            )
+        };
+        node.set_len(len - 1);
+        kv
+    }
+
+    /// Merges the children on either side of this key-value pair, and the
+    /// pair itself, into the left child, and frees the right child.
+    fn merge<A: Allocator>(self, alloc: &A) {
+        let parent = self.node;
+        let (left, right) = (parent.edge(self.idx), parent.edge(self.idx + 1));
+        let (parent_len, left_len, right_len) = (parent.len(), left.len(), right.len());
+        debug_assert!(left_len + 1 + right_len <= CAPACITY);
+        unsafe {
+            left.key_ptr(left_len)
+                .write(slice_remove(parent.key_ptr(0), parent_len, self.idx));
+            left.val_ptr(left_len)
+                .write(slice_remove(parent.val_ptr(0), parent_len, self.idx));
+            ptr::copy_nonoverlapping(right.key_ptr(0), left.key_ptr(left_len + 1), right_len);
+            ptr::copy_nonoverlapping(right.val_ptr(0), left.val_ptr(left_len + 1), right_len);
+            slice_remove(parent.edge_ptr(0), parent_len + 1, self.idx + 1);
+            if left.height > 0 {
+                ptr::copy_nonoverlapping(
+                    right.edge_ptr(0),
+                    left.edge_ptr(left_len + 1),
+                    right_len + 1,
+                );
+            }
+        }
+        parent.set_len(parent_len - 1);
+        parent.correct_parent_links(self.idx + 1..parent_len);
+        left.set_len(left_len + 1 + right_len);
+        if left.height > 0 {
+            left.correct_parent_links(left_len + 1..left_len + right_len + 2);
+        }
+        unsafe { right.dealloc(alloc) };
+    }
+
+    /// Moves `count` key-value pairs from the left child of this pair to
+    /// its right child, rotating them through the parent.
+    fn steal_left(self, count: usize) {
+        let parent = self.node;
+        let (left, right) = (parent.edge(self.idx), parent.edge(self.idx + 1));
+        let (left_len, right_len) = (left.len(), right.len());
+        debug_assert!(count <= left_len && right_len + count <= CAPACITY);
+        let new_left_len = left_len - count;
+        unsafe {
+            ptr::copy(right.key_ptr(0), right.key_ptr(count), right_len);
+            ptr::copy(right.val_ptr(0), right.val_ptr(count), right_len);
+            let key = ptr::replace(parent.key_ptr(self.idx), left.key_ptr(new_left_len).read());
+            let val = ptr::replace(parent.val_ptr(self.idx), left.val_ptr(new_left_len).read());
+            right.key_ptr(count - 1).write(key);
+            right.val_ptr(count - 1).write(val);
+            ptr::copy_nonoverlapping(left.key_ptr(new_left_len + 1), right.key_ptr(0), count - 1);
+            ptr::copy_nonoverlapping(left.val_ptr(new_left_len + 1), right.val_ptr(0), count - 1);
+            if right.height > 0 {
+                ptr::copy(right.edge_ptr(0), right.edge_ptr(count), right_len + 1);
+                ptr::copy_nonoverlapping(left.edge_ptr(new_left_len + 1), right.edge_ptr(0), count);
+            }
+        }
+        left.set_len(new_left_len);
+        right.set_len(right_len + count);
+        if right.height > 0 {
+            right.correct_parent_links(0..right_len + count + 1);
+        }
+    }
+
+    /// Moves `count` key-value pairs from the right child of this pair to
+    /// its left child, rotating them through the parent.
+    fn steal_right(self, count: usize) {
+        let parent = self.node;
+        let (left, right) = (parent.edge(self.idx), parent.edge(self.idx + 1));
+        let (left_len, right_len) = (left.len(), right.len());
+        debug_assert!(count <= right_len && left_len + count <= CAPACITY);
+        let new_right_len = right_len - count;
+        unsafe {
+            let key = ptr::replace(parent.key_ptr(self.idx), right.key_ptr(count - 1).read());
+            let val = ptr::replace(parent.val_ptr(self.idx), right.val_ptr(count - 1).read());
+            left.key_ptr(left_len).write(key);
+            left.val_ptr(left_len).write(val);
+            ptr::copy_nonoverlapping(right.key_ptr(0), left.key_ptr(left_len + 1), count - 1);
+            ptr::copy_nonoverlapping(right.val_ptr(0), left.val_ptr(left_len + 1), count - 1);
+            ptr::copy(right.key_ptr(count), right.key_ptr(0), new_right_len);
+            ptr::copy(right.val_ptr(count), right.val_ptr(0), new_right_len);
+            if left.height > 0 {
+                ptr::copy_nonoverlapping(right.edge_ptr(0), left.edge_ptr(left_len + 1), count);
+                ptr::copy(right.edge_ptr(count), right.edge_ptr(0), new_right_len + 1);
+            }
+        }
+        left.set_len(left_len + count);
+        right.set_len(new_right_len);
+        if left.height > 0 {
+            left.correct_parent_links(left_len + 1..left_len + count + 1);
+            right.correct_parent_links(0..new_right_len + 1);
+        }
+    }
+}
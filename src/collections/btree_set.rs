@@ -0,0 +1,986 @@
+//! An ordered set implemented as a [`BTreeMap`] with `()` values.
+//!
+//! The set operations (`union`, `intersection`, `difference`,
+//! `symmetric_difference`) merge both sets in a single ascending pass and
+//! return lazy iterators borrowing them; the operator impls (`|`, `&`, `-`,
+//! `^`) collect them into a new set. [`BTreeSet::lower_bound`] and
+//! [`BTreeSet::upper_bound`] return a [`Cursor`] for finding the elements
+//! nearest to a value.
+
+use super::btree_map::{self, BTreeMap, Keys};
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::TryReserveError;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator, Peekable};
+use std::ops::{BitAnd, BitOr, BitXor, Bound, RangeBounds, Sub};
+
+/// An ordered set based on a B-tree, allocated from `A`.
+///
+/// ```
+/// use mystdrs::collections::BTreeSet;
+/// use std::ops::Bound::Included;
+///
+/// let a: BTreeSet<i32> = [1, 2, 3, 8].into();
+/// let b: BTreeSet<i32> = [2, 3, 4].into();
+/// assert!(a.intersection(&b).eq(&[2, 3]));
+/// assert_eq!(&a | &b, [1, 2, 3, 4, 8].into());
+///
+/// // The nearest elements on either side of 5.
+/// let cursor = a.lower_bound(Included(&5));
+/// assert_eq!(cursor.peek_prev(), Some(&3));
+/// assert_eq!(cursor.peek_next(), Some(&8));
+/// ```
+pub struct BTreeSet<T, A: Allocator = Global> {
+    map: BTreeMap<T, (), A>,
+}
+
+impl<T> BTreeSet<T> {
+    /// Creates an empty set without allocating.
+    pub const fn new() -> Self {
+        BTreeSet {
+            map: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T, A: Allocator> BTreeSet<T, A> {
+    /// Creates an empty set in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        BTreeSet {
+            map: BTreeMap::new_in(alloc),
+        }
+    }
+
+    /// Returns the allocator backing the set.
+    pub fn allocator(&self) -> &A {
+        self.map.allocator()
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes every element.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns an iterator over the elements in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.map.keys(),
+        }
+    }
+
+    /// Returns the smallest element.
+    pub fn first(&self) -> Option<&T> {
+        self.map.first_key_value().map(|(k, _)| k)
+    }
+
+    /// Returns the largest element.
+    pub fn last(&self) -> Option<&T> {
+        self.map.last_key_value().map(|(k, _)| k)
+    }
+
+    /// Removes and returns the smallest element.
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.map.pop_first().map(|(k, _)| k)
+    }
+
+    /// Removes and returns the largest element.
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.map.pop_last().map(|(k, _)| k)
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, visiting them
+    /// in ascending order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.map.retain(|k, _| f(k));
+    }
+}
+
+impl<T: Ord, A: Allocator> BTreeSet<T, A> {
+    /// Returns `true` if the set holds `value`.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Returns the stored element equal to `value`.
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+    /// Adds `value` and returns `true` if it was not present. An equal
+    /// element already in the set is kept.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    /// Fallible version of [`insert`](BTreeSet::insert): returns an error
+    /// instead of aborting if a node cannot be allocated. On error `value`
+    /// is dropped and the set is unchanged.
+    pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
+        self.map
+            .try_insert_or_replace(value, ())
+            .map(|old| old.is_none())
+    }
+
+    /// Adds `value`, replacing and returning an equal element already in
+    /// the set.
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.map.replace_entry(value, ()).map(|(k, _)| k)
+    }
+
+    /// Removes `value` and returns `true` if it was present.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes and returns the stored element equal to `value`.
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the elements that lie in `range`, in
+    /// ascending order.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`BTreeMap::range`].
+    pub fn range<K, R>(&self, range: R) -> Range<'_, T>
+    where
+        T: Borrow<K>,
+        K: Ord + ?Sized,
+        R: RangeBounds<K>,
+    {
+        Range {
+            iter: self.map.range(range),
+        }
+    }
+
+    /// Returns a cursor at the gap before the first element inside
+    /// `bound..`. See [`BTreeMap::lower_bound`].
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.map.lower_bound(bound),
+        }
+    }
+
+    /// Returns a cursor at the gap after the last element inside
+    /// `..bound`. See [`BTreeMap::upper_bound`].
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.map.upper_bound(bound),
+        }
+    }
+
+    /// Moves every element of `other` into `self`, leaving `other` empty.
+    /// Where both sets hold equal elements, the one from `other` is kept.
+    ///
+    /// Takes time linear in the size of both sets.
+    pub fn append(&mut self, other: &mut Self) {
+        self.map.append(&mut other.map);
+    }
+
+    /// Splits the set in two at `value`: `self` keeps the elements that
+    /// are smaller and the rest are returned.
+    pub fn split_off<Q>(&mut self, value: &Q) -> Self
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        A: Clone,
+    {
+        BTreeSet {
+            map: self.map.split_off(value),
+        }
+    }
+
+    /// Returns the elements of `self` that are not in `other`, in
+    /// ascending order.
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T> {
+        Difference {
+            merge: Merge::new(self, other),
+        }
+    }
+
+    /// Returns the elements in exactly one of `self` and `other`, in
+    /// ascending order.
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T> {
+        SymmetricDifference {
+            merge: Merge::new(self, other),
+        }
+    }
+
+    /// Returns the elements in both `self` and `other`, in ascending
+    /// order. Where they are equal, the elements of `self` are yielded.
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T> {
+        Intersection {
+            merge: Merge::new(self, other),
+        }
+    }
+
+    /// Returns the elements in `self` or `other`, each once, in ascending
+    /// order. Where they are equal, the elements of `self` are yielded.
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T> {
+        Union {
+            merge: Merge::new(self, other),
+        }
+    }
+
+    /// Returns `true` if no element is in both sets.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Returns `true` if every element of `self` is in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    /// Returns `true` if every element of `other` is in `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for BTreeSet<T, A> {
+    fn clone(&self) -> Self {
+        BTreeSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for BTreeSet<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for BTreeSet<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for BTreeSet<T, A> {}
+
+impl<T: PartialOrd, A: Allocator> PartialOrd for BTreeSet<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord, A: Allocator> Ord for BTreeSet<T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash, A: Allocator> Hash for BTreeSet<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.map.hash(state);
+    }
+}
+
+impl<T> Default for BTreeSet<T> {
+    fn default() -> Self {
+        BTreeSet::new()
+    }
+}
+
+impl<T: Ord, A: Allocator> Extend<T> for BTreeSet<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|k| (k, ())));
+    }
+}
+
+impl<'a, T, A> Extend<&'a T> for BTreeSet<T, A>
+where
+    T: Ord + Copy + 'a,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BTreeSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        BTreeSet {
+            map: iter.into_iter().map(|k| (k, ())).collect(),
+        }
+    }
+}
+
+impl<T: Ord, const N: usize> From<[T; N]> for BTreeSet<T> {
+    fn from(arr: [T; N]) -> Self {
+        let mut set = BTreeSet::new();
+        set.extend(arr);
+        set
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a BTreeSet<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for BTreeSet<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter {
+            iter: self.map.into_keys(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> BitOr<&BTreeSet<T>> for &BTreeSet<T> {
+    type Output = BTreeSet<T>;
+
+    /// Returns the union of `self` and `rhs` as a new set.
+    fn bitor(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T: Ord + Clone> BitAnd<&BTreeSet<T>> for &BTreeSet<T> {
+    type Output = BTreeSet<T>;
+
+    /// Returns the intersection of `self` and `rhs` as a new set.
+    fn bitand(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T: Ord + Clone> BitXor<&BTreeSet<T>> for &BTreeSet<T> {
+    type Output = BTreeSet<T>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new set.
+    fn bitxor(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+impl<T: Ord + Clone> Sub<&BTreeSet<T>> for &BTreeSet<T> {
+    type Output = BTreeSet<T>;
+
+    /// Returns the difference of `self` and `rhs` as a new set.
+    fn sub(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+/// An iterator over the elements of a set, created by [`BTreeSet::iter`].
+pub struct Iter<'a, T> {
+    iter: Keys<'a, T, ()>,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An owning iterator over the elements of a set, created by
+/// `BTreeSet::into_iter`.
+pub struct IntoIter<T, A: Allocator = Global> {
+    iter: btree_map::IntoKeys<T, (), A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+/// An iterator over a range of a set's elements, created by
+/// [`BTreeSet::range`].
+pub struct Range<'a, T> {
+    iter: btree_map::Range<'a, T, ()>,
+}
+
+impl<T> Clone for Range<'_, T> {
+    fn clone(&self) -> Self {
+        Range {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Range<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<T> FusedIterator for Range<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Range<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A position in a set, either between two elements or at one of its
+/// ends, created by [`BTreeSet::lower_bound`] and
+/// [`BTreeSet::upper_bound`].
+pub struct Cursor<'a, T> {
+    inner: btree_map::Cursor<'a, T, ()>,
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Cursor {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Moves the cursor past the next element and returns it, or returns
+    /// `None` without moving at the end of the set.
+    pub fn move_next(&mut self) -> Option<&'a T> {
+        self.inner.move_next().map(|(k, _)| k)
+    }
+
+    /// Moves the cursor back past the previous element and returns it, or
+    /// returns `None` without moving at the start of the set.
+    pub fn move_prev(&mut self) -> Option<&'a T> {
+        self.inner.move_prev().map(|(k, _)| k)
+    }
+
+    /// Returns the element after the cursor without moving it.
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.inner.peek_next().map(|(k, _)| k)
+    }
+
+    /// Returns the element before the cursor without moving it.
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        self.inner.peek_prev().map(|(k, _)| k)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Cursor<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cursor")
+            .field("prev", &self.peek_prev())
+            .field("next", &self.peek_next())
+            .finish()
+    }
+}
+
+/// Walks two sets in step for the set-operation iterators.
+struct Merge<'a, T> {
+    a: Peekable<Iter<'a, T>>,
+    b: Peekable<Iter<'a, T>>,
+}
+
+impl<T> Clone for Merge<'_, T> {
+    fn clone(&self) -> Self {
+        Merge {
+            a: self.a.clone(),
+            b: self.b.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Merge<'a, T> {
+    fn new<A: Allocator>(a: &'a BTreeSet<T, A>, b: &'a BTreeSet<T, A>) -> Self {
+        Merge {
+            a: a.iter().peekable(),
+            b: b.iter().peekable(),
+        }
+    }
+
+    /// Takes the smaller of the two next elements from its side, or one
+    /// from each side if they are equal.
+    fn next(&mut self) -> (Option<&'a T>, Option<&'a T>) {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => Ordering::Equal,
+        };
+        match order {
+            Ordering::Less => (self.a.next(), None),
+            Ordering::Greater => (None, self.b.next()),
+            Ordering::Equal => (self.a.next(), self.b.next()),
+        }
+    }
+
+    /// Returns how many elements remain on each side.
+    fn lens(&self) -> (usize, usize) {
+        (self.a.len(), self.b.len())
+    }
+}
+
+/// A lazy iterator over the intersection of two sets, created by
+/// [`BTreeSet::intersection`].
+pub struct Intersection<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+impl<T> Clone for Intersection<'_, T> {
+    fn clone(&self) -> Self {
+        Intersection {
+            merge: self.merge.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            // Nothing more can match once either side runs out.
+            self.merge.a.peek()?;
+            self.merge.b.peek()?;
+            if let (Some(a), Some(_)) = self.merge.next() {
+                return Some(a);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.merge.lens();
+        (0, Some(a.min(b)))
+    }
+}
+
+impl<T: Ord> FusedIterator for Intersection<'_, T> {}
+
+impl<T: fmt::Debug + Ord> fmt::Debug for Intersection<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the difference of two sets, created by
+/// [`BTreeSet::difference`].
+pub struct Difference<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+impl<T> Clone for Difference<'_, T> {
+    fn clone(&self) -> Self {
+        Difference {
+            merge: self.merge.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            // Once `self` runs out, the rest of `other` is never needed.
+            self.merge.a.peek()?;
+            if let (Some(a), None) = self.merge.next() {
+                return Some(a);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.merge.lens();
+        (a.saturating_sub(b), Some(a))
+    }
+}
+
+impl<T: Ord> FusedIterator for Difference<'_, T> {}
+
+impl<T: fmt::Debug + Ord> fmt::Debug for Difference<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the symmetric difference of two sets, created by
+/// [`BTreeSet::symmetric_difference`].
+pub struct SymmetricDifference<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+impl<T> Clone for SymmetricDifference<'_, T> {
+    fn clone(&self) -> Self {
+        SymmetricDifference {
+            merge: self.merge.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match self.merge.next() {
+                (Some(_), Some(_)) => {}
+                (a, b) => return a.or(b),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.merge.lens();
+        (0, a.checked_add(b))
+    }
+}
+
+impl<T: Ord> FusedIterator for SymmetricDifference<'_, T> {}
+
+impl<T: fmt::Debug + Ord> fmt::Debug for SymmetricDifference<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the union of two sets, created by
+/// [`BTreeSet::union`].
+pub struct Union<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+impl<T> Clone for Union<'_, T> {
+    fn clone(&self) -> Self {
+        Union {
+            merge: self.merge.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let (a, b) = self.merge.next();
+        a.or(b)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.merge.lens();
+        (a.max(b), a.checked_add(b))
+    }
+}
+
+impl<T: Ord> FusedIterator for Union<'_, T> {}
+
+impl<T: fmt::Debug + Ord> fmt::Debug for Union<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BTreeSet;
+    use crate::test_util::{Budget, XorShift};
+    use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+    type StdSet = std::collections::BTreeSet<u32>;
+
+    #[test]
+    fn basic_operations() {
+        let mut set = BTreeSet::new();
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(5));
+        assert!(set.insert(1));
+        assert!(set.contains(&3));
+        assert_eq!(set.get(&5), Some(&5));
+        assert_eq!((set.first(), set.last()), (Some(&1), Some(&5)));
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!(set.take(&5), Some(5));
+        assert_eq!(set.pop_last(), Some(1));
+        assert_eq!(set.pop_first(), None);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn set_algebra_matches_std() {
+        let mut rng = XorShift::new(23);
+        for round in 0..200 {
+            let a_len = rng.below(if round < 100 { 40 } else { 400 });
+            let b_len = rng.below(40) + round % 2;
+            let a_model: StdSet = (0..a_len).map(|_| rng.below(300) as u32).collect();
+            let b_model: StdSet = (0..b_len).map(|_| rng.below(300) as u32).collect();
+            let a: BTreeSet<u32> = a_model.iter().copied().collect();
+            let b: BTreeSet<u32> = b_model.iter().copied().collect();
+
+            let union: Vec<u32> = a_model.union(&b_model).copied().collect();
+            let inter: Vec<u32> = a_model.intersection(&b_model).copied().collect();
+            let diff: Vec<u32> = a_model.difference(&b_model).copied().collect();
+            let sym: Vec<u32> = a_model.symmetric_difference(&b_model).copied().collect();
+            assert!(a.union(&b).eq(&union));
+            assert!(a.intersection(&b).eq(&inter));
+            assert!(a.difference(&b).eq(&diff));
+            assert!(a.symmetric_difference(&b).eq(&sym));
+            assert!((&a | &b).iter().eq(&union));
+            assert!((&a & &b).iter().eq(&inter));
+            assert!((&a - &b).iter().eq(&diff));
+            assert!((&a ^ &b).iter().eq(&sym));
+            assert_eq!(a.is_subset(&b), a_model.is_subset(&b_model));
+            assert_eq!(a.is_superset(&b), a_model.is_superset(&b_model));
+            assert_eq!(a.is_disjoint(&b), a_model.is_disjoint(&b_model));
+
+            for (hint, len) in [
+                (a.union(&b).size_hint(), union.len()),
+                (a.intersection(&b).size_hint(), inter.len()),
+                (a.difference(&b).size_hint(), diff.len()),
+                (a.symmetric_difference(&b).size_hint(), sym.len()),
+            ] {
+                assert!(hint.0 <= len && len <= hint.1.unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn cursors_find_nearest_elements() {
+        // Interval starts; look up the ones on either side of a point.
+        let starts: BTreeSet<u32> = [10, 20, 30, 40].into();
+        let cursor = starts.lower_bound(Included(&25));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&20), Some(&30))
+        );
+        let cursor = starts.lower_bound(Included(&20));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&10), Some(&20))
+        );
+        let cursor = starts.lower_bound(Excluded(&20));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&20), Some(&30))
+        );
+        let cursor = starts.upper_bound(Included(&20));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&20), Some(&30))
+        );
+        let cursor = starts.upper_bound(Excluded(&20));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&10), Some(&20))
+        );
+
+        let mut cursor = starts.upper_bound(Included(&5));
+        assert_eq!(cursor.move_prev(), None);
+        assert_eq!(cursor.move_next(), Some(&10));
+        assert_eq!(cursor.move_next(), Some(&20));
+        assert_eq!(cursor.move_prev(), Some(&20));
+        assert_eq!(cursor.move_prev(), Some(&10));
+        assert_eq!(cursor.move_prev(), None);
+
+        let mut cursor = starts.upper_bound(Unbounded);
+        assert_eq!(cursor.peek_next(), None);
+        let walked: Vec<u32> = std::iter::from_fn(|| cursor.move_prev().copied()).collect();
+        assert_eq!(walked, [40, 30, 20, 10]);
+        assert_eq!(
+            format!("{:?}", cursor),
+            "Cursor { prev: None, next: Some(10) }"
+        );
+
+        let empty = BTreeSet::<u32>::new();
+        let mut cursor = empty.lower_bound(Bound::Included(&1));
+        assert_eq!((cursor.move_next(), cursor.move_prev()), (None, None));
+    }
+
+    #[test]
+    fn cursors_match_std_ranges() {
+        let mut rng = XorShift::new(5);
+        let model: StdSet = (0..3000).map(|_| rng.below(20_000) as u32).collect();
+        let set: BTreeSet<u32> = model.iter().copied().collect();
+        for _ in 0..2000 {
+            let x = rng.below(20_100) as u32;
+            let mut cursor = set.lower_bound(Included(&x));
+            let mut after = model.range(x..);
+            let mut before = model.range(..x);
+            assert_eq!(cursor.clone().move_next(), after.next());
+            assert_eq!(cursor.move_prev(), before.next_back());
+            assert_eq!(cursor.move_prev(), before.next_back());
+
+            let mut cursor = set.upper_bound(Excluded(&x));
+            let mut before = model.range(..x);
+            assert_eq!(cursor.move_prev(), before.next_back());
+        }
+    }
+
+    #[test]
+    fn range_append_split_off_retain() {
+        let mut set: BTreeSet<u32> = (0..100).collect();
+        assert!(set.range(10..15).eq(&[10, 11, 12, 13, 14]));
+        assert_eq!(set.range((Excluded(90), Unbounded)).next_back(), Some(&99));
+        let high = set.split_off(&50);
+        assert!(set.iter().eq(&(0..50).collect::<Vec<_>>()));
+        assert!(high.iter().eq(&(50..100).collect::<Vec<_>>()));
+
+        let mut odd: BTreeSet<u32> = (0..200).filter(|v| v % 2 == 1).collect();
+        set.append(&mut odd);
+        assert!(odd.is_empty());
+        assert_eq!(set.len(), 50 + 75);
+        set.retain(|v| v % 5 == 0);
+        let expected = (0..50).chain(50..200).filter(|v| v < &50 || v % 2 == 1);
+        assert!(set.iter().copied().eq(expected.filter(|v| v % 5 == 0)));
+    }
+
+    #[test]
+    fn replace_swaps_the_stored_element() {
+        #[derive(Debug)]
+        struct Tagged(u32, &'static str);
+
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Tagged {}
+
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut set = BTreeSet::new();
+        assert!(set.replace(Tagged(1, "old")).is_none());
+        assert!(!set.insert(Tagged(1, "kept")));
+        assert_eq!(set.replace(Tagged(1, "new")).unwrap().1, "old");
+        assert_eq!(set.get(&Tagged(1, "")).unwrap().1, "new");
+        assert_eq!(set.take(&Tagged(1, "")).unwrap().1, "new");
+    }
+
+    #[test]
+    fn traits() {
+        let mut set: BTreeSet<u32> = [2, 1].into();
+        set.extend(&[2, 3]);
+        assert_eq!(set.len(), 3);
+        assert_eq!(format!("{:?}", set), "{1, 2, 3}");
+        assert_eq!(format!("{:?}", set.intersection(&[3, 4].into())), "[3]");
+        assert_eq!(format!("{:?}", set.union(&[0].into())), "[0, 1, 2, 3]");
+        assert!(set.clone().into_iter().rev().eq([3, 2, 1]));
+        assert_eq!(set.iter().len(), 3);
+        assert!(set < [1, 2, 4].into());
+        assert_eq!(set, [3, 2, 1].into());
+        assert_eq!(BTreeSet::<u32>::default(), BTreeSet::new());
+    }
+
+    #[test]
+    fn try_insert_reports_failure() {
+        let budget = Budget::new(0);
+        let mut set = BTreeSet::new_in(&budget);
+        assert!(set.try_insert(1).is_err());
+        budget.set(usize::MAX);
+        assert_eq!(set.try_insert(1), Ok(true));
+        budget.set(0);
+        assert_eq!(set.try_insert(1), Ok(false));
+        assert_eq!(set.try_insert(2), Ok(true));
+        while set.try_insert(set.len() + 1).is_ok() {}
+        assert!(set.iter().copied().eq(1..=set.len()));
+    }
+}
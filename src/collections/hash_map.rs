@@ -0,0 +1,1391 @@
+//! A hash map implemented as a SwissTable.
+//!
+//! Entries live in an open-addressing table next to one control byte per
+//! bucket holding seven bits of the entry's hash. Lookups scan the control
+//! bytes eight at a time with portable word arithmetic and only compare
+//! keys whose seven bits match, so a miss usually touches no keys at all.
+//! Removed entries leave tombstones where a probe sequence may run through
+//! them; tombstones are reused by later insertions and cleared out when the
+//! table is rehashed.
+
+use super::raw_table::{RawDrain, RawExtractIf, RawIntoIter, RawIter, RawTable};
+use crate::alloc::{Allocator, Global};
+use crate::hash::{BuildHasher, Hash, RandomState};
+use crate::raw_vec::TryReserveError;
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Index;
+
+/// A hash map with pluggable hashing, allocated from `A`.
+///
+/// By default keys are hashed with [`RandomState`], a randomly keyed
+/// SipHash that resists collision attacks; pass a different
+/// [`BuildHasher`] to [`with_hasher`](HashMap::with_hasher) for a faster,
+/// weaker hash.
+///
+/// ```
+/// use mystdrs::collections::HashMap;
+///
+/// let mut stock = HashMap::new();
+/// stock.insert("apples", 3);
+/// *stock.entry("pears").or_insert(0) += 5;
+/// stock.retain(|_, n| *n > 4);
+/// assert_eq!(stock.len(), 1);
+/// assert_eq!(stock["pears"], 5);
+/// ```
+pub struct HashMap<K, V, S = RandomState, A: Allocator = Global> {
+    hash_builder: S,
+    pub(super) table: RawTable<(K, V), A>,
+}
+
+fn make_hash<Q: Hash + ?Sized, S: BuildHasher>(hash_builder: &S, val: &Q) -> u64 {
+    hash_builder.hash_one(val)
+}
+
+/// Returns the function the table uses to rehash entries when it grows.
+fn make_hasher<K: Hash, V, S: BuildHasher>(hash_builder: &S) -> impl Fn(&(K, V)) -> u64 + '_ {
+    move |(k, _)| make_hash(hash_builder, k)
+}
+
+fn equivalent_key<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
+where
+    K: Borrow<Q>,
+    Q: Eq + ?Sized,
+{
+    move |(x, _)| k == x.borrow()
+}
+
+impl<K, V> HashMap<K, V> {
+    /// Creates an empty map without allocating.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    /// Creates an empty map that hashes keys with `hash_builder`.
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self::with_hasher_in(hash_builder, Global)
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries that
+    /// hashes keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher_in(capacity, hash_builder, Global)
+    }
+}
+
+impl<K, V, S, A: Allocator> HashMap<K, V, S, A> {
+    /// Creates an empty map in `alloc` without allocating.
+    pub const fn with_hasher_in(hash_builder: S, alloc: A) -> Self {
+        HashMap {
+            hash_builder,
+            table: RawTable::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty map in `alloc` with room for at least `capacity`
+    /// entries.
+    pub fn with_capacity_and_hasher_in(capacity: usize, hash_builder: S, alloc: A) -> Self {
+        HashMap {
+            hash_builder,
+            table: RawTable::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Fallible version of
+    /// [`with_capacity_and_hasher_in`](HashMap::with_capacity_and_hasher_in).
+    pub fn try_with_capacity_and_hasher_in(
+        capacity: usize,
+        hash_builder: S,
+        alloc: A,
+    ) -> Result<Self, TryReserveError> {
+        Ok(HashMap {
+            hash_builder,
+            table: RawTable::try_with_capacity_in(capacity, alloc)?,
+        })
+    }
+
+    /// Returns the map's [`BuildHasher`].
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// Returns the allocator backing the map.
+    pub fn allocator(&self) -> &A {
+        self.table.allocator()
+    }
+
+    /// Returns the number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if the map holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the keys, in arbitrary order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Returns an iterator over the values, in arbitrary order.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Returns an iterator over mutable references to the values, in
+    /// arbitrary order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Consumes the map and returns an iterator over its keys.
+    pub fn into_keys(self) -> IntoKeys<K, V, A> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Consumes the map and returns an iterator over its values.
+    pub fn into_values(self) -> IntoValues<K, V, A> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Returns an iterator over the entries, in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.table.iter(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the entries with mutable references to the
+    /// values, in arbitrary order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.table.iter(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Removes every entry and returns them as an iterator, keeping the
+    /// allocation. Entries the iterator does not yield are dropped with it;
+    /// if it is leaked, they are leaked but the map is still left empty.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        Drain {
+            inner: self.table.drain(),
+        }
+    }
+
+    /// Returns an iterator that removes and yields every entry for which
+    /// `pred` returns `true`. Entries it does not get to stay in the map.
+    ///
+    /// ```
+    /// use mystdrs::collections::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x)).collect();
+    /// let mut evens: Vec<i32> = map.extract_if(|k, _| k % 2 == 0).map(|(k, _)| k).collect();
+    /// evens.sort();
+    /// assert_eq!(evens, [0, 2, 4, 6]);
+    /// assert_eq!(map.len(), 4);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F, A>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            inner: self.table.extract_if(),
+            pred,
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut iter = self.table.iter();
+        while let Some(index) = iter.next() {
+            let (k, v) = unsafe { &mut *iter.bucket(index) };
+            if !f(k, v) {
+                // Unlink the entry before dropping it, so that a panicking
+                // destructor leaves the map consistent.
+                drop(unsafe { self.table.remove(index) });
+            }
+        }
+    }
+
+    /// Removes every entry, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
+
+impl<K, V, S, A> HashMap<K, V, S, A>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    /// Makes room for at least `additional` more entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.table
+            .reserve(additional, make_hasher::<K, V, S>(&self.hash_builder));
+    }
+
+    /// Fallible version of [`reserve`](HashMap::reserve).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.table
+            .try_reserve(additional, make_hasher::<K, V, S>(&self.hash_builder))
+    }
+
+    /// Shrinks the capacity as much as the load factor allows.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity to hold at least `max(len, min_capacity)`
+    /// entries.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.table
+            .shrink_to(min_capacity, make_hasher::<K, V, S>(&self.hash_builder));
+    }
+
+    /// Returns the entry for `key`, for in-place manipulation.
+    ///
+    /// ```
+    /// use mystdrs::collections::HashMap;
+    ///
+    /// let mut counts = HashMap::new();
+    /// for word in "a b a c a".split(' ') {
+    ///     counts.entry(word).and_modify(|n| *n += 1).or_insert(1);
+    /// }
+    /// assert_eq!(counts["a"], 3);
+    /// assert_eq!(counts["c"], 1);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, A> {
+        let hash = make_hash(&self.hash_builder, &key);
+        match self.table.find(hash, equivalent_key(&key)) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                index,
+                table: &mut self.table,
+            }),
+            None => {
+                // Grow now, while the hasher is at hand, so that inserting
+                // through the entry cannot need it.
+                self.reserve(1);
+                Entry::Vacant(VacantEntry {
+                    hash,
+                    key,
+                    table: &mut self.table,
+                })
+            }
+        }
+    }
+
+    /// Returns a reference to the value for `k`.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_inner(k).map(|(_, v)| v)
+    }
+
+    /// Returns the stored key and the value for `k`.
+    pub fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_inner(k).map(|(k, v)| (k, v))
+    }
+
+    fn get_inner<Q>(&self, k: &Q) -> Option<&(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.table.len() == 0 {
+            return None;
+        }
+        let hash = make_hash(&self.hash_builder, k);
+        self.table.get(hash, equivalent_key(k))
+    }
+
+    /// Returns `true` if the map holds an entry for `k`.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_inner(k).is_some()
+    }
+
+    /// Returns a mutable reference to the value for `k`.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.table.len() == 0 {
+            return None;
+        }
+        let hash = make_hash(&self.hash_builder, k);
+        self.table.get_mut(hash, equivalent_key(k)).map(|(_, v)| v)
+    }
+
+    /// Inserts `v` for `k` and returns the value it replaces, if any. An
+    /// existing key is kept, not replaced by `k`.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let hash = make_hash(&self.hash_builder, &k);
+        match self.table.get_mut(hash, equivalent_key(&k)) {
+            Some((_, old)) => Some(mem::replace(old, v)),
+            None => {
+                self.table
+                    .insert(hash, (k, v), make_hasher::<K, V, S>(&self.hash_builder));
+                None
+            }
+        }
+    }
+
+    /// Fallible version of [`insert`](HashMap::insert): returns an error
+    /// instead of aborting if the table cannot grow. On error `k` and `v`
+    /// are dropped and the map is unchanged.
+    ///
+    /// Like `insert`, this replaces the value of a key already in the map.
+    /// It is not std's unstable `try_insert`, which fails on such a key.
+    pub fn try_insert_or_replace(&mut self, k: K, v: V) -> Result<Option<V>, TryReserveError> {
+        let hash = make_hash(&self.hash_builder, &k);
+        if let Some((_, old)) = self.table.get_mut(hash, equivalent_key(&k)) {
+            return Ok(Some(mem::replace(old, v)));
+        }
+        self.try_reserve(1)?;
+        self.table
+            .insert(hash, (k, v), make_hasher::<K, V, S>(&self.hash_builder));
+        Ok(None)
+    }
+
+    /// Removes the entry for `k` and returns its value.
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(k).map(|(_, v)| v)
+    }
+
+    /// Removes the entry for `k` and returns the stored key and value.
+    pub fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = make_hash(&self.hash_builder, k);
+        self.table.remove_entry(hash, equivalent_key(k))
+    }
+
+    /// Inserts `k` and `v`, replacing both the key and the value of an
+    /// existing entry, which is returned. Used by `HashSet::replace`.
+    pub(crate) fn replace_entry(&mut self, k: K, v: V) -> Option<(K, V)> {
+        let hash = make_hash(&self.hash_builder, &k);
+        match self.table.get_mut(hash, equivalent_key(&k)) {
+            Some(pair) => Some(mem::replace(pair, (k, v))),
+            None => {
+                self.table
+                    .insert(hash, (k, v), make_hasher::<K, V, S>(&self.hash_builder));
+                None
+            }
+        }
+    }
+
+    /// Returns the key equal to `k`, first inserting `k` and `v` if there
+    /// is none. Used by `HashSet::get_or_insert`.
+    pub(crate) fn get_or_insert_key(&mut self, k: K, v: V) -> &K {
+        let hash = make_hash(&self.hash_builder, &k);
+        let index = match self.table.find(hash, equivalent_key(&k)) {
+            Some(index) => index,
+            None => self
+                .table
+                .insert(hash, (k, v), make_hasher::<K, V, S>(&self.hash_builder)),
+        };
+        unsafe { &(*self.table.bucket(index)).0 }
+    }
+
+    /// Returns the key equal to `k`, first inserting the entry made by `f`
+    /// if there is none. Used by `HashSet::get_or_insert_with`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key made by `f` is not equal to `k`.
+    pub(crate) fn get_or_insert_key_with<Q, F>(&mut self, k: &Q, f: F) -> &K
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&Q) -> (K, V),
+    {
+        let hash = make_hash(&self.hash_builder, k);
+        let index = match self.table.find(hash, equivalent_key(k)) {
+            Some(index) => index,
+            None => {
+                let pair = f(k);
+                assert!(k == pair.0.borrow(), "new value is not equivalent");
+                self.table
+                    .insert(hash, pair, make_hasher::<K, V, S>(&self.hash_builder))
+            }
+        };
+        unsafe { &(*self.table.bucket(index)).0 }
+    }
+}
+
+impl<K, V, S, A> Clone for HashMap<K, V, S, A>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+    A: Allocator + Clone,
+{
+    fn clone(&self) -> Self {
+        HashMap {
+            hash_builder: self.hash_builder.clone(),
+            table: self.table.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S, A: Allocator> fmt::Debug for HashMap<K, V, S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S, A> PartialEq for HashMap<K, V, S, A>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K, V, S, A> Eq for HashMap<K, V, S, A>
+where
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, Q, V, S, A> Index<&Q> for HashMap<K, V, S, A>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+    A: Allocator,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if the map holds no entry for `key`.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K, V, S, A> Extend<(K, V)> for HashMap<K, V, S, A>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        // Duplicate keys are common when extending a non-empty map, so only
+        // reserve for half of them up front.
+        let reserve = if self.is_empty() {
+            iter.size_hint().0
+        } else {
+            iter.size_hint().0.div_ceil(2)
+        };
+        self.reserve(reserve);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V, S, A> Extend<(&'a K, &'a V)> for HashMap<K, V, S, A>
+where
+    K: Eq + Hash + Copy,
+    V: Copy,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = HashMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> From<[(K, V); N]> for HashMap<K, V> {
+    fn from(arr: [(K, V); N]) -> Self {
+        let mut map = HashMap::new();
+        map.extend(arr);
+        map
+    }
+}
+
+impl<'a, K, V, S, A: Allocator> IntoIterator for &'a HashMap<K, V, S, A> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S, A: Allocator> IntoIterator for &'a mut HashMap<K, V, S, A> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S, A: Allocator> IntoIterator for HashMap<K, V, S, A> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    fn into_iter(self) -> IntoIter<K, V, A> {
+        IntoIter {
+            inner: self.table.into_iter(),
+        }
+    }
+}
+
+/// A view into a single entry of a map, created by [`HashMap::entry`].
+pub enum Entry<'a, K, V, A: Allocator = Global> {
+    Occupied(OccupiedEntry<'a, K, V, A>),
+    Vacant(VacantEntry<'a, K, V, A>),
+}
+
+impl<'a, K, V, A: Allocator> Entry<'a, K, V, A> {
+    /// Inserts `default` if the entry is vacant and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entry is vacant and returns
+    /// the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Like [`or_insert_with`](Entry::or_insert_with), but `default` is
+    /// given the key.
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V: Default, A: Allocator> Entry<'a, K, V, A> {
+    /// Inserts `V::default()` if the entry is vacant and returns the value.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for Entry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+/// An occupied entry, part of the [`Entry`] enum.
+pub struct OccupiedEntry<'a, K, V, A: Allocator = Global> {
+    index: usize,
+    table: &'a mut RawTable<(K, V), A>,
+}
+
+impl<'a, K, V, A: Allocator> OccupiedEntry<'a, K, V, A> {
+    fn pair(&self) -> &(K, V) {
+        unsafe { &*self.table.bucket(self.index) }
+    }
+
+    fn pair_mut(&mut self) -> &mut (K, V) {
+        unsafe { &mut *self.table.bucket(self.index) }
+    }
+
+    /// Returns the key stored in the map.
+    pub fn key(&self) -> &K {
+        &self.pair().0
+    }
+
+    /// Returns the value.
+    pub fn get(&self) -> &V {
+        &self.pair().1
+    }
+
+    /// Returns the value mutably.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.pair_mut().1
+    }
+
+    /// Converts the entry into a mutable reference to the value that lives
+    /// as long as the map borrow.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut (*self.table.bucket(self.index)).1 }
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry and returns its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry and returns the stored key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        unsafe { self.table.remove(self.index) }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for OccupiedEntry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+/// A vacant entry, part of the [`Entry`] enum.
+pub struct VacantEntry<'a, K, V, A: Allocator = Global> {
+    hash: u64,
+    key: K,
+    table: &'a mut RawTable<(K, V), A>,
+}
+
+impl<'a, K, V, A: Allocator> VacantEntry<'a, K, V, A> {
+    /// Returns the key that would be inserted.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes back the key without inserting.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` for the entry's key and returns a reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        // `HashMap::entry` reserved room for this insertion.
+        unsafe {
+            let index = self.table.insert_no_grow(self.hash, (self.key, value));
+            &mut (*self.table.bucket(index)).1
+        }
+    }
+}
+
+impl<K: fmt::Debug, V, A: Allocator> fmt::Debug for VacantEntry<'_, K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(self.key()).finish()
+    }
+}
+
+/// An iterator over the entries of a map, created by [`HashMap::iter`].
+pub struct Iter<'a, K, V> {
+    inner: RawIter<(K, V)>,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let index = self.inner.next()?;
+        let (k, v) = unsafe { &*self.inner.bucket(index) };
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the entries of a map, created by
+/// [`HashMap::iter_mut`].
+pub struct IterMut<'a, K, V> {
+    inner: RawIter<(K, V)>,
+    marker: PhantomData<&'a mut (K, V)>,
+}
+
+unsafe impl<K: Send, V: Send> Send for IterMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        let index = self.inner.next()?;
+        let (k, v) = unsafe { &mut *self.inner.bucket(index) };
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for IterMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter::<K, V> {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+/// An owning iterator over the entries of a map, created by
+/// `HashMap::into_iter`.
+pub struct IntoIter<K, V, A: Allocator = Global> {
+    inner: RawIntoIter<(K, V), A>,
+}
+
+impl<K, V, A: Allocator> Iterator for IntoIter<K, V, A> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoIter<K, V, A> {}
+
+impl<K: fmt::Debug, V: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter::<K, V> {
+            inner: self.inner.iter(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+/// A draining iterator over the entries of a map, created by
+/// [`HashMap::drain`].
+pub struct Drain<'a, K, V> {
+    inner: RawDrain<'a, (K, V)>,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Drain<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter::<K, V> {
+            inner: self.inner.iter(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+/// An iterator that removes the entries matching a predicate, created by
+/// [`HashMap::extract_if`].
+pub struct ExtractIf<'a, K, V, F, A: Allocator = Global>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    inner: RawExtractIf<'a, (K, V), A>,
+    pred: F,
+}
+
+impl<K, V, F, A> Iterator for ExtractIf<'_, K, V, F, A>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    A: Allocator,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let pred = &mut self.pred;
+        self.inner.next(|(k, v)| pred(k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, F, A> FusedIterator for ExtractIf<'_, K, V, F, A>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    A: Allocator,
+{
+}
+
+/// An iterator over the keys of a map, created by [`HashMap::keys`].
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K: fmt::Debug, V> fmt::Debug for Keys<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the values of a map, created by [`HashMap::values`].
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V: fmt::Debug> fmt::Debug for Values<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the values of a map, created by
+/// [`HashMap::values_mut`].
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+/// An owning iterator over the keys of a map, created by
+/// [`HashMap::into_keys`].
+pub struct IntoKeys<K, V, A: Allocator = Global> {
+    inner: IntoIter<K, V, A>,
+}
+
+impl<K, V, A: Allocator> Iterator for IntoKeys<K, V, A> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoKeys<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoKeys<K, V, A> {}
+
+/// An owning iterator over the values of a map, created by
+/// [`HashMap::into_values`].
+pub struct IntoValues<K, V, A: Allocator = Global> {
+    inner: IntoIter<K, V, A>,
+}
+
+impl<K, V, A: Allocator> Iterator for IntoValues<K, V, A> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoValues<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoValues<K, V, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, HashMap};
+    use crate::alloc::{Counting, Global};
+    use crate::hash::{BuildHasherDefault, Hasher, RandomState};
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    /// Hashes every key to the same value, so that every lookup walks one
+    /// long probe sequence full of tombstones.
+    #[derive(Default)]
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn finish(&self) -> u64 {
+            0x5555_5555_5555_5555
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    type Colliding = BuildHasherDefault<ConstantHasher>;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = HashMap::new();
+        assert_eq!(map.get(&1), None);
+        for i in 0..1000 {
+            assert_eq!(map.insert(i, i * 10), None);
+        }
+        assert_eq!(map.len(), 1000);
+        assert!(map.capacity() >= 1000);
+        assert_eq!(map.insert(7, 0), Some(70));
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&if i == 7 { 0 } else { i * 10 }));
+        }
+        for i in (0..1000).step_by(2) {
+            assert!(map.remove(&i).is_some());
+        }
+        assert_eq!(map.len(), 500);
+        assert!(!map.contains_key(&0));
+        assert_eq!(map.get_key_value(&1), Some((&1, &10)));
+        *map.get_mut(&1).unwrap() += 1;
+        assert_eq!(map[&1], 11);
+    }
+
+    #[test]
+    fn borrowed_lookups() {
+        let mut map: HashMap<String, usize> = HashMap::new();
+        map.insert("one".to_string(), 1);
+        assert_eq!(map.get("one"), Some(&1));
+        assert_eq!(map.remove_entry("one"), Some(("one".to_string(), 1)));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn index_missing_key() {
+        let map: HashMap<u8, u8> = HashMap::new();
+        let _ = map[&0];
+    }
+
+    #[test]
+    fn collisions_and_tombstones() {
+        let counting = Counting::new(Global);
+        let mut map = HashMap::with_capacity_and_hasher_in(12, Colliding::default(), &counting);
+        let allocations = counting.snapshot().allocations;
+        // Every key shares one probe sequence; cycling keys through a small
+        // live window leaves tombstones everywhere, which must be recycled
+        // rather than grow the table.
+        for i in 0..5000u32 {
+            map.insert(i, i);
+            if i >= 6 {
+                assert_eq!(map.remove(&(i - 6)), Some(i - 6));
+            }
+            assert_eq!(map.len(), (i as usize + 1).min(6));
+        }
+        for i in 4994..5000 {
+            assert_eq!(map[&i], i);
+        }
+        assert_eq!(counting.snapshot().allocations, allocations);
+    }
+
+    #[test]
+    fn entry_api() {
+        let mut map: HashMap<&str, Vec<u32>> = HashMap::new();
+        map.entry("a").or_default().push(1);
+        map.entry("a").or_default().push(2);
+        map.entry("b").or_insert_with(|| vec![3]);
+        assert_eq!(map["a"], [1, 2]);
+        assert_eq!(map.entry("c").key(), &"c");
+        assert_eq!(map.len(), 2);
+
+        match map.entry("a") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert(vec![9]), [1, 2]);
+                assert_eq!(entry.remove_entry(), ("a", vec![9]));
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry("z") {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "z"),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        let len = map
+            .entry("key")
+            .or_insert_with_key(|k| vec![k.len() as u32]);
+        assert_eq!(len, &[3]);
+        assert_eq!(
+            map.entry("b").and_modify(|v| v.clear()).or_default().len(),
+            0
+        );
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn retain_extract_if_drain() {
+        let mut map: HashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+        map.retain(|&k, v| {
+            *v += 1;
+            k % 3 != 0
+        });
+        assert_eq!(map.len(), 66);
+        assert!(map.values().all(|&v| v % 3 != 1));
+
+        let mut odd: Vec<u32> = map.extract_if(|k, _| k % 2 == 1).map(|(k, _)| k).collect();
+        odd.sort_unstable();
+        assert_eq!(odd.len(), 33);
+        assert!(odd.iter().all(|k| k % 2 == 1 && k % 3 != 0));
+        assert_eq!(map.len(), 33);
+
+        // Dropping `extract_if` early keeps what it has not visited.
+        assert!(map.extract_if(|_, _| true).next().is_some());
+        assert_eq!(map.len(), 32);
+
+        let capacity = map.capacity();
+        let mut drained: Vec<(u32, u32)> = map.drain().collect();
+        drained.sort_unstable();
+        assert_eq!(drained.len(), 32);
+        assert!(map.is_empty());
+        // Tombstones left by `retain` and `extract_if` are free again.
+        assert!(map.capacity() >= capacity);
+        map.insert(1, 1);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn leaked_drain_empties_the_map() {
+        let mut map: HashMap<u32, String> = (0..10).map(|i| (i, i.to_string())).collect();
+        let mut drain = map.drain();
+        drain.next();
+        std::mem::forget(drain);
+        assert!(map.is_empty());
+        map.insert(3, "three".to_string());
+        assert_eq!(map[&3], "three");
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        {
+            let mut map = HashMap::with_hasher_in(RandomState::new(), &counting);
+            for i in 0..50 {
+                map.insert(i, DropCounter::new(&drops, false));
+            }
+            map.insert(0, DropCounter::new(&drops, false));
+            assert_eq!(drops.get(), 1);
+            map.remove(&1);
+            map.retain(|k, _| k % 2 == 0);
+            assert_eq!(drops.get(), 2 + 24);
+            map.extract_if(|k, _| *k < 10).for_each(drop);
+            assert_eq!(drops.get(), 26 + 5);
+            map.drain().take(3).for_each(drop);
+            assert_eq!(drops.get(), 51);
+            for i in 0..20 {
+                map.insert(i, DropCounter::new(&drops, false));
+            }
+            let mut iter = map.into_iter();
+            iter.next();
+            drop(iter);
+            assert_eq!(drops.get(), 71);
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn panicking_destructors_do_not_leak() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let mut map = HashMap::with_hasher_in(RandomState::new(), &counting);
+            for i in 0..20 {
+                map.insert(i, DropCounter::new(&drops, i == 7));
+            }
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 20);
+
+        let mut map = HashMap::with_hasher_in(RandomState::new(), &counting);
+        for i in 0..20 {
+            map.insert(i, DropCounter::new(&drops, i == 3));
+        }
+        let result = catch_unwind(AssertUnwindSafe(|| map.retain(|_, _| false)));
+        assert!(result.is_err());
+        // The panicking entry was unlinked before it was dropped.
+        assert!(!map.contains_key(&3));
+        let len = map.len();
+        drop(map);
+        assert_eq!(drops.get(), 40);
+        assert!(len < 20);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn try_reserve_reports_failure() {
+        let budget = Budget::new(1024);
+        let mut map: HashMap<u64, u64, RandomState, _> =
+            HashMap::with_hasher_in(RandomState::new(), &budget);
+        assert!(map.try_reserve(10).is_ok());
+        assert!(map.try_reserve(1 << 20).is_err());
+        assert!(map.try_reserve(usize::MAX).is_err());
+        map.insert(1, 1);
+        assert_eq!(map[&1], 1);
+
+        budget.set(0);
+        assert_eq!(map.try_insert_or_replace(1, 2), Ok(Some(1)));
+        while map.try_insert_or_replace(map.len() as u64 + 1, 0).is_ok() {}
+        assert_eq!(map.len(), map.capacity());
+        assert!(map.try_insert_or_replace(u64::MAX, 0).is_err());
+        assert_eq!(map.get(&u64::MAX), None);
+        assert_eq!(map[&1], 2);
+    }
+
+    #[test]
+    fn shrink_and_clear() {
+        let mut map: HashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        map.retain(|&k, _| k < 10);
+        map.shrink_to_fit();
+        assert!(map.capacity() >= 10 && map.capacity() < 30);
+        assert!((0..10).all(|k| map[&k] == k));
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 0);
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn zero_sized_entries() {
+        let mut map = HashMap::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.iter().count(), 1);
+        assert_eq!(map.remove(&()), Some(()));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn traits() {
+        let a = HashMap::from([(1, "one"), (2, "two")]);
+        let mut b = HashMap::new();
+        b.extend([(&2, &"two"), (&1, &"one")]);
+        assert_eq!(a, b);
+        b.insert(3, "three");
+        assert_ne!(a, b);
+        assert_eq!(format!("{:?}", HashMap::from([(1, 2)])), "{1: 2}");
+        let mut keys: Vec<_> = b.clone().into_keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [1, 2, 3]);
+        for (_, v) in &mut b {
+            *v = "x";
+        }
+        assert!(b.into_values().all(|v| v == "x"));
+    }
+
+    #[test]
+    fn matches_std() {
+        let mut rng = XorShift::new(2024);
+        let mut ours = HashMap::new();
+        let mut std = std::collections::HashMap::new();
+        for _ in 0..50_000 {
+            let k = rng.below(2000) as u32;
+            match rng.below(5) {
+                0 | 1 => {
+                    let v = rng.next();
+                    assert_eq!(ours.insert(k, v), std.insert(k, v));
+                }
+                2 => assert_eq!(ours.remove(&k), std.remove(&k)),
+                3 => assert_eq!(ours.get(&k), std.get(&k)),
+                _ => {
+                    *ours.entry(k).or_insert(0) += 1;
+                    *std.entry(k).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(ours.len(), std.len());
+        }
+        assert!(std.iter().all(|(k, v)| ours.get(k) == Some(v)));
+    }
+}
@@ -0,0 +1,963 @@
+//! A hash set implemented as a [`HashMap`] with `()` values.
+//!
+//! The set operations (`union`, `intersection`, `difference`,
+//! `symmetric_difference`) return lazy iterators borrowing both sets;
+//! the operator impls (`|`, `&`, `-`, `^`) collect them into a new set.
+
+use super::hash_map::{self, HashMap, Keys};
+use super::raw_table::RawExtractIf;
+use crate::alloc::{Allocator, Global};
+use crate::hash::{BuildHasher, Hash, RandomState};
+use crate::raw_vec::TryReserveError;
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{Chain, FromIterator, FusedIterator};
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+/// A hash set with pluggable hashing, allocated from `A`.
+///
+/// ```
+/// use mystdrs::collections::HashSet;
+///
+/// let a: HashSet<i32> = [1, 2, 3].into();
+/// let b: HashSet<i32> = [2, 3, 4].into();
+/// let mut common: Vec<_> = a.intersection(&b).copied().collect();
+/// common.sort();
+/// assert_eq!(common, [2, 3]);
+/// assert_eq!(&a | &b, [1, 2, 3, 4].into());
+/// ```
+pub struct HashSet<T, S = RandomState, A: Allocator = Global> {
+    map: HashMap<T, (), S, A>,
+}
+
+impl<T> HashSet<T> {
+    /// Creates an empty set without allocating.
+    pub fn new() -> Self {
+        HashSet {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty set with room for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        HashSet {
+            map: HashMap::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, S> HashSet<T, S> {
+    /// Creates an empty set that hashes elements with `hash_builder`.
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        HashSet {
+            map: HashMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Creates an empty set with room for at least `capacity` elements
+    /// that hashes elements with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        HashSet {
+            map: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+}
+
+impl<T, S, A: Allocator> HashSet<T, S, A> {
+    /// Creates an empty set in `alloc` without allocating.
+    pub const fn with_hasher_in(hash_builder: S, alloc: A) -> Self {
+        HashSet {
+            map: HashMap::with_hasher_in(hash_builder, alloc),
+        }
+    }
+
+    /// Creates an empty set in `alloc` with room for at least `capacity`
+    /// elements.
+    pub fn with_capacity_and_hasher_in(capacity: usize, hash_builder: S, alloc: A) -> Self {
+        HashSet {
+            map: HashMap::with_capacity_and_hasher_in(capacity, hash_builder, alloc),
+        }
+    }
+
+    /// Fallible version of
+    /// [`with_capacity_and_hasher_in`](HashSet::with_capacity_and_hasher_in).
+    pub fn try_with_capacity_and_hasher_in(
+        capacity: usize,
+        hash_builder: S,
+        alloc: A,
+    ) -> Result<Self, TryReserveError> {
+        Ok(HashSet {
+            map: HashMap::try_with_capacity_and_hasher_in(capacity, hash_builder, alloc)?,
+        })
+    }
+
+    /// Returns the set's [`BuildHasher`].
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    /// Returns the allocator backing the set.
+    pub fn allocator(&self) -> &A {
+        self.map.allocator()
+    }
+
+    /// Returns the number of elements the set can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the elements, in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.map.keys(),
+        }
+    }
+
+    /// Removes every element and returns them as an iterator, keeping the
+    /// allocation.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        Drain {
+            iter: self.map.drain(),
+        }
+    }
+
+    /// Returns an iterator that removes and yields every element for which
+    /// `pred` returns `true`. Elements it does not get to stay in the set.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F, A>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        ExtractIf {
+            inner: self.map.table.extract_if(),
+            pred,
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.map.retain(|k, _| f(k));
+    }
+
+    /// Removes every element, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<T, S, A> HashSet<T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    /// Makes room for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional);
+    }
+
+    /// Fallible version of [`reserve`](HashSet::reserve).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.map.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity as much as the load factor allows.
+    pub fn shrink_to_fit(&mut self) {
+        self.map.shrink_to_fit();
+    }
+
+    /// Shrinks the capacity to hold at least `max(len, min_capacity)`
+    /// elements.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.map.shrink_to(min_capacity);
+    }
+
+    /// Returns the elements of `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T, S, A> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Returns the elements in exactly one of `self` and `other`.
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T, S, A> {
+        SymmetricDifference {
+            iter: self.difference(other).chain(other.difference(self)),
+        }
+    }
+
+    /// Returns the elements in both `self` and `other`.
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T, S, A> {
+        // Walk the smaller set and probe the larger one.
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Intersection {
+            iter: small.iter(),
+            other: large,
+        }
+    }
+
+    /// Returns the elements in `self` or `other`, each once.
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T, S, A> {
+        // Yield the larger set whole and probe it for the smaller one.
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Union {
+            iter: large.iter().chain(small.difference(large)),
+        }
+    }
+
+    /// Returns `true` if the set holds `value`.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Returns the stored element equal to `value`.
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+    /// Inserts `value` if it is absent and returns the stored element.
+    pub fn get_or_insert(&mut self, value: T) -> &T {
+        self.map.get_or_insert_key(value, ())
+    }
+
+    /// Returns the stored element equal to `value`, first inserting
+    /// `f(value)` if there is none. Lets a set of owned values be probed
+    /// with a borrowed one and only allocate on a miss.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f(value)` is not equal to `value`.
+    ///
+    /// ```
+    /// use mystdrs::collections::HashSet;
+    ///
+    /// let mut names: HashSet<String> = HashSet::new();
+    /// for name in ["ann", "bob", "ann"] {
+    ///     names.get_or_insert_with(name, str::to_owned);
+    /// }
+    /// assert_eq!(names.len(), 2);
+    /// ```
+    pub fn get_or_insert_with<Q, F>(&mut self, value: &Q, f: F) -> &T
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&Q) -> T,
+    {
+        self.map.get_or_insert_key_with(value, |q| (f(q), ()))
+    }
+
+    /// Returns `true` if no element is in both sets.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Returns `true` if every element of `self` is in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|v| other.contains(v))
+    }
+
+    /// Returns `true` if every element of `other` is in `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Adds `value` and returns `true` if it was not present. An equal
+    /// element already in the set is kept.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    /// Fallible version of [`insert`](HashSet::insert): returns an error
+    /// instead of aborting if the table cannot grow. On error `value` is
+    /// dropped and the set is unchanged.
+    pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
+        self.map
+            .try_insert_or_replace(value, ())
+            .map(|old| old.is_none())
+    }
+
+    /// Adds `value`, replacing and returning an equal element already in
+    /// the set.
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.map.replace_entry(value, ()).map(|(k, _)| k)
+    }
+
+    /// Removes `value` and returns `true` if it was present.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes and returns the stored element equal to `value`.
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+}
+
+impl<T: Clone, S: Clone, A: Allocator + Clone> Clone for HashSet<T, S, A> {
+    fn clone(&self) -> Self {
+        HashSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, S, A: Allocator> fmt::Debug for HashSet<T, S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T, S, A> PartialEq for HashSet<T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl<T, S, A> Eq for HashSet<T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<T, S: Default> Default for HashSet<T, S> {
+    fn default() -> Self {
+        HashSet::with_hasher(S::default())
+    }
+}
+
+impl<T, S, A> Extend<T> for HashSet<T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|k| (k, ())));
+    }
+}
+
+impl<'a, T, S, A> Extend<&'a T> for HashSet<T, S, A>
+where
+    T: Eq + Hash + Copy + 'a,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, S> FromIterator<T> for HashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = HashSet::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Eq + Hash, const N: usize> From<[T; N]> for HashSet<T> {
+    fn from(arr: [T; N]) -> Self {
+        let mut set = HashSet::new();
+        set.extend(arr);
+        set
+    }
+}
+
+impl<'a, T, S, A: Allocator> IntoIterator for &'a HashSet<T, S, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, S, A: Allocator> IntoIterator for HashSet<T, S, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter {
+            iter: self.map.into_keys(),
+        }
+    }
+}
+
+impl<T, S> BitOr<&HashSet<T, S>> for &HashSet<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<T, S>;
+
+    /// Returns the union of `self` and `rhs` as a new set.
+    fn bitor(self, rhs: &HashSet<T, S>) -> HashSet<T, S> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitAnd<&HashSet<T, S>> for &HashSet<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<T, S>;
+
+    /// Returns the intersection of `self` and `rhs` as a new set.
+    fn bitand(self, rhs: &HashSet<T, S>) -> HashSet<T, S> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitXor<&HashSet<T, S>> for &HashSet<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<T, S>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new set.
+    fn bitxor(self, rhs: &HashSet<T, S>) -> HashSet<T, S> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> Sub<&HashSet<T, S>> for &HashSet<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<T, S>;
+
+    /// Returns the difference of `self` and `rhs` as a new set.
+    fn sub(self, rhs: &HashSet<T, S>) -> HashSet<T, S> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+/// An iterator over the elements of a set, created by [`HashSet::iter`].
+pub struct Iter<'a, T> {
+    iter: Keys<'a, T, ()>,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An owning iterator over the elements of a set, created by
+/// `HashSet::into_iter`.
+pub struct IntoIter<T, A: Allocator = Global> {
+    iter: hash_map::IntoKeys<T, (), A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+/// A draining iterator over the elements of a set, created by
+/// [`HashSet::drain`].
+pub struct Drain<'a, T> {
+    iter: hash_map::Drain<'a, T, ()>,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> FusedIterator for Drain<'_, T> {}
+
+/// An iterator that removes the elements matching a predicate, created by
+/// [`HashSet::extract_if`].
+pub struct ExtractIf<'a, T, F, A: Allocator = Global>
+where
+    F: FnMut(&T) -> bool,
+{
+    inner: RawExtractIf<'a, (T, ()), A>,
+    pred: F,
+}
+
+impl<T, F, A> Iterator for ExtractIf<'_, T, F, A>
+where
+    F: FnMut(&T) -> bool,
+    A: Allocator,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let pred = &mut self.pred;
+        self.inner.next(|(k, _)| pred(k)).map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, F, A> FusedIterator for ExtractIf<'_, T, F, A>
+where
+    F: FnMut(&T) -> bool,
+    A: Allocator,
+{
+}
+
+/// A lazy iterator over the intersection of two sets, created by
+/// [`HashSet::intersection`].
+pub struct Intersection<'a, T, S, A: Allocator = Global> {
+    iter: Iter<'a, T>,
+    other: &'a HashSet<T, S, A>,
+}
+
+impl<T, S, A: Allocator> Clone for Intersection<'_, T, S, A> {
+    fn clone(&self) -> Self {
+        Intersection {
+            iter: self.iter.clone(),
+            other: self.other,
+        }
+    }
+}
+
+impl<'a, T, S, A> Iterator for Intersection<'a, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let other = self.other;
+        self.iter.find(|v| other.contains(v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<T, S, A> FusedIterator for Intersection<'_, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<T, S, A> fmt::Debug for Intersection<'_, T, S, A>
+where
+    T: fmt::Debug + Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the difference of two sets, created by
+/// [`HashSet::difference`].
+pub struct Difference<'a, T, S, A: Allocator = Global> {
+    iter: Iter<'a, T>,
+    other: &'a HashSet<T, S, A>,
+}
+
+impl<T, S, A: Allocator> Clone for Difference<'_, T, S, A> {
+    fn clone(&self) -> Self {
+        Difference {
+            iter: self.iter.clone(),
+            other: self.other,
+        }
+    }
+}
+
+impl<'a, T, S, A> Iterator for Difference<'a, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let other = self.other;
+        self.iter.find(|v| !other.contains(v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<T, S, A> FusedIterator for Difference<'_, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<T, S, A> fmt::Debug for Difference<'_, T, S, A>
+where
+    T: fmt::Debug + Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the symmetric difference of two sets, created by
+/// [`HashSet::symmetric_difference`].
+pub struct SymmetricDifference<'a, T, S, A: Allocator = Global> {
+    iter: Chain<Difference<'a, T, S, A>, Difference<'a, T, S, A>>,
+}
+
+impl<T, S, A: Allocator> Clone for SymmetricDifference<'_, T, S, A> {
+    fn clone(&self) -> Self {
+        SymmetricDifference {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T, S, A> Iterator for SymmetricDifference<'a, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, S, A> FusedIterator for SymmetricDifference<'_, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<T, S, A> fmt::Debug for SymmetricDifference<'_, T, S, A>
+where
+    T: fmt::Debug + Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A lazy iterator over the union of two sets, created by
+/// [`HashSet::union`].
+pub struct Union<'a, T, S, A: Allocator = Global> {
+    iter: Chain<Iter<'a, T>, Difference<'a, T, S, A>>,
+}
+
+impl<T, S, A: Allocator> Clone for Union<'_, T, S, A> {
+    fn clone(&self) -> Self {
+        Union {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T, S, A> Iterator for Union<'a, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, S, A> FusedIterator for Union<'_, T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+}
+
+impl<T, S, A> fmt::Debug for Union<'_, T, S, A>
+where
+    T: fmt::Debug + Eq + Hash,
+    S: BuildHasher,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashSet;
+    use crate::alloc::{Counting, Global};
+    use crate::hash::RandomState;
+    use crate::test_util::{Budget, XorShift};
+    use std::collections::BTreeSet;
+
+    fn sorted<'a>(iter: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+        let mut v: Vec<u32> = iter.copied().collect();
+        v.sort_unstable();
+        v
+    }
+
+    #[test]
+    fn basic_operations() {
+        let mut set = HashSet::new();
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(5));
+        assert!(set.contains(&3));
+        assert_eq!(set.get(&5), Some(&5));
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!(set.take(&5), Some(5));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn set_algebra_matches_btree_set() {
+        let mut rng = XorShift::new(11);
+        for round in 0..50 {
+            let a_len = rng.below(40);
+            let b_len = rng.below(40) + round % 2;
+            let a_model: BTreeSet<u32> = (0..a_len).map(|_| rng.below(50) as u32).collect();
+            let b_model: BTreeSet<u32> = (0..b_len).map(|_| rng.below(50) as u32).collect();
+            let a: HashSet<u32> = a_model.iter().copied().collect();
+            let b: HashSet<u32> = b_model.iter().copied().collect();
+
+            let union: Vec<u32> = a_model.union(&b_model).copied().collect();
+            let inter: Vec<u32> = a_model.intersection(&b_model).copied().collect();
+            let diff: Vec<u32> = a_model.difference(&b_model).copied().collect();
+            let sym: Vec<u32> = a_model.symmetric_difference(&b_model).copied().collect();
+            assert_eq!(sorted(a.union(&b)), union);
+            assert_eq!(sorted(a.intersection(&b)), inter);
+            assert_eq!(sorted(a.difference(&b)), diff);
+            assert_eq!(sorted(a.symmetric_difference(&b)), sym);
+            assert_eq!(sorted((&a | &b).iter()), union);
+            assert_eq!(sorted((&a & &b).iter()), inter);
+            assert_eq!(sorted((&a - &b).iter()), diff);
+            assert_eq!(sorted((&a ^ &b).iter()), sym);
+            assert_eq!(a.is_subset(&b), a_model.is_subset(&b_model));
+            assert_eq!(a.is_superset(&b), a_model.is_superset(&b_model));
+            assert_eq!(a.is_disjoint(&b), a_model.is_disjoint(&b_model));
+        }
+    }
+
+    #[test]
+    fn subset_and_equality() {
+        let small: HashSet<u32> = [1, 2].into();
+        let large: HashSet<u32> = [1, 2, 3].into();
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(!small.is_disjoint(&large));
+        assert!(small.is_disjoint(&[7].into()));
+        assert_ne!(small, large);
+        assert_eq!(small, [2, 1].into());
+        assert!(HashSet::<u32>::new().is_subset(&small));
+    }
+
+    #[test]
+    fn get_or_insert_with_borrowed_probe() {
+        let counting = Counting::new(Global);
+        let mut set: HashSet<String, RandomState, _> =
+            HashSet::with_hasher_in(RandomState::new(), &counting);
+        let first = set.get_or_insert_with("key", str::to_owned).as_ptr();
+        let allocations = counting.snapshot().allocations;
+        // A hit neither calls `f` nor allocates.
+        let again = set.get_or_insert_with("key", |_| unreachable!()).as_ptr();
+        assert_eq!(first, again);
+        assert_eq!(counting.snapshot().allocations, allocations);
+        assert_eq!(set.get_or_insert("other".to_string()), "other");
+        assert_eq!(set.get_or_insert("other".to_string()), "other");
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "new value is not equivalent")]
+    fn get_or_insert_with_mismatch() {
+        let mut set: HashSet<String> = HashSet::new();
+        set.get_or_insert_with("a", |_| "b".to_string());
+    }
+
+    #[test]
+    fn replace_swaps_the_stored_element() {
+        #[derive(Debug)]
+        struct Tagged(u32, &'static str);
+
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Tagged {}
+
+        crate::impl_hash!(Tagged { 0 });
+
+        let mut set = HashSet::new();
+        assert!(set.replace(Tagged(1, "old")).is_none());
+        assert!(!set.insert(Tagged(1, "kept")));
+        assert_eq!(set.replace(Tagged(1, "new")).unwrap().1, "old");
+        assert_eq!(set.get(&Tagged(1, "")).unwrap().1, "new");
+    }
+
+    #[test]
+    fn retain_extract_if_drain() {
+        let mut set: HashSet<u32> = (0..30).collect();
+        set.retain(|v| v % 3 != 0);
+        assert_eq!(set.len(), 20);
+        let odd = sorted(set.extract_if(|v| v % 2 == 1).collect::<Vec<_>>().iter());
+        assert_eq!(odd, [1, 5, 7, 11, 13, 17, 19, 23, 25, 29]);
+        assert_eq!(sorted(set.iter()), [2, 4, 8, 10, 14, 16, 20, 22, 26, 28]);
+        assert_eq!(set.drain().count(), 10);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn traits() {
+        let mut set: HashSet<u32> = [1, 2].into();
+        set.extend(&[2, 3]);
+        assert_eq!(set.len(), 3);
+        assert_eq!(format!("{:?}", HashSet::from([7])), "{7}");
+        assert_eq!(format!("{:?}", set.intersection(&[3].into())), "[3]");
+        let mut items: Vec<u32> = set.clone().into_iter().collect();
+        items.sort_unstable();
+        assert_eq!(items, [1, 2, 3]);
+        assert_eq!(set.iter().len(), 3);
+    }
+
+    #[test]
+    fn try_insert_reports_failure() {
+        let budget = Budget::new(0);
+        let mut set = HashSet::with_hasher_in(RandomState::new(), &budget);
+        assert!(set.try_insert(1u32).is_err());
+        assert!(set.is_empty());
+        budget.set(usize::MAX);
+        assert_eq!(set.try_insert(1), Ok(true));
+        budget.set(0);
+        assert_eq!(set.try_insert(1), Ok(false));
+        while set.try_insert(set.len() as u32 + 1).is_ok() {}
+        assert_eq!(set.len(), set.capacity());
+    }
+}
@@ -0,0 +1,707 @@
+//! A keyed priority queue whose priorities can be changed after insertion.
+//!
+//! [`IndexedHeap`] is a binary max-heap of `(key, priority)` pairs stored in
+//! a [`Vec`], plus a [`RawTable`] mapping each key to its current position
+//! in the heap. Every swap during sifting updates both moved positions in
+//! the table, so a key can be found, re-prioritised or removed in
+//! `O(log n)` time. The table stores only positions, and each slot caches
+//! its key's hash so that the positions can be found without rehashing.
+//!
+//! That is what Dijkstra-style algorithms need for decrease-key: keep
+//! priorities as `Reverse(distance)` and call
+//! [`push_increase`](IndexedHeap::push_increase) on each relaxation.
+
+use super::raw_table::RawTable;
+use crate::alloc::{Allocator, Global};
+use crate::hash::{BuildHasher, Hash, RandomState};
+use crate::raw_vec::TryReserveError;
+use crate::vec::Vec;
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::slice;
+
+struct Slot<K, P> {
+    key: K,
+    priority: P,
+    hash: u64,
+}
+
+impl<K: Clone, P: Clone> Clone for Slot<K, P> {
+    fn clone(&self) -> Self {
+        Slot {
+            key: self.key.clone(),
+            priority: self.priority.clone(),
+            hash: self.hash,
+        }
+    }
+}
+
+/// A max-heap of unique keys ordered by priority, allocated from `A`.
+///
+/// ```
+/// use mystdrs::collections::IndexedHeap;
+/// use std::cmp::Reverse;
+///
+/// // Dijkstra over a small graph, with the distance as a min-priority.
+/// let edges = [(0, 1, 4), (0, 2, 1), (2, 1, 2), (1, 3, 1), (2, 3, 5)];
+/// let mut dist = [u32::MAX; 4];
+/// let mut queue = IndexedHeap::new();
+/// queue.push(0, Reverse(0));
+/// while let Some((node, Reverse(d))) = queue.pop() {
+///     dist[node] = d;
+///     for &(from, to, w) in &edges {
+///         if from == node && d + w < dist[to] {
+///             queue.push_increase(to, Reverse(d + w));
+///         }
+///     }
+/// }
+/// assert_eq!(dist, [0, 3, 1, 4]);
+/// ```
+pub struct IndexedHeap<K, P, S = RandomState, A: Allocator = Global> {
+    heap: Vec<Slot<K, P>, A>,
+    /// The heap position of every key, hashed by the key.
+    positions: RawTable<usize, A>,
+    hash_builder: S,
+}
+
+impl<K, P> IndexedHeap<K, P> {
+    /// Creates an empty heap without allocating.
+    pub fn new() -> Self {
+        IndexedHeap::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty heap with room for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        IndexedHeap::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, P, S> IndexedHeap<K, P, S> {
+    /// Creates an empty heap that hashes keys with `hash_builder`.
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        IndexedHeap {
+            heap: Vec::new(),
+            positions: RawTable::new_in(Global),
+            hash_builder,
+        }
+    }
+
+    /// Creates an empty heap with room for at least `capacity` keys that
+    /// hashes keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        IndexedHeap::with_capacity_and_hasher_in(capacity, hash_builder, Global)
+    }
+}
+
+impl<K, P, S, A: Allocator> IndexedHeap<K, P, S, A> {
+    /// Creates an empty heap in `alloc` without allocating. The heap and
+    /// the position table each hold a copy of `alloc`.
+    pub fn with_hasher_in(hash_builder: S, alloc: A) -> Self
+    where
+        A: Clone,
+    {
+        IndexedHeap {
+            heap: Vec::new_in(alloc.clone()),
+            positions: RawTable::new_in(alloc),
+            hash_builder,
+        }
+    }
+
+    /// Creates an empty heap in `alloc` with room for at least `capacity`
+    /// keys.
+    pub fn with_capacity_and_hasher_in(capacity: usize, hash_builder: S, alloc: A) -> Self
+    where
+        A: Clone,
+    {
+        IndexedHeap {
+            heap: Vec::with_capacity_in(capacity, alloc.clone()),
+            positions: RawTable::with_capacity_in(capacity, alloc),
+            hash_builder,
+        }
+    }
+
+    /// Returns the heap's [`BuildHasher`].
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// Returns the allocator backing the heap.
+    pub fn allocator(&self) -> &A {
+        self.heap.allocator()
+    }
+
+    /// Returns the number of keys in the heap.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns `true` if the heap holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Reserves room for at least `additional` more keys.
+    pub fn reserve(&mut self, additional: usize) {
+        self.heap.reserve(additional);
+        let heap = &self.heap;
+        self.positions.reserve(additional, |&pos| heap[pos].hash);
+    }
+
+    /// Fallible version of [`reserve`](IndexedHeap::reserve).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.heap.try_reserve(additional)?;
+        let heap = &self.heap;
+        self.positions
+            .try_reserve(additional, |&pos| heap[pos].hash)
+    }
+
+    /// Returns the key with the greatest priority, and that priority.
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.heap.first().map(|slot| (&slot.key, &slot.priority))
+    }
+
+    /// Returns an iterator over the keys and priorities in heap order,
+    /// which is arbitrary apart from the greatest priority coming first.
+    pub fn iter(&self) -> Iter<'_, K, P> {
+        Iter {
+            iter: self.heap.iter(),
+        }
+    }
+
+    /// Removes every key, keeping the allocations.
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.heap.clear();
+    }
+}
+
+impl<K, P, S, A> IndexedHeap<K, P, S, A>
+where
+    K: Hash + Eq,
+    P: Ord,
+    S: BuildHasher,
+    A: Allocator,
+{
+    /// Inserts `key` with `priority`, or changes the priority of `key` if
+    /// it is already present and returns the old one.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(pos) => Some(self.set_priority(pos, priority)),
+            None => {
+                self.insert_new(Slot {
+                    key,
+                    priority,
+                    hash,
+                });
+                None
+            }
+        }
+    }
+
+    /// Fallible version of [`push`](IndexedHeap::push): returns an error
+    /// instead of aborting if the heap cannot grow. On error `key` and
+    /// `priority` are dropped and the heap is unchanged.
+    pub fn try_push(&mut self, key: K, priority: P) -> Result<Option<P>, TryReserveError> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(pos) => Ok(Some(self.set_priority(pos, priority))),
+            None => {
+                self.try_reserve(1)?;
+                self.insert_new(Slot {
+                    key,
+                    priority,
+                    hash,
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Inserts `key` with `priority`, or raises the priority of `key` to
+    /// `priority` if it is already present with a lower one. Returns `true`
+    /// if the heap changed.
+    pub fn push_increase(&mut self, key: K, priority: P) -> bool {
+        self.push_if(key, priority, |old, new| new > old)
+    }
+
+    /// Inserts `key` with `priority`, or lowers the priority of `key` to
+    /// `priority` if it is already present with a higher one. Returns
+    /// `true` if the heap changed.
+    pub fn push_decrease(&mut self, key: K, priority: P) -> bool {
+        self.push_if(key, priority, |old, new| new < old)
+    }
+
+    fn push_if(&mut self, key: K, priority: P, replace: impl FnOnce(&P, &P) -> bool) -> bool {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(pos) if replace(&self.heap[pos].priority, &priority) => {
+                self.set_priority(pos, priority);
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.insert_new(Slot {
+                    key,
+                    priority,
+                    hash,
+                });
+                true
+            }
+        }
+    }
+
+    /// Sets the priority of `key` and returns the old one, or returns
+    /// `None` and leaves the heap unchanged if `key` is absent.
+    pub fn change_priority<Q>(&mut self, key: &Q, priority: P) -> Option<P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let pos = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(self.set_priority(pos, priority))
+    }
+
+    /// Returns the priority of `key`.
+    pub fn get_priority<Q>(&self, key: &Q) -> Option<&P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let pos = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(&self.heap[pos].priority)
+    }
+
+    /// Returns `true` if the heap holds `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hash_builder.hash_one(key), key).is_some()
+    }
+
+    /// Removes and returns the key with the greatest priority, and that
+    /// priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove_at(0))
+        }
+    }
+
+    /// Removes `key` and returns it with its priority.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<(K, P)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let pos = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(self.remove_at(pos))
+    }
+
+    /// Returns the heap position of `key`, whose hash is `hash`.
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let heap = &self.heap;
+        self.positions
+            .get(hash, |&pos| heap[pos].key.borrow() == key)
+            .copied()
+    }
+
+    fn insert_new(&mut self, slot: Slot<K, P>) {
+        let pos = self.heap.len();
+        let hash = slot.hash;
+        self.heap.push(slot);
+        let heap = &self.heap;
+        self.positions.insert(hash, pos, |&p| heap[p].hash);
+        self.sift_up(pos);
+    }
+
+    fn set_priority(&mut self, pos: usize, priority: P) -> P {
+        let old = std::mem::replace(&mut self.heap[pos].priority, priority);
+        let pos = self.sift_up(pos);
+        self.sift_down(pos);
+        old
+    }
+
+    fn remove_at(&mut self, pos: usize) -> (K, P) {
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        let slot = self.heap.pop().unwrap();
+        self.positions
+            .remove_entry(slot.hash, |&p| p == last)
+            .unwrap();
+        if pos < last {
+            let pos = self.sift_up(pos);
+            self.sift_down(pos);
+        }
+        (slot.key, slot.priority)
+    }
+
+    /// Swaps the slots at `a` and `b` and updates their positions.
+    fn swap(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        // Look both entries up before changing either, since two keys with
+        // the same hash would otherwise be told apart by stale positions.
+        let in_a = self.positions.find(self.heap[a].hash, |&p| p == a);
+        let in_b = self.positions.find(self.heap[b].hash, |&p| p == b);
+        unsafe {
+            *self.positions.bucket(in_a.unwrap()) = b;
+            *self.positions.bucket(in_b.unwrap()) = a;
+        }
+        self.heap.swap(a, b);
+    }
+
+    fn sift_up(&mut self, mut pos: usize) -> usize {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.heap[pos].priority <= self.heap[parent].priority {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+        pos
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let end = self.heap.len();
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                break;
+            }
+            if child + 1 < end && self.heap[child].priority < self.heap[child + 1].priority {
+                child += 1;
+            }
+            if self.heap[pos].priority >= self.heap[child].priority {
+                break;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+}
+
+impl<K, P, S, A> Clone for IndexedHeap<K, P, S, A>
+where
+    K: Clone,
+    P: Clone,
+    S: Clone,
+    A: Allocator + Clone,
+{
+    fn clone(&self) -> Self {
+        IndexedHeap {
+            heap: self.heap.clone(),
+            positions: self.positions.clone(),
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, P: fmt::Debug, S, A: Allocator> fmt::Debug for IndexedHeap<K, P, S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, P, S: Default> Default for IndexedHeap<K, P, S> {
+    fn default() -> Self {
+        IndexedHeap::with_hasher(S::default())
+    }
+}
+
+impl<K, P, S, A> Extend<(K, P)> for IndexedHeap<K, P, S, A>
+where
+    K: Hash + Eq,
+    P: Ord,
+    S: BuildHasher,
+    A: Allocator,
+{
+    /// Pushes every pair, so a key given twice ends up with its last
+    /// priority.
+    fn extend<I: IntoIterator<Item = (K, P)>>(&mut self, iter: I) {
+        for (key, priority) in iter {
+            self.push(key, priority);
+        }
+    }
+}
+
+impl<K, P, S> FromIterator<(K, P)> for IndexedHeap<K, P, S>
+where
+    K: Hash + Eq,
+    P: Ord,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, P)>>(iter: I) -> Self {
+        let mut heap = IndexedHeap::with_hasher(S::default());
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<'a, K, P, S, A: Allocator> IntoIterator for &'a IndexedHeap<K, P, S, A> {
+    type Item = (&'a K, &'a P);
+    type IntoIter = Iter<'a, K, P>;
+
+    fn into_iter(self) -> Iter<'a, K, P> {
+        self.iter()
+    }
+}
+
+/// An iterator over the keys and priorities of a heap in heap order,
+/// created by [`IndexedHeap::iter`].
+pub struct Iter<'a, K, P> {
+    iter: slice::Iter<'a, Slot<K, P>>,
+}
+
+impl<K, P> Clone for Iter<'_, K, P> {
+    fn clone(&self) -> Self {
+        Iter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, K, P> Iterator for Iter<'a, K, P> {
+    type Item = (&'a K, &'a P);
+
+    fn next(&mut self) -> Option<(&'a K, &'a P)> {
+        self.iter.next().map(|slot| (&slot.key, &slot.priority))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, P> ExactSizeIterator for Iter<'_, K, P> {}
+
+impl<K, P> FusedIterator for Iter<'_, K, P> {}
+
+impl<K: fmt::Debug, P: fmt::Debug> fmt::Debug for Iter<'_, K, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexedHeap;
+    use crate::alloc::{Counting, Global};
+    use crate::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher, RandomState};
+    use crate::test_util::{Budget, XorShift};
+    use std::cmp::Reverse;
+    use std::collections::BTreeSet;
+
+    /// Hashes every key to the same value, so that every position lookup
+    /// has to tell keys apart by position alone.
+    #[derive(Default)]
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn finish(&self) -> u64 {
+            0x5555_5555_5555_5555
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    /// Checks heap order and that every key's recorded position is right.
+    fn check<K, P, S>(heap: &IndexedHeap<K, P, S>)
+    where
+        K: Hash + Eq,
+        P: Ord,
+        S: BuildHasher,
+    {
+        let slots = heap.heap.as_slice();
+        for (pos, slot) in slots.iter().enumerate() {
+            if pos > 0 {
+                assert!(slots[(pos - 1) / 2].priority >= slot.priority);
+            }
+            assert_eq!(slot.hash, heap.hash_builder.hash_one(&slot.key));
+            assert_eq!(heap.find(slot.hash, &slot.key), Some(pos));
+        }
+        assert_eq!(heap.positions.len(), slots.len());
+    }
+
+    fn matches_model<S: BuildHasher>(mut heap: IndexedHeap<u32, u32, S>, ops: usize) {
+        // The model orders by (priority, key) so that it can find an entry
+        // by key; the heap is only compared on priorities.
+        let mut model: BTreeSet<(u32, u32)> = BTreeSet::new();
+        let mut priorities = std::collections::HashMap::new();
+        let mut rng = XorShift::new(29);
+        for round in 0..ops {
+            let key = rng.below(64) as u32;
+            let priority = rng.below(1000) as u32;
+            let grow = (round / 500) % 2 == 0;
+            match rng.below(8) {
+                0..=2 if grow => {
+                    let old = priorities.insert(key, priority);
+                    if let Some(old) = old {
+                        model.remove(&(old, key));
+                    }
+                    model.insert((priority, key));
+                    assert_eq!(heap.push(key, priority), old);
+                }
+                0 => {
+                    let old = priorities.get(&key).copied();
+                    let changed = old.is_none_or(|old| priority > old);
+                    if changed {
+                        if let Some(old) = old {
+                            model.remove(&(old, key));
+                        }
+                        model.insert((priority, key));
+                        priorities.insert(key, priority);
+                    }
+                    assert_eq!(heap.push_increase(key, priority), changed);
+                }
+                1 => {
+                    let old = priorities.get(&key).copied();
+                    if let Some(old) = old {
+                        model.remove(&(old, key));
+                        model.insert((priority, key));
+                        priorities.insert(key, priority);
+                    }
+                    assert_eq!(heap.change_priority(&key, priority), old);
+                }
+                2 | 3 => {
+                    let removed = priorities.remove(&key);
+                    if let Some(old) = removed {
+                        model.remove(&(old, key));
+                    }
+                    assert_eq!(heap.remove(&key), removed.map(|p| (key, p)));
+                }
+                _ => {
+                    let popped = heap.pop();
+                    let top = model.iter().next_back().map(|&(p, _)| p);
+                    assert_eq!(popped.as_ref().map(|&(_, p)| p), top);
+                    if let Some((key, priority)) = popped {
+                        assert!(model.remove(&(priority, key)));
+                        priorities.remove(&key);
+                    }
+                }
+            }
+            assert_eq!(heap.len(), model.len());
+            assert_eq!(heap.get_priority(&key), priorities.get(&key));
+            check(&heap);
+        }
+    }
+
+    #[test]
+    fn matches_model_with_random_hashes() {
+        matches_model(IndexedHeap::with_hasher(RandomState::new()), 5000);
+    }
+
+    #[test]
+    fn matches_model_when_every_hash_collides() {
+        let heap = IndexedHeap::with_hasher(BuildHasherDefault::<ConstantHasher>::default());
+        matches_model(heap, 2000);
+    }
+
+    #[test]
+    fn dijkstra_on_a_grid() {
+        // Random weights on a grid, checked against Bellman-Ford.
+        let side = 12;
+        let n = side * side;
+        let mut rng = XorShift::new(41);
+        let mut edges = Vec::new();
+        for v in 0..n {
+            if v % side + 1 < side {
+                edges.push((v, v + 1, rng.below(20) as u32 + 1));
+                edges.push((v + 1, v, rng.below(20) as u32 + 1));
+            }
+            if v + side < n {
+                edges.push((v, v + side, rng.below(20) as u32 + 1));
+                edges.push((v + side, v, rng.below(20) as u32 + 1));
+            }
+        }
+
+        let mut expected = vec![u32::MAX; n];
+        expected[0] = 0;
+        for _ in 0..n {
+            for &(from, to, w) in &edges {
+                if expected[from] != u32::MAX {
+                    expected[to] = expected[to].min(expected[from] + w);
+                }
+            }
+        }
+
+        let mut dist = vec![u32::MAX; n];
+        let mut queue = IndexedHeap::new();
+        queue.push(0, Reverse(0));
+        while let Some((v, Reverse(d))) = queue.pop() {
+            dist[v] = d;
+            for &(from, to, w) in &edges {
+                if from == v && d + w < dist[to] {
+                    queue.push_increase(to, Reverse(d + w));
+                }
+            }
+        }
+        assert_eq!(dist, expected);
+    }
+
+    #[test]
+    fn borrowed_keys_and_decrease() {
+        let mut heap: IndexedHeap<String, u32> = IndexedHeap::new();
+        assert!(heap.push_decrease("a".to_string(), 5));
+        assert!(!heap.push_decrease("a".to_string(), 9));
+        assert!(heap.push_decrease("a".to_string(), 2));
+        heap.push("b".to_string(), 3);
+        assert_eq!(heap.peek(), Some((&"b".to_string(), &3)));
+        assert_eq!(heap.get_priority("a"), Some(&2));
+        assert!(heap.contains_key("b"));
+        assert_eq!(heap.change_priority("c", 1), None);
+        assert_eq!(heap.remove("b"), Some(("b".to_string(), 3)));
+        assert_eq!(heap.pop(), Some(("a".to_string(), 2)));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn allocator_and_traits() {
+        let counting = Counting::new(Global);
+        {
+            let mut heap = IndexedHeap::with_hasher_in(RandomState::new(), &counting);
+            heap.extend((0..100).map(|k| (k, k % 7)));
+            heap.push(3, 50);
+            let copy = heap.clone();
+            assert_eq!(copy.peek(), Some((&3, &50)));
+            heap.clear();
+            assert!(heap.is_empty());
+            assert_eq!(copy.len(), 100);
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+
+        let heap: IndexedHeap<u32, u32> = [(1, 1), (2, 2), (1, 3)].iter().copied().collect();
+        assert_eq!(heap.len(), 2);
+        assert_eq!(format!("{:?}", heap), "{1: 3, 2: 2}");
+        assert_eq!(heap.iter().len(), 2);
+    }
+
+    #[test]
+    fn try_push_reports_failure() {
+        let budget = Budget::new(0);
+        let mut heap = IndexedHeap::with_hasher_in(RandomState::new(), &budget);
+        assert!(heap.try_push(1, 1).is_err());
+        assert!(heap.is_empty());
+        budget.set(usize::MAX);
+        heap.try_reserve(3).unwrap();
+        budget.set(0);
+        for k in 0..3 {
+            assert_eq!(heap.try_push(k, k), Ok(None));
+        }
+        assert_eq!(heap.try_push(0, 9), Ok(Some(0)));
+        while heap.try_push(heap.len() as u32, 0).is_ok() {}
+        assert!(heap.try_reserve(1).is_err());
+        assert_eq!(heap.peek(), Some((&0, &9)));
+        assert!(heap.iter().all(|(k, _)| heap.contains_key(k)));
+    }
+}
@@ -0,0 +1,1373 @@
+//! A doubly-linked list with owned nodes.
+//!
+//! Beyond the usual deque operations, the list exposes std's still-unstable
+//! cursor API: a [`CursorMut`] points at one node and can insert, remove,
+//! split or splice whole lists around it in O(1).
+
+use crate::alloc::{Allocator, Global, Layout};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::{handle_reserve, TryReserveError};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::NonNull;
+
+/// A doubly-linked list.
+///
+/// Each element lives in its own node allocated from `A`. Moving nodes
+/// between lists, as [`append`](LinkedList::append) and the cursor splices
+/// do, relinks them without touching the allocator, so the lists involved
+/// must share an allocator that can free each other's nodes.
+pub struct LinkedList<T, A: Allocator = Global> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    alloc: A,
+    marker: PhantomData<Node<T>>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+/// A chain of nodes detached from any list: its head, tail and length.
+type Chain<T> = (NonNull<Node<T>>, NonNull<Node<T>>, usize);
+
+struct Node<T> {
+    next: Link<T>,
+    prev: Link<T>,
+    element: T,
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for LinkedList<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for LinkedList<T, A> {}
+
+impl<T> LinkedList<T> {
+    /// Creates an empty list.
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> LinkedList<T, A> {
+    /// Creates an empty list whose nodes will be allocated from `alloc`.
+    pub const fn new_in(alloc: A) -> Self {
+        LinkedList {
+            head: None,
+            tail: None,
+            len: 0,
+            alloc,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the list contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        drop(LinkedList {
+            head: self.head.take(),
+            tail: self.tail.take(),
+            len: mem::take(&mut self.len),
+            alloc: &self.alloc,
+            marker: PhantomData,
+        });
+    }
+
+    fn try_alloc_node(&self, element: T) -> Result<NonNull<Node<T>>, TryReserveError> {
+        let layout = Layout::new::<Node<T>>();
+        let node = self
+            .alloc
+            .allocate(layout)
+            .map_err(|_| TryReserveError::AllocError { layout })?
+            .cast::<Node<T>>();
+        unsafe {
+            node.as_ptr().write(Node {
+                next: None,
+                prev: None,
+                element,
+            })
+        };
+        Ok(node)
+    }
+
+    fn alloc_node(&self, element: T) -> NonNull<Node<T>> {
+        handle_reserve(self.try_alloc_node(element))
+    }
+
+    /// Frees an unlinked node and returns its element.
+    ///
+    /// # Safety
+    ///
+    /// `node` must have been allocated by this list's allocator and must no
+    /// longer be reachable from any list.
+    unsafe fn free_node(&self, node: NonNull<Node<T>>) -> T {
+        let element = node.as_ptr().read().element;
+        self.alloc.deallocate(node.cast(), Layout::new::<Node<T>>());
+        element
+    }
+
+    /// Links the chain `splice_start..=splice_end` of `splice_length` nodes
+    /// between `existing_prev` and `existing_next`, either of which may be
+    /// `None` to mean the list's end.
+    ///
+    /// # Safety
+    ///
+    /// `existing_prev` and `existing_next` must be adjacent in this list,
+    /// and the chain must be owned by nobody else.
+    unsafe fn splice_nodes(
+        &mut self,
+        existing_prev: Link<T>,
+        existing_next: Link<T>,
+        splice_start: NonNull<Node<T>>,
+        splice_end: NonNull<Node<T>>,
+        splice_length: usize,
+    ) {
+        match existing_prev {
+            Some(prev) => (*prev.as_ptr()).next = Some(splice_start),
+            None => self.head = Some(splice_start),
+        }
+        match existing_next {
+            Some(next) => (*next.as_ptr()).prev = Some(splice_end),
+            None => self.tail = Some(splice_end),
+        }
+        (*splice_start.as_ptr()).prev = existing_prev;
+        (*splice_end.as_ptr()).next = existing_next;
+        self.len += splice_length;
+    }
+
+    /// Unlinks `node` without freeing it. Its own links are left stale.
+    ///
+    /// # Safety
+    ///
+    /// `node` must belong to this list.
+    unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) {
+        let node = node.as_ptr();
+        match (*node).prev {
+            Some(prev) => (*prev.as_ptr()).next = (*node).next,
+            None => self.head = (*node).next,
+        }
+        match (*node).next {
+            Some(next) => (*next.as_ptr()).prev = (*node).prev,
+            None => self.tail = (*node).prev,
+        }
+        self.len -= 1;
+    }
+
+    /// Takes the whole chain out of the list, returning its head, tail and
+    /// length, or `None` if the list is empty.
+    fn detach_all_nodes(&mut self) -> Option<Chain<T>> {
+        let head = self.head.take()?;
+        let tail = self.tail.take()?;
+        Some((head, tail, mem::take(&mut self.len)))
+    }
+
+    /// Splits off everything before `split_node`, which sits at index `at`,
+    /// and returns it. With `None` the whole list is split off.
+    ///
+    /// # Safety
+    ///
+    /// `split_node` must belong to this list and sit at index `at`.
+    unsafe fn split_off_before_node(&mut self, split_node: Link<T>, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        let Some(split_node) = split_node else {
+            return mem::replace(self, LinkedList::new_in(self.alloc.clone()));
+        };
+        let first_tail = (*split_node.as_ptr()).prev.take();
+        let first_head = match first_tail {
+            Some(tail) => {
+                (*tail.as_ptr()).next = None;
+                self.head
+            }
+            None => None,
+        };
+        self.head = Some(split_node);
+        self.len -= at;
+        LinkedList {
+            head: first_head,
+            tail: first_tail,
+            len: at,
+            alloc: self.alloc.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Splits off everything after `split_node`, which is preceded by
+    /// `at - 1` nodes, and returns it. With `None` the whole list is split
+    /// off.
+    ///
+    /// # Safety
+    ///
+    /// `split_node` must belong to this list and sit at index `at - 1`.
+    unsafe fn split_off_after_node(&mut self, split_node: Link<T>, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        let Some(split_node) = split_node else {
+            return mem::replace(self, LinkedList::new_in(self.alloc.clone()));
+        };
+        let second_head = (*split_node.as_ptr()).next.take();
+        let second_tail = match second_head {
+            Some(head) => {
+                (*head.as_ptr()).prev = None;
+                self.tail
+            }
+            None => None,
+        };
+        let second = LinkedList {
+            head: second_head,
+            tail: second_tail,
+            len: self.len - at,
+            alloc: self.alloc.clone(),
+            marker: PhantomData,
+        };
+        self.tail = Some(split_node);
+        self.len = at;
+        second
+    }
+
+    /// Returns the front element, or `None` if the list is empty.
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.map(|node| &(*node.as_ptr()).element) }
+    }
+
+    /// Returns a mutable reference to the front element.
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.map(|node| &mut (*node.as_ptr()).element) }
+    }
+
+    /// Returns the back element, or `None` if the list is empty.
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.map(|node| &(*node.as_ptr()).element) }
+    }
+
+    /// Returns a mutable reference to the back element.
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.map(|node| &mut (*node.as_ptr()).element) }
+    }
+
+    /// Returns `true` if the list contains an element equal to `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == x)
+    }
+
+    /// Prepends an element to the list.
+    pub fn push_front(&mut self, element: T) {
+        let node = self.alloc_node(element);
+        unsafe { self.splice_nodes(None, self.head, node, node, 1) };
+    }
+
+    /// Appends an element to the list.
+    pub fn push_back(&mut self, element: T) {
+        let node = self.alloc_node(element);
+        unsafe { self.splice_nodes(self.tail, None, node, node, 1) };
+    }
+
+    /// Prepends an element, returning an error instead of aborting if the
+    /// node cannot be allocated. On error `element` is dropped.
+    pub fn try_push_front(&mut self, element: T) -> Result<(), TryReserveError> {
+        let node = self.try_alloc_node(element)?;
+        unsafe { self.splice_nodes(None, self.head, node, node, 1) };
+        Ok(())
+    }
+
+    /// Appends an element, returning an error instead of aborting if the
+    /// node cannot be allocated. On error `element` is dropped.
+    pub fn try_push_back(&mut self, element: T) -> Result<(), TryReserveError> {
+        let node = self.try_alloc_node(element)?;
+        unsafe { self.splice_nodes(self.tail, None, node, node, 1) };
+        Ok(())
+    }
+
+    /// Removes the front element and returns it, or `None` if the list is
+    /// empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let node = self.head?;
+        unsafe {
+            self.unlink_node(node);
+            Some(self.free_node(node))
+        }
+    }
+
+    /// Removes the back element and returns it, or `None` if the list is
+    /// empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.tail?;
+        unsafe {
+            self.unlink_node(node);
+            Some(self.free_node(node))
+        }
+    }
+
+    /// Moves all elements of `other` to the end of this list in O(1),
+    /// leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let Some(tail) = self.tail else {
+            mem::swap(self, other);
+            return;
+        };
+        if let Some((head, other_tail, len)) = other.detach_all_nodes() {
+            unsafe { self.splice_nodes(Some(tail), None, head, other_tail, len) };
+        }
+    }
+
+    /// Splits the list in two at index `at`, returning everything from `at`
+    /// on. Walks from whichever end is closer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        let len = self.len;
+        assert!(at <= len, "Cannot split off at a nonexistent index");
+        if at == 0 {
+            return mem::replace(self, LinkedList::new_in(self.alloc.clone()));
+        }
+        if at == len {
+            return LinkedList::new_in(self.alloc.clone());
+        }
+        let split_node = if at - 1 <= len - at {
+            let mut iter = self.iter_mut();
+            for _ in 0..at - 1 {
+                iter.next();
+            }
+            iter.head
+        } else {
+            let mut iter = self.iter_mut();
+            for _ in 0..len - at {
+                iter.next_back();
+            }
+            iter.tail
+        };
+        unsafe { self.split_off_after_node(split_node, at) }
+    }
+
+    /// Retains only the elements for which `f` returns `true`.
+    ///
+    /// Each rejected element is unlinked and its node freed before the
+    /// element is dropped, so a panic in `f` or in a destructor leaves a
+    /// valid list and leaks nothing.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut cursor = self.cursor_front_mut();
+        while let Some(element) = cursor.current() {
+            if f(element) {
+                cursor.move_next();
+            } else {
+                drop(cursor.remove_current());
+            }
+        }
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a front-to-back iterator of mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a cursor at the front element, or at the "ghost" position
+    /// if the list is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            index: 0,
+            current: self.head,
+            list: self,
+        }
+    }
+
+    /// Returns a cursor at the back element, or at the "ghost" position if
+    /// the list is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            index: self.len.saturating_sub(1),
+            current: self.tail,
+            list: self,
+        }
+    }
+
+    /// Returns a mutable cursor at the front element, or at the "ghost"
+    /// position if the list is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            index: 0,
+            current: self.head,
+            list: self,
+        }
+    }
+
+    /// Returns a mutable cursor at the back element, or at the "ghost"
+    /// position if the list is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            index: self.len.saturating_sub(1),
+            current: self.tail,
+            list: self,
+        }
+    }
+}
+
+impl<T, A: Allocator> Drop for LinkedList<T, A> {
+    fn drop(&mut self) {
+        /// Keeps popping if an element destructor panics, so the remaining
+        /// nodes are still freed.
+        struct DropGuard<'a, T, A: Allocator>(&'a mut LinkedList<T, A>);
+
+        impl<T, A: Allocator> Drop for DropGuard<'_, T, A> {
+            fn drop(&mut self) {
+                while self.0.pop_front().is_some() {}
+            }
+        }
+
+        while let Some(element) = self.pop_front() {
+            let guard = DropGuard(self);
+            drop(element);
+            mem::forget(guard);
+        }
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        LinkedList::new()
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for LinkedList<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = LinkedList::new_in(self.alloc.clone());
+        list.extend(self.iter().cloned());
+        list
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for LinkedList<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T, U, A1, A2> PartialEq<LinkedList<U, A2>> for LinkedList<T, A1>
+where
+    T: PartialEq<U>,
+    A1: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, other: &LinkedList<U, A2>) -> bool {
+        self.len == other.len && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U, A: Allocator, const N: usize> PartialEq<[U; N]> for LinkedList<T, A> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.len == N && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for LinkedList<T, A> {}
+
+impl<T: Hash, A: Allocator> Hash for LinkedList<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for LinkedList<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for LinkedList<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for LinkedList<T> {
+    fn from(arr: [T; N]) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(arr);
+        list
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a LinkedList<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut LinkedList<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for LinkedList<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter { list: self }
+    }
+}
+
+/// An iterator over the elements of a `LinkedList`, created by
+/// [`LinkedList::iter`].
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a Node<T>>,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            self.len -= 1;
+            self.head = node.next;
+            &node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            self.len -= 1;
+            self.tail = node.prev;
+            &node.element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the elements of a `LinkedList`, created by
+/// [`LinkedList::iter_mut`].
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            let node = node.as_ptr();
+            self.len -= 1;
+            self.head = (*node).next;
+            &mut (*node).element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            let node = node.as_ptr();
+            self.len -= 1;
+            self.tail = (*node).prev;
+            &mut (*node).element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let remaining = Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        };
+        f.debug_list().entries(remaining).finish()
+    }
+}
+
+/// An owning iterator over the elements of a `LinkedList`, created by
+/// [`LinkedList::into_iter`](struct.LinkedList.html#method.into_iter).
+pub struct IntoIter<T, A: Allocator = Global> {
+    list: LinkedList<T, A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.list).finish()
+    }
+}
+
+/// A read-only cursor over a `LinkedList`.
+///
+/// A cursor points at an element or at the "ghost" non-element between
+/// the back and the front, through which it wraps around. Its index is the
+/// element's position, or `None` at the ghost.
+pub struct Cursor<'a, T, A: Allocator = Global> {
+    index: usize,
+    current: Link<T>,
+    list: &'a LinkedList<T, A>,
+}
+
+impl<T, A: Allocator> Clone for Cursor<'_, T, A> {
+    fn clone(&self) -> Self {
+        Cursor { ..*self }
+    }
+}
+
+impl<'a, T, A: Allocator> Cursor<'a, T, A> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    /// Moves to the next element; from the back this reaches the ghost and
+    /// from the ghost the front.
+    pub fn move_next(&mut self) {
+        match self.current.take() {
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).next;
+                self.index += 1;
+            },
+        }
+    }
+
+    /// Moves to the previous element; from the front this reaches the
+    /// ghost and from the ghost the back.
+    pub fn move_prev(&mut self) {
+        match self.current.take() {
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            },
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { self.current.map(|node| &(*node.as_ptr()).element) }
+    }
+
+    /// Returns the element after the current one, without moving.
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe {
+            let next = match self.current {
+                None => self.list.head,
+                Some(node) => (*node.as_ptr()).next,
+            };
+            next.map(|node| &(*node.as_ptr()).element)
+        }
+    }
+
+    /// Returns the element before the current one, without moving.
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe {
+            let prev = match self.current {
+                None => self.list.tail,
+                Some(node) => (*node.as_ptr()).prev,
+            };
+            prev.map(|node| &(*node.as_ptr()).element)
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Cursor<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Cursor")
+            .field(self.list)
+            .field(&self.index())
+            .finish()
+    }
+}
+
+/// A cursor over a `LinkedList` that can edit the list around it.
+///
+/// Like [`Cursor`] it points at an element or at the ghost. Every edit is
+/// O(1), including splicing in or splitting off whole lists.
+pub struct CursorMut<'a, T, A: Allocator = Global> {
+    index: usize,
+    current: Link<T>,
+    list: &'a mut LinkedList<T, A>,
+}
+
+impl<'a, T, A: Allocator> CursorMut<'a, T, A> {
+    /// Returns the index of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    /// Moves to the next element; from the back this reaches the ghost and
+    /// from the ghost the front.
+    pub fn move_next(&mut self) {
+        match self.current.take() {
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).next;
+                self.index += 1;
+            },
+        }
+    }
+
+    /// Moves to the previous element; from the front this reaches the
+    /// ghost and from the ghost the back.
+    pub fn move_prev(&mut self) {
+        match self.current.take() {
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            },
+        }
+    }
+
+    /// Returns the current element, or `None` at the ghost.
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.current.map(|node| &mut (*node.as_ptr()).element) }
+    }
+
+    /// Returns the element after the current one, without moving.
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = match self.current {
+                None => self.list.head,
+                Some(node) => (*node.as_ptr()).next,
+            };
+            next.map(|node| &mut (*node.as_ptr()).element)
+        }
+    }
+
+    /// Returns the element before the current one, without moving.
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = match self.current {
+                None => self.list.tail,
+                Some(node) => (*node.as_ptr()).prev,
+            };
+            prev.map(|node| &mut (*node.as_ptr()).element)
+        }
+    }
+
+    /// Returns a read-only cursor at the same position, borrowing this one.
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            index: self.index,
+            current: self.current,
+            list: self.list,
+        }
+    }
+
+    fn next_node(&self) -> Link<T> {
+        match self.current {
+            None => self.list.head,
+            Some(node) => unsafe { (*node.as_ptr()).next },
+        }
+    }
+
+    fn prev_node(&self) -> Link<T> {
+        match self.current {
+            None => self.list.tail,
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+        }
+    }
+
+    /// Inserts an element after the current one. At the ghost it becomes
+    /// the new front.
+    pub fn insert_after(&mut self, item: T) {
+        let node = self.list.alloc_node(item);
+        unsafe {
+            self.list
+                .splice_nodes(self.current, self.next_node(), node, node, 1)
+        };
+        if self.current.is_none() {
+            self.index = self.list.len;
+        }
+    }
+
+    /// Inserts an element before the current one. At the ghost it becomes
+    /// the new back.
+    pub fn insert_before(&mut self, item: T) {
+        let node = self.list.alloc_node(item);
+        unsafe {
+            self.list
+                .splice_nodes(self.prev_node(), self.current, node, node, 1)
+        };
+        self.index += 1;
+    }
+
+    /// Removes the current element and returns it, moving to the next one.
+    /// At the ghost nothing is removed and `None` is returned.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let unlinked = self.current?;
+        unsafe {
+            self.current = (*unlinked.as_ptr()).next;
+            self.list.unlink_node(unlinked);
+            Some(self.list.free_node(unlinked))
+        }
+    }
+
+    /// Like [`remove_current`](CursorMut::remove_current), but hands the
+    /// node over as a one-element list instead of freeing it.
+    pub fn remove_current_as_list(&mut self) -> Option<LinkedList<T, A>>
+    where
+        A: Clone,
+    {
+        let unlinked = self.current?;
+        unsafe {
+            self.current = (*unlinked.as_ptr()).next;
+            self.list.unlink_node(unlinked);
+            (*unlinked.as_ptr()).prev = None;
+            (*unlinked.as_ptr()).next = None;
+        }
+        Some(LinkedList {
+            head: Some(unlinked),
+            tail: Some(unlinked),
+            len: 1,
+            alloc: self.list.alloc.clone(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Moves every element of `list` in after the current one, in O(1). At
+    /// the ghost they go to the front.
+    pub fn splice_after(&mut self, mut list: LinkedList<T, A>) {
+        let Some((head, tail, len)) = list.detach_all_nodes() else {
+            return;
+        };
+        unsafe {
+            self.list
+                .splice_nodes(self.current, self.next_node(), head, tail, len)
+        };
+        if self.current.is_none() {
+            self.index = self.list.len;
+        }
+    }
+
+    /// Moves every element of `list` in before the current one, in O(1).
+    /// At the ghost they go to the back.
+    pub fn splice_before(&mut self, mut list: LinkedList<T, A>) {
+        let Some((head, tail, len)) = list.detach_all_nodes() else {
+            return;
+        };
+        unsafe {
+            self.list
+                .splice_nodes(self.prev_node(), self.current, head, tail, len)
+        };
+        self.index += len;
+    }
+
+    /// Splits the list after the current element and returns the second
+    /// half, in O(1). At the ghost the whole list is returned.
+    pub fn split_after(&mut self) -> LinkedList<T, A>
+    where
+        A: Clone,
+    {
+        let at = if self.current.is_none() {
+            self.index = 0;
+            0
+        } else {
+            self.index + 1
+        };
+        unsafe { self.list.split_off_after_node(self.current, at) }
+    }
+
+    /// Splits the list before the current element and returns the first
+    /// half, in O(1). At the ghost the whole list is returned.
+    pub fn split_before(&mut self) -> LinkedList<T, A>
+    where
+        A: Clone,
+    {
+        let at = mem::take(&mut self.index);
+        unsafe { self.list.split_off_before_node(self.current, at) }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for CursorMut<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CursorMut")
+            .field(&*self.list)
+            .field(&self.index())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkedList;
+    use crate::alloc::{Counting, Global};
+    use crate::collections::TryReserveError;
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    fn check<A: crate::alloc::Allocator>(list: &LinkedList<i32, A>, model: &[i32]) {
+        assert_eq!(list.len(), model.len());
+        assert!(list.iter().eq(model));
+        assert!(list.iter().rev().eq(model.iter().rev()));
+        assert_eq!(list.front(), model.first());
+        assert_eq!(list.back(), model.last());
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut list = LinkedList::new();
+        for i in 0..10 {
+            list.push_back(i);
+            list.push_front(-i);
+        }
+        assert_eq!(list.len(), 20);
+        for i in (0..10).rev() {
+            assert_eq!(list.pop_front(), Some(-i));
+            assert_eq!(list.pop_back(), Some(i));
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn cursor_walks_through_the_ghost() {
+        let list = LinkedList::from([1, 2, 3]);
+        let mut cursor = list.cursor_front();
+        assert_eq!((cursor.index(), cursor.current()), (Some(0), Some(&1)));
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (None, None));
+        assert_eq!(
+            (cursor.peek_prev(), cursor.peek_next()),
+            (Some(&3), Some(&1))
+        );
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (Some(2), Some(&3)));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current()), (Some(0), Some(&1)));
+
+        let empty: LinkedList<i32> = LinkedList::new();
+        let mut cursor = empty.cursor_back();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn cursor_insert_and_remove() {
+        let mut list = LinkedList::from([1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.insert_before(10);
+        cursor.insert_after(20);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!((cursor.index(), cursor.current()), (Some(2), Some(&mut 20)));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(0);
+        cursor.insert_before(4);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 0));
+        *cursor.peek_next().unwrap() += 100;
+        check(&list, &[0, 101, 10, 20, 3, 4]);
+    }
+
+    #[test]
+    fn cursor_splice_and_split() {
+        let counting = Counting::new(Global);
+        let mut list = LinkedList::new_in(&counting);
+        list.extend([1, 2, 3, 4]);
+        let mut other = LinkedList::new_in(&counting);
+        other.extend([10, 20]);
+        let before = counting.snapshot();
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.splice_after(other);
+        assert_eq!(cursor.index(), Some(1));
+        let mut cursor2 = LinkedList::new_in(&counting);
+        cursor2.push_back(0);
+        cursor.splice_before(cursor2);
+        assert_eq!(cursor.index(), Some(2));
+        check(&list, &[1, 0, 2, 10, 20, 3, 4]);
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let front = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        let back = cursor.split_after();
+        assert_eq!(cursor.index(), Some(0));
+        check(&front, &[1, 0]);
+        check(&list, &[2]);
+        check(&back, &[10, 20, 3, 4]);
+
+        // Splicing at the ghost lands at the ends.
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_prev();
+        cursor.splice_after(front);
+        assert_eq!(cursor.index(), None);
+        cursor.splice_before(back);
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        check(&list, &[1, 0, 2, 10, 20, 3, 4]);
+
+        let diff = counting.snapshot().diff(&before);
+        assert_eq!(diff.allocations, 1);
+        assert_eq!(diff.deallocations, 0);
+    }
+
+    #[test]
+    fn split_at_the_ghost_takes_everything() {
+        let mut list = LinkedList::from([1, 2, 3]);
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        check(&all, &[1, 2, 3]);
+        assert!(list.is_empty());
+
+        let mut list = all;
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_prev();
+        let all = cursor.split_before();
+        check(&all, &[1, 2, 3]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn remove_current_as_list_keeps_node() {
+        let counting = Counting::new(Global);
+        let mut list = LinkedList::new_in(&counting);
+        list.extend([1, 2, 3]);
+        let before = counting.snapshot();
+        let mut cursor = list.cursor_back_mut();
+        let mut single = cursor.remove_current_as_list().unwrap();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.splice_before(single.split_off(0));
+        check(&list, &[3, 1, 2]);
+        assert!(single.is_empty());
+        assert_eq!(counting.snapshot().diff(&before), Default::default());
+    }
+
+    #[test]
+    fn append_and_split_off() {
+        let mut a = LinkedList::from([1, 2]);
+        let mut b = LinkedList::from([3, 4, 5]);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        check(&a, &[1, 2, 3, 4, 5]);
+        b.append(&mut a);
+        check(&b, &[1, 2, 3, 4, 5]);
+        for at in 0..=5 {
+            let mut list: LinkedList<i32> = (1..=5).collect();
+            let tail = list.split_off(at);
+            let model: std::vec::Vec<i32> = (1..=5).collect();
+            check(&list, &model[..at]);
+            check(&tail, &model[at..]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot split off at a nonexistent index")]
+    fn split_off_out_of_bounds() {
+        LinkedList::from([1]).split_off(2);
+    }
+
+    #[test]
+    fn panic_in_retain_predicate_leaks_nothing() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        let mut list = LinkedList::new_in(&counting);
+        for _ in 0..10 {
+            list.push_back(DropCounter::new(&drops, false));
+        }
+        let mut seen = 0;
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            list.retain(|_| {
+                seen += 1;
+                if seen == 6 {
+                    panic!("predicate");
+                }
+                seen % 2 == 0
+            })
+        }));
+        assert!(r.is_err());
+        assert_eq!(drops.get(), 3);
+        assert_eq!(list.len(), 7);
+        assert_eq!(counting.snapshot().live_blocks, 7);
+        drop(list);
+        assert_eq!(drops.get(), 10);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+    }
+
+    #[test]
+    fn panic_in_element_drop_leaks_nothing() {
+        let counting = Counting::new(Global);
+        let drops = Rc::new(Cell::new(0));
+        let mut list = LinkedList::new_in(&counting);
+        for i in 0..8 {
+            list.push_back(DropCounter::new(&drops, i == 2 || i == 5));
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(|| list.retain(|_| false)));
+        assert!(r.is_err());
+        assert_eq!((drops.get(), list.len()), (3, 5));
+        let r = panic::catch_unwind(AssertUnwindSafe(move || drop(list)));
+        assert!(r.is_err());
+        assert_eq!(drops.get(), 8);
+        let stats = counting.snapshot();
+        assert_eq!((stats.live_blocks, stats.deallocations), (0, 8));
+    }
+
+    #[test]
+    fn panic_in_clone_leaks_nothing() {
+        #[derive(Debug)]
+        struct Bomb(i32);
+        impl Clone for Bomb {
+            fn clone(&self) -> Self {
+                if self.0 == 3 {
+                    panic!("clone");
+                }
+                Bomb(self.0)
+            }
+        }
+        let counting = Counting::new(Global);
+        let mut list = LinkedList::new_in(&counting);
+        list.extend((0..6).map(Bomb));
+        let r = panic::catch_unwind(AssertUnwindSafe(|| list.clone()));
+        assert!(r.is_err());
+        assert_eq!(counting.snapshot().live_blocks, 6);
+    }
+
+    #[test]
+    fn try_push_reports_node_layout() {
+        let budget = Budget::new(2 * std::mem::size_of::<[usize; 3]>());
+        let mut list = LinkedList::new_in(&budget);
+        list.try_push_back(1usize).unwrap();
+        list.try_push_front(0).unwrap();
+        assert_eq!(
+            list.try_push_back(2),
+            Err(TryReserveError::AllocError {
+                layout: crate::alloc::Layout::new::<[usize; 3]>()
+            })
+        );
+        assert!(list.try_push_front(2).is_err());
+        assert!(list.iter().eq(&[0, 1]));
+    }
+
+    #[test]
+    fn differential_cursor_against_vec() {
+        let mut rng = XorShift::new(0x11_57);
+        for _ in 0..100 {
+            let mut list = LinkedList::new();
+            let mut model: std::vec::Vec<i32> = std::vec::Vec::new();
+            // The model cursor is an index, with `model.len()` as the ghost.
+            let mut pos = 0;
+            for step in 0..200 {
+                let mut cursor = list.cursor_front_mut();
+                for _ in 0..pos {
+                    cursor.move_next();
+                }
+                let ghost = pos == model.len();
+                match rng.below(7) {
+                    0 => {
+                        cursor.insert_before(step);
+                        model.insert(pos, step);
+                        pos += 1;
+                    }
+                    1 => {
+                        cursor.insert_after(step);
+                        if ghost {
+                            model.insert(0, step);
+                            pos += 1;
+                        } else {
+                            model.insert(pos + 1, step);
+                        }
+                    }
+                    2 => {
+                        let removed = (!ghost).then(|| model.remove(pos));
+                        assert_eq!(cursor.remove_current(), removed);
+                    }
+                    3 => {
+                        let tail = cursor.split_after();
+                        let theirs = model.split_off(if ghost { 0 } else { pos + 1 });
+                        assert!(tail.iter().eq(&theirs));
+                        if ghost {
+                            pos = 0;
+                        }
+                        // Put it back in front of the cursor instead.
+                        cursor.splice_before(tail);
+                        let n = theirs.len();
+                        model.splice(pos..pos, theirs);
+                        pos += n;
+                    }
+                    4 => {
+                        let head = cursor.split_before();
+                        let rest = model.split_off(pos);
+                        assert!(head.iter().eq(&model));
+                        let theirs = std::mem::replace(&mut model, rest);
+                        pos = 0;
+                        // Put it back behind the cursor instead.
+                        cursor.splice_after(head);
+                        if ghost {
+                            model = theirs;
+                            pos = model.len();
+                        } else {
+                            model.splice(1..1, theirs);
+                        }
+                    }
+                    5 => {
+                        cursor.move_prev();
+                        pos = if pos == 0 { model.len() } else { pos - 1 };
+                    }
+                    _ => {
+                        cursor.move_next();
+                        pos = if ghost { 0 } else { pos + 1 };
+                    }
+                }
+                assert_eq!(cursor.index(), (pos < model.len()).then_some(pos));
+                check(&list, &model);
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Collection types.
+//!
+//! Mirrors `std::collections`: each collection lives in its own module and
+//! the main types are re-exported here. Every growable collection takes an
+//! [`Allocator`](crate::alloc::Allocator) parameter defaulting to
+//! [`Global`](crate::alloc::Global) and offers `try_*` counterparts to its
+//! allocating methods that report [`TryReserveError`] instead of aborting.
+//! The maps' fallible `insert` is `try_insert_or_replace`, since std's
+//! unstable `try_insert` already means "fail if the key is present".
+
+pub mod binary_heap;
+pub mod bit_set;
+pub mod bit_vec;
+pub mod btree_map;
+mod btree_node;
+pub mod btree_set;
+pub mod hash_map;
+pub mod hash_set;
+pub mod indexed_heap;
+pub mod linked_list;
+mod raw_table;
+pub mod small_vec;
+pub mod vec_deque;
+
+pub use self::binary_heap::BinaryHeap;
+pub use self::bit_set::BitSet;
+pub use self::bit_vec::BitVec;
+pub use self::btree_map::BTreeMap;
+pub use self::btree_set::BTreeSet;
+pub use self::hash_map::HashMap;
+pub use self::hash_set::HashSet;
+pub use self::indexed_heap::IndexedHeap;
+pub use self::linked_list::LinkedList;
+pub use self::small_vec::SmallVec;
+pub use self::vec_deque::VecDeque;
+pub use crate::raw_vec::TryReserveError;
@@ -0,0 +1,1090 @@
+//! The open-addressing table behind `HashMap` and `HashSet`.
+//!
+//! This is a SwissTable: next to the buckets sits an array of one-byte
+//! control words, one per bucket. A control byte is `EMPTY`, `DELETED` (a
+//! tombstone) or, for a full bucket, the top seven bits of the element's
+//! hash (`h2`). Lookups probe eight control bytes at a time, loaded as a
+//! `u64` and matched with plain bit tricks instead of SIMD, and only compare
+//! keys whose `h2` matched.
+//!
+//! The table stores `T` and knows nothing about keys or hashing: callers
+//! pass the hash in and supply a closure to rehash elements when the table
+//! grows.
+
+use crate::alloc::{Allocator, Global, Layout};
+use crate::raw_vec::{handle_reserve, TryReserveError};
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
+use std::ptr::{self, NonNull};
+
+/// Number of control bytes probed at once.
+const GROUP_WIDTH: usize = mem::size_of::<u64>();
+
+/// Control byte of a bucket that has never held an element since the last
+/// rehash; a probe sequence can stop here.
+const EMPTY: u8 = 0b1111_1111;
+
+/// Control byte of a bucket whose element was removed while a probe
+/// sequence may still run through it.
+const DELETED: u8 = 0b1000_0000;
+
+/// Control bytes of the empty singleton table, which owns no allocation.
+static EMPTY_GROUP: [u8; GROUP_WIDTH] = [EMPTY; GROUP_WIDTH];
+
+fn is_full(ctrl: u8) -> bool {
+    ctrl & 0x80 == 0
+}
+
+/// Tells `EMPTY` from `DELETED`, given a byte that is one of them.
+fn special_is_empty(ctrl: u8) -> bool {
+    ctrl & 0x01 != 0
+}
+
+/// The hash bits that choose where probing starts.
+fn h1(hash: u64) -> usize {
+    hash as usize
+}
+
+/// The hash bits stored in the control byte.
+fn h2(hash: u64) -> u8 {
+    (hash >> (64 - 7)) as u8 & 0x7f
+}
+
+const fn repeat(byte: u8) -> u64 {
+    u64::from_ne_bytes([byte; GROUP_WIDTH])
+}
+
+/// A set of byte positions within a group, one high bit per byte.
+#[derive(Copy, Clone)]
+struct BitMask(u64);
+
+impl BitMask {
+    fn any_bit_set(self) -> bool {
+        self.0 != 0
+    }
+
+    fn lowest_set_bit(self) -> Option<usize> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as usize / 8)
+        }
+    }
+
+    /// Number of unset positions before the first set one.
+    fn trailing_zeros(self) -> usize {
+        self.0.trailing_zeros() as usize / 8
+    }
+
+    /// Number of unset positions after the last set one.
+    fn leading_zeros(self) -> usize {
+        self.0.leading_zeros() as usize / 8
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let bit = self.lowest_set_bit()?;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+/// Eight control bytes, loaded little-endian so that byte `i` of memory is
+/// byte `i` of the word on every platform.
+#[derive(Copy, Clone)]
+struct Group(u64);
+
+impl Group {
+    unsafe fn load(ptr: *const u8) -> Group {
+        Group(u64::from_le(ptr::read_unaligned(ptr as *const u64)))
+    }
+
+    unsafe fn store(self, ptr: *mut u8) {
+        ptr::write_unaligned(ptr as *mut u64, self.0.to_le());
+    }
+
+    /// Positions whose byte equals `byte`.
+    ///
+    /// This is the classic "has zero byte" trick applied to `self ^ byte`.
+    /// A borrow can produce a false positive in the byte after a true
+    /// match, which is harmless: every candidate's key is compared anyway.
+    fn match_byte(self, byte: u8) -> BitMask {
+        let cmp = self.0 ^ repeat(byte);
+        BitMask(cmp.wrapping_sub(repeat(0x01)) & !cmp & repeat(0x80))
+    }
+
+    /// Positions that are `EMPTY`: the only bytes with both of the top two
+    /// bits set.
+    fn match_empty(self) -> BitMask {
+        BitMask(self.0 & (self.0 << 1) & repeat(0x80))
+    }
+
+    /// Positions that are `EMPTY` or `DELETED`: the bytes with the top bit
+    /// set.
+    fn match_empty_or_deleted(self) -> BitMask {
+        BitMask(self.0 & repeat(0x80))
+    }
+
+    fn match_full(self) -> BitMask {
+        BitMask(!self.0 & repeat(0x80))
+    }
+
+    /// Maps `EMPTY`/`DELETED` to `EMPTY` and full to `DELETED`, all eight
+    /// bytes at once. Used to clear out tombstones before a rehash.
+    fn convert_special_to_empty_and_full_to_deleted(self) -> Group {
+        // Per byte: special -> !0 + 0 = 0xff, full -> 0x7f + 1 = 0x80.
+        let full = !self.0 & repeat(0x80);
+        Group(!full + (full >> 7))
+    }
+}
+
+/// Triangular probing over groups. With a power-of-two number of buckets
+/// this visits every group exactly once before repeating.
+struct ProbeSeq {
+    pos: usize,
+    stride: usize,
+}
+
+impl ProbeSeq {
+    fn move_next(&mut self, bucket_mask: usize) {
+        self.stride += GROUP_WIDTH;
+        self.pos = (self.pos + self.stride) & bucket_mask;
+    }
+}
+
+/// Returns the number of buckets needed to hold `cap` elements at the
+/// maximum load factor of 7/8, or `None` on overflow.
+fn capacity_to_buckets(cap: usize) -> Option<usize> {
+    debug_assert_ne!(cap, 0);
+    if cap < GROUP_WIDTH {
+        // Never fewer buckets than a group, so that a group load never
+        // wraps past the mirrored control bytes.
+        return Some(GROUP_WIDTH);
+    }
+    let adjusted = cap.checked_mul(8)? / 7;
+    adjusted.checked_next_power_of_two()
+}
+
+/// Returns the number of elements a table with `bucket_mask + 1` buckets
+/// may hold.
+fn bucket_mask_to_capacity(bucket_mask: usize) -> usize {
+    if bucket_mask < GROUP_WIDTH {
+        bucket_mask
+    } else {
+        (bucket_mask + 1) / 8 * 7
+    }
+}
+
+/// The allocation holds the buckets followed by the control bytes. The
+/// first group of control bytes is mirrored after the last one, so a group
+/// starting at any bucket can be loaded without wrapping.
+fn table_layout<T>(buckets: usize) -> Option<(Layout, usize)> {
+    let data = Layout::array::<T>(buckets).ok()?;
+    let ctrl = Layout::array::<u8>(buckets.checked_add(GROUP_WIDTH)?).ok()?;
+    data.extend(ctrl).ok()
+}
+
+/// The type-erased part of a table: everything except the buckets' type
+/// and the allocator.
+pub(crate) struct RawTableInner {
+    ctrl: NonNull<u8>,
+    bucket_mask: usize,
+    growth_left: usize,
+    items: usize,
+}
+
+impl RawTableInner {
+    const fn new() -> Self {
+        RawTableInner {
+            ctrl: unsafe { NonNull::new_unchecked(&EMPTY_GROUP as *const u8 as *mut u8) },
+            bucket_mask: 0,
+            growth_left: 0,
+            items: 0,
+        }
+    }
+
+    fn fallible_with_capacity<T, A: Allocator>(
+        alloc: &A,
+        capacity: usize,
+    ) -> Result<Self, TryReserveError> {
+        if capacity == 0 {
+            return Ok(RawTableInner::new());
+        }
+        let buckets = capacity_to_buckets(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+        Self::fallible_with_buckets::<T, A>(alloc, buckets)
+    }
+
+    /// Allocates a table of exactly `buckets` buckets, a power of two no
+    /// smaller than a group, with every control byte `EMPTY`.
+    fn fallible_with_buckets<T, A: Allocator>(
+        alloc: &A,
+        buckets: usize,
+    ) -> Result<Self, TryReserveError> {
+        debug_assert!(buckets.is_power_of_two() && buckets >= GROUP_WIDTH);
+        let (layout, ctrl_offset) =
+            table_layout::<T>(buckets).ok_or(TryReserveError::CapacityOverflow)?;
+        let block = alloc
+            .allocate(layout)
+            .map_err(|_| TryReserveError::AllocError { layout })?;
+        unsafe {
+            let ctrl = (block.as_ptr() as *mut u8).add(ctrl_offset);
+            ctrl.write_bytes(EMPTY, buckets + GROUP_WIDTH);
+            Ok(RawTableInner {
+                ctrl: NonNull::new_unchecked(ctrl),
+                bucket_mask: buckets - 1,
+                growth_left: bucket_mask_to_capacity(buckets - 1),
+                items: 0,
+            })
+        }
+    }
+
+    /// Returns the memory to `alloc`, without dropping any element.
+    ///
+    /// # Safety
+    ///
+    /// The table must have been allocated by `alloc` for buckets of `T`.
+    unsafe fn free_buckets<T, A: Allocator>(&mut self, alloc: &A) {
+        if self.is_empty_singleton() {
+            return;
+        }
+        let (layout, ctrl_offset) = table_layout::<T>(self.buckets()).unwrap();
+        let base = self.ctrl.as_ptr().sub(ctrl_offset);
+        alloc.deallocate(NonNull::new_unchecked(base), layout);
+    }
+
+    fn is_empty_singleton(&self) -> bool {
+        self.bucket_mask == 0
+    }
+
+    fn buckets(&self) -> usize {
+        self.bucket_mask + 1
+    }
+
+    unsafe fn ctrl(&self, index: usize) -> *mut u8 {
+        self.ctrl.as_ptr().add(index)
+    }
+
+    /// Returns a pointer to bucket `index`, laid out before the control
+    /// bytes.
+    fn bucket<T>(&self, index: usize) -> *mut T {
+        if mem::size_of::<T>() == 0 {
+            NonNull::dangling().as_ptr()
+        } else {
+            let data =
+                self.ctrl
+                    .as_ptr()
+                    .wrapping_sub(self.buckets() * mem::size_of::<T>()) as *mut T;
+            data.wrapping_add(index)
+        }
+    }
+
+    /// Sets a control byte along with its mirror, if it has one.
+    unsafe fn set_ctrl(&mut self, index: usize, ctrl: u8) {
+        // For the first group this is the mirror after the last bucket;
+        // everywhere else it is `index` itself.
+        let index2 = (index.wrapping_sub(GROUP_WIDTH) & self.bucket_mask) + GROUP_WIDTH;
+        *self.ctrl(index) = ctrl;
+        *self.ctrl(index2) = ctrl;
+    }
+
+    fn probe_seq(&self, hash: u64) -> ProbeSeq {
+        ProbeSeq {
+            pos: h1(hash) & self.bucket_mask,
+            stride: 0,
+        }
+    }
+
+    /// Returns the first `EMPTY` or `DELETED` bucket in `hash`'s probe
+    /// sequence. There always is one, because the load factor is below 1.
+    fn find_insert_slot(&self, hash: u64) -> usize {
+        let mut probe = self.probe_seq(hash);
+        loop {
+            let group = unsafe { Group::load(self.ctrl(probe.pos)) };
+            if let Some(bit) = group.match_empty_or_deleted().lowest_set_bit() {
+                return (probe.pos + bit) & self.bucket_mask;
+            }
+            probe.move_next(self.bucket_mask);
+        }
+    }
+
+    /// Probes `hash`'s sequence for a full bucket accepted by `eq`.
+    fn find(&self, hash: u64, mut eq: impl FnMut(usize) -> bool) -> Option<usize> {
+        let h2 = h2(hash);
+        let mut probe = self.probe_seq(hash);
+        loop {
+            let group = unsafe { Group::load(self.ctrl(probe.pos)) };
+            for bit in group.match_byte(h2) {
+                let index = (probe.pos + bit) & self.bucket_mask;
+                if eq(index) {
+                    return Some(index);
+                }
+            }
+            // An `EMPTY` byte means no insertion ever probed past here.
+            if group.match_empty().any_bit_set() {
+                return None;
+            }
+            probe.move_next(self.bucket_mask);
+        }
+    }
+
+    /// Marks bucket `index` as holding an element with `hash`.
+    unsafe fn record_item_insert_at(&mut self, index: usize, hash: u64) {
+        let old_ctrl = *self.ctrl(index);
+        self.growth_left -= special_is_empty(old_ctrl) as usize;
+        self.set_ctrl(index, h2(hash));
+        self.items += 1;
+    }
+
+    /// Marks the full bucket `index` as free, without touching its element.
+    unsafe fn erase(&mut self, index: usize) {
+        debug_assert!(is_full(*self.ctrl(index)));
+        // If the run of full-or-deleted bytes around `index` is shorter
+        // than a group, every probe that passed this bucket also saw an
+        // `EMPTY` in the same group and stopped: the bucket can become
+        // `EMPTY` again. Otherwise it must stay a tombstone.
+        let index_before = index.wrapping_sub(GROUP_WIDTH) & self.bucket_mask;
+        let empty_before = Group::load(self.ctrl(index_before)).match_empty();
+        let empty_after = Group::load(self.ctrl(index)).match_empty();
+        let ctrl = if empty_before.leading_zeros() + empty_after.trailing_zeros() >= GROUP_WIDTH {
+            DELETED
+        } else {
+            self.growth_left += 1;
+            EMPTY
+        };
+        self.set_ctrl(index, ctrl);
+        self.items -= 1;
+    }
+
+    /// Marks every bucket `EMPTY`, without dropping anything.
+    fn clear_no_drop(&mut self) {
+        if !self.is_empty_singleton() {
+            unsafe {
+                self.ctrl(0)
+                    .write_bytes(EMPTY, self.buckets() + GROUP_WIDTH)
+            };
+        }
+        self.items = 0;
+        self.growth_left = bucket_mask_to_capacity(self.bucket_mask);
+    }
+
+    fn iter<T>(&self) -> RawIter<T> {
+        let current = if self.items == 0 {
+            BitMask(0)
+        } else {
+            unsafe { Group::load(self.ctrl(0)).match_full() }
+        };
+        RawIter {
+            ctrl: self.ctrl,
+            data: self.bucket::<T>(0),
+            group: 0,
+            current,
+            items: self.items,
+        }
+    }
+}
+
+/// An open-addressing hash table of `T`, allocated from `A`.
+pub(crate) struct RawTable<T, A: Allocator = Global> {
+    table: RawTableInner,
+    alloc: A,
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for RawTable<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for RawTable<T, A> {}
+
+impl<T, A: Allocator> RawTable<T, A> {
+    /// Creates an empty table without allocating.
+    pub(crate) const fn new_in(alloc: A) -> Self {
+        RawTable {
+            table: RawTableInner::new(),
+            alloc,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates a table that can hold `capacity` elements without growing.
+    pub(crate) fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        Ok(RawTable {
+            table: RawTableInner::fallible_with_capacity::<T, A>(&alloc, capacity)?,
+            alloc,
+            marker: PhantomData,
+        })
+    }
+
+    pub(crate) fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        handle_reserve(Self::try_with_capacity_in(capacity, alloc))
+    }
+
+    pub(crate) fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.table.items
+    }
+
+    /// Number of elements the table can hold without growing.
+    pub(crate) fn capacity(&self) -> usize {
+        self.table.items + self.table.growth_left
+    }
+
+    #[cfg(test)]
+    pub(crate) fn buckets(&self) -> usize {
+        self.table.buckets()
+    }
+
+    /// Returns a pointer to bucket `index`.
+    pub(crate) fn bucket(&self, index: usize) -> *mut T {
+        self.table.bucket::<T>(index)
+    }
+
+    /// Returns the index of the element with `hash` accepted by `eq`.
+    pub(crate) fn find(&self, hash: u64, mut eq: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.table
+            .find(hash, |index| eq(unsafe { &*self.bucket(index) }))
+    }
+
+    /// Returns the element with `hash` accepted by `eq`.
+    pub(crate) fn get(&self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<&T> {
+        let index = self.find(hash, eq)?;
+        unsafe { Some(&*self.bucket(index)) }
+    }
+
+    /// Mutable version of [`get`](RawTable::get).
+    pub(crate) fn get_mut(&mut self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<&mut T> {
+        let index = self.find(hash, eq)?;
+        unsafe { Some(&mut *self.bucket(index)) }
+    }
+
+    /// Inserts `value` with `hash`, growing first if needed, and returns
+    /// its bucket index. Does not check whether an equal element exists.
+    pub(crate) fn insert(&mut self, hash: u64, value: T, hasher: impl Fn(&T) -> u64) -> usize {
+        let mut index = self.table.find_insert_slot(hash);
+        // Reusing a tombstone does not use up any growth.
+        let old_ctrl = unsafe { *self.table.ctrl(index) };
+        if self.table.growth_left == 0 && special_is_empty(old_ctrl) {
+            self.reserve(1, hasher);
+            index = self.table.find_insert_slot(hash);
+        }
+        unsafe { self.insert_at(index, hash, value) };
+        index
+    }
+
+    /// Inserts `value` with `hash` and returns its bucket index.
+    ///
+    /// # Safety
+    ///
+    /// The table must have room for one more element, e.g. after
+    /// `reserve(1, ..)`.
+    pub(crate) unsafe fn insert_no_grow(&mut self, hash: u64, value: T) -> usize {
+        let index = self.table.find_insert_slot(hash);
+        debug_assert!(self.table.growth_left > 0 || !special_is_empty(*self.table.ctrl(index)));
+        self.insert_at(index, hash, value);
+        index
+    }
+
+    unsafe fn insert_at(&mut self, index: usize, hash: u64, value: T) {
+        self.table.record_item_insert_at(index, hash);
+        self.bucket(index).write(value);
+    }
+
+    /// Removes the element in bucket `index` and returns it.
+    ///
+    /// # Safety
+    ///
+    /// Bucket `index` must be full.
+    pub(crate) unsafe fn remove(&mut self, index: usize) -> T {
+        self.table.erase(index);
+        self.bucket(index).read()
+    }
+
+    /// Removes the element with `hash` accepted by `eq`.
+    pub(crate) fn remove_entry(&mut self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<T> {
+        let index = self.find(hash, eq)?;
+        unsafe { Some(self.remove(index)) }
+    }
+
+    /// Drops every element, keeping the allocation.
+    pub(crate) fn clear(&mut self) {
+        /// Resets the control bytes even if a destructor unwinds.
+        struct Guard<'a>(&'a mut RawTableInner);
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.0.clear_no_drop();
+            }
+        }
+
+        let guard = Guard(&mut self.table);
+        unsafe { drop_elements::<T>(guard.0) };
+    }
+
+    /// Makes room for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows and aborts on allocation
+    /// failure.
+    pub(crate) fn reserve(&mut self, additional: usize, hasher: impl Fn(&T) -> u64) {
+        if additional > self.table.growth_left {
+            handle_reserve(self.reserve_rehash(additional, hasher));
+        }
+    }
+
+    /// Fallible version of [`reserve`](RawTable::reserve).
+    pub(crate) fn try_reserve(
+        &mut self,
+        additional: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        if additional > self.table.growth_left {
+            self.reserve_rehash(additional, hasher)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn reserve_rehash(
+        &mut self,
+        additional: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        let new_items = self
+            .table
+            .items
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let full_capacity = bucket_mask_to_capacity(self.table.bucket_mask);
+        if new_items <= full_capacity / 2 {
+            // Most of the missing growth is tombstones: clearing them out
+            // frees enough room without a bigger table.
+            unsafe { self.rehash_in_place(hasher) };
+            Ok(())
+        } else {
+            self.resize(new_items.max(full_capacity + 1), hasher)
+        }
+    }
+
+    /// Rebuilds the table in a new allocation sized for `capacity`.
+    fn resize(
+        &mut self,
+        capacity: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        debug_assert!(self.table.items <= capacity);
+
+        /// Frees one of the two allocations without dropping elements:
+        /// the new one if `hasher` panics, the old one on success. Until
+        /// the swap below, elements are only copied, so the old table
+        /// still owns all of them.
+        struct Guard<'a, T, A: Allocator> {
+            table: RawTableInner,
+            alloc: &'a A,
+            marker: PhantomData<T>,
+        }
+
+        impl<T, A: Allocator> Drop for Guard<'_, T, A> {
+            fn drop(&mut self) {
+                unsafe { self.table.free_buckets::<T, A>(self.alloc) };
+            }
+        }
+
+        let mut new = Guard {
+            table: RawTableInner::fallible_with_capacity::<T, A>(&self.alloc, capacity)?,
+            alloc: &self.alloc,
+            marker: PhantomData::<T>,
+        };
+        let mut iter = self.table.iter::<T>();
+        while let Some(index) = iter.next() {
+            let item = iter.bucket(index);
+            let hash = hasher(unsafe { &*item });
+            let new_index = new.table.find_insert_slot(hash);
+            unsafe {
+                new.table.record_item_insert_at(new_index, hash);
+                ptr::copy_nonoverlapping(item, new.table.bucket::<T>(new_index), 1);
+            }
+        }
+        mem::swap(&mut self.table, &mut new.table);
+        Ok(())
+    }
+
+    /// Clears out the tombstones without reallocating, by moving every
+    /// element to the first free bucket of its probe sequence.
+    ///
+    /// If `hasher` panics, the elements not yet placed are dropped and the
+    /// table is left valid but shorter.
+    unsafe fn rehash_in_place(&mut self, hasher: impl Fn(&T) -> u64) {
+        /// Drops every element still marked `DELETED`, i.e. not yet
+        /// placed, if `hasher` unwinds, then repairs the bookkeeping.
+        struct Guard<'a, T> {
+            table: &'a mut RawTableInner,
+            marker: PhantomData<T>,
+        }
+
+        impl<T> Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                for i in 0..self.table.buckets() {
+                    unsafe {
+                        if *self.table.ctrl(i) == DELETED {
+                            self.table.set_ctrl(i, EMPTY);
+                            if mem::needs_drop::<T>() {
+                                ptr::drop_in_place(self.table.bucket::<T>(i));
+                            }
+                            self.table.items -= 1;
+                        }
+                    }
+                }
+                self.table.growth_left =
+                    bucket_mask_to_capacity(self.table.bucket_mask) - self.table.items;
+            }
+        }
+
+        let table = &mut self.table;
+        // Mark every full bucket `DELETED` ("needs placing") and every
+        // free bucket `EMPTY`, then rebuild the mirror.
+        let mut i = 0;
+        while i < table.buckets() {
+            let group = Group::load(table.ctrl(i));
+            group
+                .convert_special_to_empty_and_full_to_deleted()
+                .store(table.ctrl(i));
+            i += GROUP_WIDTH;
+        }
+        ptr::copy(table.ctrl(0), table.ctrl(table.buckets()), GROUP_WIDTH);
+
+        let guard = Guard::<T> {
+            table,
+            marker: PhantomData,
+        };
+        'outer: for i in 0..guard.table.buckets() {
+            if *guard.table.ctrl(i) != DELETED {
+                continue;
+            }
+            let item = guard.table.bucket::<T>(i);
+            loop {
+                let hash = hasher(&*item);
+                let new_i = guard.table.find_insert_slot(hash);
+                // If the element would land in the same group it is in
+                // already, probing finds it just as fast where it is.
+                let probe_start = h1(hash) & guard.table.bucket_mask;
+                let group_of = |pos: usize| {
+                    (pos.wrapping_sub(probe_start) & guard.table.bucket_mask) / GROUP_WIDTH
+                };
+                if group_of(i) == group_of(new_i) {
+                    guard.table.set_ctrl(i, h2(hash));
+                    continue 'outer;
+                }
+                let new_item = guard.table.bucket::<T>(new_i);
+                let prev_ctrl = *guard.table.ctrl(new_i);
+                guard.table.set_ctrl(new_i, h2(hash));
+                if prev_ctrl == EMPTY {
+                    // Move into the free bucket and free this one.
+                    guard.table.set_ctrl(i, EMPTY);
+                    ptr::copy_nonoverlapping(item, new_item, 1);
+                    continue 'outer;
+                }
+                // The target still holds an element waiting to be placed:
+                // swap, and go on placing the one now in bucket `i`.
+                debug_assert_eq!(prev_ctrl, DELETED);
+                ptr::swap_nonoverlapping(item, new_item, 1);
+            }
+        }
+        guard.table.growth_left =
+            bucket_mask_to_capacity(guard.table.bucket_mask) - guard.table.items;
+        mem::forget(guard);
+    }
+
+    /// Shrinks the table to the smallest size that holds
+    /// `max(len, min_size)` elements.
+    pub(crate) fn shrink_to(&mut self, min_size: usize, hasher: impl Fn(&T) -> u64) {
+        let min_size = self.table.items.max(min_size);
+        if min_size == 0 {
+            let mut old = mem::replace(&mut self.table, RawTableInner::new());
+            unsafe { old.free_buckets::<T, A>(&self.alloc) };
+            return;
+        }
+        // A failed shrink leaves the table as it is; there is no reason to
+        // abort over memory we were about to give back.
+        if let Some(buckets) = capacity_to_buckets(min_size) {
+            if buckets < self.table.buckets() {
+                let _ = self.resize(min_size, hasher);
+            }
+        }
+    }
+
+    /// Iterates over the full buckets. The iterator does not borrow the
+    /// table; the caller keeps it alive and unchanged, except for erasing
+    /// buckets already yielded.
+    pub(crate) fn iter(&self) -> RawIter<T> {
+        self.table.iter::<T>()
+    }
+
+    /// Returns a cursor for removing elements while iterating, see
+    /// [`RawExtractIf`].
+    pub(crate) fn extract_if(&mut self) -> RawExtractIf<'_, T, A> {
+        RawExtractIf {
+            iter: self.iter(),
+            table: self,
+        }
+    }
+
+    /// Moves every element out through the returned iterator. The table is
+    /// emptied up front, so leaking the iterator only leaks elements.
+    pub(crate) fn drain(&mut self) -> RawDrain<'_, T> {
+        let table = mem::replace(&mut self.table, RawTableInner::new());
+        RawDrain {
+            iter: table.iter::<T>(),
+            table,
+            orig_table: &mut self.table,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for RawTable<T, A> {
+    type Item = T;
+    type IntoIter = RawIntoIter<T, A>;
+
+    /// Moves every element out through the returned iterator, which frees
+    /// the table when dropped.
+    fn into_iter(self) -> RawIntoIter<T, A> {
+        let this = ManuallyDrop::new(self);
+        RawIntoIter {
+            iter: this.table.iter::<T>(),
+            table: RawTableInner { ..this.table },
+            alloc: unsafe { ptr::read(&this.alloc) },
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Drops every element in place, going on with the rest if one destructor
+/// unwinds. Leaves the control bytes alone.
+unsafe fn drop_elements<T>(table: &mut RawTableInner) {
+    struct Guard<'a, T>(&'a mut RawIter<T>);
+
+    impl<T> Drop for Guard<'_, T> {
+        fn drop(&mut self) {
+            while let Some(index) = self.0.next() {
+                unsafe { ptr::drop_in_place(self.0.bucket(index)) };
+            }
+        }
+    }
+
+    if mem::needs_drop::<T>() && table.items != 0 {
+        let mut iter = table.iter::<T>();
+        let guard = Guard(&mut iter);
+        while let Some(index) = guard.0.next() {
+            ptr::drop_in_place(guard.0.bucket(index));
+        }
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for RawTable<T, A> {
+    fn clone(&self) -> Self {
+        let alloc = self.alloc.clone();
+        if self.table.is_empty_singleton() {
+            return RawTable::new_in(alloc);
+        }
+        let table = handle_reserve(RawTableInner::fallible_with_buckets::<T, A>(
+            &alloc,
+            self.table.buckets(),
+        ));
+        // Every element goes in the bucket it occupies here. A bucket is
+        // only marked full once its clone is written, so if a `clone`
+        // panics, dropping `new` drops exactly the clones made so far.
+        // Tombstones are not copied and become free growth.
+        let mut new = RawTable::<T, A> {
+            table,
+            alloc,
+            marker: PhantomData,
+        };
+        let mut iter = self.iter();
+        while let Some(index) = iter.next() {
+            unsafe {
+                let value = (*iter.bucket(index)).clone();
+                new.bucket(index).write(value);
+                new.table.set_ctrl(index, *self.table.ctrl(index));
+            }
+            new.table.items += 1;
+            new.table.growth_left -= 1;
+        }
+        new
+    }
+}
+
+impl<T, A: Allocator> Drop for RawTable<T, A> {
+    fn drop(&mut self) {
+        /// Frees the memory even if an element destructor unwinds.
+        struct Guard<'a, T, A: Allocator>(&'a mut RawTable<T, A>);
+
+        impl<T, A: Allocator> Drop for Guard<'_, T, A> {
+            fn drop(&mut self) {
+                let RawTable { table, alloc, .. } = &mut *self.0;
+                unsafe { table.free_buckets::<T, A>(alloc) };
+            }
+        }
+
+        let guard = Guard(self);
+        unsafe { drop_elements::<T>(&mut guard.0.table) };
+    }
+}
+
+/// An iterator over the indices of the full buckets of a table.
+pub(crate) struct RawIter<T> {
+    ctrl: NonNull<u8>,
+    data: *mut T,
+    /// Start of the group `current` was loaded from.
+    group: usize,
+    current: BitMask,
+    items: usize,
+}
+
+impl<T> RawIter<T> {
+    /// Returns a pointer to bucket `index` of the table being iterated.
+    pub(crate) fn bucket(&self, index: usize) -> *mut T {
+        if mem::size_of::<T>() == 0 {
+            NonNull::dangling().as_ptr()
+        } else {
+            self.data.wrapping_add(index)
+        }
+    }
+}
+
+unsafe impl<T: Sync> Send for RawIter<T> {}
+unsafe impl<T: Sync> Sync for RawIter<T> {}
+
+impl<T> Clone for RawIter<T> {
+    fn clone(&self) -> Self {
+        RawIter { ..*self }
+    }
+}
+
+impl<T> Iterator for RawIter<T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.items == 0 {
+            return None;
+        }
+        loop {
+            if let Some(bit) = self.current.next() {
+                self.items -= 1;
+                return Some(self.group + bit);
+            }
+            // `items` is non-zero, so a later group still has a full
+            // bucket and this never runs past the end.
+            self.group += GROUP_WIDTH;
+            self.current = unsafe { Group::load(self.ctrl.as_ptr().add(self.group)).match_full() };
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items, Some(self.items))
+    }
+}
+
+/// An owning iterator over a table, created by `RawTable::into_iter`.
+pub(crate) struct RawIntoIter<T, A: Allocator = Global> {
+    iter: RawIter<T>,
+    table: RawTableInner,
+    alloc: A,
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for RawIntoIter<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for RawIntoIter<T, A> {}
+
+impl<T, A: Allocator> RawIntoIter<T, A> {
+    pub(crate) fn iter(&self) -> RawIter<T> {
+        self.iter.clone()
+    }
+}
+
+impl<T, A: Allocator> Iterator for RawIntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let index = self.iter.next()?;
+        unsafe { Some(self.iter.bucket(index).read()) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, A: Allocator> Drop for RawIntoIter<T, A> {
+    fn drop(&mut self) {
+        /// Frees the memory even if dropping a remaining element unwinds.
+        struct Guard<'r, T, A: Allocator>(&'r mut RawIntoIter<T, A>);
+
+        impl<T, A: Allocator> Drop for Guard<'_, T, A> {
+            fn drop(&mut self) {
+                let RawIntoIter { table, alloc, .. } = &mut *self.0;
+                unsafe { table.free_buckets::<T, A>(alloc) };
+            }
+        }
+
+        let guard = Guard(self);
+        guard.0.by_ref().for_each(drop);
+    }
+}
+
+/// Walks a table once, removing the elements a predicate picks. Created by
+/// [`RawTable::extract_if`]; the predicate is passed to each `next` call so
+/// that the map and set iterators can wrap it in their own signature.
+pub(crate) struct RawExtractIf<'a, T, A: Allocator = Global> {
+    iter: RawIter<T>,
+    table: &'a mut RawTable<T, A>,
+}
+
+impl<T, A: Allocator> RawExtractIf<'_, T, A> {
+    pub(crate) fn next(&mut self, mut pred: impl FnMut(&mut T) -> bool) -> Option<T> {
+        // Erasing only rewrites the control byte of a bucket the iterator
+        // has already passed, so iteration can go on over the same table.
+        while let Some(index) = self.iter.next() {
+            if pred(unsafe { &mut *self.iter.bucket(index) }) {
+                return Some(unsafe { self.table.remove(index) });
+            }
+        }
+        None
+    }
+
+    pub(crate) fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// A draining iterator over a table, created by [`RawTable::drain`].
+pub(crate) struct RawDrain<'a, T> {
+    iter: RawIter<T>,
+    /// The table's memory, moved out while the drain runs.
+    table: RawTableInner,
+    orig_table: &'a mut RawTableInner,
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for RawDrain<'_, T> {}
+unsafe impl<T: Sync> Sync for RawDrain<'_, T> {}
+
+impl<T> RawDrain<'_, T> {
+    pub(crate) fn iter(&self) -> RawIter<T> {
+        self.iter.clone()
+    }
+}
+
+impl<T> Iterator for RawDrain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let index = self.iter.next()?;
+        unsafe { Some(self.iter.bucket(index).read()) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> Drop for RawDrain<'_, T> {
+    fn drop(&mut self) {
+        /// Hands the emptied memory back to the table even if dropping a
+        /// remaining element unwinds.
+        struct Guard<'r, 'a, T>(&'r mut RawDrain<'a, T>);
+
+        impl<T> Drop for Guard<'_, '_, T> {
+            fn drop(&mut self) {
+                self.0.table.clear_no_drop();
+                mem::swap(self.0.orig_table, &mut self.0.table);
+            }
+        }
+
+        let guard = Guard(self);
+        guard.0.by_ref().for_each(drop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capacity_to_buckets, Group, RawTable, DELETED, EMPTY};
+    use crate::alloc::{Counting, Global};
+    use crate::test_util::XorShift;
+
+    /// A deliberately terrible hash that packs keys into few groups.
+    fn hash(x: &u64) -> u64 {
+        x.wrapping_mul(0x9e37_79b9_7f4a_7c15) & 0xfe00_0000_0000_00ff
+    }
+
+    #[test]
+    fn group_matching() {
+        let bytes = [0x12, EMPTY, DELETED, 0x12, 0x00, EMPTY, 0x7f, 0x13];
+        let group = unsafe { Group::load(bytes.as_ptr()) };
+        assert_eq!(group.match_byte(0x12).collect::<Vec<_>>(), [0, 3]);
+        assert_eq!(group.match_empty().collect::<Vec<_>>(), [1, 5]);
+        assert_eq!(
+            group.match_empty_or_deleted().collect::<Vec<_>>(),
+            [1, 2, 5]
+        );
+        assert_eq!(group.match_full().collect::<Vec<_>>(), [0, 3, 4, 6, 7]);
+        let mut out = [0u8; 8];
+        unsafe {
+            group
+                .convert_special_to_empty_and_full_to_deleted()
+                .store(out.as_mut_ptr())
+        };
+        assert_eq!(
+            out,
+            [DELETED, EMPTY, EMPTY, DELETED, DELETED, EMPTY, DELETED, DELETED]
+        );
+    }
+
+    #[test]
+    fn bucket_counts() {
+        assert_eq!(capacity_to_buckets(1), Some(8));
+        assert_eq!(capacity_to_buckets(7), Some(8));
+        assert_eq!(capacity_to_buckets(8), Some(16));
+        assert_eq!(capacity_to_buckets(14), Some(16));
+        assert_eq!(capacity_to_buckets(15), Some(32));
+        assert_eq!(capacity_to_buckets(usize::MAX), None);
+    }
+
+    #[test]
+    fn tombstones_are_rehashed_in_place() {
+        let counting = Counting::new(Global);
+        let mut table = RawTable::with_capacity_in(28, &counting);
+        let buckets = table.buckets();
+        let mut rng = XorShift::new(77);
+        let mut live = std::collections::BTreeSet::new();
+        let allocations = counting.snapshot().allocations;
+        // Churn through many more keys than the table holds, keeping the
+        // live count small: tombstones pile up and must be recycled
+        // without the table ever growing.
+        for step in 0..20_000u64 {
+            if live.len() < 10 {
+                let k = rng.next();
+                table.insert(hash(&k), k, hash);
+                live.insert(k);
+            } else {
+                let k = *live.iter().nth(rng.below(live.len())).unwrap();
+                assert_eq!(table.remove_entry(hash(&k), |x| *x == k), Some(k));
+                live.remove(&k);
+            }
+            if step % 97 == 0 {
+                for k in &live {
+                    assert_eq!(table.get(hash(k), |x| x == k), Some(k));
+                }
+            }
+        }
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(counting.snapshot().allocations, allocations);
+        let mut all: Vec<u64> = table.drain().collect();
+        all.sort_unstable();
+        assert!(all.iter().eq(live.iter()));
+        assert_eq!(table.len(), 0);
+    }
+}
@@ -0,0 +1,963 @@
+//! A vector with inline storage that spills to the heap, `SmallVec<T, N>`.
+//!
+//! Up to `N` elements live inside the `SmallVec` itself; pushing past that
+//! moves them into a heap [`RawVec`](crate::raw_vec::RawVec) that then grows
+//! like [`Vec`]'s, and [`shrink_to_fit`](SmallVec::shrink_to_fit) moves them
+//! back once they fit again. Zero-sized types never allocate and are never
+//! limited to `N`. Shifting, draining and splicing share their code with
+//! `Vec` through [`crate::slice`].
+
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::{handle_reserve, RawVec, TryReserveError};
+use crate::slice::{Buffer, RawDrain, RawSplice};
+use crate::vec::Vec;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::{self, ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut, RangeBounds};
+use std::ptr;
+use std::slice;
+
+/// A vector that stores up to `N` elements inline before moving them to
+/// the heap.
+///
+/// ```
+/// use mystdrs::collections::SmallVec;
+///
+/// let mut v: SmallVec<u32, 2> = SmallVec::new();
+/// v.push(1);
+/// v.push(2);
+/// assert!(!v.spilled());
+/// v.push(3);
+/// assert!(v.spilled());
+/// assert_eq!(v, [1, 2, 3]);
+/// ```
+pub struct SmallVec<T, const N: usize, A: Allocator = Global> {
+    /// The heap buffer. It stays unallocated while the elements are
+    /// inline, so its capacity exceeds `N` exactly when it is in use; for
+    /// zero-sized `T` that is always.
+    buf: RawVec<T, A>,
+    inline: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> SmallVec<T, N> {
+    /// Constructs a new, empty `SmallVec` without allocating.
+    pub const fn new() -> Self {
+        SmallVec::new_in(Global)
+    }
+
+    /// Constructs a new, empty `SmallVec` with room for at least
+    /// `capacity` elements, allocating only if that is more than `N`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SmallVec::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, const N: usize, A: Allocator> SmallVec<T, N, A> {
+    /// Constructs a new, empty `SmallVec` in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        SmallVec {
+            buf: RawVec::new_in(alloc),
+            inline: unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() },
+            len: 0,
+        }
+    }
+
+    /// Constructs a new, empty `SmallVec` in `alloc` with room for at least
+    /// `capacity` elements.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        handle_reserve(SmallVec::try_with_capacity_in(capacity, alloc))
+    }
+
+    /// Fallible version of
+    /// [`with_capacity_in`](SmallVec::with_capacity_in).
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let mut v = SmallVec::new_in(alloc);
+        v.try_grow(capacity, true)?;
+        Ok(v)
+    }
+
+    /// Converts a [`Vec`] into a `SmallVec`, keeping its buffer if its
+    /// capacity is more than `N` and moving the elements inline otherwise.
+    pub fn from_vec(vec: Vec<T, A>) -> Self {
+        let (mut buf, len) = vec.into_raw_vec();
+        let mut inline = unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() };
+        if buf.capacity() <= N {
+            unsafe { ptr::copy_nonoverlapping(buf.ptr(), inline.as_mut_ptr().cast::<T>(), len) };
+            buf.shrink_to(0);
+        }
+        SmallVec { buf, inline, len }
+    }
+
+    /// Converts the `SmallVec` into a [`Vec`], reusing the heap buffer if
+    /// the elements have spilled.
+    pub fn into_vec(self) -> Vec<T, A> {
+        let me = ManuallyDrop::new(self);
+        let mut buf = unsafe { ptr::read(&me.buf) };
+        if !me.on_heap() {
+            buf.reserve_exact(0, me.len);
+            unsafe { ptr::copy_nonoverlapping(me.as_ptr(), buf.ptr(), me.len) };
+        }
+        unsafe { Vec::from_raw_vec(buf, me.len) }
+    }
+
+    /// Returns `true` if the elements live in a heap allocation. This is
+    /// never the case for zero-sized types.
+    pub fn spilled(&self) -> bool {
+        mem::size_of::<T>() != 0 && self.on_heap()
+    }
+
+    fn on_heap(&self) -> bool {
+        self.buf.capacity() > N
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+
+    /// Returns the number of elements the vector can hold without
+    /// reallocating: `N` while inline.
+    pub fn capacity(&self) -> usize {
+        if self.on_heap() {
+            self.buf.capacity()
+        } else {
+            N
+        }
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a raw pointer to the vector's elements, wherever they live.
+    /// The pointer is invalidated by moving the vector while it is inline.
+    pub fn as_ptr(&self) -> *const T {
+        if self.on_heap() {
+            self.buf.ptr()
+        } else {
+            self.inline.as_ptr().cast()
+        }
+    }
+
+    /// Returns a raw mutable pointer to the vector's elements.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        if self.on_heap() {
+            self.buf.ptr()
+        } else {
+            self.inline.as_mut_ptr().cast()
+        }
+    }
+
+    /// Extracts a slice containing the entire vector.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    /// Extracts a mutable slice of the entire vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    /// Forces the length of the vector to `new_len`.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be at most `capacity()` and the elements at
+    /// `old_len..new_len` must be initialized.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.len = new_len;
+    }
+
+    /// Makes room for `additional` more elements, moving them to the heap
+    /// if they no longer fit inline.
+    fn try_grow(&mut self, additional: usize, exact: bool) -> Result<(), TryReserveError> {
+        if self.on_heap() {
+            return if exact {
+                self.buf.try_reserve_exact(self.len, additional)
+            } else {
+                self.buf.try_reserve(self.len, additional)
+            };
+        }
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= N {
+            return Ok(());
+        }
+        let cap = if exact {
+            required
+        } else {
+            required.max(N.saturating_mul(2))
+        };
+        self.buf.try_reserve_exact(0, cap)?;
+        // Copy every inline slot, not just `0..len`: a splice may have
+        // parked the tail past the length.
+        unsafe { ptr::copy_nonoverlapping(self.inline.as_ptr().cast::<T>(), self.buf.ptr(), N) };
+        Ok(())
+    }
+
+    /// Reserves capacity for at least `additional` more elements. When the
+    /// elements first spill the heap buffer gets at least `2 * N` slots, and
+    /// from then on it doubles like `Vec`'s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        handle_reserve(self.try_grow(additional, false));
+    }
+
+    /// Reserves capacity for exactly `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        handle_reserve(self.try_grow(additional, true));
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_grow(additional, false)
+    }
+
+    /// Tries to reserve capacity for exactly `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_grow(additional, true)
+    }
+
+    /// Shrinks the capacity as much as possible, moving the elements back
+    /// inline and freeing the heap buffer if there are at most `N` of them.
+    pub fn shrink_to_fit(&mut self) {
+        if !self.spilled() {
+            return;
+        }
+        if self.len <= N {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.buf.ptr(),
+                    self.inline.as_mut_ptr().cast::<T>(),
+                    self.len,
+                );
+            }
+            self.buf.shrink_to(0);
+        } else {
+            self.buf.shrink_to(self.len);
+        }
+    }
+
+    /// Appends an element to the back of the vector.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.capacity() {
+            self.reserve(1);
+        }
+        unsafe {
+            ptr::write(self.as_mut_ptr().add(self.len), value);
+        }
+        self.len += 1;
+    }
+
+    /// Appends an element to the back of the vector, returning an error
+    /// instead of aborting if the buffer cannot grow. On error `value` is
+    /// dropped and the vector is unchanged.
+    pub fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+        if self.len == self.capacity() {
+            self.try_reserve(1)?;
+        }
+        unsafe {
+            ptr::write(self.as_mut_ptr().add(self.len), value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.as_ptr().add(self.len))) }
+        }
+    }
+
+    /// Inserts an element at position `index`, shifting all elements after
+    /// it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        crate::slice::assert_insert_index(index, self.len);
+        if self.len == self.capacity() {
+            self.reserve(1);
+        }
+        unsafe { crate::slice::insert(self.as_mut_ptr(), &mut self.len, index, element) };
+    }
+
+    /// Inserts an element at position `index`, returning an error instead
+    /// of aborting if the buffer cannot grow.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), TryReserveError> {
+        crate::slice::assert_insert_index(index, self.len);
+        if self.len == self.capacity() {
+            self.try_reserve(1)?;
+        }
+        unsafe { crate::slice::insert(self.as_mut_ptr(), &mut self.len, index, element) };
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index`, shifting all
+    /// elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        unsafe { crate::slice::remove(self.as_mut_ptr(), &mut self.len, index) }
+    }
+
+    /// Removes an element and returns it, replacing it with the last
+    /// element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        unsafe { crate::slice::swap_remove(self.as_mut_ptr(), &mut self.len, index) }
+    }
+
+    /// Shortens the vector, keeping the first `len` elements and dropping
+    /// the rest. Has no effect if `len` is greater than the current length.
+    /// The elements stay on the heap if they have spilled.
+    pub fn truncate(&mut self, len: usize) {
+        unsafe { crate::slice::truncate(self.as_mut_ptr(), &mut self.len, len) };
+    }
+
+    /// Clears the vector, removing all values.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements for which `f` returns `true`.
+    ///
+    /// If `f` or an element's destructor panics, the vector is left holding
+    /// the elements not yet visited plus those already kept.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        unsafe { crate::slice::retain(self.as_mut_ptr(), &mut self.len, f) };
+    }
+
+    /// Clones and appends all elements of `other` to the vector.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+        for item in other {
+            self.push(item.clone());
+        }
+    }
+
+    /// Clones and appends all elements of `other`, returning an error
+    /// instead of aborting if the buffer cannot grow. Nothing is appended
+    /// on error.
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        self.try_reserve(other.len())?;
+        self.extend_from_slice(other);
+        Ok(())
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator,
+    /// as [`Vec::drain`] does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, N, A>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Drain {
+            inner: RawDrain::new(self, range),
+        }
+    }
+
+    /// Replaces the elements in `range` with those of `replace_with`,
+    /// returning the removed elements as an iterator, as [`Vec::splice`]
+    /// does. The vector spills if the result does not fit inline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, I::IntoIter, N, A>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Splice {
+            inner: RawSplice::new(self, range, replace_with.into_iter()),
+        }
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Buffer<T> for SmallVec<T, N, A> {
+    fn as_ptr(&self) -> *const T {
+        SmallVec::as_ptr(self)
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        SmallVec::as_mut_ptr(self)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        SmallVec::reserve(self, additional);
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Drop for SmallVec<T, N, A> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Deref for SmallVec<T, N, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> DerefMut for SmallVec<T, N, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        SmallVec::new()
+    }
+}
+
+impl<T: Clone, const N: usize, A: Allocator + Clone> Clone for SmallVec<T, N, A> {
+    fn clone(&self) -> Self {
+        let mut v = SmallVec::with_capacity_in(self.len, self.allocator().clone());
+        v.extend_from_slice(self);
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize, A: Allocator> fmt::Debug for SmallVec<T, N, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T, U, const N: usize, const M: usize, A1, A2> PartialEq<SmallVec<U, M, A2>>
+    for SmallVec<T, N, A1>
+where
+    T: PartialEq<U>,
+    A1: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, other: &SmallVec<U, M, A2>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize, A: Allocator> PartialEq<[U]> for SmallVec<T, N, A> {
+    fn eq(&self, other: &[U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize, const M: usize, A: Allocator> PartialEq<[U; M]>
+    for SmallVec<T, N, A>
+{
+    fn eq(&self, other: &[U; M]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq, const N: usize, A: Allocator> Eq for SmallVec<T, N, A> {}
+
+impl<T: Hash, const N: usize, A: Allocator> Hash for SmallVec<T, N, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Extend<T> for SmallVec<T, N, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, const N: usize, A: Allocator> Extend<&'a T> for SmallVec<T, N, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = SmallVec::new();
+        v.extend(iter);
+        v
+    }
+}
+
+impl<T: Clone, const N: usize> From<&[T]> for SmallVec<T, N> {
+    fn from(s: &[T]) -> Self {
+        let mut v = SmallVec::with_capacity(s.len());
+        v.extend_from_slice(s);
+        v
+    }
+}
+
+impl<T, const N: usize, const M: usize> From<[T; M]> for SmallVec<T, N> {
+    fn from(arr: [T; M]) -> Self {
+        let mut v = SmallVec::with_capacity(M);
+        v.extend(arr);
+        v
+    }
+}
+
+impl<T, const N: usize, A: Allocator> From<Vec<T, A>> for SmallVec<T, N, A> {
+    fn from(vec: Vec<T, A>) -> Self {
+        SmallVec::from_vec(vec)
+    }
+}
+
+impl<T, const N: usize, A: Allocator> From<SmallVec<T, N, A>> for Vec<T, A> {
+    fn from(v: SmallVec<T, N, A>) -> Self {
+        v.into_vec()
+    }
+}
+
+impl<'a, T, const N: usize, A: Allocator> IntoIterator for &'a SmallVec<T, N, A> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize, A: Allocator> IntoIterator for &'a mut SmallVec<T, N, A> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> IntoIterator for SmallVec<T, N, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N, A>;
+
+    fn into_iter(mut self) -> IntoIter<T, N, A> {
+        let end = self.len;
+        // The iterator owns the elements from here on.
+        self.len = 0;
+        IntoIter {
+            vec: self,
+            start: 0,
+            end,
+        }
+    }
+}
+
+/// An iterator that moves out of a [`SmallVec`], created by
+/// `SmallVec::into_iter`.
+pub struct IntoIter<T, const N: usize, A: Allocator = Global> {
+    /// Has length zero; the elements at `start..end` are still owned.
+    vec: SmallVec<T, N, A>,
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize, A: Allocator> IntoIter<T, N, A> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.vec.as_ptr().add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Iterator for IntoIter<T, N, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.vec.as_ptr().add(self.start)) };
+            self.start += 1;
+            Some(item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.start;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize, A: Allocator> DoubleEndedIterator for IntoIter<T, N, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            unsafe { Some(ptr::read(self.vec.as_ptr().add(self.end))) }
+        }
+    }
+}
+
+impl<T, const N: usize, A: Allocator> ExactSizeIterator for IntoIter<T, N, A> {}
+
+impl<T, const N: usize, A: Allocator> FusedIterator for IntoIter<T, N, A> {}
+
+impl<T: fmt::Debug, const N: usize, A: Allocator> fmt::Debug for IntoIter<T, N, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Drop for IntoIter<T, N, A> {
+    fn drop(&mut self) {
+        unsafe {
+            let rest = ptr::slice_from_raw_parts_mut(
+                self.vec.as_mut_ptr().add(self.start),
+                self.end - self.start,
+            );
+            self.start = self.end;
+            ptr::drop_in_place(rest);
+        }
+    }
+}
+
+/// A draining iterator for [`SmallVec`], created by
+/// [`SmallVec::drain`].
+pub struct Drain<'a, T, const N: usize, A: Allocator = Global> {
+    inner: RawDrain<'a, T, SmallVec<T, N, A>>,
+}
+
+impl<T, const N: usize, A: Allocator> Drain<'_, T, N, A> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> Iterator for Drain<'_, T, N, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> DoubleEndedIterator for Drain<'_, T, N, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+impl<T, const N: usize, A: Allocator> ExactSizeIterator for Drain<'_, T, N, A> {}
+
+impl<T, const N: usize, A: Allocator> FusedIterator for Drain<'_, T, N, A> {}
+
+impl<T: fmt::Debug, const N: usize, A: Allocator> fmt::Debug for Drain<'_, T, N, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_slice()).finish()
+    }
+}
+
+/// A splicing iterator for [`SmallVec`], created by
+/// [`SmallVec::splice`].
+pub struct Splice<'a, I: Iterator, const N: usize, A: Allocator = Global> {
+    inner: RawSplice<'a, I, SmallVec<I::Item, N, A>>,
+}
+
+impl<I: Iterator, const N: usize, A: Allocator> Iterator for Splice<'_, I, N, A> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: Iterator, const N: usize, A: Allocator> DoubleEndedIterator for Splice<'_, I, N, A> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<I: Iterator, const N: usize, A: Allocator> ExactSizeIterator for Splice<'_, I, N, A> {}
+
+impl<I: Iterator, const N: usize, A: Allocator> fmt::Debug for Splice<'_, I, N, A>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Splice")
+            .field(&self.inner.as_slice())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallVec;
+    use crate::alloc::{Counting, Global};
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use crate::vec::Vec;
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn spills_and_moves_back() {
+        let counting = Counting::new(Global);
+        let mut v: SmallVec<u32, 4, _> = SmallVec::new_in(&counting);
+        assert_eq!(v.capacity(), 4);
+        v.extend(0..4);
+        assert!(!v.spilled());
+        assert_eq!(counting.snapshot().allocations, 0);
+        v.push(4);
+        assert!(v.spilled());
+        assert_eq!(v.capacity(), 8);
+        assert_eq!(v, [0, 1, 2, 3, 4]);
+        v.truncate(2);
+        assert!(v.spilled());
+        v.shrink_to_fit();
+        assert!(!v.spilled());
+        assert_eq!(v.capacity(), 4);
+        assert_eq!(v, [0, 1]);
+        assert_eq!(counting.snapshot().live_blocks, 0);
+
+        let w: SmallVec<u32, 4> = SmallVec::with_capacity(3);
+        assert!(!w.spilled());
+        let w: SmallVec<u32, 4> = SmallVec::with_capacity(5);
+        assert!(w.spilled());
+        assert_eq!(w.capacity(), 5);
+    }
+
+    #[test]
+    fn differential_against_std() {
+        let mut rng = XorShift::new(0x5ba11);
+        for _ in 0..200 {
+            let mut ours: SmallVec<u32, 4> = SmallVec::new();
+            let mut theirs: std::vec::Vec<u32> = std::vec::Vec::new();
+            for _ in 0..100 {
+                let len = theirs.len();
+                match rng.below(10) {
+                    0 | 1 => {
+                        let x = rng.next() as u32;
+                        ours.push(x);
+                        theirs.push(x);
+                    }
+                    2 => assert_eq!(ours.pop(), theirs.pop()),
+                    3 => {
+                        let i = rng.below(len + 1);
+                        let x = rng.next() as u32;
+                        ours.insert(i, x);
+                        theirs.insert(i, x);
+                    }
+                    4 if len > 0 => {
+                        let i = rng.below(len);
+                        assert_eq!(ours.remove(i), theirs.remove(i));
+                    }
+                    5 if len > 0 => {
+                        let i = rng.below(len);
+                        assert_eq!(ours.swap_remove(i), theirs.swap_remove(i));
+                    }
+                    6 => {
+                        let a = rng.below(len + 1);
+                        let b = a + rng.below(len - a + 1);
+                        let drained: std::vec::Vec<u32> = ours.drain(a..b).collect();
+                        assert_eq!(drained, theirs.drain(a..b).collect::<std::vec::Vec<_>>());
+                    }
+                    7 => {
+                        let a = rng.below(len + 1);
+                        let b = a + rng.below(len - a + 1);
+                        let n = rng.below(8) as u32;
+                        let removed: std::vec::Vec<u32> = ours.splice(a..b, 100..100 + n).collect();
+                        let expected: std::vec::Vec<u32> =
+                            theirs.splice(a..b, 100..100 + n).collect();
+                        assert_eq!(removed, expected);
+                    }
+                    8 => {
+                        let keep = rng.below(3) as u32;
+                        ours.retain(|x| x % 3 != keep);
+                        theirs.retain(|x| x % 3 != keep);
+                    }
+                    _ => {
+                        ours.shrink_to_fit();
+                        assert_eq!(ours.spilled(), ours.len() > 4);
+                    }
+                }
+                assert_eq!(ours[..], theirs[..]);
+            }
+            let collected: std::vec::Vec<u32> = ours.into_iter().collect();
+            assert_eq!(collected, theirs);
+        }
+    }
+
+    #[test]
+    fn splice_spills_with_parked_tail() {
+        // The tail sits past the length when the splice outgrows the inline
+        // slots, and must survive the move to the heap.
+        let mut v: SmallVec<u32, 4> = SmallVec::from([1, 2, 3, 4]);
+        let removed: Vec<u32> = v.splice(1..2, 10..16).collect();
+        assert_eq!(removed, [2]);
+        assert!(v.spilled());
+        assert_eq!(v, [1, 10, 11, 12, 13, 14, 15, 3, 4]);
+
+        // An iterator whose size hint says nothing takes the collecting path.
+        let mut v: SmallVec<u32, 4> = SmallVec::from([1, 2, 3]);
+        v.splice(..1, (0..6).filter(|x| x % 2 == 0));
+        assert_eq!(v, [0, 2, 4, 2, 3]);
+
+        let mut v: SmallVec<u32, 4> = SmallVec::from([1, 2, 3, 4]);
+        v.splice(1..3, None);
+        assert_eq!(v, [1, 4]);
+        assert!(!v.spilled());
+    }
+
+    #[test]
+    fn drain_keeps_tail_on_panic_and_leak() {
+        let count = Rc::new(Cell::new(0));
+        let mut v: SmallVec<DropCounter, 2> = SmallVec::new();
+        for i in 0..5 {
+            v.push(DropCounter::new(&count, i == 1));
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| drop(v.drain(1..3))));
+        assert!(result.is_err());
+        assert_eq!(count.get(), 2);
+        assert_eq!(v.len(), 3);
+        drop(v);
+        assert_eq!(count.get(), 5);
+
+        let mut v: SmallVec<u32, 2> = SmallVec::from([1, 2, 3, 4, 5]);
+        let mut drain = v.drain(1..4);
+        assert_eq!(drain.next_back(), Some(4));
+        assert_eq!(drain.as_slice(), [2, 3]);
+        std::mem::forget(drain);
+        assert_eq!(v, [1]);
+    }
+
+    #[test]
+    fn vec_conversions_reuse_the_buffer() {
+        let mut v: SmallVec<u32, 2> = SmallVec::from([1, 2, 3]);
+        let ptr = v.as_mut_ptr();
+        let vec = v.into_vec();
+        assert_eq!(vec.as_ptr(), ptr as *const u32);
+        assert_eq!(vec, [1, 2, 3]);
+        let v: SmallVec<u32, 2> = SmallVec::from_vec(vec);
+        assert!(v.spilled());
+        assert_eq!(v.as_ptr(), ptr as *const u32);
+
+        let counting = Counting::new(Global);
+        let mut vec = Vec::with_capacity_in(2, &counting);
+        vec.extend_from_slice(&[7, 8]);
+        let v: SmallVec<u32, 4, _> = SmallVec::from(vec);
+        assert!(!v.spilled());
+        assert_eq!(counting.snapshot().live_blocks, 0);
+        let vec = Vec::from(v);
+        assert_eq!(vec, [7, 8]);
+        assert_eq!(vec.capacity(), 2);
+    }
+
+    #[test]
+    fn into_iter_drops_rest() {
+        let count = Rc::new(Cell::new(0));
+        for &n in &[3, 6] {
+            let mut v: SmallVec<DropCounter, 4> = SmallVec::new();
+            for _ in 0..n {
+                v.push(DropCounter::new(&count, false));
+            }
+            let mut it = v.into_iter();
+            drop(it.next());
+            drop(it.next_back());
+            assert_eq!(it.len(), n - 2);
+        }
+        assert_eq!(count.get(), 9);
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        let mut v: SmallVec<(), 2> = SmallVec::new();
+        assert_eq!(v.capacity(), usize::MAX);
+        for _ in 0..100 {
+            v.push(());
+        }
+        assert!(!v.spilled());
+        v.splice(10..20, std::iter::repeat_n((), 15));
+        assert_eq!(v.len(), 105);
+        assert_eq!(v.into_vec().len(), 105);
+    }
+
+    #[test]
+    fn try_reserve_and_allocator() {
+        let budget = Budget::new(28);
+        let mut v: SmallVec<u32, 2, _> = SmallVec::new_in(&budget);
+        v.extend_from_slice(&[1, 2]);
+        assert!(v.try_extend_from_slice(&[3, 4, 5, 6, 7, 8]).is_err());
+        assert_eq!(v, [1, 2]);
+        assert!(!v.spilled());
+        v.try_push(3).unwrap();
+        assert_eq!(v.capacity(), 4);
+        let w = v.clone();
+        assert!(std::ptr::eq(*w.allocator(), &budget));
+        assert!(w.spilled());
+    }
+}
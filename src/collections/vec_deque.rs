@@ -0,0 +1,1502 @@
+//! A double-ended queue implemented with a growable ring buffer.
+//!
+//! The elements live at physical indices `head..head + len` taken modulo
+//! the capacity, so they may wrap around the end of the buffer.
+//! [`VecDeque::as_slices`] exposes them as at most two slices and
+//! [`VecDeque::make_contiguous`] rearranges them into one.
+
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::{handle_reserve, RawVec, TryReserveError};
+use crate::vec::Vec;
+use std::cmp;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::ManuallyDrop;
+use std::ops::{Index, IndexMut, Range, RangeBounds};
+use std::ptr;
+use std::slice;
+
+/// A double-ended queue with O(1) push and pop at both ends.
+pub struct VecDeque<T, A: Allocator = Global> {
+    head: usize,
+    len: usize,
+    buf: RawVec<T, A>,
+}
+
+/// Drops a slice when it goes out of scope, so that a panicking destructor
+/// earlier in the same scope does not leak it.
+struct Dropper<T>(*mut [T]);
+
+impl<T> Drop for Dropper<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.0) }
+    }
+}
+
+impl<T> VecDeque<T> {
+    /// Creates an empty deque without allocating.
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Creates an empty deque with room for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Fallible version of [`with_capacity`](VecDeque::with_capacity).
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> VecDeque<T, A> {
+    /// Creates an empty deque in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        VecDeque {
+            head: 0,
+            len: 0,
+            buf: RawVec::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty deque in `alloc` with room for at least `capacity`
+    /// elements.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        VecDeque {
+            head: 0,
+            len: 0,
+            buf: RawVec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Fallible version of [`with_capacity_in`](VecDeque::with_capacity_in).
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        Ok(VecDeque {
+            head: 0,
+            len: 0,
+            buf: RawVec::try_with_capacity_in(capacity, alloc)?,
+        })
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+
+    /// Returns the number of elements the deque can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Returns the number of elements in the deque.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the deque contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    fn ptr(&self) -> *mut T {
+        self.buf.ptr()
+    }
+
+    /// Maps `idx`, which may have run at most one capacity past the end,
+    /// back into the buffer.
+    fn wrap_index(&self, idx: usize) -> usize {
+        if idx >= self.capacity() {
+            idx - self.capacity()
+        } else {
+            idx
+        }
+    }
+
+    fn wrap_add(&self, idx: usize, addend: usize) -> usize {
+        self.wrap_index(idx.wrapping_add(addend))
+    }
+
+    fn wrap_sub(&self, idx: usize, subtrahend: usize) -> usize {
+        self.wrap_index(idx.wrapping_sub(subtrahend).wrapping_add(self.capacity()))
+    }
+
+    /// Converts a logical index into a physical buffer index.
+    fn to_physical_idx(&self, idx: usize) -> usize {
+        self.wrap_add(self.head, idx)
+    }
+
+    fn is_contiguous(&self) -> bool {
+        self.head <= self.capacity() - self.len
+    }
+
+    unsafe fn buffer_read(&self, off: usize) -> T {
+        ptr::read(self.ptr().add(off))
+    }
+
+    unsafe fn buffer_write(&mut self, off: usize, value: T) {
+        ptr::write(self.ptr().add(off), value);
+    }
+
+    unsafe fn copy(&mut self, src: usize, dst: usize, len: usize) {
+        ptr::copy(self.ptr().add(src), self.ptr().add(dst), len);
+    }
+
+    unsafe fn copy_nonoverlapping(&mut self, src: usize, dst: usize, len: usize) {
+        ptr::copy_nonoverlapping(self.ptr().add(src), self.ptr().add(dst), len);
+    }
+
+    /// Moves `len` elements from physical index `src` to `dst`, either run
+    /// possibly wrapping around the end of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Both runs must lie within the buffer, and the distance between them
+    /// plus `len` must not exceed the capacity: a move that overlaps itself
+    /// at both ends has no order in which the copy is safe.
+    unsafe fn wrap_copy(&mut self, src: usize, dst: usize, len: usize) {
+        let cap = self.capacity();
+        debug_assert!(cmp::min(self.wrap_sub(dst, src), self.wrap_sub(src, dst)) + len <= cap);
+        if src == dst || len == 0 {
+            return;
+        }
+        if self.wrap_sub(dst, src) < len {
+            // `dst` lands inside the source run: copy back to front, one
+            // non-wrapping chunk at a time.
+            let mut left = len;
+            while left > 0 {
+                let src_end = self.wrap_add(src, left - 1) + 1;
+                let dst_end = self.wrap_add(dst, left - 1) + 1;
+                let n = left.min(src_end).min(dst_end);
+                self.copy(src_end - n, dst_end - n, n);
+                left -= n;
+            }
+        } else {
+            let mut done = 0;
+            while done < len {
+                let s = self.wrap_add(src, done);
+                let d = self.wrap_add(dst, done);
+                let n = (len - done).min(cap - s).min(cap - d);
+                self.copy(s, d, n);
+                done += n;
+            }
+        }
+    }
+
+    /// Repairs the layout after the buffer grew from `old_cap`: a run that
+    /// wrapped around the old end would otherwise straddle the new space.
+    ///
+    /// # Safety
+    ///
+    /// Must be called right after growing, before anything else looks at
+    /// the buffer.
+    unsafe fn handle_capacity_increase(&mut self, old_cap: usize) {
+        let new_cap = self.capacity();
+        debug_assert!(new_cap >= old_cap);
+        if self.head <= old_cap - self.len {
+            return;
+        }
+        let head_len = old_cap - self.head;
+        let tail_len = self.len - head_len;
+        if tail_len < head_len && new_cap - old_cap >= tail_len {
+            // [A . . B B B]  ->  [. . . B B B A . .]
+            self.copy_nonoverlapping(0, old_cap, tail_len);
+        } else {
+            // [A A A . B B]  ->  [A A A . . . . B B]
+            let new_head = new_cap - head_len;
+            self.copy(self.head, new_head, head_len);
+            self.head = new_head;
+        }
+    }
+
+    fn grow(&mut self) {
+        let old_cap = self.capacity();
+        self.buf.grow_one(old_cap);
+        unsafe { self.handle_capacity_increase(old_cap) };
+    }
+
+    fn try_grow(&mut self) -> Result<(), TryReserveError> {
+        let old_cap = self.capacity();
+        self.buf.try_reserve(old_cap, 1)?;
+        unsafe { self.handle_capacity_increase(old_cap) };
+        Ok(())
+    }
+
+    /// Splits a logical range into the physical ranges of its two halves;
+    /// the second is empty unless the range wraps.
+    fn slice_ranges(&self, range: Range<usize>) -> (Range<usize>, Range<usize>) {
+        if range.is_empty() {
+            return (0..0, 0..0);
+        }
+        let start = self.to_physical_idx(range.start);
+        let len = range.end - range.start;
+        let room = self.capacity() - start;
+        if len <= room {
+            (start..start + len, 0..0)
+        } else {
+            (start..self.capacity(), 0..len - room)
+        }
+    }
+
+    fn raw_slices(&self, range: Range<usize>) -> (*mut [T], *mut [T]) {
+        let (a, b) = self.slice_ranges(range);
+        let ptr = self.ptr();
+        unsafe {
+            (
+                ptr::slice_from_raw_parts_mut(ptr.add(a.start), a.len()),
+                ptr::slice_from_raw_parts_mut(ptr.add(b.start), b.len()),
+            )
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let old_cap = self.capacity();
+        self.buf.reserve(self.len, additional);
+        unsafe { self.handle_capacity_increase(old_cap) };
+    }
+
+    /// Reserves capacity for exactly `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve_exact(additional));
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let old_cap = self.capacity();
+        self.buf.try_reserve(self.len, additional)?;
+        unsafe { self.handle_capacity_increase(old_cap) };
+        Ok(())
+    }
+
+    /// Tries to reserve capacity for exactly `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let old_cap = self.capacity();
+        self.buf.try_reserve_exact(self.len, additional)?;
+        unsafe { self.handle_capacity_increase(old_cap) };
+        Ok(())
+    }
+
+    /// Shrinks the capacity of the deque as much as possible. The elements
+    /// are moved to the start of the buffer first.
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity() == self.len {
+            return;
+        }
+        if self.head != 0 {
+            self.make_contiguous();
+            unsafe { self.copy(self.head, 0, self.len) };
+            self.head = 0;
+        }
+        self.buf.shrink_to(self.len);
+    }
+
+    /// Returns a reference to the element at `index`, where index 0 is the
+    /// front of the queue.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            unsafe { Some(&*self.ptr().add(self.to_physical_idx(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the element at `index`.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            unsafe { Some(&mut *self.ptr().add(self.to_physical_idx(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns the front element, or `None` if the deque is empty.
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns a mutable reference to the front element.
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    /// Returns the back element, or `None` if the deque is empty.
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.wrapping_sub(1))
+    }
+
+    /// Returns a mutable reference to the back element.
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(self.len.wrapping_sub(1))
+    }
+
+    /// Swaps the elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len());
+        assert!(j < self.len());
+        let (pi, pj) = (self.to_physical_idx(i), self.to_physical_idx(j));
+        unsafe { ptr::swap(self.ptr().add(pi), self.ptr().add(pj)) };
+    }
+
+    /// Returns `true` if the deque contains an element equal to `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let (a, b) = self.as_slices();
+        a.contains(x) || b.contains(x)
+    }
+
+    /// Appends an element to the back of the deque.
+    pub fn push_back(&mut self, value: T) {
+        if self.is_full() {
+            self.grow();
+        }
+        unsafe { self.buffer_write(self.to_physical_idx(self.len), value) };
+        self.len += 1;
+    }
+
+    /// Prepends an element to the front of the deque.
+    pub fn push_front(&mut self, value: T) {
+        if self.is_full() {
+            self.grow();
+        }
+        self.head = self.wrap_sub(self.head, 1);
+        unsafe { self.buffer_write(self.head, value) };
+        self.len += 1;
+    }
+
+    /// Appends an element to the back of the deque, returning an error
+    /// instead of aborting if the buffer cannot grow. On error `value` is
+    /// dropped and the deque is unchanged.
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryReserveError> {
+        if self.is_full() {
+            self.try_grow()?;
+        }
+        unsafe { self.buffer_write(self.to_physical_idx(self.len), value) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Prepends an element to the front of the deque, returning an error
+    /// instead of aborting if the buffer cannot grow. On error `value` is
+    /// dropped and the deque is unchanged.
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryReserveError> {
+        if self.is_full() {
+            self.try_grow()?;
+        }
+        self.head = self.wrap_sub(self.head, 1);
+        unsafe { self.buffer_write(self.head, value) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the front element and returns it, or `None` if the deque is
+    /// empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let old_head = self.head;
+        self.head = self.to_physical_idx(1);
+        self.len -= 1;
+        unsafe { Some(self.buffer_read(old_head)) }
+    }
+
+    /// Removes the back element and returns it, or `None` if the deque is
+    /// empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        unsafe { Some(self.buffer_read(self.to_physical_idx(self.len))) }
+    }
+
+    /// Inserts an element at `index`, shifting whichever side of it is
+    /// shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len(), "index out of bounds");
+        if self.is_full() {
+            self.grow();
+        }
+        unsafe { self.insert_unchecked(index, value) };
+    }
+
+    /// Inserts an element at `index`, returning an error instead of
+    /// aborting if the buffer cannot grow.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`; an out-of-bounds index is a bug, not an
+    /// allocation failure.
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), TryReserveError> {
+        assert!(index <= self.len(), "index out of bounds");
+        if self.is_full() {
+            self.try_grow()?;
+        }
+        unsafe { self.insert_unchecked(index, value) };
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// `index <= len` and there must be spare capacity for one element.
+    unsafe fn insert_unchecked(&mut self, index: usize, value: T) {
+        let after = self.len - index;
+        if after < index {
+            self.wrap_copy(
+                self.to_physical_idx(index),
+                self.to_physical_idx(index + 1),
+                after,
+            );
+        } else {
+            let old_head = self.head;
+            self.head = self.wrap_sub(self.head, 1);
+            self.wrap_copy(old_head, self.head, index);
+        }
+        self.buffer_write(self.to_physical_idx(index), value);
+        self.len += 1;
+    }
+
+    /// Removes and returns the element at `index`, shifting whichever side
+    /// of it is shorter. Returns `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let wrapped = self.to_physical_idx(index);
+        let value = unsafe { self.buffer_read(wrapped) };
+        let after = self.len - index - 1;
+        unsafe {
+            if after < index {
+                self.wrap_copy(self.wrap_add(wrapped, 1), wrapped, after);
+            } else {
+                let old_head = self.head;
+                self.head = self.to_physical_idx(1);
+                self.wrap_copy(old_head, self.head, index);
+            }
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Shortens the deque, keeping the first `len` elements and dropping
+    /// the rest. Has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let (front, back) = self.raw_slices(len..self.len);
+        self.len = len;
+        unsafe {
+            let _back = Dropper(back);
+            ptr::drop_in_place(front);
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+        self.head = 0;
+    }
+
+    /// Returns the contents as a pair of slices which, in order, make up
+    /// the deque. The second slice is empty unless the contents wrap around
+    /// the end of the buffer.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (a, b) = self.raw_slices(0..self.len);
+        unsafe { (&*a, &*b) }
+    }
+
+    /// Mutable version of [`as_slices`](VecDeque::as_slices).
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (a, b) = self.raw_slices(0..self.len);
+        unsafe { (&mut *a, &mut *b) }
+    }
+
+    /// Rearranges the buffer so the contents are one contiguous run, and
+    /// returns it. Does not allocate.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if !self.is_contiguous() {
+            let cap = self.capacity();
+            let free = cap - self.len;
+            let head_len = cap - self.head;
+            let tail_len = self.len - head_len;
+            unsafe {
+                if free >= head_len {
+                    // [A A . . . B]  ->  [B A A . . .]
+                    self.copy(0, head_len, tail_len);
+                    self.copy_nonoverlapping(self.head, 0, head_len);
+                    self.head = 0;
+                } else if free >= tail_len {
+                    // [A . . B B B]  ->  [. . B B B A]
+                    self.copy(self.head, self.head - tail_len, head_len);
+                    self.copy_nonoverlapping(0, cap - tail_len, tail_len);
+                    self.head -= tail_len;
+                } else {
+                    // Neither run fits in the gap. Close it, then rotate:
+                    // [A A A . B B B B]  ->  [A A A B B B B .]
+                    //                    ->  [B B B B A A A .]
+                    self.copy(self.head, tail_len, head_len);
+                    slice::from_raw_parts_mut(self.ptr(), self.len).rotate_left(tail_len);
+                    self.head = 0;
+                }
+            }
+        }
+        self.as_mut_slices().0
+    }
+
+    /// Rotates the deque `n` places to the left, so that the element at
+    /// index `n` becomes the front. Moves at most `min(n, len - n)`
+    /// elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > len`.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len());
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_left_inner(n);
+            } else {
+                self.rotate_right_inner(k);
+            }
+        }
+    }
+
+    /// Rotates the deque `n` places to the right, so that the element at
+    /// index 0 ends up at index `n`. Moves at most `min(n, len - n)`
+    /// elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > len`.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len());
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_right_inner(n);
+            } else {
+                self.rotate_left_inner(k);
+            }
+        }
+    }
+
+    /// Moves the first `mid` elements to just past the back.
+    unsafe fn rotate_left_inner(&mut self, mid: usize) {
+        debug_assert!(mid * 2 <= self.len);
+        self.wrap_copy(self.head, self.to_physical_idx(self.len), mid);
+        self.head = self.to_physical_idx(mid);
+    }
+
+    /// Moves the last `k` elements to just before the front.
+    unsafe fn rotate_right_inner(&mut self, k: usize) {
+        debug_assert!(k * 2 <= self.len);
+        self.head = self.wrap_sub(self.head, k);
+        self.wrap_copy(self.to_physical_idx(self.len), self.head, k);
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (a, b) = self.as_slices();
+        Iter::new(a, b)
+    }
+
+    /// Returns a front-to-back iterator of mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (a, b) = self.as_mut_slices();
+        IterMut::new(a, b)
+    }
+
+    /// Returns an iterator over the elements in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end.
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Iter<'_, T> {
+        let (a, b) = self.raw_slices(crate::slice::range(range, self.len));
+        unsafe { Iter::new(&*a, &*b) }
+    }
+
+    /// Returns an iterator of mutable references to the elements in
+    /// `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end.
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> IterMut<'_, T> {
+        let (a, b) = self.raw_slices(crate::slice::range(range, self.len));
+        unsafe { IterMut::new(&mut *a, &mut *b) }
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator.
+    /// Whatever the iterator does not yield is dropped with it, and the
+    /// gap is closed by moving the shorter side.
+    ///
+    /// If the iterator is leaked, the deque is left holding only the
+    /// elements before `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or extends past the end.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A> {
+        let Range { start, end } = crate::slice::range(range, self.len);
+        let orig_len = self.len;
+        self.len = start;
+        Drain {
+            deque: self,
+            drain_start: start,
+            drain_len: end - start,
+            idx: 0,
+            remaining: end - start,
+            orig_len,
+        }
+    }
+}
+
+impl<T, A: Allocator> Drop for VecDeque<T, A> {
+    fn drop(&mut self) {
+        // `buf` frees itself, even if an element destructor unwinds.
+        let (front, back) = self.raw_slices(0..self.len);
+        unsafe {
+            let _back = Dropper(back);
+            ptr::drop_in_place(front);
+        }
+    }
+}
+
+impl<T> Default for VecDeque<T> {
+    fn default() -> Self {
+        VecDeque::new()
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for VecDeque<T, A> {
+    fn clone(&self) -> Self {
+        let mut d = VecDeque::with_capacity_in(self.len, self.allocator().clone());
+        d.extend(self.iter().cloned());
+        d
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for VecDeque<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, U, A1, A2> PartialEq<VecDeque<U, A2>> for VecDeque<T, A1>
+where
+    T: PartialEq<U>,
+    A1: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, other: &VecDeque<U, A2>) -> bool {
+        self.len == other.len && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T, U, A1, A2> PartialEq<Vec<U, A2>> for VecDeque<T, A1>
+where
+    T: PartialEq<U>,
+    A1: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, other: &Vec<U, A2>) -> bool {
+        self.len == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U, A: Allocator, const N: usize> PartialEq<[U; N]> for VecDeque<T, A> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.len == N && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for VecDeque<T, A> {}
+
+/// Hashes element by element: `hash_slice` on the two halves would make
+/// the hash depend on where the ring buffer happens to wrap.
+impl<T: Hash, A: Allocator> Hash for VecDeque<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T, A: Allocator> Index<usize> for VecDeque<T, A> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("Out of bounds access")
+    }
+}
+
+impl<T, A: Allocator> IndexMut<usize> for VecDeque<T, A> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("Out of bounds access")
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for VecDeque<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for VecDeque<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T> FromIterator<T> for VecDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut d = VecDeque::new();
+        d.extend(iter);
+        d
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for VecDeque<T> {
+    fn from(arr: [T; N]) -> Self {
+        let mut d = VecDeque::with_capacity(N);
+        d.extend(arr);
+        d
+    }
+}
+
+impl<T, A: Allocator> From<Vec<T, A>> for VecDeque<T, A> {
+    /// Reuses the vector's buffer; this never allocates.
+    fn from(v: Vec<T, A>) -> Self {
+        let (buf, len) = v.into_raw_vec();
+        VecDeque { head: 0, len, buf }
+    }
+}
+
+impl<T, A: Allocator> From<VecDeque<T, A>> for Vec<T, A> {
+    /// Reuses the deque's buffer; this never allocates, but moves the
+    /// elements to the start of it.
+    fn from(mut d: VecDeque<T, A>) -> Self {
+        d.make_contiguous();
+        let d = ManuallyDrop::new(d);
+        unsafe {
+            let buf = ptr::read(&d.buf);
+            if d.head != 0 {
+                ptr::copy(buf.ptr().add(d.head), buf.ptr(), d.len);
+            }
+            Vec::from_raw_vec(buf, d.len)
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a VecDeque<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut VecDeque<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for VecDeque<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter { inner: self }
+    }
+}
+
+/// An iterator over the elements of a `VecDeque`, created by
+/// [`VecDeque::iter`] and [`VecDeque::range`].
+#[derive(Clone)]
+pub struct Iter<'a, T> {
+    i1: slice::Iter<'a, T>,
+    i2: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn new(a: &'a [T], b: &'a [T]) -> Self {
+        Iter {
+            i1: a.iter(),
+            i2: b.iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.i1.next().or_else(|| self.i2.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.i1.len() + self.i2.len();
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.i2.next_back().or_else(|| self.i1.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Iter")
+            .field(&self.i1.as_slice())
+            .field(&self.i2.as_slice())
+            .finish()
+    }
+}
+
+/// A mutable iterator over the elements of a `VecDeque`, created by
+/// [`VecDeque::iter_mut`] and [`VecDeque::range_mut`].
+pub struct IterMut<'a, T> {
+    i1: slice::IterMut<'a, T>,
+    i2: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> IterMut<'a, T> {
+    fn new(a: &'a mut [T], b: &'a mut [T]) -> Self {
+        IterMut {
+            i1: a.iter_mut(),
+            i2: b.iter_mut(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.i1.next().or_else(|| self.i2.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.i1.len() + self.i2.len();
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.i2.next_back().or_else(|| self.i1.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IterMut")
+            .field(&self.i1.as_slice())
+            .field(&self.i2.as_slice())
+            .finish()
+    }
+}
+
+/// An owning iterator over the elements of a `VecDeque`, created by
+/// [`VecDeque::into_iter`](struct.VecDeque.html#method.into_iter).
+pub struct IntoIter<T, A: Allocator = Global> {
+    inner: VecDeque<T, A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.inner.len, Some(self.inner.len))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.inner).finish()
+    }
+}
+
+/// A draining iterator over a range of a `VecDeque`, created by
+/// [`VecDeque::drain`].
+pub struct Drain<'a, T, A: Allocator = Global> {
+    /// While the drain is alive the deque's `len` is `drain_start`, so only
+    /// the elements before the range are visible through it.
+    deque: &'a mut VecDeque<T, A>,
+    drain_start: usize,
+    drain_len: usize,
+    /// Offset into the range of the next element yielded from the front.
+    idx: usize,
+    remaining: usize,
+    orig_len: usize,
+}
+
+impl<T, A: Allocator> Iterator for Drain<'_, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let wrapped = self.deque.to_physical_idx(self.drain_start + self.idx);
+        self.idx += 1;
+        self.remaining -= 1;
+        unsafe { Some(self.deque.buffer_read(wrapped)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for Drain<'_, T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let wrapped = self
+            .deque
+            .to_physical_idx(self.drain_start + self.idx + self.remaining);
+        unsafe { Some(self.deque.buffer_read(wrapped)) }
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for Drain<'_, T, A> {}
+
+impl<T, A: Allocator> FusedIterator for Drain<'_, T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Drain<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Drain")
+            .field("drain_start", &self.drain_start)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl<T, A: Allocator> Drop for Drain<'_, T, A> {
+    fn drop(&mut self) {
+        /// Closes the gap even if dropping an unyielded element panics.
+        struct DropGuard<'r, 'a, T, A: Allocator>(&'r mut Drain<'a, T, A>);
+
+        impl<T, A: Allocator> Drop for DropGuard<'_, '_, T, A> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                let deque = &mut *drain.deque;
+                let head_len = drain.drain_start;
+                let tail_len = drain.orig_len - head_len - drain.drain_len;
+                unsafe {
+                    if head_len < tail_len {
+                        let new_head = deque.to_physical_idx(drain.drain_len);
+                        deque.wrap_copy(deque.head, new_head, head_len);
+                        deque.head = new_head;
+                    } else {
+                        let src = deque.to_physical_idx(head_len + drain.drain_len);
+                        let dst = deque.to_physical_idx(head_len);
+                        deque.wrap_copy(src, dst, tail_len);
+                    }
+                }
+                deque.len = head_len + tail_len;
+                if deque.len == 0 {
+                    deque.head = 0;
+                }
+            }
+        }
+
+        let guard = DropGuard(self);
+        let start = guard.0.drain_start + guard.0.idx;
+        let (front, back) = guard.0.deque.raw_slices(start..start + guard.0.remaining);
+        guard.0.remaining = 0;
+        unsafe {
+            let _back = Dropper(back);
+            ptr::drop_in_place(front);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VecDeque;
+    use crate::alloc::{Counting, Global};
+    use crate::collections::TryReserveError;
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use crate::vec::Vec;
+    use std::cell::Cell;
+    use std::mem;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    const MAX_CAP: usize = 8;
+
+    /// Builds a deque of exactly `cap` slots holding `0..len`, with the
+    /// front element at physical index `head`.
+    fn ring(cap: usize, head: usize, len: usize) -> VecDeque<usize> {
+        let mut d = VecDeque::with_capacity(cap);
+        for _ in 0..head {
+            d.push_back(0);
+            d.pop_front();
+        }
+        d.extend(0..len);
+        assert_eq!((d.capacity(), d.head), (cap, head));
+        d
+    }
+
+    /// Every `(cap, head, len)` layout with `cap <= MAX_CAP`.
+    fn layouts() -> impl Iterator<Item = (usize, usize, usize)> {
+        (1..=MAX_CAP).flat_map(|cap| {
+            (0..cap).flat_map(move |head| (0..=cap).map(move |len| (cap, head, len)))
+        })
+    }
+
+    fn check(d: &VecDeque<usize>, model: &[usize]) {
+        assert_eq!(d.len(), model.len());
+        let (a, b) = d.as_slices();
+        assert_eq!([a, b].concat(), model);
+        assert!(d.iter().eq(model));
+        assert!(d.iter().rev().eq(model.iter().rev()));
+        for (i, x) in model.iter().enumerate() {
+            assert_eq!(d[i], *x);
+        }
+        assert_eq!(d.get(model.len()), None);
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut d = VecDeque::new();
+        for i in 0..50 {
+            d.push_back(i);
+            d.push_front(-i);
+        }
+        assert_eq!(d.len(), 100);
+        assert_eq!(d.front(), Some(&-49));
+        assert_eq!(d.back(), Some(&49));
+        for i in (0..50).rev() {
+            assert_eq!(d.pop_front(), Some(-i));
+            assert_eq!(d.pop_back(), Some(i));
+        }
+        assert_eq!(d.pop_front(), None);
+        assert_eq!(d.pop_back(), None);
+        assert_eq!(d.back(), None);
+    }
+
+    #[test]
+    fn wrapped_layouts_read_back_in_order() {
+        for (cap, head, len) in layouts() {
+            let d = ring(cap, head, len);
+            let model: std::vec::Vec<usize> = (0..len).collect();
+            check(&d, &model);
+            let (a, b) = d.as_slices();
+            assert_eq!(a.len(), len.min(cap - head));
+            assert_eq!(b.len(), (head + len).saturating_sub(cap));
+        }
+    }
+
+    #[test]
+    fn growth_relocates_wrapped_run() {
+        for (cap, head, len) in layouts().filter(|&(cap, _, len)| cap == len) {
+            let mut model: std::vec::Vec<usize> = (0..len).collect();
+            let mut d = ring(cap, head, len);
+            d.push_back(len);
+            model.push(len);
+            assert!(d.capacity() > cap);
+            check(&d, &model);
+
+            let mut d = ring(cap, head, len);
+            d.push_front(usize::MAX);
+            model.pop();
+            model.insert(0, usize::MAX);
+            check(&d, &model);
+            model.remove(0);
+
+            for extra in 1..=2 * MAX_CAP {
+                let mut d = ring(cap, head, len);
+                d.reserve_exact(extra);
+                assert_eq!(d.capacity(), cap + extra);
+                check(&d, &model);
+            }
+        }
+    }
+
+    #[test]
+    fn make_contiguous_every_layout() {
+        for (cap, head, len) in layouts() {
+            let mut d = ring(cap, head, len);
+            let model: std::vec::Vec<usize> = (0..len).collect();
+            assert_eq!(d.make_contiguous(), &model[..]);
+            assert_eq!(d.capacity(), cap);
+            assert!(d.as_slices().1.is_empty());
+            check(&d, &model);
+        }
+    }
+
+    #[test]
+    fn rotate_every_layout() {
+        for (cap, head, len) in layouts() {
+            for n in 0..=len {
+                let mut model: std::vec::Vec<usize> = (0..len).collect();
+                let mut d = ring(cap, head, len);
+                d.rotate_left(n);
+                model.rotate_left(n);
+                check(&d, &model);
+                d.rotate_right(n);
+                model.rotate_right(n);
+                check(&d, &model);
+                assert_eq!(d.capacity(), cap);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_ignores_where_the_buffer_wraps() {
+        use crate::hash::{BuildHasher, BuildHasherDefault, WyHasher};
+
+        let state = BuildHasherDefault::<WyHasher>::new();
+        for (cap, head, len) in layouts() {
+            let contiguous: VecDeque<usize> = (0..len).collect();
+            assert_eq!(
+                state.hash_one(&ring(cap, head, len)),
+                state.hash_one(&contiguous)
+            );
+        }
+    }
+
+    #[test]
+    fn insert_remove_every_layout() {
+        for (cap, head, len) in layouts() {
+            for i in 0..=len {
+                let mut model: std::vec::Vec<usize> = (0..len).collect();
+                let mut d = ring(cap, head, len);
+                d.insert(i, 100);
+                model.insert(i, 100);
+                check(&d, &model);
+            }
+            for i in 0..len {
+                let mut model: std::vec::Vec<usize> = (0..len).collect();
+                let mut d = ring(cap, head, len);
+                assert_eq!(d.remove(i), Some(model.remove(i)));
+                check(&d, &model);
+            }
+            assert_eq!(ring(cap, head, len).remove(len), None);
+        }
+    }
+
+    #[test]
+    fn drain_every_range() {
+        for (cap, head, len) in layouts() {
+            for start in 0..=len {
+                for end in start..=len {
+                    let mut model: std::vec::Vec<usize> = (0..len).collect();
+                    let mut d = ring(cap, head, len);
+                    let drained: std::vec::Vec<usize> = d.drain(start..end).collect();
+                    assert_eq!(
+                        drained,
+                        model.drain(start..end).collect::<std::vec::Vec<_>>()
+                    );
+                    check(&d, &model);
+
+                    // Partially consumed from both ends, then dropped.
+                    let mut model: std::vec::Vec<usize> = (0..len).collect();
+                    let mut d = ring(cap, head, len);
+                    let mut drain = d.drain(start..end);
+                    let mut theirs = model.drain(start..end);
+                    assert_eq!(drain.next(), theirs.next());
+                    assert_eq!(drain.next_back(), theirs.next_back());
+                    assert_eq!(drain.len(), theirs.len());
+                    drop((drain, theirs));
+                    check(&d, &model);
+                    assert_eq!(d.capacity(), cap);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn range_and_truncate_every_layout() {
+        for (cap, head, len) in layouts() {
+            let model: std::vec::Vec<usize> = (0..len).collect();
+            let mut d = ring(cap, head, len);
+            for start in 0..=len {
+                for end in start..=len {
+                    assert!(d.range(start..end).eq(&model[start..end]));
+                    assert!(d.range(start..end).rev().eq(model[start..end].iter().rev()));
+                }
+            }
+            for x in d.range_mut(len / 2..) {
+                *x += 1;
+            }
+            let bumped: std::vec::Vec<usize> =
+                (0..len).map(|i| i + (i >= len / 2) as usize).collect();
+            check(&d, &bumped);
+            for n in 0..=len {
+                let mut d = ring(cap, head, len);
+                d.truncate(n);
+                check(&d, &model[..n]);
+            }
+        }
+    }
+
+    #[test]
+    fn drops_wrapped_elements_exactly_once() {
+        let counter = Rc::new(Cell::new(0));
+        let mut d = VecDeque::with_capacity(8);
+        for _ in 0..5 {
+            d.push_back(DropCounter::new(&counter, false));
+        }
+        drop(d.drain(..4));
+        for _ in 0..6 {
+            d.push_back(DropCounter::new(&counter, false));
+        }
+        assert_eq!(counter.get(), 4);
+        assert!(!d.as_slices().1.is_empty());
+        d.truncate(3);
+        assert_eq!(counter.get(), 8);
+        drop(d);
+        assert_eq!(counter.get(), 11);
+    }
+
+    #[test]
+    fn panic_in_element_drop_still_drops_both_halves() {
+        let counter = Rc::new(Cell::new(0));
+        let mut d = VecDeque::with_capacity(8);
+        for _ in 0..6 {
+            d.push_back(DropCounter::new(&counter, false));
+        }
+        for _ in 0..6 {
+            d.pop_front();
+        }
+        assert_eq!(counter.get(), 6);
+        for i in 0..8 {
+            d.push_back(DropCounter::new(&counter, i == 0));
+        }
+        assert!(!d.as_slices().1.is_empty());
+        let r = panic::catch_unwind(AssertUnwindSafe(move || drop(d)));
+        assert!(r.is_err());
+        assert_eq!(counter.get(), 14);
+    }
+
+    #[test]
+    fn panic_while_dropping_drain_restores_deque() {
+        let counter = Rc::new(Cell::new(0));
+        let mut d = VecDeque::new();
+        for i in 0..10 {
+            d.push_back(DropCounter::new(&counter, i == 4));
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut drain = d.drain(3..7);
+            drop(drain.next());
+        }));
+        assert!(r.is_err());
+        assert_eq!(counter.get(), 4);
+        assert_eq!(d.len(), 6);
+        drop(d);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[test]
+    fn leaked_drain_keeps_prefix() {
+        let mut d: VecDeque<String> = (0..6).map(|i| i.to_string()).collect();
+        mem::forget(d.drain(2..4));
+        assert_eq!(d, ["0", "1"]);
+        d.push_back("x".to_string());
+        assert_eq!(d, ["0", "1", "x"]);
+    }
+
+    #[test]
+    fn vec_round_trip_reuses_buffer() {
+        let counting = Counting::new(Global);
+        let mut v = Vec::with_capacity_in(8, &counting);
+        v.extend(0..6);
+        let before = counting.snapshot();
+        let mut d = VecDeque::from(v);
+        d.rotate_left(4);
+        d.push_front(9);
+        assert_eq!(d, [9, 4, 5, 0, 1, 2, 3]);
+        let v: Vec<i32, _> = d.into();
+        assert_eq!(v, [9, 4, 5, 0, 1, 2, 3]);
+        assert_eq!(v.capacity(), 8);
+        let diff = counting.snapshot().diff(&before);
+        assert_eq!(diff.allocations + diff.reallocations, 0);
+    }
+
+    #[test]
+    fn shrink_to_fit_wrapped() {
+        let mut d = ring(8, 6, 5);
+        d.pop_back();
+        d.shrink_to_fit();
+        assert_eq!(d.capacity(), 4);
+        check(&d, &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        let mut d = VecDeque::new();
+        assert_eq!(d.capacity(), usize::MAX);
+        for _ in 0..100 {
+            d.push_back(());
+            d.push_front(());
+        }
+        d.insert(7, ());
+        d.rotate_left(50);
+        assert_eq!(d.remove(3), Some(()));
+        assert_eq!(d.drain(10..20).count(), 10);
+        assert_eq!(d.len(), 190);
+        assert_eq!(d.into_iter().count(), 190);
+    }
+
+    #[test]
+    fn try_push_leaves_deque_unchanged_on_failure() {
+        let budget = Budget::new(16);
+        let mut d: VecDeque<u32, _> = VecDeque::with_capacity_in(4, &budget);
+        for i in 0..4 {
+            d.try_push_front(i).unwrap();
+        }
+        assert!(matches!(
+            d.try_push_back(9),
+            Err(TryReserveError::AllocError { .. })
+        ));
+        assert!(d.try_push_front(9).is_err());
+        assert!(d.try_insert(2, 9).is_err());
+        assert!(d.try_reserve(1).is_err());
+        assert_eq!(d, [3, 2, 1, 0]);
+        assert_eq!(
+            d.try_reserve_exact(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        budget.set(usize::MAX);
+        d.try_push_back(4).unwrap();
+        assert_eq!(d, [3, 2, 1, 0, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn insert_out_of_bounds() {
+        let mut d = VecDeque::from([1, 2]);
+        d.insert(3, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds access")]
+    fn index_out_of_bounds() {
+        let d = VecDeque::from([1, 2]);
+        let _ = d[2];
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 3 out of range for slice of length 2")]
+    fn drain_out_of_bounds() {
+        let mut d = VecDeque::from([1, 2]);
+        d.drain(1..3);
+    }
+
+    #[test]
+    fn differential_against_std() {
+        let mut rng = XorShift::new(0xdec0de);
+        for _ in 0..100 {
+            let mut ours: VecDeque<u32> = VecDeque::new();
+            let mut theirs = std::collections::VecDeque::new();
+            for _ in 0..300 {
+                let len = theirs.len();
+                match rng.below(10) {
+                    0 | 1 => {
+                        let x = rng.next() as u32;
+                        ours.push_back(x);
+                        theirs.push_back(x);
+                    }
+                    2 | 3 => {
+                        let x = rng.next() as u32;
+                        ours.push_front(x);
+                        theirs.push_front(x);
+                    }
+                    4 => assert_eq!(ours.pop_front(), theirs.pop_front()),
+                    5 => assert_eq!(ours.pop_back(), theirs.pop_back()),
+                    6 => {
+                        let i = rng.below(len + 1);
+                        ours.insert(i, i as u32);
+                        theirs.insert(i, i as u32);
+                    }
+                    7 => {
+                        let i = rng.below(len + 1);
+                        assert_eq!(ours.remove(i), theirs.remove(i));
+                    }
+                    8 => {
+                        let n = rng.below(len + 1);
+                        ours.rotate_left(n);
+                        theirs.rotate_left(n);
+                    }
+                    _ => {
+                        let a = rng.below(len + 1);
+                        let b = rng.below(len + 1);
+                        let (a, b) = (a.min(b), a.max(b));
+                        assert!(ours.drain(a..b).eq(theirs.drain(a..b)));
+                    }
+                }
+                assert!(ours.iter().eq(theirs.iter()));
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! The hash used inside rustc, from the Firefox codebase.
+
+use super::Hasher;
+use std::convert::TryInto;
+
+/// A very fast hash for small keys such as integers and short strings.
+///
+/// Each word is folded into the state with a rotate, an xor and a multiply.
+/// The output is far from uniform for adversarial input, so use it only
+/// for keys an attacker cannot choose.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl FxHasher {
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        while bytes.len() >= 8 {
+            self.add_to_hash(u64::from_ne_bytes(bytes[..8].try_into().unwrap()));
+            bytes = &bytes[8..];
+        }
+        if bytes.len() >= 4 {
+            self.add_to_hash(u32::from_ne_bytes(bytes[..4].try_into().unwrap()) as u64);
+            bytes = &bytes[4..];
+        }
+        if bytes.len() >= 2 {
+            self.add_to_hash(u16::from_ne_bytes(bytes[..2].try_into().unwrap()) as u64);
+            bytes = &bytes[2..];
+        }
+        if let Some(&byte) = bytes.first() {
+            self.add_to_hash(byte as u64);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+}
+
+/// Lets the hasher drive types that implement `std::hash::Hash`.
+impl std::hash::Hasher for FxHasher {
+    fn finish(&self) -> u64 {
+        Hasher::finish(self)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        Hasher::write(self, bytes);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        Hasher::write_u8(self, i);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        Hasher::write_u16(self, i);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        Hasher::write_u32(self, i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        Hasher::write_u64(self, i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        Hasher::write_usize(self, i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FxHasher;
+    use crate::hash::Hasher;
+
+    fn fx(f: impl FnOnce(&mut FxHasher)) -> u64 {
+        let mut hasher = FxHasher::default();
+        f(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(fx(|_| {}), 0);
+        assert_eq!(fx(|h| h.write_u64(1)), 0x517c_c1b7_2722_0a95);
+        assert_eq!(
+            fx(|h| {
+                h.write_u64(1);
+                h.write_u64(2)
+            }),
+            (0x517c_c1b7_2722_0a95_u64.rotate_left(5) ^ 2).wrapping_mul(0x517c_c1b7_2722_0a95)
+        );
+    }
+
+    #[test]
+    fn integer_and_byte_writes_agree() {
+        assert_eq!(
+            fx(|h| h.write_u64(42)),
+            fx(|h| h.write(&42u64.to_ne_bytes()))
+        );
+        assert_eq!(
+            fx(|h| h.write_u32(42)),
+            fx(|h| h.write(&42u32.to_ne_bytes()))
+        );
+        assert_eq!(
+            fx(|h| h.write_u16(42)),
+            fx(|h| h.write(&42u16.to_ne_bytes()))
+        );
+        assert_eq!(fx(|h| h.write_u8(42)), fx(|h| h.write(&[42])));
+        // Fifteen bytes fold as one word, then four, two and one bytes.
+        let bytes: Vec<u8> = (1..16).collect();
+        let mut expected = FxHasher::default();
+        expected.write_u64(u64::from_ne_bytes([1, 2, 3, 4, 5, 6, 7, 8]));
+        expected.write_u32(u32::from_ne_bytes([9, 10, 11, 12]));
+        expected.write_u16(u16::from_ne_bytes([13, 14]));
+        expected.write_u8(15);
+        assert_eq!(fx(|h| h.write(&bytes)), expected.finish());
+    }
+}
@@ -0,0 +1,431 @@
+//! [`Hash`] for primitive and standard library types.
+//!
+//! Every impl makes the same calls into the hasher that the type's
+//! `std::hash::Hash` impl makes, so that keys hash identically under either
+//! trait and a `#[derive(Hash)]` type can be ported with [`impl_hash!`]
+//! without changing its hashes.
+//!
+//! [`impl_hash!`]: crate::impl_hash
+
+use super::{Hash, Hasher};
+use std::cmp::Ordering;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::{mem, slice};
+
+macro_rules! impl_int {
+    ($($ty:ty => $write:ident),* $(,)?) => {$(
+        impl Hash for $ty {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                state.$write(*self);
+            }
+
+            fn hash_slice<H: Hasher>(data: &[$ty], state: &mut H) {
+                // Integers have no padding, so the slice's bytes are exactly
+                // the native-endian bytes of its elements.
+                let len = mem::size_of_val(data);
+                let bytes = unsafe { slice::from_raw_parts(data.as_ptr().cast::<u8>(), len) };
+                state.write(bytes);
+            }
+        }
+    )*};
+}
+
+impl_int! {
+    u8 => write_u8,
+    u16 => write_u16,
+    u32 => write_u32,
+    u64 => write_u64,
+    u128 => write_u128,
+    usize => write_usize,
+    i8 => write_i8,
+    i16 => write_i16,
+    i32 => write_i32,
+    i64 => write_i64,
+    i128 => write_i128,
+    isize => write_isize,
+}
+
+impl Hash for bool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(*self as u8);
+    }
+}
+
+impl Hash for char {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(*self as u32);
+    }
+}
+
+impl Hash for () {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl Hash for Ordering {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_i8(*self as i8);
+    }
+}
+
+/// The trailing `0xff` cannot occur in UTF-8, so no string's hash input is
+/// a prefix of another's.
+impl Hash for str {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.as_bytes());
+        state.write_u8(0xff);
+    }
+}
+
+impl Hash for String {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+/// Writes the length first, so that the elements of nested slices cannot
+/// run into each other.
+impl<T: Hash> Hash for [T] {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        T::hash_slice(self, state);
+    }
+}
+
+impl<T: Hash, const N: usize> Hash for [T; N] {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl<T: Hash> Hash for std::vec::Vec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for &T {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for &mut T {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for Box<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for Rc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for Arc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+/// Hashes the address, not the pointee.
+impl<T> Hash for *const T {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(*self as usize);
+    }
+}
+
+/// Hashes the address, not the pointee.
+impl<T> Hash for *mut T {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(*self as usize);
+    }
+}
+
+/// Writes the discriminant and then the payload, like a derived impl.
+impl<T: Hash> Hash for Option<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            None => state.write_isize(0),
+            Some(value) => {
+                state.write_isize(1);
+                value.hash(state);
+            }
+        }
+    }
+}
+
+/// Writes the discriminant and then the payload, like a derived impl.
+impl<T: Hash, E: Hash> Hash for Result<T, E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Ok(value) => {
+                state.write_isize(0);
+                value.hash(state);
+            }
+            Err(error) => {
+                state.write_isize(1);
+                error.hash(state);
+            }
+        }
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($name:ident)+))+) => {$(
+        #[allow(non_snake_case)]
+        impl<$($name: Hash),+> Hash for ($($name,)+) {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                let ($($name,)+) = self;
+                $($name.hash(state);)+
+            }
+        }
+    )+};
+}
+
+impl_tuple! {
+    (A)
+    (A B)
+    (A B C)
+    (A B C D)
+    (A B C D E)
+    (A B C D E F)
+    (A B C D E F G)
+    (A B C D E F G I)
+    (A B C D E F G I J)
+    (A B C D E F G I J K)
+    (A B C D E F G I J K L)
+    (A B C D E F G I J K L M)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher, SipHasher13};
+
+    /// Logs every call made into it, so that call sequences can be compared
+    /// rather than just their hashes.
+    #[derive(Default)]
+    struct Recorder(std::vec::Vec<String>);
+
+    impl Hasher for Recorder {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.push(format!("bytes {:?}", bytes));
+        }
+
+        fn write_u8(&mut self, i: u8) {
+            self.0.push(format!("u8 {}", i));
+        }
+
+        fn write_u16(&mut self, i: u16) {
+            self.0.push(format!("u16 {}", i));
+        }
+
+        fn write_u32(&mut self, i: u32) {
+            self.0.push(format!("u32 {}", i));
+        }
+
+        fn write_u64(&mut self, i: u64) {
+            self.0.push(format!("u64 {}", i));
+        }
+
+        fn write_u128(&mut self, i: u128) {
+            self.0.push(format!("u128 {}", i));
+        }
+
+        fn write_usize(&mut self, i: usize) {
+            self.0.push(format!("usize {}", i));
+        }
+
+        fn write_i8(&mut self, i: i8) {
+            self.0.push(format!("i8 {}", i));
+        }
+
+        fn write_i16(&mut self, i: i16) {
+            self.0.push(format!("i16 {}", i));
+        }
+
+        fn write_i32(&mut self, i: i32) {
+            self.0.push(format!("i32 {}", i));
+        }
+
+        fn write_i64(&mut self, i: i64) {
+            self.0.push(format!("i64 {}", i));
+        }
+
+        fn write_i128(&mut self, i: i128) {
+            self.0.push(format!("i128 {}", i));
+        }
+
+        fn write_isize(&mut self, i: isize) {
+            self.0.push(format!("isize {}", i));
+        }
+    }
+
+    impl std::hash::Hasher for Recorder {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            Hasher::write(self, bytes);
+        }
+
+        fn write_u8(&mut self, i: u8) {
+            Hasher::write_u8(self, i);
+        }
+
+        fn write_u16(&mut self, i: u16) {
+            Hasher::write_u16(self, i);
+        }
+
+        fn write_u32(&mut self, i: u32) {
+            Hasher::write_u32(self, i);
+        }
+
+        fn write_u64(&mut self, i: u64) {
+            Hasher::write_u64(self, i);
+        }
+
+        fn write_u128(&mut self, i: u128) {
+            Hasher::write_u128(self, i);
+        }
+
+        fn write_usize(&mut self, i: usize) {
+            Hasher::write_usize(self, i);
+        }
+
+        fn write_i8(&mut self, i: i8) {
+            Hasher::write_i8(self, i);
+        }
+
+        fn write_i16(&mut self, i: i16) {
+            Hasher::write_i16(self, i);
+        }
+
+        fn write_i32(&mut self, i: i32) {
+            Hasher::write_i32(self, i);
+        }
+
+        fn write_i64(&mut self, i: i64) {
+            Hasher::write_i64(self, i);
+        }
+
+        fn write_i128(&mut self, i: i128) {
+            Hasher::write_i128(self, i);
+        }
+
+        fn write_isize(&mut self, i: isize) {
+            Hasher::write_isize(self, i);
+        }
+    }
+
+    fn ours<T: Hash + ?Sized>(value: &T) -> std::vec::Vec<String> {
+        let mut recorder = Recorder::default();
+        value.hash(&mut recorder);
+        recorder.0
+    }
+
+    fn std<T: std::hash::Hash + ?Sized>(value: &T) -> std::vec::Vec<String> {
+        let mut recorder = Recorder::default();
+        value.hash(&mut recorder);
+        recorder.0
+    }
+
+    macro_rules! assert_matches_std {
+        ($($value:expr),* $(,)?) => {$(
+            assert_eq!(ours(&$value), std(&$value), "{}", stringify!($value));
+        )*};
+    }
+
+    #[test]
+    fn calls_match_std() {
+        assert_matches_std!(
+            7u8,
+            -7i16,
+            0xdead_beefu32,
+            u64::MAX,
+            i128::MIN,
+            usize::MAX,
+            -1isize,
+            true,
+            'é',
+            (),
+            std::cmp::Ordering::Less,
+            "hello",
+            *"",
+            String::from("owned"),
+            [1u32, 2, 3],
+            [1u32, 2, 3][..],
+            ["a", "bc"],
+            [[1u8], [2u8]],
+            vec![vec![1i64], vec![], vec![2, 3]],
+            (1u8, "two", 3.0f64.to_bits(), ('4', false)),
+            (1u8, 2u16, 3u32, 4u64, 5u128, 6usize, 7i8, 8i16, 9i32, 10i64, 11i128, 12isize),
+            Some(3u8),
+            None::<u8>,
+            Ok::<&str, u8>("ok"),
+            Err::<&str, u8>(9),
+            Box::new([Some(1u16), None]),
+            std::rc::Rc::new("rc"),
+            std::sync::Arc::new(5u8),
+            &mut 6u8,
+        );
+    }
+
+    #[test]
+    fn impl_hash_matches_derive() {
+        #[derive(std::hash::Hash)]
+        struct Named {
+            id: u32,
+            name: &'static str,
+            tags: std::vec::Vec<Option<char>>,
+        }
+        crate::impl_hash!(Named { id, name, tags });
+
+        #[derive(std::hash::Hash)]
+        struct Pair<A, B>(A, B);
+        crate::impl_hash!(impl<A, B> Pair<A, B> { 0, 1 });
+
+        #[derive(std::hash::Hash)]
+        struct Unit;
+        crate::impl_hash!(Unit {});
+
+        let named = Named {
+            id: 4,
+            name: "four",
+            tags: vec![Some('a'), None],
+        };
+        assert_matches_std!(named, Pair(1u8, "x"), Pair(Pair((), -1i8), [true]), Unit);
+    }
+
+    #[test]
+    fn prefix_free() {
+        let state = BuildHasherDefault::<SipHasher13>::new();
+        let pairs = [
+            (state.hash_one(&("ab", "c")), state.hash_one(&("a", "bc"))),
+            (state.hash_one(&("", "a")), state.hash_one(&("a", ""))),
+            (
+                state.hash_one(&[&[1u8][..], &[]]),
+                state.hash_one(&[&[][..], &[1u8]]),
+            ),
+            (
+                state.hash_one(&(vec![1u8, 2], vec![3u8])),
+                state.hash_one(&(vec![1u8], vec![2u8, 3])),
+            ),
+            (state.hash_one(&Some(0u8)), state.hash_one(&None::<u8>)),
+        ];
+        for (left, right) in pairs.iter() {
+            assert_ne!(left, right);
+        }
+    }
+}
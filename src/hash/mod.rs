@@ -0,0 +1,301 @@
+//! Hashing.
+//!
+//! Defines the crate's own [`Hash`], [`Hasher`] and [`BuildHasher`] traits,
+//! which the maps in [`collections`](crate::collections) hash through, and
+//! the hashers that implement them. [`RandomState`] builds randomly keyed
+//! SipHash-1-3 hashers and is the maps' default, which makes them resistant
+//! to collision attacks. Maps whose keys are not under an attacker's
+//! control can opt into [`FxHasher`] or [`WyHasher`] through
+//! [`BuildHasherDefault`] instead.
+//!
+//! [`Hash`] is implemented for the primitive and standard library types
+//! and makes the same calls into the hasher as `std::hash::Hash` does, so
+//! a value hashes the same under either trait. User types implement it
+//! with [`impl_hash!`](crate::impl_hash), which hashes fields in order like
+//! `#[derive(Hash)]`.
+//!
+//! The hashers and hasher builders also implement their `std::hash`
+//! counterparts, so they can be used with std's collections as well.
+
+mod fx;
+mod impls;
+mod random;
+mod sip;
+mod wy;
+
+pub use self::fx::FxHasher;
+pub use self::random::{DefaultHasher, RandomState};
+pub use self::sip::{SipHasher13, SipHasher24};
+pub use self::wy::WyHasher;
+
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A type that can be fed to a [`Hasher`].
+///
+/// Values that compare equal must hash equally. A type's hash must also be
+/// prefix-free: no value may write a sequence of calls that is a prefix of
+/// another value's, or else `("ab", "c")` and `("a", "bc")` would collide.
+/// That is why slices write their length first and `str` writes a
+/// terminating `0xff`, a byte that never occurs in UTF-8.
+pub trait Hash {
+    /// Feeds `self` into `state`.
+    fn hash<H: Hasher>(&self, state: &mut H);
+
+    /// Feeds every element of `data` into `state`, without a length.
+    /// Integer types override this to write the whole slice at once.
+    fn hash_slice<H: Hasher>(data: &[Self], state: &mut H)
+    where
+        Self: Sized,
+    {
+        for piece in data {
+            piece.hash(state);
+        }
+    }
+}
+
+/// Creates [`Hasher`]s that all hash the same way, like the hash state of
+/// a map.
+pub trait BuildHasher {
+    type Hasher: Hasher;
+
+    /// Returns a new hasher.
+    fn build_hasher(&self) -> Self::Hasher;
+
+    /// Hashes `x` with a new hasher.
+    fn hash_one<T: Hash + ?Sized>(&self, x: &T) -> u64 {
+        let mut hasher = self.build_hasher();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Implements [`Hash`] for a struct by hashing the listed fields in order,
+/// which is what `#[derive(Hash)]` does.
+///
+/// List every field compared by the type's `PartialEq`, by name or, for
+/// tuple structs, by index. A leading `impl<...>` declares type parameters,
+/// each of which is required to implement `Hash`.
+///
+/// ```
+/// use mystdrs::hash::{BuildHasher, BuildHasherDefault, FxHasher};
+///
+/// #[derive(PartialEq, Eq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+/// mystdrs::impl_hash!(Point { x, y });
+///
+/// #[derive(PartialEq, Eq)]
+/// struct Tagged<T>(T, &'static str);
+/// mystdrs::impl_hash!(impl<T> Tagged<T> { 0, 1 });
+///
+/// let state = BuildHasherDefault::<FxHasher>::default();
+/// assert_eq!(
+///     state.hash_one(&Point { x: 1, y: 2 }),
+///     state.hash_one(&(1i32, 2i32)),
+/// );
+/// assert_ne!(state.hash_one(&Tagged(1u8, "a")), state.hash_one(&Tagged(1u8, "b")));
+/// ```
+#[macro_export]
+macro_rules! impl_hash {
+    (impl<$($param:ident),+> $ty:ty { $($field:tt),* $(,)? }) => {
+        impl<$($param: $crate::hash::Hash),+> $crate::hash::Hash for $ty {
+            #[allow(unused_variables)]
+            fn hash<__H: $crate::hash::Hasher>(&self, state: &mut __H) {
+                $($crate::hash::Hash::hash(&self.$field, state);)*
+            }
+        }
+    };
+    ($ty:ty { $($field:tt),* $(,)? }) => {
+        impl $crate::hash::Hash for $ty {
+            #[allow(unused_variables)]
+            fn hash<__H: $crate::hash::Hasher>(&self, state: &mut __H) {
+                $($crate::hash::Hash::hash(&self.$field, state);)*
+            }
+        }
+    };
+}
+
+/// A streaming hash function: bytes go in through the `write` methods and
+/// [`finish`](Hasher::finish) returns the hash of everything written so far.
+///
+/// The provided integer methods write native-endian bytes. Hashers may
+/// override them with faster paths, so only identical sequences of calls
+/// are guaranteed to produce identical hashes.
+pub trait Hasher {
+    /// Returns the hash of the bytes written so far. Does not reset the
+    /// hasher.
+    fn finish(&self) -> u64;
+
+    /// Writes `bytes` into the hasher.
+    fn write(&mut self, bytes: &[u8]);
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_ne_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_ne_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_ne_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_ne_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write(&i.to_ne_bytes());
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i as usize);
+    }
+}
+
+impl<H: Hasher + ?Sized> Hasher for &mut H {
+    fn finish(&self) -> u64 {
+        (**self).finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        (**self).write(bytes);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        (**self).write_u8(i);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        (**self).write_u16(i);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        (**self).write_u32(i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        (**self).write_u64(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        (**self).write_u128(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        (**self).write_usize(i);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        (**self).write_i8(i);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        (**self).write_i16(i);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        (**self).write_i32(i);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        (**self).write_i64(i);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        (**self).write_i128(i);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        (**self).write_isize(i);
+    }
+}
+
+/// Builds hashers of type `H` with `H::default()`, for hashers that take no
+/// key.
+///
+/// ```
+/// use mystdrs::collections::HashMap;
+/// use mystdrs::hash::{BuildHasherDefault, FxHasher};
+///
+/// let mut map: HashMap<u32, &str, BuildHasherDefault<FxHasher>> = HashMap::default();
+/// map.insert(7, "seven");
+/// assert_eq!(map[&7], "seven");
+/// ```
+pub struct BuildHasherDefault<H>(PhantomData<fn() -> H>);
+
+impl<H> BuildHasherDefault<H> {
+    pub const fn new() -> Self {
+        BuildHasherDefault(PhantomData)
+    }
+}
+
+impl<H> Clone for BuildHasherDefault<H> {
+    fn clone(&self) -> Self {
+        BuildHasherDefault::new()
+    }
+}
+
+impl<H> Default for BuildHasherDefault<H> {
+    fn default() -> Self {
+        BuildHasherDefault::new()
+    }
+}
+
+impl<H> PartialEq for BuildHasherDefault<H> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<H> Eq for BuildHasherDefault<H> {}
+
+impl<H> fmt::Debug for BuildHasherDefault<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BuildHasherDefault")
+    }
+}
+
+impl<H: Default + Hasher> BuildHasher for BuildHasherDefault<H> {
+    type Hasher = H;
+
+    fn build_hasher(&self) -> H {
+        H::default()
+    }
+}
+
+impl<H: Default + std::hash::Hasher> std::hash::BuildHasher for BuildHasherDefault<H> {
+    type Hasher = H;
+
+    fn build_hasher(&self) -> H {
+        H::default()
+    }
+}
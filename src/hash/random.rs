@@ -0,0 +1,190 @@
+//! Randomly keyed hashing for the maps.
+
+use super::{BuildHasher, Hasher, SipHasher13};
+use std::cell::Cell;
+use std::convert::TryInto;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+
+/// Builds [`DefaultHasher`]s keyed with random keys.
+///
+/// Each thread draws one random key from the operating system the first
+/// time it is needed; every `RandomState` created afterwards on that thread
+/// uses the key plus a counter. Maps with different states therefore hash
+/// differently, which is enough to keep an attacker who cannot see the
+/// hashes from forcing collisions.
+///
+/// ```
+/// use mystdrs::hash::{BuildHasher, Hasher, RandomState};
+///
+/// let state = RandomState::new();
+/// let mut a = state.build_hasher();
+/// let mut b = state.build_hasher();
+/// a.write(b"key");
+/// b.write(b"key");
+/// assert_eq!(a.finish(), b.finish());
+/// ```
+#[derive(Clone)]
+pub struct RandomState {
+    k0: u64,
+    k1: u64,
+}
+
+impl RandomState {
+    /// Creates a state with fresh keys.
+    pub fn new() -> RandomState {
+        thread_local!(static KEYS: Cell<Option<(u64, u64)>> = const { Cell::new(None) });
+
+        KEYS.with(|keys| {
+            let (k0, k1) = keys.get().unwrap_or_else(random_keys);
+            keys.set(Some((k0.wrapping_add(1), k1)));
+            RandomState { k0, k1 }
+        })
+    }
+}
+
+impl Default for RandomState {
+    fn default() -> RandomState {
+        RandomState::new()
+    }
+}
+
+impl fmt::Debug for RandomState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomState").finish_non_exhaustive()
+    }
+}
+
+impl BuildHasher for RandomState {
+    type Hasher = DefaultHasher;
+
+    /// Returns a hasher keyed with this state's keys.
+    fn build_hasher(&self) -> DefaultHasher {
+        DefaultHasher(SipHasher13::new_with_keys(self.k0, self.k1))
+    }
+}
+
+impl std::hash::BuildHasher for RandomState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        BuildHasher::build_hasher(self)
+    }
+}
+
+/// The hasher built by [`RandomState`]. The algorithm is SipHash-1-3 today
+/// but is not guaranteed to stay so; hashes must not be persisted.
+#[derive(Clone, Debug, Default)]
+pub struct DefaultHasher(SipHasher13);
+
+impl DefaultHasher {
+    /// Creates a hasher with fixed keys, for hashes that only need to be
+    /// stable within one build.
+    pub fn new() -> DefaultHasher {
+        DefaultHasher(SipHasher13::new_with_keys(0, 0))
+    }
+}
+
+impl Hasher for DefaultHasher {
+    fn finish(&self) -> u64 {
+        Hasher::finish(&self.0)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        Hasher::write(&mut self.0, bytes);
+    }
+}
+
+impl std::hash::Hasher for DefaultHasher {
+    fn finish(&self) -> u64 {
+        Hasher::finish(&self.0)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        Hasher::write(&mut self.0, bytes);
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn fill_from_syscall(buf: &mut [u8]) -> bool {
+    extern "C" {
+        fn getrandom(buf: *mut u8, buflen: usize, flags: u32) -> isize;
+    }
+
+    let mut filled = 0;
+    while filled < buf.len() {
+        let rest = &mut buf[filled..];
+        let n = unsafe { getrandom(rest.as_mut_ptr(), rest.len(), 0) };
+        if n <= 0 {
+            // Interrupted, or not supported by the kernel: let the caller
+            // fall back to the device file.
+            return false;
+        }
+        filled += n as usize;
+    }
+    true
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn fill_from_syscall(_: &mut [u8]) -> bool {
+    false
+}
+
+fn fill_from_device(buf: &mut [u8]) -> bool {
+    File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(buf))
+        .is_ok()
+}
+
+/// Returns 128 random bits from the operating system.
+///
+/// # Panics
+///
+/// Panics if neither the `getrandom` system call nor `/dev/urandom` is
+/// available. A predictable key would silently give up the protection
+/// this type exists for.
+fn random_keys() -> (u64, u64) {
+    let mut buf = [0; 16];
+    if !fill_from_syscall(&mut buf) && !fill_from_device(&mut buf) {
+        panic!("failed to obtain random keys for RandomState");
+    }
+    let (k0, k1) = buf.split_at(8);
+    (
+        u64::from_ne_bytes(k0.try_into().unwrap()),
+        u64::from_ne_bytes(k1.try_into().unwrap()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DefaultHasher, RandomState};
+    use crate::hash::{BuildHasher, Hasher};
+
+    fn hash_with(state: &RandomState, bytes: &[u8]) -> u64 {
+        let mut hasher = state.build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn states_are_keyed_differently() {
+        let a = RandomState::new();
+        let b = RandomState::new();
+        assert_eq!(hash_with(&a, b"x"), hash_with(&a.clone(), b"x"));
+        assert_ne!(hash_with(&a, b"x"), hash_with(&b, b"x"));
+        // Keys come from the OS, not a constant.
+        let mut fixed = DefaultHasher::new();
+        fixed.write(b"x");
+        assert_ne!(hash_with(&a, b"x"), fixed.finish());
+    }
+
+    #[test]
+    fn threads_draw_their_own_keys() {
+        let here = hash_with(&RandomState::new(), b"x");
+        let there = std::thread::spawn(|| hash_with(&RandomState::new(), b"x"))
+            .join()
+            .unwrap();
+        assert_ne!(here, there);
+    }
+}
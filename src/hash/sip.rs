@@ -0,0 +1,383 @@
+//! SipHash, the keyed hash behind [`RandomState`](super::RandomState).
+//!
+//! SipHash-c-d runs `c` compression rounds per 8-byte block and `d`
+//! finalization rounds. SipHash-2-4 is the variant from the original paper;
+//! SipHash-1-3 trades some of its security margin for speed and is what
+//! the maps use by default, as in std.
+
+use super::Hasher;
+use std::cmp;
+use std::convert::TryInto;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// SipHash-1-3 with a 128-bit key.
+#[derive(Debug, Clone, Default)]
+pub struct SipHasher13 {
+    hasher: Sip<Sip13Rounds>,
+}
+
+/// SipHash-2-4 with a 128-bit key.
+#[derive(Debug, Clone, Default)]
+pub struct SipHasher24 {
+    hasher: Sip<Sip24Rounds>,
+}
+
+impl SipHasher13 {
+    /// Creates a hasher keyed with zeros.
+    pub fn new() -> Self {
+        Self::new_with_keys(0, 0)
+    }
+
+    /// Creates a hasher keyed with `k0` and `k1`, the two little-endian
+    /// halves of the 128-bit key.
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        SipHasher13 {
+            hasher: Sip::new_with_keys(k0, k1),
+        }
+    }
+}
+
+impl SipHasher24 {
+    /// Creates a hasher keyed with zeros.
+    pub fn new() -> Self {
+        Self::new_with_keys(0, 0)
+    }
+
+    /// Creates a hasher keyed with `k0` and `k1`, the two little-endian
+    /// halves of the 128-bit key.
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        SipHasher24 {
+            hasher: Sip::new_with_keys(k0, k1),
+        }
+    }
+}
+
+impl Hasher for SipHasher13 {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+    }
+}
+
+/// Lets the hashers drive types that implement `std::hash::Hash`.
+impl std::hash::Hasher for SipHasher13 {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+    }
+}
+
+/// Lets the hashers drive types that implement `std::hash::Hash`.
+impl std::hash::Hasher for SipHasher24 {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+    }
+}
+
+/// The round counts of a SipHash variant.
+trait Rounds {
+    fn c_rounds(state: &mut State);
+    fn d_rounds(state: &mut State);
+}
+
+#[derive(Debug, Clone, Default)]
+struct Sip13Rounds;
+
+impl Rounds for Sip13Rounds {
+    fn c_rounds(state: &mut State) {
+        state.round();
+    }
+
+    fn d_rounds(state: &mut State) {
+        state.round();
+        state.round();
+        state.round();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Sip24Rounds;
+
+impl Rounds for Sip24Rounds {
+    fn c_rounds(state: &mut State) {
+        state.round();
+        state.round();
+    }
+
+    fn d_rounds(state: &mut State) {
+        state.round();
+        state.round();
+        state.round();
+        state.round();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+}
+
+impl State {
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13);
+        self.v1 ^= self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16);
+        self.v3 ^= self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21);
+        self.v3 ^= self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17);
+        self.v1 ^= self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+}
+
+/// Reads up to eight bytes as a little-endian integer.
+fn load_le(bytes: &[u8]) -> u64 {
+    debug_assert!(bytes.len() <= 8);
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// A streaming SipHash state. Input is consumed in 8-byte blocks; up to
+/// seven trailing bytes wait in `tail` for the next write or for `finish`.
+struct Sip<R: Rounds> {
+    state: State,
+    /// Total number of bytes written; only its low byte is hashed.
+    length: usize,
+    tail: u64,
+    ntail: usize,
+    marker: PhantomData<R>,
+}
+
+impl<R: Rounds> Sip<R> {
+    fn new_with_keys(k0: u64, k1: u64) -> Self {
+        Sip {
+            state: State {
+                v0: k0 ^ 0x736f_6d65_7073_6575,
+                v1: k1 ^ 0x646f_7261_6e64_6f6d,
+                v2: k0 ^ 0x6c79_6765_6e65_7261,
+                v3: k1 ^ 0x7465_6462_7974_6573,
+            },
+            length: 0,
+            tail: 0,
+            ntail: 0,
+            marker: PhantomData,
+        }
+    }
+
+    fn compress(&mut self, block: u64) {
+        self.state.v3 ^= block;
+        R::c_rounds(&mut self.state);
+        self.state.v0 ^= block;
+    }
+
+    fn write(&mut self, mut msg: &[u8]) {
+        self.length = self.length.wrapping_add(msg.len());
+        if self.ntail != 0 {
+            let needed = cmp::min(8 - self.ntail, msg.len());
+            self.tail |= load_le(&msg[..needed]) << (8 * self.ntail);
+            self.ntail += needed;
+            msg = &msg[needed..];
+            if self.ntail < 8 {
+                return;
+            }
+            self.compress(self.tail);
+            self.tail = 0;
+            self.ntail = 0;
+        }
+        let mut blocks = msg.chunks_exact(8);
+        for block in &mut blocks {
+            self.compress(u64::from_le_bytes(block.try_into().unwrap()));
+        }
+        let rest = blocks.remainder();
+        self.tail = load_le(rest);
+        self.ntail = rest.len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.state;
+        let block = ((self.length as u64 & 0xff) << 56) | self.tail;
+        state.v3 ^= block;
+        R::c_rounds(&mut state);
+        state.v0 ^= block;
+        state.v2 ^= 0xff;
+        R::d_rounds(&mut state);
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+impl<R: Rounds> Clone for Sip<R> {
+    fn clone(&self) -> Self {
+        Sip {
+            state: self.state,
+            length: self.length,
+            tail: self.tail,
+            ntail: self.ntail,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Rounds> Default for Sip<R> {
+    fn default() -> Self {
+        Sip::new_with_keys(0, 0)
+    }
+}
+
+/// Keeps the key-derived state out of debug output.
+impl<R: Rounds> fmt::Debug for Sip<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sip").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SipHasher13, SipHasher24};
+    use crate::hash::Hasher;
+    use std::convert::TryInto;
+
+    /// The reference vectors from the SipHash paper: the key is the bytes
+    /// `00..=0f` and message `i` is the bytes `00..i`.
+    #[rustfmt::skip]
+    const SIP24_VECTORS: [u64; 64] = [
+        0x726fdb47dd0e0e31, 0x74f839c593dc67fd, 0x0d6c8009d9a94f5a, 0x85676696d7fb7e2d,
+        0xcf2794e0277187b7, 0x18765564cd99a68d, 0xcbc9466e58fee3ce, 0xab0200f58b01d137,
+        0x93f5f5799a932462, 0x9e0082df0ba9e4b0, 0x7a5dbbc594ddb9f3, 0xf4b32f46226bada7,
+        0x751e8fbc860ee5fb, 0x14ea5627c0843d90, 0xf723ca908e7af2ee, 0xa129ca6149be45e5,
+        0x3f2acc7f57c29bdb, 0x699ae9f52cbe4794, 0x4bc1b3f0968dd39c, 0xbb6dc91da77961bd,
+        0xbed65cf21aa2ee98, 0xd0f2cbb02e3b67c7, 0x93536795e3a33e88, 0xa80c038ccd5ccec8,
+        0xb8ad50c6f649af94, 0xbce192de8a85b8ea, 0x17d835b85bbb15f3, 0x2f2e6163076bcfad,
+        0xde4daaaca71dc9a5, 0xa6a2506687956571, 0xad87a3535c49ef28, 0x32d892fad841c342,
+        0x7127512f72f27cce, 0xa7f32346f95978e3, 0x12e0b01abb051238, 0x15e034d40fa197ae,
+        0x314dffbe0815a3b4, 0x027990f029623981, 0xcadcd4e59ef40c4d, 0x9abfd8766a33735c,
+        0x0e3ea96b5304a7d0, 0xad0c42d6fc585992, 0x187306c89bc215a9, 0xd4a60abcf3792b95,
+        0xf935451de4f21df2, 0xa9538f0419755787, 0xdb9acddff56ca510, 0xd06c98cd5c0975eb,
+        0xe612a3cb9ecba951, 0xc766e62cfcadaf96, 0xee64435a9752fe72, 0xa192d576b245165a,
+        0x0a8787bf8ecb74b2, 0x81b3e73d20b49b6f, 0x7fa8220ba3b2ecea, 0x245731c13ca42499,
+        0xb78dbfaf3a8d83bd, 0xea1ad565322a1a0b, 0x60e61c23a3795013, 0x6606d7e446282b93,
+        0x6ca4ecb15c5f91e1, 0x9f626da15c9625f3, 0xe51b38608ef25f57, 0x958a324ceb064572,
+    ];
+
+    /// The same messages and key hashed with SipHash-1-3.
+    #[rustfmt::skip]
+    const SIP13_VECTORS: [u64; 64] = [
+        0xabac0158050fc4dc, 0xc9f49bf37d57ca93, 0x82cb9b024dc7d44d, 0x8bf80ab8e7ddf7fb,
+        0xcf75576088d38328, 0xdef9d52f49533b67, 0xc50d2b50c59f22a7, 0xd3927d989bb11140,
+        0x369095118d299a8e, 0x25a48eb36c063de4, 0x79de85ee92ff097f, 0x70c118c1f94dc352,
+        0x78a384b157b4d9a2, 0x306f760c1229ffa7, 0x605aa111c0f95d34, 0xd320d86d2a519956,
+        0xcc4fdd1a7d908b66, 0x9cf2689063dbd80c, 0x8ffc389cb473e63e, 0xf21f9de58d297d1c,
+        0xc0dc2f46a6cce040, 0xb992abfe2b45f844, 0x7ffe7b9ba320872e, 0x525a0e7fdae6c123,
+        0xf464aeb267349c8c, 0x45cd5928705b0979, 0x3a3e35e3ca9913a5, 0xa91dc74e4ade3b35,
+        0xfb0bed02ef6cd00d, 0x88d93cb44ab1e1f4, 0x540f11d643c5e663, 0x2370dd1f8c21d1bc,
+        0x81157b6c16a7b60d, 0x4d54b9e57a8ff9bf, 0x759f12781f2a753e, 0xcea1a3bebf186b91,
+        0x2cf508d3ada26206, 0xb6101c2da3c33057, 0xb3f47496ae3a36a1, 0x626b57547b108392,
+        0xc1d2363299e41531, 0x667cc1923f1ad944, 0x65704ffec8138825, 0x24f280d1c28949a6,
+        0xc2ca1cedfaf8876b, 0xc2164bfc9f042196, 0xa16e9c9368b1d623, 0x49fb169c8b5114fd,
+        0x9f3143f8df074c46, 0xc6fdaf2412cc86b3, 0x7eaf49d10a52098f, 0x1cf313559d292f9a,
+        0xc44a30dda2f41f12, 0x36fae98943a71ed0, 0x318fb34c73f0bce6, 0xa27abf3670a7e980,
+        0xb4bcc0db243c6d75, 0x23f8d852fdb71513, 0x8f035f4da67d8a08, 0xd89cd0e5b7e8f148,
+        0xf6f4e6bcf7a644ee, 0xaec59ad80f1837f2, 0xc3b2f6154b6694e0, 0x9d199062b7bbb3a8,
+    ];
+
+    fn reference_keys() -> (u64, u64) {
+        let key: Vec<u8> = (0..16).collect();
+        (
+            u64::from_le_bytes(key[..8].try_into().unwrap()),
+            u64::from_le_bytes(key[8..].try_into().unwrap()),
+        )
+    }
+
+    #[test]
+    fn sip24_reference_vectors() {
+        let (k0, k1) = reference_keys();
+        for (len, &expected) in SIP24_VECTORS.iter().enumerate() {
+            let msg: Vec<u8> = (0..len as u8).collect();
+            let mut hasher = SipHasher24::new_with_keys(k0, k1);
+            hasher.write(&msg);
+            assert_eq!(hasher.finish(), expected, "message length {}", len);
+        }
+    }
+
+    #[test]
+    fn sip13_reference_vectors() {
+        let (k0, k1) = reference_keys();
+        for (len, &expected) in SIP13_VECTORS.iter().enumerate() {
+            let msg: Vec<u8> = (0..len as u8).collect();
+            let mut hasher = SipHasher13::new_with_keys(k0, k1);
+            hasher.write(&msg);
+            assert_eq!(hasher.finish(), expected, "message length {}", len);
+        }
+    }
+
+    #[test]
+    fn split_writes_match_one_write() {
+        let msg: Vec<u8> = (0..40).collect();
+        let mut whole = SipHasher13::new_with_keys(7, 9);
+        whole.write(&msg);
+        for a in 0..msg.len() {
+            for b in a..msg.len() {
+                let mut split = SipHasher13::new_with_keys(7, 9);
+                split.write(&msg[..a]);
+                split.write(&msg[a..b]);
+                split.write(&msg[b..]);
+                assert_eq!(split.finish(), whole.finish(), "split at {} and {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn integers_hash_as_native_bytes() {
+        let mut ints = SipHasher24::new_with_keys(1, 2);
+        ints.write_u8(1);
+        ints.write_u32(0xdead_beef);
+        ints.write_i64(-5);
+        ints.write_usize(usize::MAX);
+        let mut bytes = SipHasher24::new_with_keys(1, 2);
+        bytes.write(&[1]);
+        bytes.write(&0xdead_beef_u32.to_ne_bytes());
+        bytes.write(&(-5i64).to_ne_bytes());
+        bytes.write(&usize::MAX.to_ne_bytes());
+        assert_eq!(ints.finish(), bytes.finish());
+    }
+
+    #[test]
+    fn finish_does_not_reset() {
+        let mut hasher = SipHasher13::new();
+        hasher.write(b"abc");
+        let first = hasher.finish();
+        assert_eq!(hasher.finish(), first);
+        hasher.write(b"d");
+        assert_ne!(hasher.finish(), first);
+    }
+}
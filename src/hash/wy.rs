@@ -0,0 +1,204 @@
+//! A hasher built on the wyhash mixing function.
+
+use super::Hasher;
+use std::convert::TryInto;
+
+const P0: u64 = 0xa076_1d64_78bd_642f;
+const P1: u64 = 0xe703_7ed1_a0b4_28db;
+const P2: u64 = 0x8ebc_6af0_9c88_c6e3;
+const P3: u64 = 0x5899_65cc_7537_4cc3;
+
+/// A fast hash with good output quality, seeded but not keyed.
+///
+/// Byte strings go through the wyhash algorithm, one 128-bit multiply per
+/// 16 bytes; integers take a single multiply. Each write is mixed into the
+/// state as a whole, so unlike SipHash, splitting the same bytes over
+/// several writes changes the hash. Like [`FxHasher`](super::FxHasher) it
+/// offers no protection against chosen keys, but its output bits are well
+/// distributed even for patterned input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WyHasher {
+    state: u64,
+}
+
+impl WyHasher {
+    /// Creates a hasher whose output depends on `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        WyHasher { state: seed }
+    }
+}
+
+/// The full 128-bit product of `a` and `b`, as (low, high).
+fn mum(a: u64, b: u64) -> (u64, u64) {
+    let r = a as u128 * b as u128;
+    (r as u64, (r >> 64) as u64)
+}
+
+fn mix(a: u64, b: u64) -> u64 {
+    let (lo, hi) = mum(a, b);
+    lo ^ hi
+}
+
+fn read8(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn read4(bytes: &[u8], at: usize) -> u64 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as u64
+}
+
+/// wyhash of `bytes` under `seed`.
+fn wyhash(bytes: &[u8], mut seed: u64) -> u64 {
+    let len = bytes.len();
+    seed ^= mix(seed ^ P0, P1);
+    let (a, b);
+    if len <= 16 {
+        if len >= 4 {
+            // Two overlapping pairs of 4-byte reads cover 4 to 16 bytes.
+            let quarter = (len >> 3) << 2;
+            a = (read4(bytes, 0) << 32) | read4(bytes, quarter);
+            b = (read4(bytes, len - 4) << 32) | read4(bytes, len - 4 - quarter);
+        } else if len > 0 {
+            a = ((bytes[0] as u64) << 16) | ((bytes[len >> 1] as u64) << 8) | bytes[len - 1] as u64;
+            b = 0;
+        } else {
+            a = 0;
+            b = 0;
+        }
+    } else {
+        let mut rest = bytes;
+        if rest.len() > 48 {
+            // Three independent lanes keep the multiplier busy.
+            let (mut see1, mut see2) = (seed, seed);
+            while rest.len() > 48 {
+                seed = mix(read8(rest, 0) ^ P1, read8(rest, 8) ^ seed);
+                see1 = mix(read8(rest, 16) ^ P2, read8(rest, 24) ^ see1);
+                see2 = mix(read8(rest, 32) ^ P3, read8(rest, 40) ^ see2);
+                rest = &rest[48..];
+            }
+            seed ^= see1 ^ see2;
+        }
+        while rest.len() > 16 {
+            seed = mix(read8(rest, 0) ^ P1, read8(rest, 8) ^ seed);
+            rest = &rest[16..];
+        }
+        // The last 16 bytes of the input, overlapping what was mixed in.
+        let end = len - 16;
+        a = read8(bytes, end);
+        b = read8(bytes, end + 8);
+    }
+    let (a, b) = mum(a ^ P1, b ^ seed);
+    mix(a ^ P0 ^ len as u64, b ^ P1)
+}
+
+impl Hasher for WyHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = wyhash(bytes, self.state);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.state = mix(self.state ^ P0, i ^ P1);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Lets the hasher drive types that implement `std::hash::Hash`.
+impl std::hash::Hasher for WyHasher {
+    fn finish(&self) -> u64 {
+        Hasher::finish(self)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        Hasher::write(self, bytes);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        Hasher::write_u8(self, i);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        Hasher::write_u16(self, i);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        Hasher::write_u32(self, i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        Hasher::write_u64(self, i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        Hasher::write_usize(self, i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wyhash, WyHasher};
+    use crate::hash::Hasher;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_length_and_byte_matters() {
+        // Hash every prefix of a buffer, and every prefix with one byte
+        // flipped: all of them must differ. This walks each length branch
+        // and checks that no byte is skipped by the overlapping reads.
+        let buf: Vec<u8> = (0..120).map(|i| (i * 7 + 3) as u8).collect();
+        let mut seen = HashSet::new();
+        for len in 0..buf.len() {
+            assert!(seen.insert(wyhash(&buf[..len], 0)), "length {}", len);
+            for i in 0..len {
+                let mut flipped = buf[..len].to_vec();
+                flipped[i] ^= 0x10;
+                assert!(
+                    seen.insert(wyhash(&flipped, 0)),
+                    "length {} byte {}",
+                    len,
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn seed_changes_output() {
+        let mut a = WyHasher::with_seed(1);
+        let mut b = WyHasher::with_seed(2);
+        a.write(b"same");
+        b.write(b"same");
+        assert_ne!(a.finish(), b.finish());
+    }
+
+    #[test]
+    fn sequential_integers_spread_over_the_top_bits() {
+        // The maps take their control bytes from the top seven bits, so
+        // those must vary even when the keys only differ in low bits.
+        let mut buckets = [0u32; 128];
+        for i in 0..128 * 64u64 {
+            let mut hasher = WyHasher::default();
+            hasher.write_u64(i);
+            buckets[(hasher.finish() >> 57) as usize] += 1;
+        }
+        assert!(buckets.iter().all(|&n| n > 32 && n < 96), "{:?}", buckets);
+    }
+}
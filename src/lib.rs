@@ -1,3 +1,19 @@
+pub mod alloc;
+pub mod array_string;
+pub mod array_vec;
+pub mod char;
+pub mod collections;
+pub mod hash;
+pub mod raw_vec;
+pub mod slice;
+pub mod str;
+pub mod string;
+pub mod vec;
+pub mod wtf8;
+
+#[cfg(test)]
+mod test_util;
+
 #[cfg(test)]
 mod tests {
     #[test]
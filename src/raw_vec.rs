@@ -0,0 +1,367 @@
+//! The allocation layer shared by every contiguous container in the crate.
+//!
+//! `RawVec<T, A>` owns a buffer large enough for `capacity()` values of `T`
+//! but knows nothing about which of them are initialized; that bookkeeping
+//! is left to the container built on top of it: `Vec`, `VecDeque` and
+//! `SmallVec`'s spilled storage. Containers such as `String` and
+//! `BinaryHeap` sit on a `Vec` instead. All layout arithmetic lives here so
+//! that it only has to be audited once, and every request goes through the
+//! collection's [`Allocator`](crate::alloc::Allocator).
+
+use crate::alloc::{Allocator, Global, Layout};
+use std::alloc;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::NonNull;
+
+/// The error type for `try_reserve` methods.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The computed capacity exceeded the collection's maximum, which is
+    /// usually `isize::MAX` bytes.
+    CapacityOverflow,
+    /// The allocator returned an error for the given layout.
+    AllocError {
+        /// The layout of the allocation request that failed.
+        layout: Layout,
+    },
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        match self {
+            TryReserveError::CapacityOverflow => {
+                f.write_str(" because the computed capacity exceeded the collection's maximum")
+            }
+            TryReserveError::AllocError { .. } => {
+                f.write_str(" because the memory allocator returned an error")
+            }
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+/// Converts a fallible result into the infallible behaviour std collections
+/// have: panic on overflow, abort through `handle_alloc_error` on OOM.
+pub(crate) fn handle_reserve<T>(result: Result<T, TryReserveError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(TryReserveError::CapacityOverflow) => capacity_overflow(),
+        Err(TryReserveError::AllocError { layout }) => alloc::handle_alloc_error(layout),
+    }
+}
+
+pub(crate) fn capacity_overflow() -> ! {
+    panic!("capacity overflow");
+}
+
+/// A low-level owned buffer of `T` with a tracked capacity, allocated from
+/// `A`.
+///
+/// Zero-sized types never allocate and report a capacity of `usize::MAX`.
+/// Dropping a `RawVec` frees the buffer but never drops its contents.
+pub struct RawVec<T, A: Allocator = Global> {
+    ptr: NonNull<T>,
+    cap: usize,
+    alloc: A,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for RawVec<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for RawVec<T, A> {}
+
+impl<T> RawVec<T, Global> {
+    /// Creates an empty buffer without allocating.
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Creates a buffer with room for exactly `capacity` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout overflows `isize::MAX` bytes and aborts if the
+    /// allocator fails.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Fallible version of [`with_capacity`](RawVec::with_capacity).
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> RawVec<T, A> {
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
+    /// Smallest non-zero capacity handed out by amortized growth. Tiny
+    /// buffers are a waste of an allocator round-trip.
+    const MIN_NON_ZERO_CAP: usize = if mem::size_of::<T>() == 1 {
+        8
+    } else if mem::size_of::<T>() <= 1024 {
+        4
+    } else {
+        1
+    };
+
+    /// Creates an empty buffer in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        RawVec {
+            ptr: NonNull::dangling(),
+            cap: if mem::size_of::<T>() == 0 {
+                usize::MAX
+            } else {
+                0
+            },
+            alloc,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a buffer in `alloc` with room for exactly `capacity` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout overflows `isize::MAX` bytes and aborts if the
+    /// allocator fails.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        handle_reserve(Self::try_with_capacity_in(capacity, alloc))
+    }
+
+    /// Fallible version of [`with_capacity_in`](RawVec::with_capacity_in).
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let mut buf = RawVec::new_in(alloc);
+        buf.try_reserve_exact(0, capacity)?;
+        Ok(buf)
+    }
+
+    /// Reconstitutes a buffer from a pointer, capacity and allocator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by `alloc` with the layout of
+    /// `[T; capacity]`, or be dangling if that layout is empty.
+    pub unsafe fn from_raw_parts_in(ptr: *mut T, capacity: usize, alloc: A) -> Self {
+        RawVec {
+            ptr: NonNull::new_unchecked(ptr),
+            cap: if Self::IS_ZST { usize::MAX } else { capacity },
+            alloc,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the start of the buffer. Dangling (but aligned)
+    /// if nothing has been allocated.
+    pub fn ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the capacity of the buffer; `usize::MAX` for ZSTs.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns a reference to the allocator backing this buffer.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Ensures the buffer can hold `len + additional` values, growing
+    /// amortized (at least doubling) if it cannot.
+    ///
+    /// # Panics
+    ///
+    /// Panics on capacity overflow and aborts on allocation failure.
+    pub fn reserve(&mut self, len: usize, additional: usize) {
+        if self.needs_to_grow(len, additional) {
+            handle_reserve(self.grow_amortized(len, additional));
+        }
+    }
+
+    /// Fallible version of [`reserve`](RawVec::reserve).
+    pub fn try_reserve(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+        if self.needs_to_grow(len, additional) {
+            self.grow_amortized(len, additional)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensures the buffer can hold exactly `len + additional` values,
+    /// without over-allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics on capacity overflow and aborts on allocation failure.
+    pub fn reserve_exact(&mut self, len: usize, additional: usize) {
+        handle_reserve(self.try_reserve_exact(len, additional));
+    }
+
+    /// Fallible version of [`reserve_exact`](RawVec::reserve_exact).
+    pub fn try_reserve_exact(
+        &mut self,
+        len: usize,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        if !self.needs_to_grow(len, additional) {
+            return Ok(());
+        }
+        let cap = len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        self.finish_grow(cap)
+    }
+
+    /// Doubles the buffer; the hot path of `push`.
+    pub fn grow_one(&mut self, len: usize) {
+        handle_reserve(self.grow_amortized(len, 1));
+    }
+
+    /// Shrinks the buffer to exactly `cap` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is greater than the current capacity and aborts if
+    /// the allocator fails.
+    pub fn shrink_to(&mut self, cap: usize) {
+        assert!(cap <= self.cap, "Tried to shrink to a larger capacity");
+        if Self::IS_ZST || cap == self.cap {
+            return;
+        }
+        let old_layout = Layout::array::<T>(self.cap).unwrap();
+        let new_layout = Layout::array::<T>(cap).unwrap();
+        if cap == 0 {
+            unsafe { self.alloc.deallocate(self.ptr.cast(), old_layout) };
+            self.ptr = NonNull::dangling();
+        } else {
+            match unsafe { self.alloc.shrink(self.ptr.cast(), old_layout, new_layout) } {
+                Ok(block) => self.ptr = block.cast(),
+                Err(_) => alloc::handle_alloc_error(new_layout),
+            }
+        }
+        self.cap = cap;
+    }
+
+    fn needs_to_grow(&self, len: usize, additional: usize) -> bool {
+        additional > self.cap.wrapping_sub(len)
+    }
+
+    fn grow_amortized(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+        if Self::IS_ZST {
+            // The capacity is already `usize::MAX`, so the request overflowed.
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        let required = len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let cap = required.max(self.cap * 2).max(Self::MIN_NON_ZERO_CAP);
+        self.finish_grow(cap)
+    }
+
+    fn finish_grow(&mut self, cap: usize) -> Result<(), TryReserveError> {
+        if Self::IS_ZST {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        let new_layout = Layout::array::<T>(cap).map_err(|_| TryReserveError::CapacityOverflow)?;
+        let block = unsafe {
+            if self.cap == 0 {
+                self.alloc.allocate(new_layout)
+            } else {
+                let old_layout = Layout::array::<T>(self.cap).unwrap();
+                self.alloc.grow(self.ptr.cast(), old_layout, new_layout)
+            }
+        };
+        let block = block.map_err(|_| TryReserveError::AllocError { layout: new_layout })?;
+        self.ptr = block.cast();
+        self.cap = cap;
+        Ok(())
+    }
+}
+
+impl<T, A: Allocator + Default> Default for RawVec<T, A> {
+    fn default() -> Self {
+        RawVec::new_in(A::default())
+    }
+}
+
+impl<T, A: Allocator> Drop for RawVec<T, A> {
+    fn drop(&mut self) {
+        if !Self::IS_ZST && self.cap != 0 {
+            unsafe {
+                self.alloc
+                    .deallocate(self.ptr.cast(), Layout::array::<T>(self.cap).unwrap())
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RawVec, TryReserveError};
+
+    #[test]
+    fn amortized_growth() {
+        let mut buf: RawVec<u32> = RawVec::new();
+        assert_eq!(buf.capacity(), 0);
+        buf.reserve(0, 1);
+        assert_eq!(buf.capacity(), 4);
+        buf.reserve(4, 1);
+        assert_eq!(buf.capacity(), 8);
+        buf.reserve(8, 100);
+        assert_eq!(buf.capacity(), 108);
+        buf.reserve_exact(108, 1);
+        assert_eq!(buf.capacity(), 109);
+        buf.shrink_to(3);
+        assert_eq!(buf.capacity(), 3);
+        buf.shrink_to(0);
+        assert_eq!(buf.capacity(), 0);
+    }
+
+    #[test]
+    fn zero_sized() {
+        let mut buf: RawVec<()> = RawVec::with_capacity(10);
+        assert_eq!(buf.capacity(), usize::MAX);
+        assert_eq!(buf.try_reserve(usize::MAX - 1, 1), Ok(()));
+        assert_eq!(
+            buf.try_reserve(usize::MAX, 1),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            buf.try_reserve_exact(1, usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn capacity_overflow_is_not_alloc_error() {
+        let mut buf: RawVec<u64> = RawVec::new();
+        // Fits in `usize` but not in `isize::MAX` bytes.
+        assert_eq!(
+            buf.try_reserve(0, usize::MAX / 8),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            buf.try_reserve_exact(0, usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(buf.capacity(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_panics_on_overflow() {
+        let mut buf: RawVec<u16> = RawVec::new();
+        buf.reserve(0, usize::MAX);
+    }
+
+    #[test]
+    fn display() {
+        let msg = TryReserveError::CapacityOverflow.to_string();
+        assert!(msg.contains("capacity exceeded"));
+    }
+}
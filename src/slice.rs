@@ -0,0 +1,482 @@
+//! Slice utilities shared by the collections.
+//!
+//! Besides [`range`], this holds the element-shifting code behind the
+//! methods that [`Vec`](crate::vec::Vec) and
+//! [`ArrayVec`](crate::array_vec::ArrayVec) have in common. Each helper
+//! works on a base pointer and the container's length field, so the
+//! containers differ only in where the buffer lives and how it grows.
+//! Draining and splicing need to grow the buffer as well, so they work
+//! through the [`Buffer`] trait instead.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
+use std::{ptr, slice};
+
+/// Converts any `RangeBounds<usize>` into a `Range` checked against `len`,
+/// the way slice indexing does. Used by every `drain`/`range` style method.
+///
+/// # Panics
+///
+/// Panics with the same messages as std's `Vec::drain` if either bound
+/// lies past `len` or the start is greater than the end. An out-of-range
+/// end is reported first, then an out-of-range start, and bounds are
+/// reported as written, so `..=4` on a length of 4 names index 4.
+///
+/// ```
+/// use mystdrs::slice;
+///
+/// assert_eq!(slice::range(2.., 5), 2..5);
+/// assert_eq!(slice::range(..=3, 5), 0..4);
+/// ```
+#[track_caller]
+pub fn range<R>(range: R, len: usize) -> Range<usize>
+where
+    R: RangeBounds<usize>,
+{
+    // Bounds are checked before the `+ 1`s below, which therefore cannot
+    // overflow.
+    let end = match range.end_bound() {
+        Bound::Included(&end) if end >= len => end_out_of_range(end, len),
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) if end > len => end_out_of_range(end, len),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    let start = match range.start_bound() {
+        Bound::Included(&start) if start > len => start_out_of_range(start, len),
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) if start >= len => start_out_of_range(start, len),
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    if start > end {
+        panic!("slice index starts at {} but ends at {}", start, end);
+    }
+    start..end
+}
+
+#[cold]
+#[track_caller]
+fn start_out_of_range(start: usize, len: usize) -> ! {
+    panic!(
+        "range start index {} out of range for slice of length {}",
+        start, len
+    );
+}
+
+#[cold]
+#[track_caller]
+fn end_out_of_range(end: usize, len: usize) -> ! {
+    panic!(
+        "range end index {} out of range for slice of length {}",
+        end, len
+    );
+}
+
+/// Panics unless `index` is a valid insertion position for `len`
+/// elements.
+pub(crate) fn assert_insert_index(index: usize, len: usize) {
+    if index > len {
+        panic!(
+            "insertion index (is {}) should be <= len (is {})",
+            index, len
+        );
+    }
+}
+
+/// Inserts `element` at `index`, shifting the elements after it right.
+///
+/// # Safety
+///
+/// `base` must have room for `*len + 1` elements, the first `*len` of them
+/// initialized, and `index <= *len`.
+pub(crate) unsafe fn insert<T>(base: *mut T, len: &mut usize, index: usize, element: T) {
+    let p = base.add(index);
+    ptr::copy(p, p.add(1), *len - index);
+    ptr::write(p, element);
+    *len += 1;
+}
+
+/// Removes and returns the element at `index`, shifting the elements after
+/// it left.
+///
+/// # Panics
+///
+/// Panics if `index >= *len`.
+///
+/// # Safety
+///
+/// The first `*len` elements at `base` must be initialized.
+pub(crate) unsafe fn remove<T>(base: *mut T, len: &mut usize, index: usize) -> T {
+    if index >= *len {
+        panic!("removal index (is {}) should be < len (is {})", index, len);
+    }
+    let p = base.add(index);
+    let value = ptr::read(p);
+    ptr::copy(p.add(1), p, *len - index - 1);
+    *len -= 1;
+    value
+}
+
+/// Removes and returns the element at `index`, moving the last element
+/// into its place.
+///
+/// # Panics
+///
+/// Panics if `index >= *len`.
+///
+/// # Safety
+///
+/// The first `*len` elements at `base` must be initialized.
+pub(crate) unsafe fn swap_remove<T>(base: *mut T, len: &mut usize, index: usize) -> T {
+    if index >= *len {
+        panic!(
+            "swap_remove index (is {}) should be < len (is {})",
+            index, len
+        );
+    }
+    let value = ptr::read(base.add(index));
+    ptr::copy(base.add(*len - 1), base.add(index), 1);
+    *len -= 1;
+    value
+}
+
+/// Drops the elements from `new_len` on. Has no effect if `new_len` is not
+/// below `*len`.
+///
+/// # Safety
+///
+/// The first `*len` elements at `base` must be initialized.
+pub(crate) unsafe fn truncate<T>(base: *mut T, len: &mut usize, new_len: usize) {
+    if new_len >= *len {
+        return;
+    }
+    let tail = ptr::slice_from_raw_parts_mut(base.add(new_len), *len - new_len);
+    // Shorten first: if a destructor panics, `drop_in_place` still drops
+    // the rest of the tail and the container never sees it again.
+    *len = new_len;
+    ptr::drop_in_place(tail);
+}
+
+/// Drops the elements for which `f` returns `false` and closes the gaps.
+///
+/// If `f` or an element's destructor panics, the container is left
+/// holding the elements not yet visited plus those already kept.
+///
+/// # Safety
+///
+/// The first `*len` elements at `base` must be initialized.
+pub(crate) unsafe fn retain<T, F>(base: *mut T, len: &mut usize, mut f: F)
+where
+    F: FnMut(&T) -> bool,
+{
+    struct Guard<'a, T> {
+        base: *mut T,
+        len: &'a mut usize,
+        processed: usize,
+        deleted: usize,
+        original_len: usize,
+    }
+
+    impl<T> Drop for Guard<'_, T> {
+        fn drop(&mut self) {
+            unsafe {
+                if self.deleted > 0 {
+                    ptr::copy(
+                        self.base.add(self.processed),
+                        self.base.add(self.processed - self.deleted),
+                        self.original_len - self.processed,
+                    );
+                }
+            }
+            *self.len = self.original_len - self.deleted;
+        }
+    }
+
+    let original_len = *len;
+    // Hide everything from the container while elements are shuffled.
+    *len = 0;
+    let mut g = Guard {
+        base,
+        len,
+        processed: 0,
+        deleted: 0,
+        original_len,
+    };
+    while g.processed < original_len {
+        let cur = base.add(g.processed);
+        if !f(&*cur) {
+            g.processed += 1;
+            g.deleted += 1;
+            ptr::drop_in_place(cur);
+            continue;
+        }
+        if g.deleted > 0 {
+            ptr::copy_nonoverlapping(cur, cur.sub(g.deleted), 1);
+        }
+        g.processed += 1;
+    }
+}
+
+/// A growable contiguous buffer, implemented by the vectors that share
+/// [`RawDrain`] and [`RawSplice`].
+pub(crate) trait Buffer<T> {
+    fn as_ptr(&self) -> *const T;
+
+    fn as_mut_ptr(&mut self) -> *mut T;
+
+    fn len(&self) -> usize;
+
+    /// # Safety
+    ///
+    /// As for `Vec::set_len`.
+    unsafe fn set_len(&mut self, len: usize);
+
+    /// Makes room for `additional` elements past the length. Slots beyond
+    /// the length must keep their contents if the buffer moves.
+    fn reserve(&mut self, additional: usize);
+}
+
+/// The shared state behind the vectors' `drain` iterators.
+///
+/// The buffer's length is cut to the start of the drained range until the
+/// drain is dropped, so leaking the drain leaks the drained elements and
+/// the tail rather than exposing moved-out slots.
+pub(crate) struct RawDrain<'a, T, B: Buffer<T> + ?Sized> {
+    buf: &'a mut B,
+    /// The drained elements not yet yielded.
+    idx: usize,
+    end: usize,
+    /// Where the elements after the drained range currently sit.
+    tail_start: usize,
+    tail_len: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T, B: Buffer<T> + ?Sized> RawDrain<'a, T, B> {
+    /// Drains `range`, which must already be checked against the length.
+    pub(crate) fn new(buf: &'a mut B, range: Range<usize>) -> Self {
+        let len = buf.len();
+        unsafe { buf.set_len(range.start) };
+        RawDrain {
+            buf,
+            idx: range.start,
+            end: range.end,
+            tail_start: range.end,
+            tail_len: len - range.end,
+            marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().add(self.idx), self.end - self.idx) }
+    }
+
+    /// Writes items from `iter` into the gap between the length and the
+    /// tail. Returns `false` if `iter` ran out first.
+    unsafe fn fill<I: Iterator<Item = T>>(&mut self, iter: &mut I) -> bool {
+        while self.buf.len() < self.tail_start {
+            match iter.next() {
+                Some(item) => {
+                    let len = self.buf.len();
+                    ptr::write(self.buf.as_mut_ptr().add(len), item);
+                    self.buf.set_len(len + 1);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Widens the gap before the tail by `additional` slots.
+    unsafe fn move_tail(&mut self, additional: usize) {
+        let used = self.tail_start + self.tail_len;
+        self.buf.reserve(used - self.buf.len() + additional);
+        let base = self.buf.as_mut_ptr();
+        let new_tail_start = self.tail_start + additional;
+        ptr::copy(
+            base.add(self.tail_start),
+            base.add(new_tail_start),
+            self.tail_len,
+        );
+        self.tail_start = new_tail_start;
+    }
+}
+
+impl<T, B: Buffer<T> + ?Sized> Iterator for RawDrain<'_, T, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.idx == self.end {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.buf.as_ptr().add(self.idx)) };
+            self.idx += 1;
+            Some(item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.idx;
+        (n, Some(n))
+    }
+}
+
+impl<T, B: Buffer<T> + ?Sized> DoubleEndedIterator for RawDrain<'_, T, B> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.idx == self.end {
+            None
+        } else {
+            self.end -= 1;
+            unsafe { Some(ptr::read(self.buf.as_ptr().add(self.end))) }
+        }
+    }
+}
+
+impl<T, B: Buffer<T> + ?Sized> ExactSizeIterator for RawDrain<'_, T, B> {}
+
+impl<T, B: Buffer<T> + ?Sized> FusedIterator for RawDrain<'_, T, B> {}
+
+impl<T, B: Buffer<T> + ?Sized> Drop for RawDrain<'_, T, B> {
+    fn drop(&mut self) {
+        /// Closes the gap even if a drained element's destructor panics.
+        struct MoveTail<'r, 'a, T, B: Buffer<T> + ?Sized>(&'r mut RawDrain<'a, T, B>);
+
+        impl<T, B: Buffer<T> + ?Sized> Drop for MoveTail<'_, '_, T, B> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                unsafe {
+                    let len = drain.buf.len();
+                    if drain.tail_start != len {
+                        let base = drain.buf.as_mut_ptr();
+                        ptr::copy(base.add(drain.tail_start), base.add(len), drain.tail_len);
+                    }
+                    drain.buf.set_len(len + drain.tail_len);
+                }
+            }
+        }
+
+        let rest = unsafe {
+            ptr::slice_from_raw_parts_mut(self.buf.as_mut_ptr().add(self.idx), self.end - self.idx)
+        };
+        self.idx = self.end;
+        let guard = MoveTail(self);
+        unsafe { ptr::drop_in_place(rest) };
+        drop(guard);
+    }
+}
+
+/// The shared state behind the vectors' `splice` iterators: a drain that
+/// writes `replace_with` into the gap when it is dropped.
+pub(crate) struct RawSplice<'a, I: Iterator, B: Buffer<I::Item> + ?Sized> {
+    drain: RawDrain<'a, I::Item, B>,
+    replace_with: I,
+}
+
+impl<'a, I: Iterator, B: Buffer<I::Item> + ?Sized> RawSplice<'a, I, B> {
+    pub(crate) fn new(buf: &'a mut B, range: Range<usize>, replace_with: I) -> Self {
+        RawSplice {
+            drain: RawDrain::new(buf, range),
+            replace_with,
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[I::Item] {
+        self.drain.as_slice()
+    }
+}
+
+impl<I: Iterator, B: Buffer<I::Item> + ?Sized> Iterator for RawSplice<'_, I, B> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<I: Iterator, B: Buffer<I::Item> + ?Sized> DoubleEndedIterator for RawSplice<'_, I, B> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<I: Iterator, B: Buffer<I::Item> + ?Sized> ExactSizeIterator for RawSplice<'_, I, B> {}
+
+impl<I: Iterator, B: Buffer<I::Item> + ?Sized> Drop for RawSplice<'_, I, B> {
+    fn drop(&mut self) {
+        self.drain.by_ref().for_each(drop);
+        unsafe {
+            if !self.drain.fill(&mut self.replace_with) {
+                return;
+            }
+            // Make room for what the iterator promises, then for whatever
+            // is left once it has been collected; the drain's destructor
+            // moves the tail back down over any unused slots.
+            let (lower, _) = self.replace_with.size_hint();
+            if lower > 0 {
+                self.drain.move_tail(lower);
+                if !self.drain.fill(&mut self.replace_with) {
+                    return;
+                }
+            }
+            let rest: crate::vec::Vec<I::Item> = self.replace_with.by_ref().collect();
+            if !rest.is_empty() {
+                self.drain.move_tail(rest.len());
+                let filled = self.drain.fill(&mut rest.into_iter());
+                debug_assert!(filled);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::range;
+    use std::ops::Bound;
+
+    #[test]
+    fn normalizes_bounds() {
+        assert_eq!(range(.., 4), 0..4);
+        assert_eq!(range(1..3, 4), 1..3);
+        assert_eq!(range(4..4, 4), 4..4);
+        assert_eq!(range((Bound::Excluded(0), Bound::Included(2)), 4), 1..3);
+    }
+
+    #[test]
+    #[should_panic(expected = "slice index starts at 3 but ends at 2")]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn start_after_end() {
+        range(3..2, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 5 out of range for slice of length 4")]
+    fn end_out_of_range() {
+        range(..5, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start index 5 out of range for slice of length 4")]
+    fn start_out_of_range() {
+        range(5.., 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 4 out of range for slice of length 4")]
+    fn inclusive_end_is_reported_as_written() {
+        range(..=4, 4);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "range end index 18446744073709551615 out of range for slice of length 4"
+    )]
+    fn inclusive_end_overflow() {
+        range(..=usize::MAX, 4);
+    }
+}
@@ -0,0 +1,451 @@
+//! Iterators over string slices.
+//!
+//! The splitting and matching iterators are driven by a
+//! [`Searcher`](super::pattern::Searcher), so they work with any
+//! [`Pattern`]. They are double-ended when the searcher is a
+//! [`DoubleEndedSearcher`].
+
+use super::pattern::{DoubleEndedSearcher, Pattern, ReverseSearcher, Searcher};
+use crate::char::encode_utf16_raw;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::str::Chars;
+
+/// Returns an iterator over `s` encoded as UTF-16.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let units: Vec<u16> = str::encode_utf16("a𝄞").collect();
+/// assert_eq!(units, [0x61, 0xD834, 0xDD1E]);
+/// ```
+pub fn encode_utf16(s: &str) -> EncodeUtf16<'_> {
+    EncodeUtf16 {
+        chars: s.chars(),
+        extra: 0,
+    }
+}
+
+/// An iterator over the UTF-16 code units of a string slice, created by
+/// [`encode_utf16`].
+#[derive(Clone)]
+pub struct EncodeUtf16<'a> {
+    chars: Chars<'a>,
+    /// The trail surrogate still owed for the last char, or zero.
+    extra: u16,
+}
+
+impl Iterator for EncodeUtf16<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        if self.extra != 0 {
+            let trail = self.extra;
+            self.extra = 0;
+            return Some(trail);
+        }
+        let ch = self.chars.next()?;
+        let mut buf = [0; 2];
+        match *encode_utf16_raw(u32::from(ch), &mut buf) {
+            [unit] => Some(unit),
+            [lead, trail] => {
+                self.extra = trail;
+                Some(lead)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // A char of n UTF-8 bytes takes one unit if n < 4 and two units
+        // otherwise.
+        let bytes = self.chars.as_str().len();
+        let extra = usize::from(self.extra != 0);
+        (bytes.div_ceil(3) + extra, Some(bytes + extra))
+    }
+}
+
+impl FusedIterator for EncodeUtf16<'_> {}
+
+impl fmt::Debug for EncodeUtf16<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncodeUtf16")
+            .field("rest", &self.chars.as_str())
+            .finish()
+    }
+}
+
+/// The state shared by [`Split`], [`RSplit`] and [`SplitN`].
+struct SplitInternal<'a, P: Pattern<'a>> {
+    /// Start of the part not yet returned from the front.
+    start: usize,
+    /// End of the part not yet returned from the back.
+    end: usize,
+    matcher: P::Searcher,
+    finished: bool,
+}
+
+impl<'a, P: Pattern<'a>> SplitInternal<'a, P> {
+    fn new(haystack: &'a str, pat: P) -> Self {
+        SplitInternal {
+            start: 0,
+            end: haystack.len(),
+            matcher: pat.into_searcher(haystack),
+            finished: false,
+        }
+    }
+
+    /// Returns everything not yet returned, once.
+    fn get_end(&mut self) -> Option<&'a str> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        Some(unsafe { self.matcher.haystack().get_unchecked(self.start..self.end) })
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.finished {
+            return None;
+        }
+        match self.matcher.next_match() {
+            Some((a, b)) => {
+                let piece = unsafe { self.matcher.haystack().get_unchecked(self.start..a) };
+                self.start = b;
+                Some(piece)
+            }
+            None => self.get_end(),
+        }
+    }
+
+    fn next_back(&mut self) -> Option<&'a str>
+    where
+        P::Searcher: ReverseSearcher<'a>,
+    {
+        if self.finished {
+            return None;
+        }
+        match self.matcher.next_match_back() {
+            Some((a, b)) => {
+                let piece = unsafe { self.matcher.haystack().get_unchecked(b..self.end) };
+                self.end = a;
+                Some(piece)
+            }
+            None => self.get_end(),
+        }
+    }
+}
+
+impl<'a, P: Pattern<'a>> Clone for SplitInternal<'a, P>
+where
+    P::Searcher: Clone,
+{
+    fn clone(&self) -> Self {
+        SplitInternal {
+            start: self.start,
+            end: self.end,
+            matcher: self.matcher.clone(),
+            finished: self.finished,
+        }
+    }
+}
+
+impl<'a, P: Pattern<'a>> fmt::Debug for SplitInternal<'a, P>
+where
+    P::Searcher: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplitInternal")
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("matcher", &self.matcher)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+/// Implements `Clone` and `Debug` for an iterator wrapping a `Searcher`,
+/// with the searcher's own impls as the bounds.
+macro_rules! searcher_iter_impls {
+    ($name:ident { $($field:ident),* }) => {
+        impl<'a, P: Pattern<'a>> Clone for $name<'a, P>
+        where
+            P::Searcher: Clone,
+        {
+            fn clone(&self) -> Self {
+                $name {
+                    $($field: self.$field.clone()),*
+                }
+            }
+        }
+
+        impl<'a, P: Pattern<'a>> fmt::Debug for $name<'a, P>
+        where
+            P::Searcher: fmt::Debug,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($field), &self.$field))*
+                    .finish()
+            }
+        }
+    };
+}
+
+/// Returns an iterator over the pieces of `haystack` between matches of
+/// `pat`.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let parts: Vec<&str> = str::split("a,b,,c", ',').collect();
+/// assert_eq!(parts, ["a", "b", "", "c"]);
+/// let parts: Vec<&str> = str::split("lionXXtigerXleopard", 'X').rev().collect();
+/// assert_eq!(parts, ["leopard", "tiger", "", "lion"]);
+/// ```
+pub fn split<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> Split<'a, P> {
+    Split {
+        inner: SplitInternal::new(haystack, pat),
+    }
+}
+
+/// An iterator over the pieces of a string slice between matches of a
+/// pattern, created by [`split`].
+pub struct Split<'a, P: Pattern<'a>> {
+    inner: SplitInternal<'a, P>,
+}
+
+searcher_iter_impls!(Split { inner });
+
+impl<'a, P: Pattern<'a>> Iterator for Split<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.inner.next()
+    }
+}
+
+impl<'a, P: Pattern<'a>> DoubleEndedIterator for Split<'a, P>
+where
+    P::Searcher: DoubleEndedSearcher<'a>,
+{
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, P: Pattern<'a>> FusedIterator for Split<'a, P> {}
+
+/// Returns an iterator over the pieces of `haystack` between matches of
+/// `pat`, from the back.
+///
+/// Unlike reversing [`split`], this works for patterns whose matches
+/// depend on the direction of the search.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let parts: Vec<&str> = str::rsplit("aaaaa", "aa").collect();
+/// assert_eq!(parts, ["", "", "a"]);
+/// ```
+pub fn rsplit<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> RSplit<'a, P>
+where
+    P::Searcher: ReverseSearcher<'a>,
+{
+    RSplit {
+        inner: SplitInternal::new(haystack, pat),
+    }
+}
+
+/// An iterator over the pieces of a string slice between matches of a
+/// pattern from the back, created by [`rsplit`].
+pub struct RSplit<'a, P: Pattern<'a>> {
+    inner: SplitInternal<'a, P>,
+}
+
+searcher_iter_impls!(RSplit { inner });
+
+impl<'a, P: Pattern<'a>> Iterator for RSplit<'a, P>
+where
+    P::Searcher: ReverseSearcher<'a>,
+{
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, P: Pattern<'a>> DoubleEndedIterator for RSplit<'a, P>
+where
+    P::Searcher: DoubleEndedSearcher<'a>,
+{
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.inner.next()
+    }
+}
+
+impl<'a, P: Pattern<'a>> FusedIterator for RSplit<'a, P> where P::Searcher: ReverseSearcher<'a> {}
+
+/// Returns an iterator over at most `n` pieces of `haystack` between
+/// matches of `pat`. The last piece is the rest of the haystack.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let parts: Vec<&str> = str::splitn("key=value=more", 2, '=').collect();
+/// assert_eq!(parts, ["key", "value=more"]);
+/// assert_eq!(str::splitn("abc", 0, 'b').next(), None);
+/// ```
+pub fn splitn<'a, P: Pattern<'a>>(haystack: &'a str, n: usize, pat: P) -> SplitN<'a, P> {
+    SplitN {
+        inner: SplitInternal::new(haystack, pat),
+        count: n,
+    }
+}
+
+/// An iterator over at most a given number of pieces of a string slice,
+/// created by [`splitn`].
+pub struct SplitN<'a, P: Pattern<'a>> {
+    inner: SplitInternal<'a, P>,
+    /// How many more pieces may be returned.
+    count: usize,
+}
+
+searcher_iter_impls!(SplitN { inner, count });
+
+impl<'a, P: Pattern<'a>> Iterator for SplitN<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        match self.count {
+            0 => None,
+            1 => {
+                self.count = 0;
+                self.inner.get_end()
+            }
+            _ => {
+                self.count -= 1;
+                self.inner.next()
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.count == 0 || self.inner.finished {
+            (0, Some(0))
+        } else {
+            (1, Some(self.count))
+        }
+    }
+}
+
+impl<'a, P: Pattern<'a>> FusedIterator for SplitN<'a, P> {}
+
+/// Returns an iterator over the non-overlapping matches of `pat` in
+/// `haystack`, with their byte offsets.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let found: Vec<_> = str::match_indices("abcXXXabcYYYabc", "abc").collect();
+/// assert_eq!(found, [(0, "abc"), (6, "abc"), (12, "abc")]);
+/// ```
+pub fn match_indices<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> MatchIndices<'a, P> {
+    MatchIndices {
+        matcher: pat.into_searcher(haystack),
+    }
+}
+
+/// An iterator over the matches of a pattern and their byte offsets,
+/// created by [`match_indices`].
+pub struct MatchIndices<'a, P: Pattern<'a>> {
+    matcher: P::Searcher,
+}
+
+searcher_iter_impls!(MatchIndices { matcher });
+
+impl<'a, P: Pattern<'a>> Iterator for MatchIndices<'a, P> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        let (a, b) = self.matcher.next_match()?;
+        Some((a, unsafe { self.matcher.haystack().get_unchecked(a..b) }))
+    }
+}
+
+impl<'a, P: Pattern<'a>> DoubleEndedIterator for MatchIndices<'a, P>
+where
+    P::Searcher: DoubleEndedSearcher<'a>,
+{
+    fn next_back(&mut self) -> Option<(usize, &'a str)> {
+        let (a, b) = self.matcher.next_match_back()?;
+        Some((a, unsafe { self.matcher.haystack().get_unchecked(a..b) }))
+    }
+}
+
+impl<'a, P: Pattern<'a>> FusedIterator for MatchIndices<'a, P> {}
+
+/// Returns an iterator over the non-overlapping matches of `pat` in
+/// `haystack`.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let digits: Vec<&str> = str::matches("1abc2abc3", char::is_numeric).collect();
+/// assert_eq!(digits, ["1", "2", "3"]);
+/// ```
+pub fn matches<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> Matches<'a, P> {
+    Matches {
+        inner: match_indices(haystack, pat),
+    }
+}
+
+/// An iterator over the matches of a pattern, created by [`matches`].
+pub struct Matches<'a, P: Pattern<'a>> {
+    inner: MatchIndices<'a, P>,
+}
+
+searcher_iter_impls!(Matches { inner });
+
+impl<'a, P: Pattern<'a>> Iterator for Matches<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.inner.next().map(|(_, s)| s)
+    }
+}
+
+impl<'a, P: Pattern<'a>> DoubleEndedIterator for Matches<'a, P>
+where
+    P::Searcher: DoubleEndedSearcher<'a>,
+{
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.inner.next_back().map(|(_, s)| s)
+    }
+}
+
+impl<'a, P: Pattern<'a>> FusedIterator for Matches<'a, P> {}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_utf16;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn encode_utf16_matches_std() {
+        let mut rng = XorShift::new(1600);
+        for _ in 0..2_000 {
+            let s: String = (0..rng.below(20)).map(|_| rng.char()).collect();
+            let units: Vec<u16> = encode_utf16(&s).collect();
+            assert_eq!(units, s.encode_utf16().collect::<Vec<u16>>());
+
+            let mut iter = encode_utf16(&s);
+            for left in (0..=units.len()).rev() {
+                let (low, high) = iter.size_hint();
+                assert!(low <= left && left <= high.unwrap(), "{:?}", s);
+                iter.next();
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Splitting bytes into valid UTF-8 and invalid sequences, the basis of
+//! [`String::from_utf8_lossy`](crate::string::String::from_utf8_lossy).
+
+use super::validations::run_utf8_validation;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::str;
+
+/// Returns an iterator over `v` split into runs of valid UTF-8, each
+/// followed by the invalid sequence that ended it.
+///
+/// An invalid sequence is the longest prefix of a valid sequence, or a
+/// single byte if none starts there, so replacing each one with U+FFFD
+/// follows the Unicode "substitution of maximal subparts" practice, as
+/// std does.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let chunks: Vec<_> = str::utf8_chunks(b"a\xF0\x9F\x92b\xFF")
+///     .map(|c| (c.valid(), c.invalid()))
+///     .collect();
+/// assert_eq!(chunks, [("a", &b"\xF0\x9F\x92"[..]), ("b", &b"\xFF"[..])]);
+/// ```
+pub fn utf8_chunks(v: &[u8]) -> Utf8Chunks<'_> {
+    Utf8Chunks { source: v }
+}
+
+/// A run of valid UTF-8 and the invalid sequence after it, from
+/// [`Utf8Chunks`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utf8Chunk<'a> {
+    valid: &'a str,
+    invalid: &'a [u8],
+}
+
+impl<'a> Utf8Chunk<'a> {
+    /// Returns the valid run, which may be empty.
+    pub fn valid(&self) -> &'a str {
+        self.valid
+    }
+
+    /// Returns the invalid sequence, which is empty only for the last
+    /// chunk.
+    pub fn invalid(&self) -> &'a [u8] {
+        self.invalid
+    }
+}
+
+/// An iterator over the [`Utf8Chunk`]s of a byte slice, created by
+/// [`utf8_chunks`].
+#[derive(Clone)]
+pub struct Utf8Chunks<'a> {
+    source: &'a [u8],
+}
+
+impl<'a> Iterator for Utf8Chunks<'a> {
+    type Item = Utf8Chunk<'a>;
+
+    fn next(&mut self) -> Option<Utf8Chunk<'a>> {
+        if self.source.is_empty() {
+            return None;
+        }
+        let (valid_up_to, invalid_len) = match run_utf8_validation(self.source) {
+            Ok(()) => (self.source.len(), 0),
+            Err(e) => (
+                e.valid_up_to(),
+                e.error_len().unwrap_or(self.source.len() - e.valid_up_to()),
+            ),
+        };
+        let (valid, rest) = self.source.split_at(valid_up_to);
+        let (invalid, rest) = rest.split_at(invalid_len);
+        self.source = rest;
+        Some(Utf8Chunk {
+            valid: unsafe { str::from_utf8_unchecked(valid) },
+            invalid,
+        })
+    }
+}
+
+impl FusedIterator for Utf8Chunks<'_> {}
+
+impl fmt::Debug for Utf8Chunks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Utf8Chunks")
+            .field("source", &self.source)
+            .finish()
+    }
+}
@@ -0,0 +1,198 @@
+//! UTF-8 string slice utilities.
+//!
+//! [`from_utf8`] validates bytes with the crate's own DFA rather than
+//! std's, reporting failures as a [`Utf8Error`] with the same
+//! `valid_up_to`/`error_len` contract. [`utf8_chunks`] splits ill-formed
+//! input the way lossy decoding needs, and [`encode_utf16`] goes the other
+//! way to UTF-16.
+//!
+//! Searching is generic over a [`Pattern`]: a `char`, a `&str`, a
+//! `&[char]` or a `FnMut(char) -> bool`. These are free functions rather
+//! than methods, since `str`'s own methods would shadow any extension
+//! trait.
+
+mod iter;
+mod lossy;
+pub mod pattern;
+mod validations;
+
+pub use self::iter::{
+    encode_utf16, match_indices, matches, rsplit, split, splitn, EncodeUtf16, MatchIndices,
+    Matches, RSplit, Split, SplitN,
+};
+pub use self::lossy::{utf8_chunks, Utf8Chunk, Utf8Chunks};
+pub use self::validations::Utf8Error;
+
+pub(crate) use self::validations::{next_code_point, run_utf8_validation};
+
+use self::pattern::{DoubleEndedSearcher, Pattern, ReverseSearcher, Searcher};
+use std::str;
+
+/// Converts a byte slice to a string slice if it is UTF-8.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// assert_eq!(str::from_utf8("κόσμε".as_bytes()), Ok("κόσμε"));
+/// assert_eq!(str::from_utf8(b"\xC0\xAF").unwrap_err().error_len(), Some(1));
+/// ```
+pub fn from_utf8(v: &[u8]) -> Result<&str, Utf8Error> {
+    run_utf8_validation(v)?;
+    Ok(unsafe { str::from_utf8_unchecked(v) })
+}
+
+/// Converts a mutable byte slice to a mutable string slice if it is UTF-8.
+pub fn from_utf8_mut(v: &mut [u8]) -> Result<&mut str, Utf8Error> {
+    run_utf8_validation(v)?;
+    Ok(unsafe { str::from_utf8_unchecked_mut(v) })
+}
+
+/// Returns the byte index of the first match of `pat` in `haystack`.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// assert_eq!(str::find("Löwe 老虎", '老'), Some(6));
+/// assert_eq!(str::find("Löwe 老虎", "we"), Some(3));
+/// assert_eq!(str::find("Löwe 老虎", &['x', 'w'][..]), Some(3));
+/// assert_eq!(str::find("Löwe 老虎", char::is_whitespace), Some(5));
+/// assert_eq!(str::find("Löwe 老虎", "tiger"), None);
+/// ```
+pub fn find<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> Option<usize> {
+    pat.into_searcher(haystack).next_match().map(|(a, _)| a)
+}
+
+/// Returns the byte index of the start of the last match of `pat` in
+/// `haystack`.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// assert_eq!(str::rfind("aaaa", "aa"), Some(2));
+/// assert_eq!(str::rfind("Löwe 老虎", 'L'), Some(0));
+/// ```
+pub fn rfind<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> Option<usize>
+where
+    P::Searcher: ReverseSearcher<'a>,
+{
+    pat.into_searcher(haystack)
+        .next_match_back()
+        .map(|(a, _)| a)
+}
+
+/// Returns `haystack` with every match of `pat` at its start and end
+/// removed.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// assert_eq!(str::trim_matches("11foo1bar11", '1'), "foo1bar");
+/// assert_eq!(str::trim_matches("123foo1bar123", char::is_numeric), "foo1bar");
+/// ```
+pub fn trim_matches<'a, P: Pattern<'a>>(haystack: &'a str, pat: P) -> &'a str
+where
+    P::Searcher: DoubleEndedSearcher<'a>,
+{
+    let mut matcher = pat.into_searcher(haystack);
+    let (start, end) = match matcher.next_reject() {
+        // The back reject is the first one if there is only one.
+        Some((a, b)) => (a, matcher.next_reject_back().map_or(b, |(_, b)| b)),
+        None => (0, 0),
+    };
+    unsafe { haystack.get_unchecked(start..end) }
+}
+
+/// Returns `haystack` without `prefix`, or `None` if it does not start
+/// with it.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// assert_eq!(str::strip_prefix("foo:bar", "foo:"), Some("bar"));
+/// assert_eq!(str::strip_prefix("foo:bar", 'f'), Some("oo:bar"));
+/// assert_eq!(str::strip_prefix("foo:bar", "bar"), None);
+/// ```
+pub fn strip_prefix<'a, P: Pattern<'a>>(haystack: &'a str, prefix: P) -> Option<&'a str> {
+    prefix.strip_prefix_of(haystack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_utf8, from_utf8_mut, utf8_chunks};
+    use crate::string::String;
+
+    /// Markus Kuhn's UTF-8 decoder stress test of 2003-02-19, unmodified.
+    /// Each test case sits on a line of its own.
+    const KUHN: &[u8] = include_bytes!("fixtures/UTF-8-test.txt");
+
+    fn check(v: &[u8]) {
+        let ours = from_utf8(v).map_err(|e| (e.valid_up_to(), e.error_len(), e.to_string()));
+        let std =
+            std::str::from_utf8(v).map_err(|e| (e.valid_up_to(), e.error_len(), e.to_string()));
+        assert_eq!(ours, std, "{:x?}", v);
+        assert_eq!(
+            String::from_utf8_lossy(v),
+            *std::string::String::from_utf8_lossy(v),
+            "{:x?}",
+            v
+        );
+        let chunks: Vec<_> = utf8_chunks(v).map(|c| (c.valid(), c.invalid())).collect();
+        let std_chunks: Vec<_> = v.utf8_chunks().map(|c| (c.valid(), c.invalid())).collect();
+        assert_eq!(chunks, std_chunks, "{:x?}", v);
+    }
+
+    #[test]
+    fn kuhn_stress_test_matches_std() {
+        let mut malformed = 0;
+        for line in KUHN.split(|&b| b == b'\n') {
+            check(line);
+            if from_utf8(line).is_err() {
+                malformed += 1;
+            }
+        }
+        check(KUHN);
+        // 2.1.5-2.1.6, 2.2.4-2.2.6 and 2.3.5; all of 3, where 3.1.9 spans
+        // four lines and 3.2.1 two; all of 4; and 5.1-5.2.
+        assert_eq!(malformed, 6 + 32 + 15 + 15);
+        assert_eq!(
+            String::from_utf8_lossy(b"3.5.3 \"\xfe\xfe\xff\xff\""),
+            "3.5.3 \"\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\""
+        );
+    }
+
+    #[test]
+    fn lossy_replaces_maximal_subparts() {
+        assert_eq!(String::from_utf8_lossy(b""), "");
+        assert_eq!(String::from_utf8_lossy(b"hello"), "hello");
+        // A truncated sequence is one replacement; bytes that could never
+        // start one are one each.
+        assert_eq!(String::from_utf8_lossy(b"\xf0\x90\x80"), "\u{FFFD}");
+        assert_eq!(String::from_utf8_lossy(b"\xc0\x80"), "\u{FFFD}\u{FFFD}");
+        assert_eq!(
+            String::from_utf8_lossy(b"\xed\xa0\x80x"),
+            "\u{FFFD}\u{FFFD}\u{FFFD}x"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(b"\xe1\x80\xe2\xf0\x91\x92\xf1\xbfA"),
+            "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}A"
+        );
+    }
+
+    #[test]
+    fn from_utf8_mut_and_errors() {
+        let mut v = *b"abc";
+        from_utf8_mut(&mut v).unwrap().make_ascii_uppercase();
+        assert_eq!(&v, b"ABC");
+
+        let err = String::from_utf8(crate::vec::Vec::from(&b"ok\xff"[..])).unwrap_err();
+        assert_eq!(err.as_bytes(), b"ok\xff");
+        assert_eq!(err.utf8_error().valid_up_to(), 2);
+        assert_eq!(
+            err.to_string(),
+            "invalid utf-8 sequence of 1 bytes from index 2"
+        );
+        assert_eq!(err.into_bytes(), [b'o', b'k', 0xff]);
+        let s = String::from_utf8(crate::vec::Vec::from(&b"\xce\xba"[..])).unwrap();
+        assert_eq!(s, "κ");
+    }
+}
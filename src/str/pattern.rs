@@ -0,0 +1,1208 @@
+//! Patterns for searching string slices.
+//!
+//! A [`Pattern`] turns into a [`Searcher`], which walks a haystack from the
+//! front and reports each stretch of it as a [`SearchStep`]: a match or a
+//! rejected stretch. Searchers that can also walk from the back implement
+//! [`ReverseSearcher`], and [`DoubleEndedSearcher`] marks those whose
+//! backward matches are the forward ones in reverse, which is what lets
+//! [`split`](super::split) and [`matches`](super::matches) be
+//! double-ended.
+//!
+//! Patterns are implemented for:
+//!
+//! - `char`, which scans for the last byte of its UTF-8 encoding;
+//! - `&str`, which uses the Two-Way algorithm of Crochemore and Perrin,
+//!   linear in the worst case and without allocating;
+//! - `&[char]` and `FnMut(char) -> bool`, which test one char at a time.
+//!
+//! ```
+//! use mystdrs::str::pattern::{Pattern, SearchStep, Searcher};
+//!
+//! let mut searcher = "na".into_searcher("banana");
+//! assert_eq!(searcher.next(), SearchStep::Reject(0, 2));
+//! assert_eq!(searcher.next(), SearchStep::Match(2, 4));
+//! assert_eq!(searcher.next_match(), Some((4, 6)));
+//! assert_eq!(searcher.next(), SearchStep::Done);
+//! ```
+
+use crate::alloc::Allocator;
+use crate::string::String;
+use std::cmp;
+use std::str::CharIndices;
+
+/// Something that can be searched for in a string slice.
+pub trait Pattern<'a>: Sized {
+    /// The searcher this pattern turns into.
+    type Searcher: Searcher<'a>;
+
+    /// Returns a searcher for this pattern over `haystack`.
+    fn into_searcher(self, haystack: &'a str) -> Self::Searcher;
+
+    /// Returns `haystack` without a match at its start, or `None` if there
+    /// is none.
+    fn strip_prefix_of(self, haystack: &'a str) -> Option<&'a str> {
+        match self.into_searcher(haystack).next() {
+            SearchStep::Match(0, end) => Some(unsafe { haystack.get_unchecked(end..) }),
+            _ => None,
+        }
+    }
+}
+
+/// One stretch of a haystack, reported by a [`Searcher`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SearchStep {
+    /// `haystack[a..b]` matches the pattern.
+    Match(usize, usize),
+    /// `haystack[a..b]` holds no match. Several rejects may come in a row.
+    Reject(usize, usize),
+    /// Every byte of the haystack has been reported.
+    Done,
+}
+
+/// Walks a haystack from the front, reporting matches and rejects.
+///
+/// # Safety
+///
+/// The steps returned by [`next`](Searcher::next) must be non-overlapping,
+/// must cover the whole haystack without gaps, and must start and end on
+/// char boundaries. Callers slice the haystack at the reported indices
+/// without checking them.
+pub unsafe trait Searcher<'a> {
+    /// Returns the haystack being searched.
+    fn haystack(&self) -> &'a str;
+
+    /// Reports the next stretch of the haystack.
+    fn next(&mut self) -> SearchStep;
+
+    /// Returns the next match, skipping rejects.
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        loop {
+            match self.next() {
+                SearchStep::Match(a, b) => return Some((a, b)),
+                SearchStep::Done => return None,
+                SearchStep::Reject(..) => {}
+            }
+        }
+    }
+
+    /// Returns the next reject, skipping matches.
+    fn next_reject(&mut self) -> Option<(usize, usize)> {
+        loop {
+            match self.next() {
+                SearchStep::Reject(a, b) => return Some((a, b)),
+                SearchStep::Done => return None,
+                SearchStep::Match(..) => {}
+            }
+        }
+    }
+}
+
+/// A [`Searcher`] that can also walk from the back of the haystack.
+///
+/// # Safety
+///
+/// The same rules as for [`Searcher`] apply to
+/// [`next_back`](ReverseSearcher::next_back).
+pub unsafe trait ReverseSearcher<'a>: Searcher<'a> {
+    /// Reports the next stretch of the haystack from the back.
+    fn next_back(&mut self) -> SearchStep;
+
+    /// Returns the next match from the back, skipping rejects.
+    fn next_match_back(&mut self) -> Option<(usize, usize)> {
+        loop {
+            match self.next_back() {
+                SearchStep::Match(a, b) => return Some((a, b)),
+                SearchStep::Done => return None,
+                SearchStep::Reject(..) => {}
+            }
+        }
+    }
+
+    /// Returns the next reject from the back, skipping matches.
+    fn next_reject_back(&mut self) -> Option<(usize, usize)> {
+        loop {
+            match self.next_back() {
+                SearchStep::Reject(a, b) => return Some((a, b)),
+                SearchStep::Done => return None,
+                SearchStep::Match(..) => {}
+            }
+        }
+    }
+}
+
+/// Marks a [`ReverseSearcher`] whose matches from the back are exactly its
+/// matches from the front, in reverse.
+///
+/// A `char` searcher qualifies. A `&str` searcher does not: searching `"aa"`
+/// in `"aaa"` finds `0..2` from the front but `1..3` from the back.
+pub trait DoubleEndedSearcher<'a>: ReverseSearcher<'a> {}
+
+impl<'a> Pattern<'a> for char {
+    type Searcher = CharSearcher<'a>;
+
+    fn into_searcher(self, haystack: &'a str) -> CharSearcher<'a> {
+        let mut utf8_encoded = [0; 4];
+        let utf8_size = self.encode_utf8(&mut utf8_encoded).len();
+        CharSearcher {
+            haystack,
+            finger: 0,
+            finger_back: haystack.len(),
+            needle: self,
+            utf8_size,
+            utf8_encoded,
+        }
+    }
+
+    fn strip_prefix_of(self, haystack: &'a str) -> Option<&'a str> {
+        let mut chars = haystack.chars();
+        if chars.next() == Some(self) {
+            Some(chars.as_str())
+        } else {
+            None
+        }
+    }
+}
+
+/// The searcher for a `char`.
+///
+/// Matches are found by scanning for the last byte of the char's UTF-8
+/// encoding and then checking the bytes before it. The last byte of a
+/// multi-byte char is a continuation byte, so a hit can never straddle the
+/// start of the unsearched part.
+#[derive(Clone, Debug)]
+pub struct CharSearcher<'a> {
+    haystack: &'a str,
+    /// Start of the unsearched part.
+    finger: usize,
+    /// End of the unsearched part.
+    finger_back: usize,
+    needle: char,
+    utf8_size: usize,
+    utf8_encoded: [u8; 4],
+}
+
+impl CharSearcher<'_> {
+    fn last_byte(&self) -> u8 {
+        self.utf8_encoded[self.utf8_size - 1]
+    }
+
+    fn is_match_at(&self, start: usize) -> bool {
+        self.haystack.as_bytes().get(start..start + self.utf8_size)
+            == Some(&self.utf8_encoded[..self.utf8_size])
+    }
+}
+
+unsafe impl<'a> Searcher<'a> for CharSearcher<'a> {
+    fn haystack(&self) -> &'a str {
+        self.haystack
+    }
+
+    fn next(&mut self) -> SearchStep {
+        let start = self.finger;
+        let rest = unsafe { self.haystack.get_unchecked(start..self.finger_back) };
+        match rest.chars().next() {
+            Some(ch) => {
+                self.finger += ch.len_utf8();
+                if ch == self.needle {
+                    SearchStep::Match(start, self.finger)
+                } else {
+                    SearchStep::Reject(start, self.finger)
+                }
+            }
+            None => SearchStep::Done,
+        }
+    }
+
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        let last_byte = self.last_byte();
+        loop {
+            let bytes = &self.haystack.as_bytes()[self.finger..self.finger_back];
+            let end = match bytes.iter().position(|&b| b == last_byte) {
+                Some(index) => self.finger + index + 1,
+                None => {
+                    self.finger = self.finger_back;
+                    return None;
+                }
+            };
+            self.finger = end;
+            if let Some(start) = end.checked_sub(self.utf8_size) {
+                if self.is_match_at(start) {
+                    return Some((start, end));
+                }
+            }
+            // The byte may have been inside another char.
+            while !self.haystack.is_char_boundary(self.finger) {
+                self.finger += 1;
+            }
+        }
+    }
+}
+
+unsafe impl<'a> ReverseSearcher<'a> for CharSearcher<'a> {
+    fn next_back(&mut self) -> SearchStep {
+        let end = self.finger_back;
+        let rest = unsafe { self.haystack.get_unchecked(self.finger..end) };
+        match rest.chars().next_back() {
+            Some(ch) => {
+                self.finger_back -= ch.len_utf8();
+                if ch == self.needle {
+                    SearchStep::Match(self.finger_back, end)
+                } else {
+                    SearchStep::Reject(self.finger_back, end)
+                }
+            }
+            None => SearchStep::Done,
+        }
+    }
+
+    fn next_match_back(&mut self) -> Option<(usize, usize)> {
+        let last_byte = self.last_byte();
+        loop {
+            let bytes = &self.haystack.as_bytes()[self.finger..self.finger_back];
+            let last = match bytes.iter().rposition(|&b| b == last_byte) {
+                Some(index) => self.finger + index,
+                None => {
+                    self.finger_back = self.finger;
+                    return None;
+                }
+            };
+            if let Some(start) = (last + 1).checked_sub(self.utf8_size) {
+                if start >= self.finger && self.is_match_at(start) {
+                    self.finger_back = start;
+                    return Some((start, last + 1));
+                }
+            }
+            // Nothing at or after the byte can match, and the char it is
+            // part of starts at the boundary before it.
+            self.finger_back = last;
+            while !self.haystack.is_char_boundary(self.finger_back) {
+                self.finger_back -= 1;
+            }
+        }
+    }
+}
+
+impl<'a> DoubleEndedSearcher<'a> for CharSearcher<'a> {}
+
+/// Something that decides, one char at a time, whether a char matches.
+trait MultiCharEq {
+    fn matches(&mut self, ch: char) -> bool;
+}
+
+impl<F: FnMut(char) -> bool> MultiCharEq for F {
+    fn matches(&mut self, ch: char) -> bool {
+        self(ch)
+    }
+}
+
+impl MultiCharEq for &[char] {
+    fn matches(&mut self, ch: char) -> bool {
+        self.contains(&ch)
+    }
+}
+
+/// The searcher shared by patterns that test one char at a time.
+#[derive(Clone, Debug)]
+struct MultiCharEqSearcher<'a, C> {
+    haystack: &'a str,
+    char_indices: CharIndices<'a>,
+    char_eq: C,
+}
+
+impl<'a, C> MultiCharEqSearcher<'a, C> {
+    fn new(haystack: &'a str, char_eq: C) -> Self {
+        MultiCharEqSearcher {
+            haystack,
+            char_indices: haystack.char_indices(),
+            char_eq,
+        }
+    }
+}
+
+unsafe impl<'a, C: MultiCharEq> Searcher<'a> for MultiCharEqSearcher<'a, C> {
+    fn haystack(&self) -> &'a str {
+        self.haystack
+    }
+
+    fn next(&mut self) -> SearchStep {
+        match self.char_indices.next() {
+            Some((i, ch)) => {
+                let end = i + ch.len_utf8();
+                if self.char_eq.matches(ch) {
+                    SearchStep::Match(i, end)
+                } else {
+                    SearchStep::Reject(i, end)
+                }
+            }
+            None => SearchStep::Done,
+        }
+    }
+}
+
+unsafe impl<'a, C: MultiCharEq> ReverseSearcher<'a> for MultiCharEqSearcher<'a, C> {
+    fn next_back(&mut self) -> SearchStep {
+        match self.char_indices.next_back() {
+            Some((i, ch)) => {
+                let end = i + ch.len_utf8();
+                if self.char_eq.matches(ch) {
+                    SearchStep::Match(i, end)
+                } else {
+                    SearchStep::Reject(i, end)
+                }
+            }
+            None => SearchStep::Done,
+        }
+    }
+}
+
+/// Implements the searcher traits for a wrapper around
+/// `MultiCharEqSearcher`.
+macro_rules! multi_char_eq_searcher {
+    (impl<$($param:tt),*> for $searcher:ty $(where $($bound:tt)+)?) => {
+        unsafe impl<$($param),*> Searcher<'a> for $searcher $(where $($bound)+)? {
+            fn haystack(&self) -> &'a str {
+                self.0.haystack()
+            }
+
+            fn next(&mut self) -> SearchStep {
+                self.0.next()
+            }
+        }
+
+        unsafe impl<$($param),*> ReverseSearcher<'a> for $searcher $(where $($bound)+)? {
+            fn next_back(&mut self) -> SearchStep {
+                self.0.next_back()
+            }
+        }
+
+        impl<$($param),*> DoubleEndedSearcher<'a> for $searcher $(where $($bound)+)? {}
+    };
+}
+
+impl<'a, 'b> Pattern<'a> for &'b [char] {
+    type Searcher = CharSliceSearcher<'a, 'b>;
+
+    fn into_searcher(self, haystack: &'a str) -> CharSliceSearcher<'a, 'b> {
+        CharSliceSearcher(MultiCharEqSearcher::new(haystack, self))
+    }
+}
+
+/// The searcher for a `&[char]`, matching any char in the slice.
+#[derive(Clone, Debug)]
+pub struct CharSliceSearcher<'a, 'b>(MultiCharEqSearcher<'a, &'b [char]>);
+
+multi_char_eq_searcher!(impl<'a, 'b> for CharSliceSearcher<'a, 'b>);
+
+impl<'a, F: FnMut(char) -> bool> Pattern<'a> for F {
+    type Searcher = CharPredicateSearcher<'a, F>;
+
+    fn into_searcher(self, haystack: &'a str) -> CharPredicateSearcher<'a, F> {
+        CharPredicateSearcher(MultiCharEqSearcher::new(haystack, self))
+    }
+}
+
+/// The searcher for a `FnMut(char) -> bool`, matching every char it
+/// accepts.
+#[derive(Clone)]
+pub struct CharPredicateSearcher<'a, F>(MultiCharEqSearcher<'a, F>);
+
+impl<F> std::fmt::Debug for CharPredicateSearcher<'_, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CharPredicateSearcher")
+            .field("haystack", &self.0.haystack)
+            .field("char_indices", &self.0.char_indices)
+            .finish()
+    }
+}
+
+multi_char_eq_searcher!(impl<'a, F> for CharPredicateSearcher<'a, F> where F: FnMut(char) -> bool);
+
+impl<'a, 'b> Pattern<'a> for &'b str {
+    type Searcher = StrSearcher<'a, 'b>;
+
+    fn into_searcher(self, haystack: &'a str) -> StrSearcher<'a, 'b> {
+        StrSearcher::new(haystack, self)
+    }
+
+    fn strip_prefix_of(self, haystack: &'a str) -> Option<&'a str> {
+        if haystack.as_bytes().starts_with(self.as_bytes()) {
+            Some(unsafe { haystack.get_unchecked(self.len()..) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, 'b, A: Allocator> Pattern<'a> for &'b String<A> {
+    type Searcher = StrSearcher<'a, 'b>;
+
+    fn into_searcher(self, haystack: &'a str) -> StrSearcher<'a, 'b> {
+        self.as_str().into_searcher(haystack)
+    }
+
+    fn strip_prefix_of(self, haystack: &'a str) -> Option<&'a str> {
+        self.as_str().strip_prefix_of(haystack)
+    }
+}
+
+/// The searcher for a `&str`.
+#[derive(Clone, Debug)]
+pub struct StrSearcher<'a, 'b> {
+    haystack: &'a str,
+    needle: &'b str,
+    searcher: StrSearcherImpl,
+}
+
+#[derive(Clone, Debug)]
+enum StrSearcherImpl {
+    Empty(EmptyNeedle),
+    TwoWay(TwoWaySearcher),
+}
+
+/// The empty needle matches at every char boundary, so its searcher
+/// alternates between an empty match and a one-char reject.
+#[derive(Clone, Debug)]
+struct EmptyNeedle {
+    position: usize,
+    end: usize,
+    is_match_fw: bool,
+    is_match_bw: bool,
+    is_finished: bool,
+}
+
+impl<'a, 'b> StrSearcher<'a, 'b> {
+    fn new(haystack: &'a str, needle: &'b str) -> Self {
+        let searcher = if needle.is_empty() {
+            StrSearcherImpl::Empty(EmptyNeedle {
+                position: 0,
+                end: haystack.len(),
+                is_match_fw: true,
+                is_match_bw: true,
+                is_finished: false,
+            })
+        } else {
+            StrSearcherImpl::TwoWay(TwoWaySearcher::new(needle.as_bytes(), haystack.len()))
+        };
+        StrSearcher {
+            haystack,
+            needle,
+            searcher,
+        }
+    }
+}
+
+unsafe impl<'a> Searcher<'a> for StrSearcher<'a, '_> {
+    fn haystack(&self) -> &'a str {
+        self.haystack
+    }
+
+    fn next(&mut self) -> SearchStep {
+        match self.searcher {
+            StrSearcherImpl::Empty(ref mut searcher) => {
+                if searcher.is_finished {
+                    return SearchStep::Done;
+                }
+                let is_match = searcher.is_match_fw;
+                searcher.is_match_fw = !searcher.is_match_fw;
+                let pos = searcher.position;
+                match self.haystack[pos..searcher.end].chars().next() {
+                    _ if is_match => SearchStep::Match(pos, pos),
+                    None => {
+                        searcher.is_finished = true;
+                        SearchStep::Done
+                    }
+                    Some(ch) => {
+                        searcher.position += ch.len_utf8();
+                        SearchStep::Reject(pos, searcher.position)
+                    }
+                }
+            }
+            StrSearcherImpl::TwoWay(ref mut searcher) => {
+                if searcher.position == self.haystack.len() {
+                    return SearchStep::Done;
+                }
+                let long_period = searcher.memory == usize::MAX;
+                let step = searcher.next::<RejectAndMatch>(
+                    self.haystack.as_bytes(),
+                    self.needle.as_bytes(),
+                    long_period,
+                );
+                match step {
+                    SearchStep::Reject(a, mut b) => {
+                        // The searcher skips by bytes, so round the reject
+                        // up to a char boundary.
+                        while !self.haystack.is_char_boundary(b) {
+                            b += 1;
+                        }
+                        searcher.position = cmp::max(b, searcher.position);
+                        SearchStep::Reject(a, b)
+                    }
+                    step => step,
+                }
+            }
+        }
+    }
+
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        match self.searcher {
+            StrSearcherImpl::Empty(..) => loop {
+                match self.next() {
+                    SearchStep::Match(a, b) => return Some((a, b)),
+                    SearchStep::Done => return None,
+                    SearchStep::Reject(..) => {}
+                }
+            },
+            StrSearcherImpl::TwoWay(ref mut searcher) => {
+                let long_period = searcher.memory == usize::MAX;
+                searcher.next::<MatchOnly>(
+                    self.haystack.as_bytes(),
+                    self.needle.as_bytes(),
+                    long_period,
+                )
+            }
+        }
+    }
+}
+
+unsafe impl<'a> ReverseSearcher<'a> for StrSearcher<'a, '_> {
+    fn next_back(&mut self) -> SearchStep {
+        match self.searcher {
+            StrSearcherImpl::Empty(ref mut searcher) => {
+                if searcher.is_finished {
+                    return SearchStep::Done;
+                }
+                let is_match = searcher.is_match_bw;
+                searcher.is_match_bw = !searcher.is_match_bw;
+                let end = searcher.end;
+                match self.haystack[searcher.position..end].chars().next_back() {
+                    _ if is_match => SearchStep::Match(end, end),
+                    None => {
+                        searcher.is_finished = true;
+                        SearchStep::Done
+                    }
+                    Some(ch) => {
+                        searcher.end -= ch.len_utf8();
+                        SearchStep::Reject(searcher.end, end)
+                    }
+                }
+            }
+            StrSearcherImpl::TwoWay(ref mut searcher) => {
+                if searcher.end == 0 {
+                    return SearchStep::Done;
+                }
+                let long_period = searcher.memory == usize::MAX;
+                let step = searcher.next_back::<RejectAndMatch>(
+                    self.haystack.as_bytes(),
+                    self.needle.as_bytes(),
+                    long_period,
+                );
+                match step {
+                    SearchStep::Reject(mut a, b) => {
+                        while !self.haystack.is_char_boundary(a) {
+                            a -= 1;
+                        }
+                        searcher.end = cmp::min(a, searcher.end);
+                        SearchStep::Reject(a, b)
+                    }
+                    step => step,
+                }
+            }
+        }
+    }
+
+    fn next_match_back(&mut self) -> Option<(usize, usize)> {
+        match self.searcher {
+            StrSearcherImpl::Empty(..) => loop {
+                match self.next_back() {
+                    SearchStep::Match(a, b) => return Some((a, b)),
+                    SearchStep::Done => return None,
+                    SearchStep::Reject(..) => {}
+                }
+            },
+            StrSearcherImpl::TwoWay(ref mut searcher) => {
+                let long_period = searcher.memory == usize::MAX;
+                searcher.next_back::<MatchOnly>(
+                    self.haystack.as_bytes(),
+                    self.needle.as_bytes(),
+                    long_period,
+                )
+            }
+        }
+    }
+}
+
+/// The Two-Way string matching algorithm of Crochemore and Perrin.
+///
+/// The needle is split at a critical factorization `needle[..crit_pos]`,
+/// `needle[crit_pos..]`. Each attempt compares the right half left to
+/// right, then the left half right to left. A mismatch in the right half
+/// at `i` shifts the window by `i - crit_pos + 1`, and a mismatch in the
+/// left half shifts it by the needle's period. Both shifts are safe
+/// because of the factorization, so no byte of the haystack is compared
+/// more than twice.
+///
+/// When the needle is periodic (its left half occurs again one period
+/// later), a full period shift keeps the bytes `memory` already known to
+/// match, so they are not compared again. Otherwise the period is replaced
+/// by a lower bound that is still a safe shift, and `memory` is
+/// `usize::MAX` to mark this long-period mode.
+///
+/// `byteset` has bit `b & 63` set for each byte `b` in the needle, so a
+/// window whose last byte cannot be in the needle is skipped at once.
+#[derive(Clone, Debug)]
+struct TwoWaySearcher {
+    crit_pos: usize,
+    /// The critical position used when searching backwards.
+    crit_pos_back: usize,
+    period: usize,
+    byteset: u64,
+    /// Start of the next forward window.
+    position: usize,
+    /// End of the next backward window.
+    end: usize,
+    /// How much of the next forward window's start is known to match.
+    memory: usize,
+    /// Where the part of the next backward window known to match begins.
+    memory_back: usize,
+}
+
+impl TwoWaySearcher {
+    fn new(needle: &[u8], end: usize) -> Self {
+        let (crit_pos_less, period_less) = maximal_suffix(needle, false);
+        let (crit_pos_greater, period_greater) = maximal_suffix(needle, true);
+        // The later of the two maximal suffixes gives a critical
+        // factorization.
+        let (crit_pos, period) = if crit_pos_less > crit_pos_greater {
+            (crit_pos_less, period_less)
+        } else {
+            (crit_pos_greater, period_greater)
+        };
+
+        if needle[..crit_pos] == needle[period..period + crit_pos] {
+            // The needle is periodic. The backward search needs its own
+            // critical position, found from the reversed needle.
+            let crit_pos_back = needle.len()
+                - cmp::max(
+                    reverse_maximal_suffix(needle, period, false),
+                    reverse_maximal_suffix(needle, period, true),
+                );
+            TwoWaySearcher {
+                crit_pos,
+                crit_pos_back,
+                period,
+                byteset: byteset(&needle[..period]),
+                position: 0,
+                end,
+                memory: 0,
+                memory_back: needle.len(),
+            }
+        } else {
+            TwoWaySearcher {
+                crit_pos,
+                crit_pos_back: crit_pos,
+                period: cmp::max(crit_pos, needle.len() - crit_pos) + 1,
+                byteset: byteset(needle),
+                position: 0,
+                end,
+                memory: usize::MAX,
+                memory_back: usize::MAX,
+            }
+        }
+    }
+
+    fn byteset_contains(&self, byte: u8) -> bool {
+        (self.byteset >> (byte & 0x3F)) & 1 != 0
+    }
+
+    fn next<S: TwoWayStrategy>(
+        &mut self,
+        haystack: &[u8],
+        needle: &[u8],
+        long_period: bool,
+    ) -> S::Output {
+        let old_pos = self.position;
+        let needle_last = needle.len() - 1;
+        'search: loop {
+            let tail_byte = match haystack.get(self.position.wrapping_add(needle_last)) {
+                Some(&b) => b,
+                None => {
+                    self.position = haystack.len();
+                    return S::rejecting(old_pos, self.position);
+                }
+            };
+            if S::use_early_reject() && old_pos != self.position {
+                return S::rejecting(old_pos, self.position);
+            }
+
+            if !self.byteset_contains(tail_byte) {
+                self.position += needle.len();
+                if !long_period {
+                    self.memory = 0;
+                }
+                continue 'search;
+            }
+
+            let start = if long_period {
+                self.crit_pos
+            } else {
+                cmp::max(self.crit_pos, self.memory)
+            };
+            for i in start..needle.len() {
+                if needle[i] != haystack[self.position + i] {
+                    self.position += i - self.crit_pos + 1;
+                    if !long_period {
+                        self.memory = 0;
+                    }
+                    continue 'search;
+                }
+            }
+
+            let start = if long_period { 0 } else { self.memory };
+            for i in (start..self.crit_pos).rev() {
+                if needle[i] != haystack[self.position + i] {
+                    self.position += self.period;
+                    if !long_period {
+                        self.memory = needle.len() - self.period;
+                    }
+                    continue 'search;
+                }
+            }
+
+            let match_pos = self.position;
+            self.position += needle.len();
+            if !long_period {
+                self.memory = 0;
+            }
+            return S::matching(match_pos, match_pos + needle.len());
+        }
+    }
+
+    /// The mirror image of [`next`](TwoWaySearcher::next): the left half is
+    /// compared right to left first, then the right half left to right.
+    fn next_back<S: TwoWayStrategy>(
+        &mut self,
+        haystack: &[u8],
+        needle: &[u8],
+        long_period: bool,
+    ) -> S::Output {
+        let old_end = self.end;
+        'search: loop {
+            let front_byte = match haystack.get(self.end.wrapping_sub(needle.len())) {
+                Some(&b) => b,
+                None => {
+                    self.end = 0;
+                    return S::rejecting(0, old_end);
+                }
+            };
+            if S::use_early_reject() && old_end != self.end {
+                return S::rejecting(self.end, old_end);
+            }
+
+            if !self.byteset_contains(front_byte) {
+                self.end -= needle.len();
+                if !long_period {
+                    self.memory_back = needle.len();
+                }
+                continue 'search;
+            }
+
+            let crit = if long_period {
+                self.crit_pos_back
+            } else {
+                cmp::min(self.crit_pos_back, self.memory_back)
+            };
+            for i in (0..crit).rev() {
+                if needle[i] != haystack[self.end - needle.len() + i] {
+                    self.end -= self.crit_pos_back - i;
+                    if !long_period {
+                        self.memory_back = needle.len();
+                    }
+                    continue 'search;
+                }
+            }
+
+            let needle_end = if long_period {
+                needle.len()
+            } else {
+                self.memory_back
+            };
+            for i in self.crit_pos_back..needle_end {
+                if needle[i] != haystack[self.end - needle.len() + i] {
+                    self.end -= self.period;
+                    if !long_period {
+                        self.memory_back = self.period;
+                    }
+                    continue 'search;
+                }
+            }
+
+            let match_pos = self.end - needle.len();
+            self.end -= needle.len();
+            if !long_period {
+                self.memory_back = needle.len();
+            }
+            return S::matching(match_pos, match_pos + needle.len());
+        }
+    }
+}
+
+fn byteset(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |set, &b| set | 1 << (b & 0x3F))
+}
+
+/// Returns the start of the maximal suffix of `arr` in lexicographic order
+/// (reversed if `order_greater`) and the period of that suffix.
+fn maximal_suffix(arr: &[u8], order_greater: bool) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+
+    while let Some(&a) = arr.get(right + offset) {
+        let b = arr[left + offset];
+        if (a < b && !order_greater) || (a > b && order_greater) {
+            // The suffix at `right` is smaller, so it extends the period.
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            // The suffix at `right` is larger and becomes the candidate.
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (left, period)
+}
+
+/// Like [`maximal_suffix`] on the reversed `arr`, returning the length of
+/// the maximal suffix's complement. Stops early once the period reaches
+/// `known_period`, the period of the whole needle.
+fn reverse_maximal_suffix(arr: &[u8], known_period: usize, order_greater: bool) -> usize {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+    let n = arr.len();
+
+    while right + offset < n {
+        let a = arr[n - (1 + right + offset)];
+        let b = arr[n - (1 + left + offset)];
+        if (a < b && !order_greater) || (a > b && order_greater) {
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+        if period == known_period {
+            break;
+        }
+    }
+    debug_assert!(period <= known_period);
+    left
+}
+
+/// Whether [`TwoWaySearcher`] reports rejects as it goes or only matches.
+trait TwoWayStrategy {
+    type Output;
+    fn use_early_reject() -> bool;
+    fn rejecting(a: usize, b: usize) -> Self::Output;
+    fn matching(a: usize, b: usize) -> Self::Output;
+}
+
+/// Skips straight to the next match.
+enum MatchOnly {}
+
+impl TwoWayStrategy for MatchOnly {
+    type Output = Option<(usize, usize)>;
+
+    fn use_early_reject() -> bool {
+        false
+    }
+
+    fn rejecting(_: usize, _: usize) -> Self::Output {
+        None
+    }
+
+    fn matching(a: usize, b: usize) -> Self::Output {
+        Some((a, b))
+    }
+}
+
+/// Reports each stretch skipped as a reject, for [`Searcher::next`].
+enum RejectAndMatch {}
+
+impl TwoWayStrategy for RejectAndMatch {
+    type Output = SearchStep;
+
+    fn use_early_reject() -> bool {
+        true
+    }
+
+    fn rejecting(a: usize, b: usize) -> SearchStep {
+        SearchStep::Reject(a, b)
+    }
+
+    fn matching(a: usize, b: usize) -> SearchStep {
+        SearchStep::Match(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, ReverseSearcher, SearchStep, Searcher};
+    use crate::str;
+    use crate::test_util::XorShift;
+
+    /// Compares every searching function with std's method of the same
+    /// name. `$pat` is evaluated afresh for each call.
+    macro_rules! check_against_std {
+        ($haystack:expr, $pat:expr) => {{
+            let h: &str = $haystack;
+            assert_eq!(str::find(h, $pat), h.find($pat), "{:?}", h);
+            assert_eq!(str::rfind(h, $pat), h.rfind($pat), "{:?}", h);
+            assert_eq!(
+                str::split(h, $pat).collect::<Vec<_>>(),
+                h.split($pat).collect::<Vec<_>>(),
+                "{:?}",
+                h
+            );
+            assert_eq!(
+                str::rsplit(h, $pat).collect::<Vec<_>>(),
+                h.rsplit($pat).collect::<Vec<_>>(),
+                "{:?}",
+                h
+            );
+            for n in 0..4 {
+                assert_eq!(
+                    str::splitn(h, n, $pat).collect::<Vec<_>>(),
+                    h.splitn(n, $pat).collect::<Vec<_>>(),
+                    "{:?}",
+                    h
+                );
+            }
+            assert_eq!(
+                str::matches(h, $pat).collect::<Vec<_>>(),
+                h.matches($pat).collect::<Vec<_>>(),
+                "{:?}",
+                h
+            );
+            assert_eq!(
+                str::match_indices(h, $pat).collect::<Vec<_>>(),
+                h.match_indices($pat).collect::<Vec<_>>(),
+                "{:?}",
+                h
+            );
+            assert_eq!(str::strip_prefix(h, $pat), h.strip_prefix($pat), "{:?}", h);
+        }};
+    }
+
+    /// Like `check_against_std!`, plus the functions that need a
+    /// double-ended searcher.
+    macro_rules! check_double_ended_against_std {
+        ($haystack:expr, $pat:expr) => {{
+            let h: &str = $haystack;
+            check_against_std!(h, $pat);
+            assert_eq!(
+                str::split(h, $pat).rev().collect::<Vec<_>>(),
+                h.split($pat).rev().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                str::match_indices(h, $pat).rev().collect::<Vec<_>>(),
+                h.match_indices($pat).rev().collect::<Vec<_>>()
+            );
+            assert_eq!(str::trim_matches(h, $pat), h.trim_matches($pat), "{:?}", h);
+
+            // Alternate ends, so that both fingers move within one searcher.
+            let (mut ours, mut std) = (str::split(h, $pat), h.split($pat));
+            for i in 0.. {
+                let (a, b) = if i % 2 == 0 {
+                    (ours.next(), std.next())
+                } else {
+                    (ours.next_back(), std.next_back())
+                };
+                assert_eq!(a, b, "{:?}", h);
+                if a.is_none() {
+                    break;
+                }
+            }
+        }};
+    }
+
+    /// Checks that the steps of `searcher` tile the haystack on char
+    /// boundaries and returns the matches among them.
+    fn forward_steps<'a, S: Searcher<'a>>(mut searcher: S) -> Vec<(usize, usize)> {
+        let haystack = searcher.haystack();
+        let (mut pos, mut found) = (0, Vec::new());
+        loop {
+            let (a, b) = match searcher.next() {
+                SearchStep::Match(a, b) => {
+                    found.push((a, b));
+                    (a, b)
+                }
+                SearchStep::Reject(a, b) => {
+                    assert!(a < b, "{:?}", haystack);
+                    (a, b)
+                }
+                SearchStep::Done => break,
+            };
+            assert_eq!(a, pos, "{:?}", haystack);
+            assert!(haystack.is_char_boundary(b), "{:?}", haystack);
+            pos = b;
+        }
+        assert_eq!(pos, haystack.len(), "{:?}", haystack);
+        found
+    }
+
+    fn backward_steps<'a, S: ReverseSearcher<'a>>(mut searcher: S) -> Vec<(usize, usize)> {
+        let haystack = searcher.haystack();
+        let (mut pos, mut found) = (haystack.len(), Vec::new());
+        loop {
+            let (a, b) = match searcher.next_back() {
+                SearchStep::Match(a, b) => {
+                    found.push((a, b));
+                    (a, b)
+                }
+                SearchStep::Reject(a, b) => {
+                    assert!(a < b, "{:?}", haystack);
+                    (a, b)
+                }
+                SearchStep::Done => break,
+            };
+            assert_eq!(b, pos, "{:?}", haystack);
+            assert!(haystack.is_char_boundary(a), "{:?}", haystack);
+            pos = a;
+        }
+        assert_eq!(pos, 0, "{:?}", haystack);
+        found
+    }
+
+    fn check_steps<'a, P: Pattern<'a> + Copy>(haystack: &'a str, pat: P)
+    where
+        P::Searcher: ReverseSearcher<'a>,
+    {
+        let matches: Vec<_> = std::iter::from_fn({
+            let mut searcher = pat.into_searcher(haystack);
+            move || searcher.next_match()
+        })
+        .collect();
+        assert_eq!(forward_steps(pat.into_searcher(haystack)), matches);
+        let matches_back: Vec<_> = std::iter::from_fn({
+            let mut searcher = pat.into_searcher(haystack);
+            move || searcher.next_match_back()
+        })
+        .collect();
+        assert_eq!(backward_steps(pat.into_searcher(haystack)), matches_back);
+    }
+
+    fn random_string(rng: &mut XorShift, alphabet: &[char], max_len: usize) -> String {
+        (0..rng.below(max_len + 1))
+            .map(|_| alphabet[rng.below(alphabet.len())])
+            .collect()
+    }
+
+    #[test]
+    fn str_patterns_match_std() {
+        let mut rng = XorShift::new(0x2_3A7);
+        // Small alphabets make periodic needles and near misses common.
+        // 'é', 'ȩ' and '€' share bytes, so byte-level skips land inside
+        // chars.
+        for alphabet in [&['a', 'b'][..], &['a', 'b', 'é', 'ȩ', '€']] {
+            for _ in 0..3_000 {
+                let haystack = random_string(&mut rng, alphabet, 24);
+                let needle = random_string(&mut rng, alphabet, 5);
+                check_against_std!(&haystack, needle.as_str());
+                check_steps(&haystack, needle.as_str());
+            }
+        }
+        check_against_std!("", "");
+        check_against_std!("aé", "");
+        check_steps("aé€", "");
+    }
+
+    #[test]
+    fn char_patterns_match_std() {
+        let mut rng = XorShift::new(99);
+        let alphabet = ['a', 'é', 'ȩ', '€', '₩', '𝄞'];
+        for _ in 0..3_000 {
+            let haystack = random_string(&mut rng, &alphabet, 16);
+            let needle = alphabet[rng.below(alphabet.len())];
+            check_double_ended_against_std!(&haystack, needle);
+            check_steps(&haystack, needle);
+
+            let set = [alphabet[rng.below(6)], alphabet[rng.below(6)]];
+            check_double_ended_against_std!(&haystack, &set[..]);
+            check_steps(&haystack, &set[..]);
+
+            let pred = |ch: char| ch.len_utf8() == 3;
+            check_double_ended_against_std!(&haystack, pred);
+            check_steps(&haystack, pred);
+        }
+    }
+
+    #[test]
+    fn char_search_mixing_step_and_match() {
+        // `next_match` skips over a 'é' whose last byte is a hit for 'ȩ'.
+        // The searcher must leave its finger on a char boundary so that
+        // `next` can carry on.
+        let mut searcher = 'ȩ'.into_searcher("€é€ȩ");
+        assert_eq!(searcher.next_match(), Some((8, 10)));
+        assert_eq!(searcher.next(), SearchStep::Done);
+
+        // "\u{2a69}" ends in the bytes "\xa9\xa9"; neither hit is a 'é'.
+        let mut searcher = 'é'.into_searcher("x\u{2a69}y");
+        assert_eq!(searcher.next_match_back(), None);
+        let mut searcher = 'é'.into_searcher("x\u{2a69}éy");
+        assert_eq!(searcher.next_match_back(), Some((4, 6)));
+        assert_eq!(searcher.next_back(), SearchStep::Reject(1, 4));
+        assert_eq!(searcher.next_back(), SearchStep::Reject(0, 1));
+        assert_eq!(searcher.next_back(), SearchStep::Done);
+    }
+
+    #[test]
+    fn two_way_handles_periodic_needles() {
+        let cases = [
+            ("aaaaaaaaab", "aaab"),
+            ("abababababababac", "ababac"),
+            ("abcabcabcabd abcabd", "abcabd"),
+            ("aabaabaabaaab", "aabaaab"),
+            ("bbbbbbbbbbbbbbbbbbbbba", "bbbba"),
+            ("xyzxyzxyzxyxyzxyz", "xyzxyz"),
+        ];
+        for &(haystack, needle) in &cases {
+            check_against_std!(haystack, needle);
+            check_steps(haystack, needle);
+        }
+        let haystack = "a".repeat(1_000) + "b";
+        let needle = "a".repeat(100) + "b";
+        assert_eq!(str::find(&haystack, needle.as_str()), Some(900));
+        assert_eq!(str::rfind(&haystack, needle.as_str()), Some(900));
+        assert_eq!(str::find(&haystack, "ba"), None);
+    }
+
+    #[test]
+    fn string_pattern() {
+        let needle = crate::string::String::from("ab");
+        assert_eq!(str::find("xxabab", &needle), Some(2));
+        assert_eq!(str::strip_prefix("abc", &needle), Some("c"));
+    }
+}
@@ -0,0 +1,351 @@
+//! UTF-8 validation.
+//!
+//! Runs of ASCII are skipped a word at a time. Everything else goes through
+//! a table-driven DFA: each byte is mapped to one of a dozen classes, and
+//! the class and current state index the transition table. The states
+//! encode exactly which continuation bytes may come next, so overlong
+//! forms, surrogates and values past U+10FFFF are rejected at the first
+//! byte that rules them out, which is also where [`Utf8Error::error_len`]
+//! has to stop.
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+/// An error returned when a byte slice is not UTF-8.
+///
+/// ```
+/// use mystdrs::str;
+///
+/// let err = str::from_utf8(b"ab\xE2\x82z").unwrap_err();
+/// assert_eq!(err.valid_up_to(), 2);
+/// assert_eq!(err.error_len(), Some(2));
+///
+/// let err = str::from_utf8(b"ab\xE2\x82").unwrap_err();
+/// assert_eq!(err.error_len(), None);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Utf8Error {
+    valid_up_to: usize,
+    error_len: Option<u8>,
+}
+
+impl Utf8Error {
+    /// Returns the length of the longest valid prefix of the input.
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /// Returns the length of the invalid sequence after
+    /// [`valid_up_to`](Utf8Error::valid_up_to): the longest prefix of a
+    /// valid sequence, or one byte if none starts there. Returns `None` if
+    /// the input ended partway through a sequence that more bytes could
+    /// have completed.
+    pub fn error_len(&self) -> Option<usize> {
+        self.error_len.map(usize::from)
+    }
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error_len {
+            Some(error_len) => write!(
+                f,
+                "invalid utf-8 sequence of {} bytes from index {}",
+                error_len, self.valid_up_to
+            ),
+            None => write!(
+                f,
+                "incomplete utf-8 byte sequence from index {}",
+                self.valid_up_to
+            ),
+        }
+    }
+}
+
+impl Error for Utf8Error {}
+
+// Byte classes.
+const ASCII: u8 = 0;
+/// `80..=8F`
+const CONT_LOW: u8 = 1;
+/// `90..=9F`
+const CONT_MID: u8 = 2;
+/// `A0..=BF`
+const CONT_HIGH: u8 = 3;
+/// `C2..=DF`
+const LEAD_2: u8 = 4;
+const LEAD_E0: u8 = 5;
+/// `E1..=EC` and `EE..=EF`
+const LEAD_3: u8 = 6;
+const LEAD_ED: u8 = 7;
+const LEAD_F0: u8 = 8;
+/// `F1..=F3`
+const LEAD_4: u8 = 9;
+const LEAD_F4: u8 = 10;
+/// `C0`, `C1` and `F5..=FF`, which never appear in UTF-8.
+const INVALID: u8 = 11;
+const CLASSES: usize = 12;
+
+// States, named after what the next byte must be.
+const ACCEPT: u8 = 0;
+const REJECT: u8 = 1;
+/// Any continuation byte, then done.
+const NEED_1: u8 = 2;
+/// Any two continuation bytes.
+const NEED_2: u8 = 3;
+/// Any three continuation bytes.
+const NEED_3: u8 = 4;
+/// After `E0`: `A0..=BF`, or the sequence would be overlong.
+const AFTER_E0: u8 = 5;
+/// After `ED`: `80..=9F`, or it would encode a surrogate.
+const AFTER_ED: u8 = 6;
+/// After `F0`: `90..=BF`, or the sequence would be overlong.
+const AFTER_F0: u8 = 7;
+/// After `F4`: `80..=8F`, or it would pass U+10FFFF.
+const AFTER_F4: u8 = 8;
+const STATES: usize = 9;
+
+const fn class_of(byte: u8) -> u8 {
+    match byte {
+        0x00..=0x7F => ASCII,
+        0x80..=0x8F => CONT_LOW,
+        0x90..=0x9F => CONT_MID,
+        0xA0..=0xBF => CONT_HIGH,
+        0xC2..=0xDF => LEAD_2,
+        0xE0 => LEAD_E0,
+        0xE1..=0xEC | 0xEE..=0xEF => LEAD_3,
+        0xED => LEAD_ED,
+        0xF0 => LEAD_F0,
+        0xF1..=0xF3 => LEAD_4,
+        0xF4 => LEAD_F4,
+        _ => INVALID,
+    }
+}
+
+const fn transition(state: u8, class: u8) -> u8 {
+    match (state, class) {
+        (ACCEPT, ASCII) => ACCEPT,
+        (ACCEPT, LEAD_2) => NEED_1,
+        (ACCEPT, LEAD_E0) => AFTER_E0,
+        (ACCEPT, LEAD_3) => NEED_2,
+        (ACCEPT, LEAD_ED) => AFTER_ED,
+        (ACCEPT, LEAD_F0) => AFTER_F0,
+        (ACCEPT, LEAD_4) => NEED_3,
+        (ACCEPT, LEAD_F4) => AFTER_F4,
+        (NEED_1, CONT_LOW..=CONT_HIGH) => ACCEPT,
+        (NEED_2, CONT_LOW..=CONT_HIGH) => NEED_1,
+        (NEED_3, CONT_LOW..=CONT_HIGH) => NEED_2,
+        (AFTER_E0, CONT_HIGH) => NEED_1,
+        (AFTER_ED, CONT_LOW..=CONT_MID) => NEED_1,
+        (AFTER_F0, CONT_MID..=CONT_HIGH) => NEED_2,
+        (AFTER_F4, CONT_LOW) => NEED_2,
+        _ => REJECT,
+    }
+}
+
+const CLASS: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = class_of(byte as u8);
+        byte += 1;
+    }
+    table
+};
+
+/// Indexed by `state * CLASSES + class`.
+const TRANSITION: [u8; STATES * CLASSES] = {
+    let mut table = [0; STATES * CLASSES];
+    let mut i = 0;
+    while i < STATES * CLASSES {
+        table[i] = transition((i / CLASSES) as u8, (i % CLASSES) as u8);
+        i += 1;
+    }
+    table
+};
+
+const WORD: usize = mem::size_of::<usize>();
+const HIGH_BITS: usize = usize::from_ne_bytes([0x80; WORD]);
+
+/// Returns the index of the first non-ASCII byte of `v` at or after `i`,
+/// or `v.len()`.
+fn skip_ascii(v: &[u8], mut i: usize) -> usize {
+    while let Some(chunk) = v.get(i..i + WORD) {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if word & HIGH_BITS != 0 {
+            break;
+        }
+        i += WORD;
+    }
+    while i < v.len() && v[i] < 0x80 {
+        i += 1;
+    }
+    i
+}
+
+/// Decodes the code point at the front of `bytes`, which must start with a
+/// well-formed UTF-8 sequence or a WTF-8 encoded surrogate, and advances
+/// past it. Returns `None` if `bytes` is empty.
+pub(crate) fn next_code_point(bytes: &mut slice::Iter<'_, u8>) -> Option<u32> {
+    let lead = *bytes.next()?;
+    if lead < 0x80 {
+        return Some(u32::from(lead));
+    }
+    let width = match lead {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    };
+    let mut code = u32::from(lead & (0x7F >> width));
+    for _ in 1..width {
+        code = code << 6 | u32::from(bytes.next()? & 0x3F);
+    }
+    Some(code)
+}
+
+/// Checks that `v` is UTF-8.
+pub(crate) fn run_utf8_validation(v: &[u8]) -> Result<(), Utf8Error> {
+    let mut i = 0;
+    while i < v.len() {
+        if v[i] < 0x80 {
+            i = skip_ascii(v, i);
+            continue;
+        }
+        let start = i;
+        let mut state = ACCEPT;
+        loop {
+            let class = CLASS[usize::from(v[i])];
+            state = TRANSITION[usize::from(state) * CLASSES + usize::from(class)];
+            if state == REJECT {
+                // The byte that failed is not part of the error unless it
+                // was the lead byte.
+                let error_len = (i - start).max(1) as u8;
+                return Err(Utf8Error {
+                    valid_up_to: start,
+                    error_len: Some(error_len),
+                });
+            }
+            i += 1;
+            if state == ACCEPT {
+                break;
+            }
+            if i == v.len() {
+                return Err(Utf8Error {
+                    valid_up_to: start,
+                    error_len: None,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_code_point, run_utf8_validation};
+    use crate::test_util::XorShift;
+
+    /// `Ok`, or the error's `valid_up_to` and `error_len`.
+    type Outcome = Result<(), (usize, Option<usize>)>;
+
+    fn assert_matches_std(v: &[u8]) {
+        let ours = run_utf8_validation(v).map_err(|e| (e.valid_up_to(), e.error_len()));
+        let std = std::str::from_utf8(v)
+            .map(drop)
+            .map_err(|e| (e.valid_up_to(), e.error_len()));
+        assert_eq!(ours, std, "{:x?}", v);
+    }
+
+    #[test]
+    fn random_bytes_match_std() {
+        let mut rng = XorShift::new(0x7574_6638);
+        // Bytes drawn from the ones that matter to the DFA: ASCII, every
+        // continuation range and every kind of lead byte.
+        const PALETTE: [u8; 16] = [
+            b'a', 0x00, 0x7f, 0x80, 0x8f, 0x90, 0x9f, 0xa0, 0xbf, 0xc1, 0xc2, 0xe0, 0xed, 0xf0,
+            0xf4, 0xf5,
+        ];
+        for _ in 0..20_000 {
+            let v: Vec<u8> = (0..rng.below(12))
+                .map(|_| PALETTE[rng.below(PALETTE.len())])
+                .collect();
+            assert_matches_std(&v);
+        }
+        for _ in 0..2_000 {
+            let v: Vec<u8> = (0..rng.below(40)).map(|_| rng.next() as u8).collect();
+            assert_matches_std(&v);
+        }
+    }
+
+    #[test]
+    fn corrupted_text_matches_std() {
+        let mut rng = XorShift::new(8);
+        for _ in 0..2_000 {
+            let s: String = (0..rng.below(30)).map(|_| rng.char()).collect();
+            let mut v = s.into_bytes();
+            assert_matches_std(&v);
+            if !v.is_empty() {
+                let i = rng.below(v.len());
+                v[i] = rng.next() as u8;
+                assert_matches_std(&v);
+                let end = rng.below(v.len());
+                assert_matches_std(&v[..end]);
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_fast_path_finds_errors_at_every_offset() {
+        // Long enough for several whole words before and after the bad
+        // byte, at every alignment.
+        for len in 0..40 {
+            for bad in 0..len {
+                let mut v = vec![b'x'; len];
+                v[bad] = 0x80;
+                assert_matches_std(&v);
+                v[bad] = 0xe2;
+                assert_matches_std(&v);
+            }
+            assert!(run_utf8_validation(&vec![b'x'; len]).is_ok());
+        }
+        let mut v = vec![b'x'; 100];
+        v.extend_from_slice("κόσμε".as_bytes());
+        v.extend(std::iter::repeat_n(b'y', 100));
+        assert!(run_utf8_validation(&v).is_ok());
+    }
+
+    #[test]
+    fn next_code_point_decodes_every_width() {
+        let s = "a\u{e9}\u{20ac}\u{1d11e}\u{10ffff}";
+        let mut bytes = s.as_bytes().iter();
+        let decoded: Vec<u32> = std::iter::from_fn(|| next_code_point(&mut bytes)).collect();
+        assert_eq!(decoded, s.chars().map(u32::from).collect::<Vec<u32>>());
+        // WTF-8 surrogates decode like any three-byte sequence.
+        assert_eq!(next_code_point(&mut b"\xed\xa0\x80".iter()), Some(0xD800));
+    }
+
+    #[test]
+    fn edge_sequences() {
+        let cases: &[(&[u8], Outcome)] = &[
+            (b"\xed\x9f\xbf", Ok(())),
+            (b"\xed\xa0\x80", Err((0, Some(1)))),
+            (b"\xe0\x9f\xbf", Err((0, Some(1)))),
+            (b"\xe0\xa0", Err((0, None))),
+            (b"\xf0\x8f\xbf\xbf", Err((0, Some(1)))),
+            (b"\xf4\x8f\xbf\xbf", Ok(())),
+            (b"\xf4\x90\x80\x80", Err((0, Some(1)))),
+            (b"\xf1\x80\x80z", Err((0, Some(3)))),
+            (b"a\xc0\x80", Err((1, Some(1)))),
+            (b"ab\xfe", Err((2, Some(1)))),
+            (b"\xff", Err((0, Some(1)))),
+        ];
+        for &(v, expected) in cases {
+            let got = run_utf8_validation(v).map_err(|e| (e.valid_up_to(), e.error_len()));
+            assert_eq!(got, expected, "{:x?}", v);
+            assert_matches_std(v);
+        }
+    }
+}
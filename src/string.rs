@@ -0,0 +1,897 @@
+//! A growable UTF-8 string, `String`.
+//!
+//! The bytes live in a [`Vec<u8, A>`](crate::vec::Vec), and every method
+//! that takes a byte index checks that it lies on a char boundary, panicking
+//! with the same message std's `String` uses. Draining and range
+//! replacement go through the vector's [`drain`](Vec::drain) and
+//! [`splice`](Vec::splice). Bytes are validated by
+//! [`crate::str::from_utf8`].
+
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::TryReserveError;
+use crate::str::Utf8Error;
+use crate::vec::{self, Vec};
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::{Add, AddAssign, Deref, DerefMut, Range, RangeBounds};
+use std::ptr;
+use std::str;
+
+/// A growable UTF-8 string.
+///
+/// ```
+/// use mystdrs::string::String;
+///
+/// let mut s = String::from("héllo");
+/// s.push_str(" wörld");
+/// s.replace_range(..6, "goodbye");
+/// assert_eq!(s, "goodbye wörld");
+/// let tail = s.split_off(7);
+/// assert_eq!((s.as_str(), tail.as_str()), ("goodbye", " wörld"));
+/// ```
+pub struct String<A: Allocator = Global> {
+    vec: Vec<u8, A>,
+}
+
+impl String {
+    /// Creates an empty string without allocating.
+    pub const fn new() -> Self {
+        String { vec: Vec::new() }
+    }
+
+    /// Creates an empty string with room for at least `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        String {
+            vec: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Converts bytes to a string, replacing each invalid sequence with
+    /// U+FFFD REPLACEMENT CHARACTER exactly as std's `from_utf8_lossy`
+    /// does. Always allocates, even when `v` is valid.
+    ///
+    /// ```
+    /// use mystdrs::string::String;
+    ///
+    /// let s = String::from_utf8_lossy(b"ok\xF0\x90\x80 \xED\xA0\x80!");
+    /// assert_eq!(s, "ok\u{FFFD} \u{FFFD}\u{FFFD}\u{FFFD}!");
+    /// ```
+    pub fn from_utf8_lossy(v: &[u8]) -> Self {
+        let mut s = String::with_capacity(v.len());
+        for chunk in crate::str::utf8_chunks(v) {
+            s.push_str(chunk.valid());
+            if !chunk.invalid().is_empty() {
+                s.push('\u{FFFD}');
+            }
+        }
+        s
+    }
+
+    /// Decodes UTF-16, replacing each unpaired surrogate with U+FFFD
+    /// REPLACEMENT CHARACTER. [`Wtf8Buf`](crate::wtf8::Wtf8Buf) keeps them
+    /// instead.
+    ///
+    /// ```
+    /// use mystdrs::string::String;
+    ///
+    /// let s = String::from_utf16_lossy(&[0xD834, 0xDD1E, 0x006D, 0xDD1E]);
+    /// assert_eq!(s, "𝄞m\u{FFFD}");
+    /// ```
+    pub fn from_utf16_lossy(v: &[u16]) -> Self {
+        crate::char::decode_utf16(v.iter().copied())
+            .map(|decoded| decoded.unwrap_or('\u{FFFD}'))
+            .collect()
+    }
+}
+
+impl<A: Allocator> String<A> {
+    /// Creates an empty string in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        String {
+            vec: Vec::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty string in `alloc` with room for at least
+    /// `capacity` bytes.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        String {
+            vec: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Converts a vector of bytes to a string if it is UTF-8, without
+    /// copying. On error the bytes are handed back in the
+    /// [`FromUtf8Error`].
+    pub fn from_utf8(bytes: Vec<u8, A>) -> Result<Self, FromUtf8Error<A>> {
+        match crate::str::run_utf8_validation(&bytes) {
+            Ok(()) => Ok(String { vec: bytes }),
+            Err(error) => Err(FromUtf8Error { bytes, error }),
+        }
+    }
+
+    /// Converts a vector of bytes to a string without checking that it is
+    /// UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid UTF-8.
+    pub unsafe fn from_utf8_unchecked(bytes: Vec<u8, A>) -> Self {
+        String { vec: bytes }
+    }
+
+    /// Converts the string into its bytes, without copying.
+    pub fn into_bytes(self) -> Vec<u8, A> {
+        self.vec
+    }
+
+    /// Returns a mutable reference to the bytes.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be valid UTF-8 again by the time the borrow ends.
+    pub unsafe fn as_mut_vec(&mut self) -> &mut Vec<u8, A> {
+        &mut self.vec
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.vec.allocator()
+    }
+
+    /// Returns the capacity in bytes.
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Returns the length of the string in bytes.
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Returns `true` if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Extracts a string slice containing the whole string.
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.vec) }
+    }
+
+    /// Extracts a mutable string slice containing the whole string.
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { str::from_utf8_unchecked_mut(&mut self.vec) }
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional);
+    }
+
+    /// Reserves capacity for exactly `additional` more bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.vec.reserve_exact(additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more bytes,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.vec.try_reserve(additional)
+    }
+
+    /// Tries to reserve capacity for exactly `additional` more bytes,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.vec.try_reserve_exact(additional)
+    }
+
+    /// Shrinks the capacity to the length.
+    pub fn shrink_to_fit(&mut self) {
+        self.vec.shrink_to_fit();
+    }
+
+    /// Appends `ch` to the end of the string.
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Appends `ch`, returning an error instead of aborting if the buffer
+    /// cannot grow.
+    pub fn try_push(&mut self, ch: char) -> Result<(), TryReserveError> {
+        self.try_push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Appends `s` to the end of the string.
+    pub fn push_str(&mut self, s: &str) {
+        self.vec.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends `s`, returning an error instead of aborting if the buffer
+    /// cannot grow. Nothing is appended on error.
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        self.vec.try_extend_from_slice(s.as_bytes())
+    }
+
+    /// Removes the last character and returns it, or `None` if the string
+    /// is empty.
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.chars().next_back()?;
+        let new_len = self.len() - ch.len_utf8();
+        unsafe { self.vec.set_len(new_len) };
+        Some(ch)
+    }
+
+    /// Shortens the string to `new_len` bytes. Has no effect if `new_len`
+    /// is greater than the current length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            assert!(self.is_char_boundary(new_len));
+            self.vec.truncate(new_len);
+        }
+    }
+
+    /// Empties the string, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+
+    /// Inserts `ch` at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary.
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        assert!(self.is_char_boundary(idx));
+        self.insert_bytes(idx, ch.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    /// Inserts `s` at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds or not on a char boundary.
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(self.is_char_boundary(idx));
+        self.insert_bytes(idx, s.as_bytes());
+    }
+
+    fn insert_bytes(&mut self, idx: usize, bytes: &[u8]) {
+        let len = self.len();
+        self.vec.reserve(bytes.len());
+        unsafe {
+            let p = self.vec.as_mut_ptr().add(idx);
+            ptr::copy(p, p.add(bytes.len()), len - idx);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), p, bytes.len());
+            self.vec.set_len(len + bytes.len());
+        }
+    }
+
+    /// Removes and returns the character at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not the start of a character in the string.
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = match self[idx..].chars().next() {
+            Some(ch) => ch,
+            None => panic!("cannot remove a char from the end of a string"),
+        };
+        self.vec.drain(idx..idx + ch.len_utf8());
+        ch
+    }
+
+    /// Keeps only the characters for which `f` returns `true`.
+    ///
+    /// If `f` panics, the string is left holding the characters kept so
+    /// far.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> bool,
+    {
+        struct Guard<'a, A: Allocator> {
+            vec: &'a mut Vec<u8, A>,
+            kept: usize,
+        }
+
+        impl<A: Allocator> Drop for Guard<'_, A> {
+            fn drop(&mut self) {
+                unsafe { self.vec.set_len(self.kept) };
+            }
+        }
+
+        let len = self.len();
+        // Hide everything from the string while bytes are shuffled, since
+        // the stretch between kept and unvisited bytes may not be UTF-8.
+        unsafe { self.vec.set_len(0) };
+        let mut g = Guard {
+            vec: &mut self.vec,
+            kept: 0,
+        };
+        let mut idx = 0;
+        while idx < len {
+            let base = g.vec.as_mut_ptr();
+            let ch = unsafe {
+                let rest = std::slice::from_raw_parts(base.add(idx), len - idx);
+                str::from_utf8_unchecked(rest).chars().next().unwrap()
+            };
+            let ch_len = ch.len_utf8();
+            if f(ch) {
+                unsafe { ptr::copy(base.add(idx), base.add(g.kept), ch_len) };
+                g.kept += ch_len;
+            }
+            idx += ch_len;
+        }
+    }
+
+    /// Removes the bytes in `range` and returns their characters as an
+    /// iterator. The range is removed even if the iterator is not
+    /// consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or either end does not lie on
+    /// a char boundary.
+    ///
+    /// ```
+    /// use mystdrs::string::String;
+    ///
+    /// let mut s = String::from("α is alpha");
+    /// let alpha: String = s.drain(..s.find(' ').unwrap()).collect();
+    /// assert_eq!(alpha, "α");
+    /// assert_eq!(s, " is alpha");
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, A>
+    where
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = crate::slice::range(range, self.len());
+        assert!(self.is_char_boundary(start));
+        assert!(self.is_char_boundary(end));
+        Drain {
+            inner: self.vec.drain(start..end),
+        }
+    }
+
+    /// Replaces the bytes in `range` with `replace_with`, which need not be
+    /// the same length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or either end does not lie on
+    /// a char boundary.
+    pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = crate::slice::range(range, self.len());
+        assert!(
+            self.is_char_boundary(start),
+            "start of range should be a character boundary"
+        );
+        assert!(
+            self.is_char_boundary(end),
+            "end of range should be a character boundary"
+        );
+        self.vec.splice(start..end, replace_with.bytes());
+    }
+
+    /// Splits the string in two at byte position `at`, returning the bytes
+    /// from `at` on in a new string in the same allocator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is out of bounds or not on a char boundary.
+    pub fn split_off(&mut self, at: usize) -> String<A>
+    where
+        A: Clone,
+    {
+        assert!(self.is_char_boundary(at));
+        let mut other = String::with_capacity_in(self.len() - at, self.allocator().clone());
+        other.push_str(&self[at..]);
+        self.vec.truncate(at);
+        other
+    }
+}
+
+impl<A: Allocator> Deref for String<A> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<A: Allocator> DerefMut for String<A> {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<A: Allocator + Clone> Clone for String<A> {
+    fn clone(&self) -> Self {
+        String {
+            vec: self.vec.clone(),
+        }
+    }
+}
+
+impl Default for String {
+    fn default() -> Self {
+        String::new()
+    }
+}
+
+impl<A: Allocator> fmt::Debug for String<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<A: Allocator> fmt::Display for String<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<A: Allocator> fmt::Write for String<A> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, ch: char) -> fmt::Result {
+        self.push(ch);
+        Ok(())
+    }
+}
+
+impl<A1: Allocator, A2: Allocator> PartialEq<String<A2>> for String<A1> {
+    fn eq(&self, other: &String<A2>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<A: Allocator> PartialEq<str> for String<A> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<A: Allocator> PartialEq<&str> for String<A> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<A: Allocator> PartialEq<String<A>> for str {
+    fn eq(&self, other: &String<A>) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl<A: Allocator> PartialEq<String<A>> for &str {
+    fn eq(&self, other: &String<A>) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl<A: Allocator> Eq for String<A> {}
+
+impl<A: Allocator> PartialOrd for String<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Allocator> Ord for String<A> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<A: Allocator> Hash for String<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl<A: Allocator> Borrow<str> for String<A> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<A: Allocator> AsRef<str> for String<A> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<A: Allocator> AsRef<[u8]> for String<A> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<&str> for String {
+    fn from(s: &str) -> Self {
+        let mut string = String::with_capacity(s.len());
+        string.push_str(s);
+        string
+    }
+}
+
+impl From<char> for String {
+    fn from(ch: char) -> Self {
+        String::from(ch.encode_utf8(&mut [0; 4]) as &str)
+    }
+}
+
+impl<A: Allocator> From<String<A>> for Vec<u8, A> {
+    fn from(s: String<A>) -> Self {
+        s.into_bytes()
+    }
+}
+
+impl<A: Allocator> Add<&str> for String<A> {
+    type Output = String<A>;
+
+    fn add(mut self, other: &str) -> String<A> {
+        self.push_str(other);
+        self
+    }
+}
+
+impl<A: Allocator> AddAssign<&str> for String<A> {
+    fn add_assign(&mut self, other: &str) {
+        self.push_str(other);
+    }
+}
+
+impl<A: Allocator> Extend<char> for String<A> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for ch in iter {
+            self.push(ch);
+        }
+    }
+}
+
+impl<'a, A: Allocator> Extend<&'a char> for String<A> {
+    fn extend<I: IntoIterator<Item = &'a char>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<'a, A: Allocator> Extend<&'a str> for String<A> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(s);
+        }
+    }
+}
+
+impl FromIterator<char> for String {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut s = String::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<&'a char> for String {
+    fn from_iter<I: IntoIterator<Item = &'a char>>(iter: I) -> Self {
+        let mut s = String::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<&'a str> for String {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut s = String::new();
+        s.extend(iter);
+        s
+    }
+}
+
+/// The error returned by [`String::from_utf8`], holding the rejected
+/// bytes.
+pub struct FromUtf8Error<A: Allocator = Global> {
+    bytes: Vec<u8, A>,
+    error: Utf8Error,
+}
+
+impl<A: Allocator> FromUtf8Error<A> {
+    /// Returns the bytes that were being converted.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes that were being converted, without copying.
+    pub fn into_bytes(self) -> Vec<u8, A> {
+        self.bytes
+    }
+
+    /// Returns where and why the conversion failed.
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl<A: Allocator + Clone> Clone for FromUtf8Error<A> {
+    fn clone(&self) -> Self {
+        FromUtf8Error {
+            bytes: self.bytes.clone(),
+            error: self.error,
+        }
+    }
+}
+
+impl<A: Allocator> PartialEq for FromUtf8Error<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.error == other.error && self.bytes == other.bytes
+    }
+}
+
+impl<A: Allocator> Eq for FromUtf8Error<A> {}
+
+impl<A: Allocator> fmt::Debug for FromUtf8Error<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromUtf8Error")
+            .field("bytes", &self.bytes)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<A: Allocator> fmt::Display for FromUtf8Error<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<A: Allocator> Error for FromUtf8Error<A> {}
+
+/// A draining iterator over the characters of a [`String`], created by
+/// [`String::drain`].
+pub struct Drain<'a, A: Allocator = Global> {
+    /// Drains whole characters at a time, so the remaining bytes stay
+    /// UTF-8.
+    inner: vec::Drain<'a, u8, A>,
+}
+
+impl<A: Allocator> Drain<'_, A> {
+    /// Returns the remaining characters as a string slice.
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.inner.as_slice()) }
+    }
+}
+
+impl<A: Allocator> Iterator for Drain<'_, A> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.inner.nth(ch.len_utf8() - 1);
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.inner.len();
+        (n.div_ceil(4), Some(n))
+    }
+}
+
+impl<A: Allocator> DoubleEndedIterator for Drain<'_, A> {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.inner.nth_back(ch.len_utf8() - 1);
+        Some(ch)
+    }
+}
+
+impl<A: Allocator> FusedIterator for Drain<'_, A> {}
+
+impl<A: Allocator> fmt::Debug for Drain<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_str()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::String;
+    use crate::alloc::{Counting, Global};
+    use crate::test_util::{Budget, XorShift};
+
+    fn random_string(rng: &mut XorShift, max_chars: usize) -> std::string::String {
+        (0..rng.below(max_chars + 1)).map(|_| rng.char()).collect()
+    }
+
+    /// Picks a char boundary of `s` uniformly among all of them.
+    fn boundary(rng: &mut XorShift, s: &str) -> usize {
+        let boundaries: std::vec::Vec<usize> =
+            (0..=s.len()).filter(|&i| s.is_char_boundary(i)).collect();
+        boundaries[rng.below(boundaries.len())]
+    }
+
+    #[test]
+    fn differential_against_std() {
+        let mut rng = XorShift::new(0x57e1);
+        for _ in 0..200 {
+            let mut ours = String::new();
+            let mut theirs = std::string::String::new();
+            for _ in 0..60 {
+                match rng.below(11) {
+                    0 => {
+                        let ch = rng.char();
+                        ours.push(ch);
+                        theirs.push(ch);
+                    }
+                    1 => {
+                        let s = random_string(&mut rng, 6);
+                        ours.push_str(&s);
+                        theirs.push_str(&s);
+                    }
+                    2 => {
+                        let (idx, ch) = (boundary(&mut rng, &theirs), rng.char());
+                        ours.insert(idx, ch);
+                        theirs.insert(idx, ch);
+                    }
+                    3 => {
+                        let idx = boundary(&mut rng, &theirs);
+                        let s = random_string(&mut rng, 4);
+                        ours.insert_str(idx, &s);
+                        theirs.insert_str(idx, &s);
+                    }
+                    4 if !theirs.is_empty() => {
+                        let n = rng.below(theirs.chars().count());
+                        let idx = theirs.char_indices().nth(n).unwrap().0;
+                        assert_eq!(ours.remove(idx), theirs.remove(idx));
+                    }
+                    5 => assert_eq!(ours.pop(), theirs.pop()),
+                    6 => {
+                        let idx = boundary(&mut rng, &theirs);
+                        ours.truncate(idx);
+                        theirs.truncate(idx);
+                    }
+                    7 => {
+                        let a = boundary(&mut rng, &theirs);
+                        let b = a + boundary(&mut rng, &theirs[a..]);
+                        let mut drain = ours.drain(a..b);
+                        let mut expected = theirs.drain(a..b);
+                        assert_eq!(drain.next_back(), expected.next_back());
+                        assert_eq!(drain.as_str(), expected.as_str());
+                        assert!(drain.eq(expected));
+                    }
+                    8 => {
+                        let a = boundary(&mut rng, &theirs);
+                        let b = a + boundary(&mut rng, &theirs[a..]);
+                        let s = random_string(&mut rng, 5);
+                        ours.replace_range(a..b, &s);
+                        theirs.replace_range(a..b, &s);
+                    }
+                    9 => {
+                        let at = boundary(&mut rng, &theirs);
+                        assert_eq!(ours.split_off(at), theirs.split_off(at).as_str());
+                    }
+                    _ => {
+                        let cutoff = rng.char();
+                        ours.retain(|ch| ch < cutoff);
+                        theirs.retain(|ch| ch < cutoff);
+                    }
+                }
+                assert_eq!(ours, theirs.as_str());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: self.is_char_boundary(idx)")]
+    fn insert_inside_char() {
+        String::from("é").insert(1, 'a');
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 1 is not a char boundary; it is inside 'é'")]
+    fn remove_inside_char() {
+        String::from("é").remove(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot remove a char from the end of a string")]
+    fn remove_at_end() {
+        String::from("é").remove(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: self.is_char_boundary(new_len)")]
+    fn truncate_inside_char() {
+        String::from("é").truncate(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: self.is_char_boundary(end)")]
+    fn drain_inside_char() {
+        String::from("é").drain(..1);
+    }
+
+    #[test]
+    #[should_panic(expected = "start of range should be a character boundary")]
+    fn replace_range_inside_char() {
+        String::from("é").replace_range(1.., "");
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 5 out of range for slice of length 2")]
+    fn replace_range_out_of_bounds() {
+        String::from("é").replace_range(..5, "");
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: self.is_char_boundary(at)")]
+    fn split_off_out_of_bounds() {
+        String::from("é").split_off(3);
+    }
+
+    #[test]
+    fn retain_keeps_prefix_on_panic() {
+        let mut s = String::from("aébc");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            s.retain(|ch| {
+                assert_ne!(ch, 'b');
+                ch != 'a'
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(s, "é");
+    }
+
+    #[test]
+    fn traits_and_allocator() {
+        use std::fmt::Write;
+
+        let mut s: String = "ab".chars().chain(Some('ç')).collect();
+        write!(s, "{}", 12).unwrap();
+        s += "!";
+        let mut s = s + "?";
+        assert_eq!(s, "abç12!?");
+        assert_eq!(format!("{:?}", s), "\"abç12!?\"");
+        assert!(String::from("ab").cmp(&String::from("b")).is_lt());
+        assert_eq!(s.drain(..).rev().collect::<String>(), "?!21çba");
+
+        let counting = Counting::new(Global);
+        {
+            let mut s = String::new_in(&counting);
+            s.push_str("hello, world");
+            let t = s.split_off(5);
+            assert!(std::ptr::eq(*t.allocator(), &counting));
+            assert_eq!(s.clone() + t.as_str(), "hello, world");
+        }
+        assert_eq!(counting.snapshot().live_blocks, 0);
+
+        let budget = Budget::new(8);
+        let mut s = String::new_in(&budget);
+        s.try_push_str("12345678").unwrap();
+        assert!(s.try_push('9').is_err());
+        assert_eq!(s, "12345678");
+    }
+}
@@ -0,0 +1,134 @@
+//! Helpers shared by the unit tests of every module.
+
+use crate::alloc::{AllocError, Allocator, Global, Layout};
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+/// A small xorshift generator so that randomized tests are reproducible and
+/// the crate keeps its zero-dependency manifest.
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a value in `0..n`. `n` must be non-zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Returns a character whose UTF-8 encoding is one to four bytes long
+    /// with equal odds, so that every width shows up in string tests.
+    pub fn char(&mut self) -> char {
+        let (lo, hi) = match self.below(4) {
+            0 => (0x20, 0x7f),
+            1 => (0x80, 0x800),
+            2 => (0x800, 0x10000),
+            _ => (0x10000, 0x110000),
+        };
+        loop {
+            if let Some(ch) = std::char::from_u32(lo + self.below((hi - lo) as usize) as u32) {
+                return ch;
+            }
+        }
+    }
+}
+
+/// Counts how many times it has been dropped, optionally panicking while
+/// doing so.
+#[derive(Debug)]
+pub struct DropCounter {
+    count: Rc<Cell<usize>>,
+    panic_on_drop: bool,
+}
+
+impl DropCounter {
+    pub fn new(count: &Rc<Cell<usize>>, panic_on_drop: bool) -> Self {
+        DropCounter {
+            count: Rc::clone(count),
+            panic_on_drop,
+        }
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.count.set(self.count.get() + 1);
+        if self.panic_on_drop && !std::thread::panicking() {
+            panic!("DropCounter panicked on drop");
+        }
+    }
+}
+
+/// An allocator that forwards to [`Global`] until a byte budget is spent
+/// and then refuses every further request. That is how the `try_*` APIs are
+/// exercised without actually exhausting memory.
+///
+/// Every allocated byte is charged, growing a block is charged only for the
+/// growth, and freed memory is never credited back, which keeps the budget
+/// deterministic.
+pub struct Budget {
+    left: Cell<usize>,
+}
+
+impl Budget {
+    pub fn new(bytes: usize) -> Self {
+        Budget {
+            left: Cell::new(bytes),
+        }
+    }
+
+    pub fn set(&self, bytes: usize) {
+        self.left.set(bytes);
+    }
+
+    fn charge(&self, size: usize) -> Result<(), AllocError> {
+        let left = self.left.get();
+        if size > left {
+            return Err(AllocError);
+        }
+        self.left.set(left - size);
+        Ok(())
+    }
+}
+
+unsafe impl Allocator for Budget {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.charge(layout.size())?;
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        Global.deallocate(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.charge(new_layout.size() - old_layout.size())?;
+        Global.grow(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        Global.shrink(ptr, old_layout, new_layout)
+    }
+}
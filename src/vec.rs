@@ -0,0 +1,1075 @@
+//! A contiguous growable array type, `Vec<T>`.
+//!
+//! The buffer is a [`RawVec`](crate::raw_vec::RawVec) grown by doubling, so
+//! `push` runs in amortized constant time. Zero-sized types never allocate:
+//! their capacity is reported as `usize::MAX`. Like every collection in the
+//! crate, `Vec` is generic over its [`Allocator`], defaulting to [`Global`].
+
+use crate::alloc::{Allocator, Global};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::{RawVec, TryReserveError};
+use crate::slice::{Buffer, RawDrain, RawSplice};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut, RangeBounds};
+use std::ptr;
+use std::slice;
+
+/// A contiguous growable array type.
+pub struct Vec<T, A: Allocator = Global> {
+    buf: RawVec<T, A>,
+    len: usize,
+}
+
+impl<T> Vec<T> {
+    /// Constructs a new, empty `Vec<T>` without allocating.
+    pub const fn new() -> Self {
+        Vec {
+            buf: RawVec::new(),
+            len: 0,
+        }
+    }
+
+    /// Constructs a new, empty `Vec<T>` with room for at least `capacity`
+    /// elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Vec {
+            buf: RawVec::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    /// Fallible version of [`with_capacity`](Vec::with_capacity).
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Ok(Vec {
+            buf: RawVec::try_with_capacity(capacity)?,
+            len: 0,
+        })
+    }
+}
+
+impl<T, A: Allocator> Vec<T, A> {
+    /// Constructs a new, empty `Vec<T, A>` in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        Vec {
+            buf: RawVec::new_in(alloc),
+            len: 0,
+        }
+    }
+
+    /// Constructs a new, empty `Vec<T, A>` in `alloc` with room for at
+    /// least `capacity` elements.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Vec {
+            buf: RawVec::with_capacity_in(capacity, alloc),
+            len: 0,
+        }
+    }
+
+    /// Fallible version of [`with_capacity_in`](Vec::with_capacity_in).
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        Ok(Vec {
+            buf: RawVec::try_with_capacity_in(capacity, alloc)?,
+            len: 0,
+        })
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+
+    /// Returns the number of elements the vector can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a raw pointer to the vector's buffer.
+    pub fn as_ptr(&self) -> *const T {
+        self.buf.ptr()
+    }
+
+    /// Returns a raw mutable pointer to the vector's buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.buf.ptr()
+    }
+
+    /// Extracts a slice containing the entire vector.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.ptr(), self.len) }
+    }
+
+    /// Extracts a mutable slice of the entire vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buf.ptr(), self.len) }
+    }
+
+    /// Forces the length of the vector to `new_len`.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be at most `capacity()` and the elements at
+    /// `old_len..new_len` must be initialized.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.len = new_len;
+    }
+
+    /// Reserves capacity for at least `additional` more elements. The
+    /// buffer grows to at least double its current size so that repeated
+    /// calls are amortized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(self.len, additional);
+    }
+
+    /// Reserves capacity for exactly `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.buf.reserve_exact(self.len, additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.buf.try_reserve(self.len, additional)
+    }
+
+    /// Tries to reserve capacity for exactly `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.buf.try_reserve_exact(self.len, additional)
+    }
+
+    /// Shrinks the capacity of the vector as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity() > self.len {
+            self.buf.shrink_to(self.len);
+        }
+    }
+
+    /// Appends an element to the back of the vector.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.buf.capacity() {
+            self.buf.grow_one(self.len);
+        }
+        unsafe {
+            ptr::write(self.buf.ptr().add(self.len), value);
+        }
+        self.len += 1;
+    }
+
+    /// Appends an element to the back of the vector, returning an error
+    /// instead of aborting if the buffer cannot grow. On error `value` is
+    /// dropped and the vector is unchanged.
+    pub fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+        if self.len == self.buf.capacity() {
+            self.buf.try_reserve(self.len, 1)?;
+        }
+        unsafe {
+            ptr::write(self.buf.ptr().add(self.len), value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is
+    /// empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.buf.ptr().add(self.len))) }
+        }
+    }
+
+    /// Inserts an element at position `index`, shifting all elements after
+    /// it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        crate::slice::assert_insert_index(index, self.len);
+        if self.len == self.buf.capacity() {
+            self.buf.grow_one(self.len);
+        }
+        unsafe { crate::slice::insert(self.buf.ptr(), &mut self.len, index, element) };
+    }
+
+    /// Inserts an element at position `index`, returning an error instead
+    /// of aborting if the buffer cannot grow.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`; an out-of-bounds index is a bug, not an
+    /// allocation failure.
+    pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), TryReserveError> {
+        crate::slice::assert_insert_index(index, self.len);
+        if self.len == self.buf.capacity() {
+            self.buf.try_reserve(self.len, 1)?;
+        }
+        unsafe { crate::slice::insert(self.buf.ptr(), &mut self.len, index, element) };
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index`, shifting all
+    /// elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        unsafe { crate::slice::remove(self.buf.ptr(), &mut self.len, index) }
+    }
+
+    /// Removes an element from the vector and returns it, replacing it with
+    /// the last element. This does not preserve ordering but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        unsafe { crate::slice::swap_remove(self.buf.ptr(), &mut self.len, index) }
+    }
+
+    /// Shortens the vector, keeping the first `len` elements and dropping
+    /// the rest. Has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        unsafe { crate::slice::truncate(self.buf.ptr(), &mut self.len, len) };
+    }
+
+    /// Clears the vector, removing all values.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements for which `f` returns `true`.
+    ///
+    /// If `f` or an element's destructor panics, the vector is left holding
+    /// the elements not yet visited plus those already kept.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        unsafe { crate::slice::retain(self.buf.ptr(), &mut self.len, f) };
+    }
+
+    /// Clones and appends all elements of `other` to the vector.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+        for item in other {
+            // `push` will not reallocate; the length is bumped per element
+            // so a panicking `clone` leaves the vector consistent.
+            self.push(item.clone());
+        }
+    }
+
+    /// Clones and appends all elements of `other`, returning an error
+    /// instead of aborting if the buffer cannot grow. Nothing is appended
+    /// on error.
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        self.try_reserve(other.len())?;
+        self.extend_from_slice(other);
+        Ok(())
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator.
+    /// Whatever the iterator has not yielded is dropped with it, and the
+    /// elements after the range are then shifted down.
+    ///
+    /// If the iterator is leaked, the vector keeps only the elements
+    /// before the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// ```
+    /// use mystdrs::vec::Vec;
+    ///
+    /// let mut v = Vec::from([1, 2, 3, 4, 5]);
+    /// let drained: Vec<i32> = v.drain(1..3).collect();
+    /// assert_eq!(drained, [2, 3]);
+    /// assert_eq!(v, [1, 4, 5]);
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, A>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Drain {
+            inner: RawDrain::new(self, range),
+        }
+    }
+
+    /// Replaces the elements in `range` with those of `replace_with`,
+    /// returning the removed elements as an iterator.
+    ///
+    /// The replacement happens when the iterator is dropped. Elements that
+    /// fit in the drained gap or in `replace_with`'s `size_hint` lower
+    /// bound are written in place; any others are collected first so that
+    /// the tail only moves once more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// ```
+    /// use mystdrs::vec::Vec;
+    ///
+    /// let mut v = Vec::from([1, 2, 3, 4]);
+    /// let removed: Vec<i32> = v.splice(1..3, [7, 8, 9]).collect();
+    /// assert_eq!(removed, [2, 3]);
+    /// assert_eq!(v, [1, 7, 8, 9, 4]);
+    /// ```
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, I::IntoIter, A>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let range = crate::slice::range(range, self.len);
+        Splice {
+            inner: RawSplice::new(self, range, replace_with.into_iter()),
+        }
+    }
+
+    /// Splits the vector into its buffer and length without dropping
+    /// anything.
+    pub(crate) fn into_raw_vec(self) -> (RawVec<T, A>, usize) {
+        let me = ManuallyDrop::new(self);
+        (unsafe { ptr::read(&me.buf) }, me.len)
+    }
+
+    /// Reassembles a vector from a buffer whose first `len` slots are
+    /// initialized.
+    ///
+    /// # Safety
+    ///
+    /// `len <= buf.capacity()` and the elements at `0..len` must be
+    /// initialized.
+    pub(crate) unsafe fn from_raw_vec(buf: RawVec<T, A>, len: usize) -> Self {
+        Vec { buf, len }
+    }
+}
+
+impl<T, A: Allocator> Buffer<T> for Vec<T, A> {
+    fn as_ptr(&self) -> *const T {
+        self.buf.ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.buf.ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(self.len, additional);
+    }
+}
+
+impl<T, A: Allocator> Drop for Vec<T, A> {
+    fn drop(&mut self) {
+        // `buf` is freed by its own destructor, which still runs if an
+        // element destructor unwinds out of here.
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, A: Allocator> Deref for Vec<T, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: Allocator> DerefMut for Vec<T, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> Default for Vec<T> {
+    fn default() -> Self {
+        Vec::new()
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for Vec<T, A> {
+    fn clone(&self) -> Self {
+        let mut v = Vec::with_capacity_in(self.len, self.allocator().clone());
+        v.extend_from_slice(self);
+        v
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Vec<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T, U, A1, A2> PartialEq<Vec<U, A2>> for Vec<T, A1>
+where
+    T: PartialEq<U>,
+    A1: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, other: &Vec<U, A2>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq<U>, U, A: Allocator> PartialEq<[U]> for Vec<T, A> {
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: PartialEq<U>, U, A: Allocator, const N: usize> PartialEq<[U; N]> for Vec<T, A> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.as_slice() == &other[..]
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for Vec<T, A> {}
+
+impl<T: Hash, A: Allocator> Hash for Vec<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state);
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for Vec<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for Vec<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T> FromIterator<T> for Vec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = Vec::new();
+        v.extend(iter);
+        v
+    }
+}
+
+impl<T: Clone> From<&[T]> for Vec<T> {
+    fn from(s: &[T]) -> Self {
+        let mut v = Vec::with_capacity(s.len());
+        v.extend_from_slice(s);
+        v
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vec<T> {
+    fn from(arr: [T; N]) -> Self {
+        let mut v = Vec::with_capacity(N);
+        v.extend(arr);
+        v
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a Vec<T, A> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut Vec<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for Vec<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        let (buf, len) = self.into_raw_vec();
+        IntoIter {
+            buf,
+            start: 0,
+            end: len,
+        }
+    }
+}
+
+/// An iterator that moves out of a vector, created by
+/// [`Vec::into_iter`](struct.Vec.html#method.into_iter).
+pub struct IntoIter<T, A: Allocator = Global> {
+    buf: RawVec<T, A>,
+    start: usize,
+    end: usize,
+}
+
+impl<T, A: Allocator> IntoIter<T, A> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.ptr().add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.buf.ptr().add(self.start)) };
+            self.start += 1;
+            Some(item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.start;
+        (n, Some(n))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            unsafe { Some(ptr::read(self.buf.ptr().add(self.end))) }
+        }
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T, A: Allocator> Drop for IntoIter<T, A> {
+    fn drop(&mut self) {
+        unsafe {
+            let rest = ptr::slice_from_raw_parts_mut(
+                self.buf.ptr().add(self.start),
+                self.end - self.start,
+            );
+            self.start = self.end;
+            ptr::drop_in_place(rest);
+        }
+    }
+}
+
+/// A draining iterator for [`Vec`], created by
+/// [`Vec::drain`](struct.Vec.html#method.drain).
+pub struct Drain<'a, T, A: Allocator = Global> {
+    inner: RawDrain<'a, T, Vec<T, A>>,
+}
+
+impl<T, A: Allocator> Drain<'_, T, A> {
+    /// Returns the remaining items of this iterator as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<T, A: Allocator> Iterator for Drain<'_, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for Drain<'_, T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for Drain<'_, T, A> {}
+
+impl<T, A: Allocator> FusedIterator for Drain<'_, T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Drain<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_slice()).finish()
+    }
+}
+
+/// A splicing iterator for [`Vec`], created by
+/// [`Vec::splice`](struct.Vec.html#method.splice).
+pub struct Splice<'a, I: Iterator, A: Allocator = Global> {
+    inner: RawSplice<'a, I, Vec<I::Item, A>>,
+}
+
+impl<I: Iterator, A: Allocator> Iterator for Splice<'_, I, A> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: Iterator, A: Allocator> DoubleEndedIterator for Splice<'_, I, A> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<I: Iterator, A: Allocator> ExactSizeIterator for Splice<'_, I, A> {}
+
+impl<I: Iterator, A: Allocator> fmt::Debug for Splice<'_, I, A>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Splice")
+            .field(&self.inner.as_slice())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vec;
+    use crate::raw_vec::TryReserveError;
+    use crate::test_util::{Budget, DropCounter, XorShift};
+    use std::alloc::Layout;
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn push_pop() {
+        let mut v = Vec::new();
+        for i in 0..100 {
+            v.push(i);
+        }
+        assert_eq!(v.len(), 100);
+        assert!(v.capacity() >= 100);
+        for i in (0..100).rev() {
+            assert_eq!(v.pop(), Some(i));
+        }
+        assert_eq!(v.pop(), None);
+    }
+
+    #[test]
+    fn capacity_doubles() {
+        let mut v: Vec<u64> = Vec::new();
+        assert_eq!(v.capacity(), 0);
+        v.push(0);
+        let mut last = v.capacity();
+        for i in 0..1000 {
+            v.push(i);
+            if v.capacity() != last {
+                assert_eq!(v.capacity(), last * 2);
+                last = v.capacity();
+            }
+        }
+    }
+
+    #[test]
+    fn reserve_and_shrink() {
+        let mut v: Vec<u32> = Vec::with_capacity(10);
+        assert!(v.capacity() >= 10);
+        v.extend(0..3);
+        v.shrink_to_fit();
+        assert_eq!(v.capacity(), 3);
+        assert_eq!(v, [0, 1, 2]);
+        v.clear();
+        v.shrink_to_fit();
+        assert_eq!(v.capacity(), 0);
+        v.reserve_exact(7);
+        assert_eq!(v.capacity(), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_overflow() {
+        let mut v: Vec<u64> = Vec::new();
+        v.reserve(usize::MAX / 4);
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        let mut v = Vec::new();
+        assert_eq!(v.capacity(), usize::MAX);
+        for _ in 0..1000 {
+            v.push(());
+        }
+        v.insert(10, ());
+        assert_eq!(v.len(), 1001);
+        assert_eq!(v.remove(0), ());
+        assert_eq!(v.swap_remove(5), ());
+        v.truncate(10);
+        v.shrink_to_fit();
+        assert_eq!(v.len(), 10);
+        assert_eq!(v.into_iter().count(), 10);
+    }
+
+    #[test]
+    fn zero_sized_drops() {
+        thread_local!(static DROPS: Cell<usize> = const { Cell::new(0) });
+        struct Token;
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS.with(|d| d.set(d.get() + 1));
+            }
+        }
+        let mut v = Vec::new();
+        for _ in 0..10 {
+            v.push(Token);
+        }
+        v.truncate(4);
+        assert_eq!(DROPS.with(Cell::get), 6);
+        drop(v);
+        assert_eq!(DROPS.with(Cell::get), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 2) should be <= len (is 1)")]
+    fn insert_out_of_bounds() {
+        let mut v = Vec::new();
+        v.push(1);
+        v.insert(2, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal index (is 1) should be < len (is 1)")]
+    fn remove_out_of_bounds() {
+        let mut v = Vec::new();
+        v.push(1);
+        v.remove(1);
+    }
+
+    #[test]
+    fn into_iter_double_ended() {
+        let v: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let mut it = v.into_iter();
+        assert_eq!(it.next().as_deref(), Some("0"));
+        assert_eq!(it.next_back().as_deref(), Some("5"));
+        assert_eq!(it.as_slice(), ["1", "2", "3", "4"]);
+        // The remaining strings are freed when the iterator drops.
+    }
+
+    #[test]
+    fn retain_keeps_order() {
+        let mut v: Vec<i32> = (0..20).collect();
+        v.retain(|x| x % 3 == 0);
+        assert_eq!(v, [0, 3, 6, 9, 12, 15, 18]);
+    }
+
+    #[test]
+    fn panic_in_element_drop_during_truncate() {
+        let counter = Rc::new(Cell::new(0));
+        let mut v = Vec::new();
+        for i in 0..8 {
+            v.push(DropCounter::new(&counter, i == 5));
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(|| v.truncate(2)));
+        assert!(r.is_err());
+        assert_eq!(v.len(), 2);
+        assert_eq!(counter.get(), 6);
+        drop(v);
+        assert_eq!(counter.get(), 8);
+    }
+
+    #[test]
+    fn panic_in_element_drop_during_drop() {
+        let counter = Rc::new(Cell::new(0));
+        let mut v = Vec::new();
+        for i in 0..8 {
+            v.push(DropCounter::new(&counter, i == 1));
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(move || drop(v)));
+        assert!(r.is_err());
+        assert_eq!(counter.get(), 8);
+    }
+
+    #[test]
+    fn panic_in_into_iter_drop() {
+        let counter = Rc::new(Cell::new(0));
+        let mut v = Vec::new();
+        for i in 0..8 {
+            v.push(DropCounter::new(&counter, i == 6));
+        }
+        let mut it = v.into_iter();
+        drop(it.next());
+        let r = panic::catch_unwind(AssertUnwindSafe(move || drop(it)));
+        assert!(r.is_err());
+        assert_eq!(counter.get(), 8);
+    }
+
+    #[test]
+    fn panic_in_retain_predicate() {
+        let counter = Rc::new(Cell::new(0));
+        let mut v = Vec::new();
+        for _ in 0..10 {
+            v.push(DropCounter::new(&counter, false));
+        }
+        let mut seen = 0;
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.retain(|_| {
+                seen += 1;
+                if seen == 6 {
+                    panic!("predicate");
+                }
+                seen % 2 == 0
+            })
+        }));
+        assert!(r.is_err());
+        // Visited 5, dropped 3 of them; the unvisited 5 are shifted down.
+        assert_eq!(counter.get(), 3);
+        assert_eq!(v.len(), 7);
+        drop(v);
+        assert_eq!(counter.get(), 10);
+    }
+
+    #[test]
+    fn panic_in_clone_during_extend_from_slice() {
+        #[derive(Debug)]
+        struct Bomb(usize);
+        impl Clone for Bomb {
+            fn clone(&self) -> Self {
+                if self.0 == 3 {
+                    panic!("clone");
+                }
+                Bomb(self.0)
+            }
+        }
+        let src: std::vec::Vec<Bomb> = (0..6).map(Bomb).collect();
+        let mut v = Vec::new();
+        let r = panic::catch_unwind(AssertUnwindSafe(|| v.extend_from_slice(&src)));
+        assert!(r.is_err());
+        assert_eq!(v.len(), 3);
+    }
+
+    #[test]
+    fn differential_against_std() {
+        let mut rng = XorShift::new(0x5eed);
+        for _ in 0..200 {
+            let mut ours: Vec<u32> = Vec::new();
+            let mut theirs: std::vec::Vec<u32> = std::vec::Vec::new();
+            for _ in 0..200 {
+                let len = theirs.len();
+                match rng.below(9) {
+                    0 | 1 => {
+                        let x = rng.next() as u32;
+                        ours.push(x);
+                        theirs.push(x);
+                    }
+                    2 => assert_eq!(ours.pop(), theirs.pop()),
+                    3 => {
+                        let i = rng.below(len + 1);
+                        let x = rng.next() as u32;
+                        ours.insert(i, x);
+                        theirs.insert(i, x);
+                    }
+                    4 if len > 0 => {
+                        let i = rng.below(len);
+                        assert_eq!(ours.remove(i), theirs.remove(i));
+                    }
+                    5 if len > 0 => {
+                        let i = rng.below(len);
+                        assert_eq!(ours.swap_remove(i), theirs.swap_remove(i));
+                    }
+                    6 => {
+                        let n = rng.below(len + 2);
+                        ours.truncate(n);
+                        theirs.truncate(n);
+                    }
+                    7 => {
+                        let n = rng.below(16);
+                        ours.reserve(n);
+                        theirs.reserve(n);
+                        assert!(ours.capacity() >= ours.len() + n);
+                    }
+                    _ => {
+                        ours.shrink_to_fit();
+                        assert_eq!(ours.capacity(), ours.len());
+                    }
+                }
+                assert_eq!(ours.as_slice(), theirs.as_slice());
+            }
+            let collected: std::vec::Vec<u32> = ours.into_iter().collect();
+            assert_eq!(collected, theirs);
+        }
+    }
+
+    #[test]
+    fn try_reserve_reports_failing_layout() {
+        let budget = Budget::new(usize::MAX);
+        let mut v: Vec<u64, _> = Vec::new_in(&budget);
+        v.push(1);
+        budget.set(64);
+        assert_eq!(
+            v.try_reserve(100),
+            Err(TryReserveError::AllocError {
+                layout: Layout::array::<u64>(101).unwrap()
+            })
+        );
+        assert_eq!(v, [1]);
+        assert_eq!(v.capacity(), 4);
+    }
+
+    #[test]
+    fn try_push_stops_at_budget() {
+        let budget = Budget::new(64);
+        let mut v: Vec<u32, _> = Vec::new_in(&budget);
+        let r = (0..).try_for_each(|i| v.try_push(i));
+        // Growing 4 -> 8 -> 16 elements costs 16 + 16 + 32 bytes, which
+        // spends the whole budget; doubling again would need 64 more.
+        assert!(matches!(r, Err(TryReserveError::AllocError { .. })));
+        assert_eq!(v.len(), 16);
+        assert_eq!(v.capacity(), 16);
+        assert!(v.iter().copied().eq(0..16));
+        budget.set(64);
+        v.push(16);
+        assert_eq!(v.len(), 17);
+    }
+
+    #[test]
+    fn try_insert_and_extend_leave_vec_unchanged_on_failure() {
+        let budget = Budget::new(8);
+        let mut v: Vec<u8, _> = Vec::with_capacity_in(8, &budget);
+        v.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(v.try_insert(0, 0).is_err());
+        assert!(v.try_extend_from_slice(&[9; 4]).is_err());
+        assert!(v.try_push(9).is_err());
+        assert!(Vec::<u8, _>::try_with_capacity_in(1, &budget).is_err());
+        // Zero-sized requests never reach the allocator.
+        assert!(Vec::<u8, _>::try_with_capacity_in(0, &budget).is_ok());
+        assert!(Vec::<(), _>::try_with_capacity_in(100, &budget).is_ok());
+        assert_eq!(v, [1, 2, 3, 4, 5, 6, 7, 8]);
+        budget.set(usize::MAX);
+        assert_eq!(v.try_insert(0, 0), Ok(()));
+        assert_eq!(v[0], 0);
+    }
+
+    #[test]
+    fn clone_uses_same_allocator() {
+        let budget = Budget::new(12);
+        let mut v: Vec<u16, _> = Vec::with_capacity_in(3, &budget);
+        v.extend_from_slice(&[1, 2, 3]);
+        let w = v.clone();
+        assert_eq!(w, v);
+        assert!(std::ptr::eq(*w.allocator(), &budget));
+        assert!(v.try_push(4).is_err());
+    }
+
+    #[test]
+    fn try_with_capacity_overflow() {
+        assert_eq!(
+            Vec::<u32>::try_with_capacity(usize::MAX).err(),
+            Some(TryReserveError::CapacityOverflow)
+        );
+        let mut v: Vec<u8> = Vec::new();
+        v.push(0);
+        assert_eq!(
+            v.try_reserve_exact(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn drain_and_splice_match_std() {
+        let mut rng = XorShift::new(0xd4a1);
+        for _ in 0..300 {
+            let len = rng.below(12);
+            let mut ours: Vec<u32> = (0..len as u32).collect();
+            let mut theirs: std::vec::Vec<u32> = (0..len as u32).collect();
+            let a = rng.below(len + 1);
+            let b = a + rng.below(len - a + 1);
+            if rng.below(2) == 0 {
+                let mut drain = ours.drain(a..b);
+                let mut expected = theirs.drain(a..b);
+                assert_eq!(drain.next_back(), expected.next_back());
+            } else {
+                let n = rng.below(8) as u32;
+                let filter = rng.below(2) == 0;
+                let with = (50..50 + n).filter(move |x| !filter || x % 2 == 0);
+                let removed: std::vec::Vec<u32> = ours.splice(a..b, with.clone()).collect();
+                assert_eq!(
+                    removed,
+                    theirs.splice(a..b, with).collect::<std::vec::Vec<_>>()
+                );
+            }
+            assert_eq!(ours, theirs[..]);
+        }
+    }
+
+    #[test]
+    fn panic_in_drain_drop_keeps_tail() {
+        let count = Rc::new(Cell::new(0));
+        let mut v = Vec::new();
+        for i in 0..6 {
+            v.push(DropCounter::new(&count, i == 2));
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| drop(v.drain(1..4))));
+        assert!(result.is_err());
+        assert_eq!(count.get(), 3);
+        assert_eq!(v.len(), 3);
+        drop(v);
+        assert_eq!(count.get(), 6);
+    }
+}
@@ -0,0 +1,702 @@
+//! WTF-8, a superset of UTF-8 that can also hold unpaired surrogates.
+//!
+//! UTF-16 from Windows, such as file names, may contain surrogates that are
+//! not part of a pair, and those cannot be stored in a [`String`] without
+//! losing information. [`Wtf8Buf`] encodes each unpaired surrogate as the
+//! three bytes UTF-8 would use if surrogates were allowed. It keeps each
+//! proper pair as the supplementary character the pair stands for, so
+//! [`Wtf8Buf::from_wide`] and [`Wtf8Buf::encode_wide`] round-trip any
+//! sequence of `u16`.
+//!
+//! A lead surrogate is never stored directly before a trail surrogate.
+//! Appending one after the other joins them, as the
+//! [WTF-8 spec](https://simonsapin.github.io/wtf-8/) requires, so equal
+//! UTF-16 always gives equal bytes.
+
+use crate::alloc::{Allocator, Global};
+use crate::char::{self, decode_utf16};
+use crate::hash::{Hash, Hasher};
+use crate::raw_vec::TryReserveError;
+use crate::string::String;
+use crate::vec::Vec;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+use std::iter::{FromIterator, FusedIterator};
+use std::slice;
+use std::str;
+
+/// A Unicode code point: a scalar value or a surrogate.
+///
+/// ```
+/// use mystdrs::wtf8::CodePoint;
+///
+/// let lead = CodePoint::from_u32(0xD800).unwrap();
+/// assert_eq!(lead.to_char(), None);
+/// assert_eq!(lead.to_char_lossy(), '\u{FFFD}');
+/// assert_eq!(CodePoint::from_char('é').to_u32(), 0xE9);
+/// assert_eq!(CodePoint::from_u32(0x11_0000), None);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CodePoint {
+    value: u32,
+}
+
+crate::impl_hash!(CodePoint { value });
+
+impl CodePoint {
+    /// Returns the code point `value`, or `None` if it is past U+10FFFF.
+    pub fn from_u32(value: u32) -> Option<CodePoint> {
+        if value <= 0x10_FFFF {
+            Some(CodePoint { value })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the code point of `ch`.
+    pub fn from_char(ch: char) -> CodePoint {
+        CodePoint {
+            value: u32::from(ch),
+        }
+    }
+
+    /// Returns the numeric value of the code point.
+    pub fn to_u32(self) -> u32 {
+        self.value
+    }
+
+    /// Returns the code point as a `char`, or `None` if it is a surrogate.
+    pub fn to_char(self) -> Option<char> {
+        std::char::from_u32(self.value)
+    }
+
+    /// Returns the code point as a `char`, with surrogates replaced by
+    /// U+FFFD REPLACEMENT CHARACTER.
+    pub fn to_char_lossy(self) -> char {
+        self.to_char().unwrap_or('\u{FFFD}')
+    }
+}
+
+impl fmt::Debug for CodePoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "U+{:04X}", self.value)
+    }
+}
+
+impl From<char> for CodePoint {
+    fn from(ch: char) -> Self {
+        CodePoint::from_char(ch)
+    }
+}
+
+/// A growable WTF-8 string.
+///
+/// ```
+/// use mystdrs::wtf8::Wtf8Buf;
+///
+/// // "a", an unpaired lead surrogate, then "b".
+/// let units = [0x61, 0xD800, 0x62];
+/// let wtf8 = Wtf8Buf::from_wide(&units);
+/// assert_eq!(wtf8.as_bytes(), b"a\xED\xA0\x80b");
+/// assert_eq!(wtf8.as_str(), None);
+/// assert_eq!(wtf8.encode_wide().collect::<Vec<u16>>(), units);
+/// assert_eq!(wtf8.into_string_lossy(), "a\u{FFFD}b");
+/// ```
+pub struct Wtf8Buf<A: Allocator = Global> {
+    bytes: Vec<u8, A>,
+}
+
+impl Wtf8Buf {
+    /// Creates an empty string without allocating.
+    pub const fn new() -> Self {
+        Wtf8Buf { bytes: Vec::new() }
+    }
+
+    /// Creates an empty string with room for at least `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Wtf8Buf {
+            bytes: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Converts UTF-16, well-formed or not, to WTF-8. Surrogate pairs
+    /// become supplementary characters and every other surrogate is kept
+    /// as is.
+    pub fn from_wide(v: &[u16]) -> Self {
+        let mut buf = Wtf8Buf::with_capacity(v.len());
+        for decoded in decode_utf16(v.iter().copied()) {
+            match decoded {
+                Ok(ch) => buf.push_char(ch),
+                // `decode_utf16` has already paired every lead surrogate
+                // that had a trail surrogate after it.
+                Err(e) => buf.push_code_point_unchecked(u32::from(e.unpaired_surrogate())),
+            }
+        }
+        buf
+    }
+}
+
+impl<A: Allocator> Wtf8Buf<A> {
+    /// Creates an empty string in `alloc` without allocating.
+    pub const fn new_in(alloc: A) -> Self {
+        Wtf8Buf {
+            bytes: Vec::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty string in `alloc` with room for at least
+    /// `capacity` bytes.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Wtf8Buf {
+            bytes: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Converts a `String` to WTF-8 without copying.
+    pub fn from_string(s: String<A>) -> Self {
+        Wtf8Buf {
+            bytes: s.into_bytes(),
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.bytes.allocator()
+    }
+
+    /// Returns the length in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns `true` if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the number of bytes the string can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Returns the WTF-8 bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Reserves room for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.bytes.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more bytes, returning an
+    /// error instead of aborting if the buffer cannot grow.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.bytes.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity as close to the length as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.bytes.shrink_to_fit();
+    }
+
+    /// Removes all contents, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// Appends `ch` to the end of the string.
+    pub fn push_char(&mut self, ch: char) {
+        self.push_code_point_unchecked(u32::from(ch));
+    }
+
+    /// Appends `ch`, returning an error instead of aborting if the buffer
+    /// cannot grow. Nothing is appended on error.
+    pub fn try_push_char(&mut self, ch: char) -> Result<(), TryReserveError> {
+        self.try_reserve(ch.len_utf8())?;
+        self.push_char(ch);
+        Ok(())
+    }
+
+    /// Appends `s` to the end of the string.
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends `s`, returning an error instead of aborting if the buffer
+    /// cannot grow. Nothing is appended on error.
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        self.bytes.try_extend_from_slice(s.as_bytes())
+    }
+
+    /// Appends `code_point`, joining it with the last code point if the two
+    /// form a surrogate pair.
+    ///
+    /// ```
+    /// use mystdrs::wtf8::{CodePoint, Wtf8Buf};
+    ///
+    /// let mut s = Wtf8Buf::new();
+    /// s.push(CodePoint::from_u32(0xD834).unwrap());
+    /// s.push(CodePoint::from_u32(0xDD1E).unwrap());
+    /// assert_eq!(s.as_str(), Some("𝄞"));
+    /// ```
+    pub fn push(&mut self, code_point: CodePoint) {
+        let code = code_point.to_u32();
+        if let Ok(unit) = u16::try_from(code) {
+            if char::is_trail_surrogate(unit) {
+                if let Some(lead) = self.final_lead_surrogate() {
+                    self.bytes.truncate(self.len() - 3);
+                    self.push_code_point_unchecked(char::combine_surrogates(lead, unit));
+                    return;
+                }
+            }
+        }
+        self.push_code_point_unchecked(code);
+    }
+
+    /// Appends `code_point` like [`push`](Wtf8Buf::push), returning an
+    /// error instead of aborting if the buffer cannot grow. Nothing is
+    /// appended on error.
+    pub fn try_push(&mut self, code_point: CodePoint) -> Result<(), TryReserveError> {
+        // Joining a pair replaces three bytes with four, so the code
+        // point's own length is always enough.
+        let len = char::encode_utf8_raw(code_point.to_u32(), &mut [0; 4]).len();
+        self.try_reserve(len)?;
+        self.push(code_point);
+        Ok(())
+    }
+
+    /// Appends `other`, joining a lead surrogate at the end of `self` with
+    /// a trail surrogate at the start of `other`.
+    pub fn push_wtf8<B: Allocator>(&mut self, other: &Wtf8Buf<B>) {
+        match (
+            self.final_lead_surrogate(),
+            initial_trail_surrogate(&other.bytes),
+        ) {
+            (Some(lead), Some(trail)) => {
+                self.bytes.truncate(self.len() - 3);
+                self.push_code_point_unchecked(char::combine_surrogates(lead, trail));
+                self.bytes.extend_from_slice(&other.bytes[3..]);
+            }
+            _ => self.bytes.extend_from_slice(&other.bytes),
+        }
+    }
+
+    /// Appends `other` like [`push_wtf8`](Wtf8Buf::push_wtf8), returning an
+    /// error instead of aborting if the buffer cannot grow. Nothing is
+    /// appended on error.
+    pub fn try_push_wtf8<B: Allocator>(
+        &mut self,
+        other: &Wtf8Buf<B>,
+    ) -> Result<(), TryReserveError> {
+        // Joining a pair only makes the result shorter.
+        self.try_reserve(other.len())?;
+        self.push_wtf8(other);
+        Ok(())
+    }
+
+    /// Returns the string as UTF-8, or `None` if it holds a surrogate.
+    pub fn as_str(&self) -> Option<&str> {
+        match next_surrogate(&self.bytes, 0) {
+            Some(_) => None,
+            None => Some(unsafe { str::from_utf8_unchecked(&self.bytes) }),
+        }
+    }
+
+    /// Converts to a `String` without copying, or hands the string back if
+    /// it holds a surrogate.
+    pub fn into_string(self) -> Result<String<A>, Self> {
+        match next_surrogate(&self.bytes, 0) {
+            Some(_) => Err(self),
+            None => Ok(unsafe { String::from_utf8_unchecked(self.bytes) }),
+        }
+    }
+
+    /// Converts to a `String` without copying, replacing each surrogate
+    /// with U+FFFD REPLACEMENT CHARACTER. Both take three bytes, so the
+    /// replacement happens in place.
+    pub fn into_string_lossy(mut self) -> String<A> {
+        let mut pos = 0;
+        while let Some(i) = next_surrogate(&self.bytes, pos) {
+            self.bytes[i..i + 3].copy_from_slice("\u{FFFD}".as_bytes());
+            pos = i + 3;
+        }
+        unsafe { String::from_utf8_unchecked(self.bytes) }
+    }
+
+    /// Returns an iterator over the code points.
+    pub fn code_points(&self) -> CodePoints<'_> {
+        CodePoints {
+            bytes: self.bytes.iter(),
+        }
+    }
+
+    /// Returns an iterator over the string encoded as UTF-16, with each
+    /// surrogate written as itself.
+    pub fn encode_wide(&self) -> EncodeWide<'_> {
+        EncodeWide {
+            code_points: self.code_points(),
+            extra: 0,
+        }
+    }
+
+    /// Appends `code` without checking whether it completes a surrogate
+    /// pair.
+    fn push_code_point_unchecked(&mut self, code: u32) {
+        self.bytes
+            .extend_from_slice(char::encode_utf8_raw(code, &mut [0; 4]));
+    }
+
+    fn final_lead_surrogate(&self) -> Option<u16> {
+        match self.bytes[..] {
+            [.., 0xED, second @ 0xA0..=0xAF, third] => Some(decode_surrogate(second, third)),
+            _ => None,
+        }
+    }
+}
+
+fn initial_trail_surrogate(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [0xED, second @ 0xB0..=0xBF, third, ..] => Some(decode_surrogate(second, third)),
+        _ => None,
+    }
+}
+
+/// Decodes the last two bytes of a surrogate, whose first byte is `ED`.
+fn decode_surrogate(second: u8, third: u8) -> u16 {
+    0xD000 | u16::from(second & 0x3F) << 6 | u16::from(third & 0x3F)
+}
+
+/// Returns the index of the first surrogate in `bytes` at or after `pos`,
+/// which must be a code point boundary. Only surrogates start with `ED`
+/// followed by a byte of `A0` or more.
+fn next_surrogate(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    while let Some(offset) = bytes[pos..].iter().position(|&b| b == 0xED) {
+        let i = pos + offset;
+        if bytes[i + 1] >= 0xA0 {
+            return Some(i);
+        }
+        pos = i + 3;
+    }
+    None
+}
+
+/// A piece of WTF-8: a run of UTF-8 or a single surrogate.
+enum Segment<'a> {
+    Str(&'a str),
+    Surrogate(u16),
+}
+
+/// Splits WTF-8 into [`Segment`]s, for formatting.
+struct Segments<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        match next_surrogate(self.bytes, 0) {
+            Some(0) => {
+                let surrogate = decode_surrogate(self.bytes[1], self.bytes[2]);
+                self.bytes = &self.bytes[3..];
+                Some(Segment::Surrogate(surrogate))
+            }
+            next => {
+                let (s, rest) = self.bytes.split_at(next.unwrap_or(self.bytes.len()));
+                self.bytes = rest;
+                Some(Segment::Str(unsafe { str::from_utf8_unchecked(s) }))
+            }
+        }
+    }
+}
+
+impl<A: Allocator + Clone> Clone for Wtf8Buf<A> {
+    fn clone(&self) -> Self {
+        Wtf8Buf {
+            bytes: self.bytes.clone(),
+        }
+    }
+}
+
+impl Default for Wtf8Buf {
+    fn default() -> Self {
+        Wtf8Buf::new()
+    }
+}
+
+/// Formats like a string, with each surrogate escaped as `\u{d800}`.
+impl<A: Allocator> fmt::Debug for Wtf8Buf<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for segment in (Segments { bytes: &self.bytes }) {
+            match segment {
+                Segment::Str(s) => {
+                    for ch in s.chars() {
+                        match ch {
+                            '\'' => f.write_char(ch)?,
+                            _ => write!(f, "{}", ch.escape_debug())?,
+                        }
+                    }
+                }
+                Segment::Surrogate(surrogate) => write!(f, "\\u{{{:x}}}", surrogate)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Formats lossily, writing U+FFFD REPLACEMENT CHARACTER for each
+/// surrogate.
+impl<A: Allocator> fmt::Display for Wtf8Buf<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in (Segments { bytes: &self.bytes }) {
+            match segment {
+                Segment::Str(s) => f.write_str(s)?,
+                Segment::Surrogate(_) => f.write_char('\u{FFFD}')?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<A1: Allocator, A2: Allocator> PartialEq<Wtf8Buf<A2>> for Wtf8Buf<A1> {
+    fn eq(&self, other: &Wtf8Buf<A2>) -> bool {
+        self.bytes[..] == other.bytes[..]
+    }
+}
+
+impl<A: Allocator> Eq for Wtf8Buf<A> {}
+
+/// Orders by code point, which for WTF-8 is the order of the bytes.
+impl<A: Allocator> PartialOrd for Wtf8Buf<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Allocator> Ord for Wtf8Buf<A> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.bytes[..].cmp(&other.bytes[..])
+    }
+}
+
+impl<A: Allocator> Hash for Wtf8Buf<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes[..].hash(state);
+    }
+}
+
+impl From<&str> for Wtf8Buf {
+    fn from(s: &str) -> Self {
+        let mut buf = Wtf8Buf::with_capacity(s.len());
+        buf.push_str(s);
+        buf
+    }
+}
+
+impl<A: Allocator> From<String<A>> for Wtf8Buf<A> {
+    fn from(s: String<A>) -> Self {
+        Wtf8Buf::from_string(s)
+    }
+}
+
+impl<A: Allocator> Extend<CodePoint> for Wtf8Buf<A> {
+    fn extend<I: IntoIterator<Item = CodePoint>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for code_point in iter {
+            self.push(code_point);
+        }
+    }
+}
+
+impl FromIterator<CodePoint> for Wtf8Buf {
+    fn from_iter<I: IntoIterator<Item = CodePoint>>(iter: I) -> Self {
+        let mut buf = Wtf8Buf::new();
+        buf.extend(iter);
+        buf
+    }
+}
+
+/// An iterator over the code points of a [`Wtf8Buf`], created by
+/// [`Wtf8Buf::code_points`].
+#[derive(Clone, Debug)]
+pub struct CodePoints<'a> {
+    bytes: slice::Iter<'a, u8>,
+}
+
+impl Iterator for CodePoints<'_> {
+    type Item = CodePoint;
+
+    fn next(&mut self) -> Option<CodePoint> {
+        crate::str::next_code_point(&mut self.bytes).map(|value| CodePoint { value })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bytes.len();
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl FusedIterator for CodePoints<'_> {}
+
+/// An iterator over the UTF-16 code units of a [`Wtf8Buf`], created by
+/// [`Wtf8Buf::encode_wide`].
+#[derive(Clone, Debug)]
+pub struct EncodeWide<'a> {
+    code_points: CodePoints<'a>,
+    /// The trail surrogate still owed for the last code point, or zero.
+    extra: u16,
+}
+
+impl Iterator for EncodeWide<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        if self.extra != 0 {
+            let trail = self.extra;
+            self.extra = 0;
+            return Some(trail);
+        }
+        let code_point = self.code_points.next()?;
+        match *char::encode_utf16_raw(code_point.to_u32(), &mut [0; 2]) {
+            [unit] => Some(unit),
+            [lead, trail] => {
+                self.extra = trail;
+                Some(lead)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // As for UTF-8, a sequence of n bytes takes one unit if n < 4 and
+        // two units otherwise.
+        let bytes = self.code_points.bytes.len();
+        let extra = usize::from(self.extra != 0);
+        (bytes.div_ceil(3) + extra, Some(bytes + extra))
+    }
+}
+
+impl FusedIterator for EncodeWide<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodePoint, Wtf8Buf};
+    use crate::string::String;
+    use crate::test_util::{Budget, XorShift};
+
+    /// Units that make surrogate pairs, lone surrogates and ordinary
+    /// characters of every UTF-8 width likely neighbours.
+    fn random_wide(rng: &mut XorShift, max_len: usize) -> Vec<u16> {
+        const UNITS: [u16; 8] = [0x41, 0xE9, 0x20AC, 0xFFFD, 0xD800, 0xDBFF, 0xDC00, 0xDFFF];
+        (0..rng.below(max_len + 1))
+            .map(|_| UNITS[rng.below(UNITS.len())])
+            .collect()
+    }
+
+    #[test]
+    fn from_wide_round_trips_and_matches_std() {
+        let mut rng = XorShift::new(0x57F8);
+        for _ in 0..5_000 {
+            let units = random_wide(&mut rng, 12);
+            let wtf8 = Wtf8Buf::from_wide(&units);
+            assert_eq!(wtf8.encode_wide().collect::<Vec<u16>>(), units);
+
+            let std = std::string::String::from_utf16(&units).ok();
+            assert_eq!(wtf8.as_str(), std.as_deref());
+            let lossy = std::string::String::from_utf16_lossy(&units);
+            assert_eq!(String::from_utf16_lossy(&units), *lossy);
+            assert_eq!(wtf8.to_string(), lossy);
+            assert_eq!(wtf8.clone().into_string_lossy(), *lossy);
+            assert_eq!(wtf8.into_string().ok().as_deref(), std.as_deref());
+        }
+    }
+
+    #[test]
+    fn size_hints_bound_encode_wide_and_code_points() {
+        let mut rng = XorShift::new(3);
+        for _ in 0..1_000 {
+            let units = random_wide(&mut rng, 12);
+            let wtf8 = Wtf8Buf::from_wide(&units);
+            let mut wide = wtf8.encode_wide();
+            for left in (0..=units.len()).rev() {
+                let (low, high) = wide.size_hint();
+                assert!(low <= left && left <= high.unwrap(), "{:x?}", units);
+                wide.next();
+            }
+            let count = wtf8.code_points().count();
+            let (low, high) = wtf8.code_points().size_hint();
+            assert!(low <= count && count <= high.unwrap());
+        }
+    }
+
+    #[test]
+    fn concatenation_joins_surrogate_pairs() {
+        let mut rng = XorShift::new(7);
+        for _ in 0..5_000 {
+            let left = random_wide(&mut rng, 6);
+            let right = random_wide(&mut rng, 6);
+            let whole: Vec<u16> = left.iter().chain(&right).copied().collect();
+            let expected = Wtf8Buf::from_wide(&whole);
+
+            let mut joined = Wtf8Buf::from_wide(&left);
+            joined.push_wtf8(&Wtf8Buf::from_wide(&right));
+            assert_eq!(joined, expected, "{:x?} + {:x?}", left, right);
+
+            let pushed: Wtf8Buf = whole
+                .iter()
+                .map(|&unit| CodePoint::from_u32(u32::from(unit)).unwrap())
+                .collect();
+            assert_eq!(pushed, expected, "{:x?}", whole);
+            let code_points: Vec<CodePoint> = expected.code_points().collect();
+            assert_eq!(code_points.iter().copied().collect::<Wtf8Buf>(), expected);
+        }
+    }
+
+    #[test]
+    fn try_pushes_report_failure() {
+        let budget = Budget::new(0);
+        let mut s = Wtf8Buf::new_in(&budget);
+        let lead = CodePoint::from_u32(0xD834).unwrap();
+        let trail = CodePoint::from_u32(0xDD1E).unwrap();
+        assert!(s.try_push_char('a').is_err());
+        assert!(s.try_push_str("a").is_err());
+        assert!(s.try_push(lead).is_err());
+        assert!(s.try_push_wtf8(&Wtf8Buf::from("a")).is_err());
+        assert!(s.is_empty());
+
+        budget.set(usize::MAX);
+        s.reserve(8);
+        budget.set(0);
+        let room = s.capacity();
+        s.try_push_char('é').unwrap();
+        s.try_push(lead).unwrap();
+        s.try_push(trail).unwrap();
+        assert_eq!(s.as_str(), Some("é𝄞"));
+        while s.try_push_str("x").is_ok() {}
+        assert_eq!(s.len(), room);
+        assert!(s.try_push(trail).is_err());
+        assert!(s.try_push_wtf8(&Wtf8Buf::from("y")).is_err());
+        assert_eq!(s.len(), room);
+    }
+
+    #[test]
+    fn formatting() {
+        let mut s = Wtf8Buf::from("it's \"é\"\n");
+        s.push(CodePoint::from_u32(0xDC00).unwrap());
+        s.push_str("x");
+        assert_eq!(format!("{:?}", s), "\"it's \\\"é\\\"\\n\\u{dc00}x\"");
+        assert_eq!(s.to_string(), "it's \"é\"\n\u{FFFD}x");
+        assert_eq!(
+            format!("{:?}", Wtf8Buf::from("\u{301}a\u{301}")),
+            format!("{:?}", "\u{301}a\u{301}")
+        );
+        assert_eq!(format!("{:?}", CodePoint::from_char('é')), "U+00E9");
+    }
+}